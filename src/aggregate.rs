@@ -1,12 +1,16 @@
-use crate::{crossdev, InodeFilter, WalkOptions, WalkResult};
+use crate::{
+    privileged::{self, is_permission_denied},
+    EntriesUntil, FileSizes, InodeFilter, OutputTemplate, SeenDirectories, SymlinkLoop,
+    TemplateValues, WalkOptions, WalkResult,
+};
 use anyhow::Result;
 use colored::{Color, Colorize};
-use filesize::PathExt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{
     borrow::Cow,
-    io,
-    path::Path,
+    collections::BTreeMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
@@ -14,26 +18,64 @@ use std::{
     thread,
 };
 
+/// The amount of lines each thread formats at a time when writing sorted aggregates
+const LINES_PER_THREAD: usize = 4096;
+/// How long unsorted lines may stay in the output buffer before they are written
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What was found below one of the paths to aggregate
+struct Aggregate {
+    path: PathBuf,
+    num_bytes: u128,
+    num_errors: u64,
+    num_loops: usize,
+    /// The amount of directories that weren't counted as they were seen before at another path
+    num_duplicates: usize,
+    num_entries: u64,
+    /// If set, the traversal was stopped before everything below `path` was seen
+    incomplete: bool,
+}
+
 /// Aggregate the given `paths` and write information about them to `out` in a human-readable format.
 /// If `compute_total` is set, it will write an additional line with the total size across all given `paths`.
 /// If `sort_by_size_in_bytes` is set, we will sort all sizes (ascending) before outputting them.
+/// If `rescan_with_privileges` is set, directories we are not permitted to read are measured again through `sudo`.
+/// If `template` is set, each line is formatted according to it instead.
+/// If `depth` is set, each directory up to this many levels below the given `paths` gets a line too. Unless sorted,
+/// directories come before the directory they are in like with `du`, so each path is followed by its total.
+/// If `timeout` is set, the traversal stops once it has taken that long, even if reading a directory doesn't return,
+/// and everything seen until then is written sorted by size. Sizes that are lower bounds are marked as incomplete,
+/// and inputs that weren't reached are left out and counted in [`Statistics::inputs_not_scanned`].
+///
+/// Output is buffered, and the sorted lines are formatted on multiple threads if there are many of them.
+#[allow(clippy::too_many_arguments)]
 pub fn aggregate(
-    mut out: impl io::Write,
+    out: impl io::Write,
     err: Option<impl io::Write + Send + 'static>,
     walk_options: WalkOptions,
     compute_total: bool,
     sort_by_size_in_bytes: bool,
+    rescan_with_privileges: bool,
+    template: Option<&OutputTemplate>,
+    depth: Option<usize>,
+    timeout: Option<Duration>,
     paths: impl IntoIterator<Item = impl AsRef<Path>>,
 ) -> Result<(WalkResult, Statistics)> {
+    let mut out = io::BufWriter::new(out);
+    let mut last_flush = Instant::now();
+    let deadline = timeout.map(|timeout| last_flush + timeout);
+    // partial results are only useful once the largest ones can be found at the end
+    let sort_by_size_in_bytes = sort_by_size_in_bytes || timeout.is_some();
     let mut res = WalkResult::default();
     let mut stats = Statistics {
-        smallest_file_in_bytes: u128::max_value(),
+        smallest_file_in_bytes: u128::MAX,
         ..Default::default()
     };
     let mut total = 0;
     let mut num_roots = 0;
     let mut aggregates = Vec::new();
     let mut inodes = InodeFilter::default();
+    let seen = SeenDirectories::default();
     let paths: Vec<_> = paths.into_iter().collect();
     let shared_count = Arc::new(AtomicU64::new(0));
 
@@ -55,31 +97,58 @@ pub fn aggregate(
         });
     }
 
+    if let Some(header) = template.and_then(OutputTemplate::header) {
+        writeln!(out, "{}", header)?;
+    }
+    let num_paths = paths.len();
     for path in paths.into_iter() {
+        if res.timed_out {
+            break;
+        }
         num_roots += 1;
         let mut num_bytes = 0u128;
         let mut num_errors = 0u64;
-        let device_id = crossdev::init(path.as_ref())?;
-        for entry in walk_options.iter_from_path(path.as_ref()) {
+        let mut num_loops = 0;
+        let mut num_duplicates = 0;
+        let mut num_entries = 0u64;
+        let mut permission_denied = Vec::new();
+        let mut directories = BTreeMap::<PathBuf, Aggregate>::new();
+        let sizes = FileSizes::new(&walk_options, path.as_ref())?;
+        let mut entries = EntriesUntil::new(
+            walk_options.pausable_iter_from_path(path.as_ref(), None, Some(seen.clone())),
+            deadline,
+        );
+        for entry in entries.by_ref() {
             stats.entries_traversed += 1;
+            num_entries += 1;
             shared_count.fetch_add(1, Ordering::Relaxed);
+            let counts_before = (num_errors, num_loops, num_duplicates);
+            let mut counted_path = None;
             match entry {
                 Ok(entry) => {
+                    if depth.is_some_and(|depth| depth > 0 && entry.depth > 0) {
+                        counted_path = Some((entry.path(), entry.file_type().is_dir()));
+                    }
+                    if let Some(symlink_loop) = SymlinkLoop::from_entry(&entry) {
+                        num_loops += 1;
+                        res.symlink_loops.push(symlink_loop);
+                    }
+                    if let Some(duplicate) = seen.duplicate_of(&entry) {
+                        num_duplicates += 1;
+                        res.duplicate_directories.push(duplicate);
+                    }
+                    if let Some(err) = &entry.read_children_error {
+                        num_errors += 1;
+                        if is_permission_denied(err) {
+                            permission_denied.push(entry.path());
+                        }
+                    }
                     let file_size = match entry.client_state {
-                        Some(Ok(ref m))
-                            if !m.is_dir()
-                                && (walk_options.count_hard_links || inodes.add(m))
-                                && (walk_options.cross_filesystems
-                                    || crossdev::is_same_device(device_id, m)) =>
-                        {
-                            if walk_options.apparent_size {
-                                m.len()
-                            } else {
-                                entry.path().size_on_disk_fast(m).unwrap_or_else(|_| {
-                                    num_errors += 1;
-                                    0
-                                })
-                            }
+                        Some(Ok(ref m)) if sizes.counts(m, &mut inodes) => {
+                            sizes.size_of(&entry, m).unwrap_or_else(|_| {
+                                num_errors += 1;
+                                0
+                            })
                         }
                         Some(Ok(_)) => 0,
                         Some(Err(_)) => {
@@ -91,60 +160,245 @@ pub fn aggregate(
                     stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(file_size);
                     stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(file_size);
                     num_bytes += file_size;
+                    if let Some((entry_path, is_dir)) = &counted_path {
+                        let (errors, loops, duplicates) = counts_before;
+                        let counts = Aggregate {
+                            path: PathBuf::new(),
+                            num_bytes: file_size,
+                            num_errors: num_errors - errors,
+                            num_loops: num_loops - loops,
+                            num_duplicates: num_duplicates - duplicates,
+                            num_entries: 1,
+                            incomplete: false,
+                        };
+                        for directory in
+                            directories_containing(path.as_ref(), entry_path, *is_dir, depth)
+                        {
+                            directories
+                                .entry(directory)
+                                .or_insert_with_key(|path| Aggregate::empty(path.clone()))
+                                .add(&counts);
+                        }
+                    }
                 }
-                Err(_) => num_errors += 1,
+                Err(err) => match SymlinkLoop::from_error(&err) {
+                    Some(symlink_loop) => {
+                        num_loops += 1;
+                        res.symlink_loops.push(symlink_loop);
+                    }
+                    None => num_errors += 1,
+                },
             }
         }
+        res.timed_out = entries.timed_out();
 
+        if rescan_with_privileges && !res.timed_out && !permission_denied.is_empty() {
+            let sizes = privileged::sizes_of(&walk_options, &permission_denied)?;
+            for ((bytes, errors), denied) in sizes.into_iter().zip(&permission_denied) {
+                num_bytes += bytes;
+                num_errors += errors;
+                for directory in directories_containing(path.as_ref(), denied, true, depth) {
+                    if let Some(row) = directories.get_mut(&directory) {
+                        row.num_bytes += bytes;
+                        // the error of not being able to read it is replaced by the ones of the privileged scan
+                        row.num_errors = row.num_errors + errors - 1;
+                    }
+                }
+            }
+            num_errors -= permission_denied.len() as u64;
+        }
+
+        let aggregate = Aggregate {
+            path: path.as_ref().to_owned(),
+            num_bytes,
+            num_errors,
+            num_loops,
+            num_duplicates,
+            num_entries,
+            incomplete: res.timed_out,
+        };
         if sort_by_size_in_bytes {
-            aggregates.push((path.as_ref().to_owned(), num_bytes, num_errors));
+            aggregates.push(aggregate);
+            aggregates.extend(directories.into_values().map(|mut directory| {
+                directory.incomplete = res.timed_out;
+                directory
+            }));
         } else {
-            output_colored_path(
-                &mut out,
-                &walk_options,
-                &path,
-                num_bytes,
-                num_errors,
-                path_color_of(&path),
-            )?;
+            let mut directories: Vec<_> = directories.into_values().collect();
+            directories.sort_by(|a, b| children_first(&a.path, &b.path));
+            for directory in directories.iter().chain(Some(&aggregate)) {
+                write_aggregate(&mut out, &walk_options, template, directory, path_color_of)?;
+            }
+            // keep showing results as they come in without paying for a write per line
+            if last_flush.elapsed() >= FLUSH_INTERVAL {
+                out.flush()?;
+                last_flush = Instant::now();
+            }
         }
         total += num_bytes;
         res.num_errors += num_errors;
     }
 
+    stats.total_bytes = total;
     if stats.entries_traversed == 0 {
         stats.smallest_file_in_bytes = 0;
     }
 
     if sort_by_size_in_bytes {
-        aggregates.sort_by_key(|&(_, num_bytes, _)| num_bytes);
-        for (path, num_bytes, num_errors) in aggregates.into_iter() {
-            output_colored_path(
-                &mut out,
-                &walk_options,
-                &path,
-                num_bytes,
-                num_errors,
-                path_color_of(&path),
-            )?;
-        }
+        aggregates.sort_by_key(|aggregate| aggregate.num_bytes);
+        write_aggregates(&mut out, &walk_options, template, &aggregates)?;
     }
 
     if num_roots > 1 && compute_total {
-        output_colored_path(
-            &mut out,
-            &walk_options,
-            Path::new("total"),
-            total,
-            res.num_errors,
-            None,
-        )?;
+        let total = Aggregate {
+            path: "total".into(),
+            num_bytes: total,
+            num_errors: res.num_errors,
+            num_loops: res.symlink_loops.len(),
+            num_duplicates: res.duplicate_directories.len(),
+            num_entries: stats.entries_traversed,
+            incomplete: res.timed_out,
+        };
+        write_aggregate(&mut out, &walk_options, template, &total, |_| None)?;
     }
+    stats.inputs_not_scanned = num_paths - num_roots;
+    out.flush()?;
     Ok((res, stats))
 }
 
-fn path_color_of(path: impl AsRef<Path>) -> Option<Color> {
-    if path.as_ref().is_file() {
+impl Aggregate {
+    fn empty(path: PathBuf) -> Self {
+        Aggregate {
+            path,
+            num_bytes: 0,
+            num_errors: 0,
+            num_loops: 0,
+            num_duplicates: 0,
+            num_entries: 0,
+            incomplete: false,
+        }
+    }
+
+    fn add(&mut self, other: &Aggregate) {
+        self.num_bytes += other.num_bytes;
+        self.num_errors += other.num_errors;
+        self.num_loops += other.num_loops;
+        self.num_duplicates += other.num_duplicates;
+        self.num_entries += other.num_entries;
+    }
+}
+
+/// The directories at most `depth` levels below `root` that contain `path`, including `path` itself if it `is_dir`.
+fn directories_containing(
+    root: &Path,
+    path: &Path,
+    is_dir: bool,
+    depth: Option<usize>,
+) -> Vec<PathBuf> {
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) => relative,
+        Err(_) => return Vec::new(),
+    };
+    let components: Vec<_> = relative.components().collect();
+    let levels = match is_dir {
+        true => components.len(),
+        false => components.len().saturating_sub(1),
+    };
+    (1..=levels.min(depth.unwrap_or(0)))
+        .map(|level| root.join(components[..level].iter().collect::<PathBuf>()))
+        .collect()
+}
+
+/// Order `a` and `b` by their components, but with paths after all the paths below them.
+fn children_first(a: &Path, b: &Path) -> std::cmp::Ordering {
+    let (mut a, mut b) = (a.components(), b.components());
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(a), Some(b)) => return a.cmp(&b),
+            (Some(_), None) => return std::cmp::Ordering::Less,
+            (None, Some(_)) => return std::cmp::Ordering::Greater,
+            (None, None) => return std::cmp::Ordering::Equal,
+        }
+    }
+}
+
+/// Write a line for each of `aggregates` to `out`, in order.
+///
+/// Formatting includes finding out whether each path is a file, so with many lines it's split among threads,
+/// each formatting a batch of lines into its own buffer.
+fn write_aggregates(
+    out: &mut impl io::Write,
+    walk_options: &WalkOptions,
+    template: Option<&OutputTemplate>,
+    aggregates: &[Aggregate],
+) -> io::Result<()> {
+    let threads = match walk_options.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+    if threads == 1 || aggregates.len() <= LINES_PER_THREAD {
+        for aggregate in aggregates {
+            write_aggregate(out, walk_options, template, aggregate, path_color_of)?;
+        }
+        return Ok(());
+    }
+    for batch in aggregates.chunks(threads * LINES_PER_THREAD) {
+        let buffers = thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .chunks(LINES_PER_THREAD)
+                .map(|lines| {
+                    scope.spawn(move || -> io::Result<Vec<u8>> {
+                        let mut buf = Vec::new();
+                        for aggregate in lines {
+                            write_aggregate(
+                                &mut buf,
+                                walk_options,
+                                template,
+                                aggregate,
+                                path_color_of,
+                            )?;
+                        }
+                        Ok(buf)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("formatting doesn't panic"))
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+        for buf in buffers {
+            out.write_all(&buf)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_aggregate(
+    out: &mut impl io::Write,
+    walk_options: &WalkOptions,
+    template: Option<&OutputTemplate>,
+    aggregate: &Aggregate,
+    path_color: impl FnOnce(&Path) -> Option<Color>,
+) -> io::Result<()> {
+    match template {
+        Some(template) => template.write_line(
+            out,
+            walk_options.byte_format,
+            &TemplateValues {
+                path: &aggregate.path,
+                num_bytes: aggregate.num_bytes,
+                num_entries: aggregate.num_entries,
+                num_errors: aggregate.num_errors,
+            },
+        ),
+        None => output_colored_path(out, walk_options, aggregate, path_color(&aggregate.path)),
+    }
+}
+
+fn path_color_of(path: &Path) -> Option<Color> {
+    if path.is_file() {
         None
     } else {
         Some(Color::Cyan)
@@ -154,14 +408,21 @@ fn path_color_of(path: impl AsRef<Path>) -> Option<Color> {
 fn output_colored_path(
     out: &mut impl io::Write,
     options: &WalkOptions,
-    path: impl AsRef<Path>,
-    num_bytes: u128,
-    num_errors: u64,
+    aggregate: &Aggregate,
     path_color: Option<colored::Color>,
 ) -> std::result::Result<(), io::Error> {
+    let &Aggregate {
+        ref path,
+        num_bytes,
+        num_errors,
+        num_loops,
+        num_duplicates,
+        incomplete,
+        ..
+    } = aggregate;
     writeln!(
         out,
-        "{:>byte_column_width$} {}{}",
+        "{:>byte_column_width$} {}{}{}{}{}",
         options
             .byte_format
             .display(num_bytes)
@@ -169,7 +430,7 @@ fn output_colored_path(
             .as_str()
             .green(),
         {
-            let path = path.as_ref().display().to_string();
+            let path = path.display().to_string();
             match path_color {
                 Some(color) => path.color(color),
                 None => path.normal(),
@@ -184,6 +445,25 @@ fn output_colored_path(
                 if num_errors > 1 { "s" } else { "" }
             ))
         },
+        if num_loops == 0 {
+            Cow::Borrowed("")
+        } else {
+            Cow::Owned(format!(
+                "  <{} symlink loop{}>",
+                num_loops,
+                if num_loops > 1 { "s" } else { "" }
+            ))
+        },
+        if num_duplicates == 0 {
+            Cow::Borrowed("")
+        } else {
+            Cow::Owned(format!(
+                "  <{} duplicate director{} counted once>",
+                num_duplicates,
+                if num_duplicates > 1 { "ies" } else { "y" }
+            ))
+        },
+        if incomplete { "  <incomplete>" } else { "" },
         byte_column_width = options.byte_format.width()
     )
 }
@@ -197,4 +477,156 @@ pub struct Statistics {
     pub smallest_file_in_bytes: u128,
     /// The size of the largest file encountered in bytes
     pub largest_file_in_bytes: u128,
+    /// The size of all given paths combined in bytes
+    pub total_bytes: u128,
+    /// The amount of given paths which weren't traversed at all, as the traversal was stopped before
+    pub inputs_not_scanned: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testutils::TempTree, ByteFormat};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn many_lines_are_formatted_in_parallel_and_written_in_order() -> io::Result<()> {
+        let aggregates: Vec<_> = (0..LINES_PER_THREAD * 5 + 3)
+            .map(|n| Aggregate {
+                path: format!("input-{}", n).into(),
+                num_bytes: n as u128,
+                num_errors: (n % 7 == 0) as u64,
+                num_loops: 0,
+                num_duplicates: 0,
+                num_entries: 1,
+                incomplete: false,
+            })
+            .collect();
+        let output_with = |threads| -> io::Result<String> {
+            let mut out = Vec::new();
+            write_aggregates(
+                &mut out,
+                &WalkOptions {
+                    threads,
+                    ..WalkOptions::for_tests()
+                },
+                None,
+                &aggregates,
+            )?;
+            Ok(String::from_utf8(out).expect("utf8"))
+        };
+        let sequential = output_with(1)?;
+        assert_eq!(sequential.lines().count(), aggregates.len());
+        assert!(sequential
+            .lines()
+            .last()
+            .is_some_and(|line| line.contains(&format!("input-{}", aggregates.len() - 1))));
+        assert_eq!(output_with(3)?, sequential);
+        Ok(())
+    }
+
+    #[test]
+    fn directories_up_to_the_depth_get_rows_of_their_own() -> Result<()> {
+        let root = TempTree::new("aggregate-depth");
+        root.file("a/b/deep", vec![0; 100])?;
+        root.dir("a-c")?;
+        root.file("a/file", vec![0; 10])?;
+        root.file("top", vec![0; 1])?;
+        let mut out = Vec::new();
+        aggregate(
+            &mut out,
+            None::<io::Stderr>,
+            WalkOptions::for_tests(),
+            false,
+            false,
+            false,
+            Some(&"csv".parse()?),
+            Some(2),
+            None,
+            Some(&root),
+        )?;
+
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "path,size_bytes,entries,errors\n{},100,2,0\n{},110,4,0\n{},0,1,0\n{},111,7,0\n",
+                root.join("a/b").display(),
+                root.join("a").display(),
+                root.join("a-c").display(),
+                root.display(),
+            ),
+            "files don't get a row, and directories come before the one they are in"
+        );
+        assert_eq!(
+            children_first(Path::new("a/b"), Path::new("a")),
+            Ordering::Less
+        );
+        assert_eq!(
+            children_first(Path::new("a/b"), Path::new("a-c")),
+            Ordering::Less
+        );
+        assert_eq!(
+            children_first(Path::new("b"), Path::new("a/b")),
+            Ordering::Greater
+        );
+        Ok(())
+    }
+
+    /// Turns colors off until dropped, as the override is shared by the whole process.
+    struct ColorsOff;
+
+    impl ColorsOff {
+        fn new() -> Self {
+            colored::control::set_override(false);
+            ColorsOff
+        }
+    }
+
+    impl Drop for ColorsOff {
+        fn drop(&mut self) {
+            colored::control::unset_override();
+        }
+    }
+
+    #[test]
+    fn a_timeout_stops_the_traversal_and_marks_partial_sizes() -> Result<()> {
+        let root = TempTree::new("aggregate-timeout");
+        root.file("first/a", vec![0; 10])?;
+        root.file("first/b", vec![0; 10])?;
+        root.dir("second")?;
+        let _colors = ColorsOff::new();
+        let mut out = Vec::new();
+        let (res, stats) = aggregate(
+            &mut out,
+            None::<io::Stderr>,
+            WalkOptions::for_tests(),
+            true,
+            false,
+            false,
+            None,
+            None,
+            Some(Duration::ZERO),
+            vec![root.join("first"), root.join("second")],
+        )?;
+
+        assert!(res.timed_out);
+        assert!(!res.interrupted, "only the user interrupts");
+        assert_eq!(
+            stats.entries_traversed, 0,
+            "nothing is seen once the deadline passed"
+        );
+        assert_eq!(stats.inputs_not_scanned, 1);
+        let w = ByteFormat::Bytes.width();
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "{:>w$} {}  <incomplete>\n",
+                "0 b",
+                root.join("first").display(),
+                w = w
+            ),
+            "no total is printed for a single scanned input"
+        );
+        Ok(())
+    }
 }
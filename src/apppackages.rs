@@ -0,0 +1,389 @@
+use crate::{ByteFormat, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where Flatpak and Snap keep the applications they installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppPackageLocations {
+    /// The Flatpak installations, each with `app` and `runtime` directories of deployments
+    pub flatpak_installations: Vec<PathBuf>,
+    /// The directory with one `<name>_<revision>.snap` image per retained revision
+    pub snap_images: PathBuf,
+    /// The directory the images are mounted in, with a `<name>/current` link to the current revision
+    pub snap_mounts: PathBuf,
+    /// The directory with the data of each snap, in a directory per revision and in `common`
+    pub snap_data: PathBuf,
+}
+
+impl Default for AppPackageLocations {
+    /// The system-wide locations, along with the Flatpak installation of the current user.
+    fn default() -> Self {
+        let mut flatpak_installations = vec![PathBuf::from("/var/lib/flatpak")];
+        if let Some(home) = std::env::var_os("HOME") {
+            flatpak_installations.push(PathBuf::from(home).join(".local/share/flatpak"));
+        }
+        AppPackageLocations {
+            flatpak_installations,
+            snap_images: "/var/lib/snapd/snaps".into(),
+            snap_mounts: "/snap".into(),
+            snap_data: "/var/snap".into(),
+        }
+    }
+}
+
+/// The space taken by one application or runtime
+#[derive(Default, Debug, PartialEq, Eq)]
+struct AppUsage {
+    /// The size of the revisions in use
+    bytes: u128,
+    /// The size of the revisions kept besides the ones in use, which pruning them would free
+    old_bytes: u128,
+    old_revisions: u64,
+}
+
+/// Measures the size of files, counting those shared by hard links only once
+struct Sizes<'a> {
+    walk_options: &'a WalkOptions,
+    inodes: InodeFilter,
+    res: WalkResult,
+}
+
+impl Sizes<'_> {
+    /// The size of all files at or below `path`, or 0 if it doesn't exist.
+    fn of(&mut self, path: &Path) -> u128 {
+        let file_sizes = match FileSizes::new(self.walk_options, path) {
+            Ok(file_sizes) => file_sizes,
+            Err(_) => return 0,
+        };
+        let mut bytes = 0;
+        for entry in self.walk_options.iter_from_path(path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    self.res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                self.res.num_errors += 1;
+            }
+            match &entry.client_state {
+                Some(Ok(m)) if file_sizes.counts(m, &mut self.inodes) => {
+                    match file_sizes.size_of(&entry, m) {
+                        Ok(size) => bytes += size as u128,
+                        Err(_) => self.res.num_errors += 1,
+                    }
+                }
+                Some(Err(_)) => self.res.num_errors += 1,
+                _ => {}
+            }
+        }
+        bytes
+    }
+}
+
+/// The names of all entries in `dir`, sorted, or none if it can't be read.
+fn names_in(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The name of the entry the link at `link` points to.
+fn link_target_name(link: &Path) -> Option<String> {
+    fs::read_link(link)
+        .ok()?
+        .file_name()?
+        .to_str()
+        .map(ToOwned::to_owned)
+}
+
+/// Flatpak deploys each commit of an application or runtime into `<kind>/<id>/<arch>/<branch>/<commit>`, with an
+/// `active` link to the deployment in use. Deployments in use are measured first, so files they share with older
+/// ones count for them.
+fn flatpak_usage(installation: &Path, sizes: &mut Sizes<'_>) -> Vec<(String, AppUsage)> {
+    let mut apps = Vec::new();
+    for kind in ["app", "runtime"] {
+        let kind_dir = installation.join(kind);
+        for id in names_in(&kind_dir) {
+            let mut usage = AppUsage::default();
+            let mut old = Vec::new();
+            for arch in names_in(&kind_dir.join(&id)) {
+                let arch_dir = kind_dir.join(&id).join(arch);
+                for branch in names_in(&arch_dir) {
+                    let branch_dir = arch_dir.join(branch);
+                    let active = link_target_name(&branch_dir.join("active"));
+                    for commit in names_in(&branch_dir) {
+                        let deployment = branch_dir.join(&commit);
+                        if commit == "active" || !deployment.is_dir() {
+                            continue;
+                        }
+                        match active.as_deref() == Some(commit.as_str()) {
+                            true => usage.bytes += sizes.of(&deployment),
+                            false => old.push(deployment),
+                        }
+                    }
+                }
+            }
+            for deployment in old {
+                usage.old_bytes += sizes.of(&deployment);
+                usage.old_revisions += 1;
+            }
+            apps.push((format!("{} ({})", id, kind), usage));
+        }
+    }
+    apps
+}
+
+/// Snapd keeps an image for each retained revision of a snap, and a data directory next to the `common` one for
+/// each of them. The current revision is the one linked as `current`, or else the latest one.
+fn snap_usage(locations: &AppPackageLocations, sizes: &mut Sizes<'_>) -> Vec<(String, AppUsage)> {
+    let mut revisions_by_snap = BTreeMap::<String, Vec<String>>::new();
+    for image in names_in(&locations.snap_images) {
+        if let Some((snap, revision)) = image
+            .strip_suffix(".snap")
+            .and_then(|name| name.rsplit_once('_'))
+        {
+            revisions_by_snap
+                .entry(snap.to_owned())
+                .or_default()
+                .push(revision.to_owned());
+        }
+    }
+    revisions_by_snap
+        .into_iter()
+        .map(|(snap, mut revisions)| {
+            revisions.sort_by_key(|revision| revision.parse::<u64>().ok());
+            let current = link_target_name(&locations.snap_mounts.join(&snap).join("current"))
+                .or_else(|| revisions.last().cloned());
+            let data = locations.snap_data.join(&snap);
+            let mut usage = AppUsage {
+                bytes: sizes.of(&data.join("common")),
+                ..Default::default()
+            };
+            for revision in &revisions {
+                let bytes = sizes.of(&locations
+                    .snap_images
+                    .join(format!("{}_{}.snap", snap, revision)))
+                    + sizes.of(&data.join(revision));
+                match current.as_ref() == Some(revision) {
+                    true => usage.bytes += bytes,
+                    false => {
+                        usage.old_bytes += bytes;
+                        usage.old_revisions += 1;
+                    }
+                }
+            }
+            (snap, usage)
+        })
+        .collect()
+}
+
+/// Write the space taken by each Flatpak and Snap application and runtime to `out`, along with how much their old
+/// revisions take that pruning them would free, and how to do that.
+pub fn summarize_app_packages(
+    out: impl io::Write,
+    walk_options: WalkOptions,
+    locations: &AppPackageLocations,
+) -> Result<WalkResult> {
+    let mut sizes = Sizes {
+        walk_options: &walk_options,
+        inodes: InodeFilter::default(),
+        res: WalkResult::default(),
+    };
+    let mut sections: Vec<_> = locations
+        .flatpak_installations
+        .iter()
+        .map(|installation| {
+            (
+                format!("Flatpak in {}", installation.display()),
+                flatpak_usage(installation, &mut sizes),
+            )
+        })
+        .collect();
+    sections.push((
+        format!("Snap in {}", locations.snap_images.display()),
+        snap_usage(locations, &mut sizes),
+    ));
+    write_app_usage(out, walk_options.byte_format, sections)?;
+    Ok(sizes.res)
+}
+
+fn write_app_usage(
+    mut out: impl io::Write,
+    format: ByteFormat,
+    sections: Vec<(String, Vec<(String, AppUsage)>)>,
+) -> io::Result<()> {
+    let mut total = AppUsage::default();
+    let (mut apps, mut old_flatpak, mut old_snaps) = (0, 0, 0);
+    for (title, mut usages) in sections {
+        if usages.is_empty() {
+            continue;
+        }
+        usages.sort_by(|(ln, l), (rn, r)| {
+            (l.bytes + l.old_bytes)
+                .cmp(&(r.bytes + r.old_bytes))
+                .then(ln.cmp(rn))
+        });
+        writeln!(out, "{}:", title)?;
+        for (name, usage) in &usages {
+            let old = match usage.old_revisions {
+                0 => "-".to_owned(),
+                n => format!(
+                    "{} in {} old revision{}",
+                    format.display(usage.old_bytes).to_string().trim(),
+                    n,
+                    plural(n)
+                ),
+            };
+            writeln!(
+                out,
+                "{:>w$} {:>o$} {}",
+                format.display(usage.bytes).to_string(),
+                old,
+                name,
+                w = format.width(),
+                o = format.width() + " in 9 old revisions".len()
+            )?;
+            total.bytes += usage.bytes;
+            total.old_bytes += usage.old_bytes;
+            total.old_revisions += usage.old_revisions;
+            match title.starts_with("Snap") {
+                true => old_snaps += usage.old_revisions,
+                false => old_flatpak += usage.old_revisions,
+            }
+        }
+        writeln!(out)?;
+        apps += usages.len() as u64;
+    }
+    if apps == 0 {
+        return writeln!(out, "There are no Flatpak or Snap applications");
+    }
+    writeln!(
+        out,
+        "{} in {} application{} and runtime{}, of which {} in {} old revision{} could be freed by pruning them",
+        format.display(total.bytes + total.old_bytes).to_string().trim(),
+        apps,
+        plural(apps),
+        plural(apps),
+        format.display(total.old_bytes).to_string().trim(),
+        total.old_revisions,
+        plural(total.old_revisions)
+    )?;
+    if old_snaps > 0 {
+        writeln!(
+            out,
+            "Snap keeps old revisions to revert to: 'sudo snap remove <name> --revision=<revision>' removes one, \
+             'sudo snap set system refresh.retain=2' keeps fewer of them"
+        )?;
+    }
+    if old_flatpak > 0 {
+        writeln!(
+            out,
+            "Flatpak removes old deployments once they are unused: 'flatpak repair' removes those that were left \
+             behind, 'flatpak uninstall --unused' also removes runtimes no application needs"
+        )?;
+    }
+    Ok(())
+}
+
+fn plural(n: u64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+
+    #[test]
+    #[cfg(unix)]
+    fn applications_are_summarized_with_their_old_revisions() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let root = TempTree::new("app-packages");
+        let firefox = root.join("flatpak/app/org.mozilla.firefox/x86_64/stable");
+        fs::create_dir_all(firefox.join("aaaa/files"))?;
+        fs::create_dir_all(firefox.join("bbbb/files"))?;
+        fs::write(firefox.join("aaaa/files/firefox"), vec![0; 100])?;
+        fs::write(firefox.join("bbbb/files/firefox"), vec![0; 90])?;
+        symlink("bbbb", firefox.join("active"))?;
+        let platform = root.join("flatpak/runtime/org.freedesktop.Platform/x86_64/23.08");
+        fs::create_dir_all(platform.join("cccc"))?;
+        fs::write(platform.join("cccc/libc.so"), vec![0; 1000])?;
+        symlink("cccc", platform.join("active"))?;
+
+        fs::create_dir_all(root.join("snaps"))?;
+        for (revision, size) in [("10", 50), ("11", 60), ("12", 70)] {
+            fs::write(
+                root.join(format!("snaps/core_{}.snap", revision)),
+                vec![0; size],
+            )?;
+            fs::create_dir_all(root.join(format!("data/core/{}", revision)))?;
+            fs::write(
+                root.join(format!("data/core/{}/state", revision)),
+                vec![0; 5],
+            )?;
+        }
+        fs::write(root.join("snaps/core_13.snap.partial"), vec![0; 1])?;
+        fs::remove_dir_all(root.join("data/core/10"))?;
+        fs::create_dir_all(root.join("data/core/common"))?;
+        fs::write(root.join("data/core/common/shared"), vec![0; 3])?;
+        fs::create_dir_all(root.join("mounts/core"))?;
+        symlink("11", root.join("mounts/core/current"))?;
+
+        let mut out = Vec::new();
+        let res = summarize_app_packages(
+            &mut out,
+            WalkOptions::for_tests(),
+            &AppPackageLocations {
+                flatpak_installations: vec![root.join("flatpak"), root.join("missing")],
+                snap_images: root.join("snaps"),
+                snap_mounts: root.join("mounts"),
+                snap_data: root.join("data"),
+            },
+        );
+        assert_eq!(res?.num_errors, 0);
+
+        let w = ByteFormat::Bytes.width();
+        let o = w + " in 9 old revisions".len();
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "Flatpak in {}:\n\
+                 {:>w$} {:>o$} org.mozilla.firefox (app)\n\
+                 {:>w$} {:>o$} org.freedesktop.Platform (runtime)\n\n\
+                 Snap in {}:\n\
+                 {:>w$} {:>o$} core\n\n\
+                 1383 b in 3 applications and runtimes, of which 225 b in 3 old revisions could be freed by pruning them\n\
+                 Snap keeps old revisions to revert to: 'sudo snap remove <name> --revision=<revision>' removes one, \
+                 'sudo snap set system refresh.retain=2' keeps fewer of them\n\
+                 Flatpak removes old deployments once they are unused: 'flatpak repair' removes those that were left \
+                 behind, 'flatpak uninstall --unused' also removes runtimes no application needs\n",
+                root.join("flatpak").display(),
+                "90 b",
+                "100 b in 1 old revision",
+                "1000 b",
+                "-",
+                root.join("snaps").display(),
+                "68 b",
+                "125 b in 2 old revisions",
+                w = w,
+                o = o
+            )
+        );
+        Ok(())
+    }
+}
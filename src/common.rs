@@ -1,6 +1,23 @@
-use crate::traverse::{EntryData, Tree, TreeIndex};
+use crate::{
+    ignorefiles::IgnoreRules,
+    traverse::{EntryData, Tree, TreeIndex},
+    DuplicateDirectory, FilesystemQuirks, InodeFilter, SeenDirectories,
+};
 use byte_unit::{n_gb_bytes, n_gib_bytes, n_mb_bytes, n_mib_bytes, ByteUnit};
-use std::{fmt, path::Path};
+use filesize::PathExt;
+use std::{
+    borrow::Cow,
+    fmt,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Condvar, Mutex,
+    },
+    time::Instant,
+};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
 
 pub fn get_entry_or_panic(tree: &Tree, node_idx: TreeIndex) -> &EntryData {
     tree.node_weight(node_idx)
@@ -11,8 +28,24 @@ pub(crate) fn get_size_or_panic(tree: &Tree, node_idx: TreeIndex) -> u128 {
     get_entry_or_panic(tree, node_idx).size
 }
 
+/// `path` in Unicode normalization form C, so names written decomposed as on macOS compare equal to
+/// the composed ones used elsewhere. Paths which aren't valid UTF-8 are returned unchanged.
+pub fn normalized(path: &Path) -> Cow<'_, Path> {
+    match path.to_str() {
+        Some(s) if is_nfc_quick(s.chars()) != IsNormalized::Yes => {
+            Cow::Owned(PathBuf::from(s.nfc().collect::<String>()))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Return true if `a` and `b` are the same path once both are [normalized][normalized()].
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || normalized(a) == normalized(b)
+}
+
 /// Specifies a way to format bytes
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ByteFormat {
     /// metric format, based on 1000.
     Metric,
@@ -52,6 +85,16 @@ impl ByteFormat {
             }
             + THE_SPACE_BETWEEN_UNIT_AND_NUMBER
     }
+    /// Switch to the next of the metric, binary, bytes and gigabytes formats, or to metric from any other format.
+    pub fn cycle(&mut self) {
+        use ByteFormat::*;
+        *self = match self {
+            Metric => Binary,
+            Binary => Bytes,
+            Bytes => GB,
+            GB | GiB | MB | MiB => Metric,
+        }
+    }
     pub fn display(self, bytes: u128) -> ByteFormatDisplay {
         ByteFormatDisplay {
             format: self,
@@ -123,25 +166,113 @@ pub struct WalkOptions {
     pub apparent_size: bool,
     pub sorting: TraversalSorting,
     pub cross_filesystems: bool,
+    /// If set, symbolic links are followed and traversed like the entries they point to
+    pub follow_links: bool,
+    /// If set and `apparent_size` is unset, the size on disk is the apparent size rounded up to the block size
+    /// of the filesystem instead of the amount of allocated 512-byte blocks.
+    pub round_to_fs_blocks: bool,
+    /// If set, entries excluded by `.gitignore` or `.ignore` files are skipped along with `.git` directories,
+    /// which leaves only what would be tracked in a repository.
+    pub respect_ignore_files: bool,
 }
 
-type WalkDir = jwalk::WalkDirGeneric<((), Option<Result<std::fs::Metadata, jwalk::Error>>)>;
+type ClientState = (
+    Option<Arc<IgnoreRules>>,
+    Option<Result<std::fs::Metadata, jwalk::Error>>,
+);
+type WalkDir = jwalk::WalkDirGeneric<ClientState>;
+pub(crate) type DirEntry = jwalk::DirEntry<ClientState>;
 
 impl WalkOptions {
+    /// Describe how sizes are computed, to help explaining differences to other tools.
+    pub fn size_model_description(&self) -> &'static str {
+        match (self.apparent_size, self.round_to_fs_blocks) {
+            (true, _) => "apparent size (st_size)",
+            (false, true) => "apparent size rounded up to filesystem blocks (statvfs)",
+            (false, false) => "allocated 512-byte blocks (st_blocks), like du",
+        }
+    }
+
     pub(crate) fn iter_from_path(&self, path: &Path) -> WalkDir {
+        self.pausable_iter_from_path(path, None, None)
+    }
+
+    /// Like [`iter_from_path()`](Self::iter_from_path()), but workers stop reading directories while `pause` is set.
+    /// If `seen` is set, directories already in it or seen earlier in this walk at another path aren't read again,
+    /// which keeps directories reachable through multiple bind mounts from being counted more than once.
+    pub(crate) fn pausable_iter_from_path(
+        &self,
+        path: &Path,
+        pause: Option<Pause>,
+        seen: Option<SeenDirectories>,
+    ) -> WalkDir {
+        let follow_links = self.follow_links;
+        let respect_ignore_files = self.respect_ignore_files;
+        let device_id = (!self.cross_filesystems)
+            .then(|| crate::crossdev::init(path).ok())
+            .flatten();
         WalkDir::new(path)
-            .follow_links(false)
+            .follow_links(self.follow_links)
             .sort(match self.sorting {
                 TraversalSorting::None => false,
                 TraversalSorting::AlphabeticalByFileName => true,
             })
             .skip_hidden(false)
-            .process_read_dir(|_, _, _, dir_entry_results| {
+            .root_read_dir_state(
+                respect_ignore_files
+                    .then(|| IgnoreRules::above(path))
+                    .flatten(),
+            )
+            .process_read_dir(move |_, path, ignore_rules, dir_entry_results| {
+                if let Some(pause) = &pause {
+                    pause.wait_while_paused();
+                }
+                if respect_ignore_files {
+                    *ignore_rules = IgnoreRules::extended(ignore_rules.take(), path);
+                    dir_entry_results.retain(|dir_entry_result| match dir_entry_result {
+                        Ok(dir_entry) => {
+                            let is_dir = dir_entry.file_type.is_dir();
+                            let is_ignored = (is_dir && dir_entry.file_name == ".git")
+                                || ignore_rules.as_ref().is_some_and(|rules| {
+                                    rules.is_ignored(&dir_entry.path(), is_dir)
+                                });
+                            !is_ignored
+                        }
+                        Err(_) => true,
+                    });
+                }
                 dir_entry_results.iter_mut().for_each(|dir_entry_result| {
                     if let Ok(dir_entry) = dir_entry_result {
                         if dir_entry.file_type.is_file() || dir_entry.file_type().is_symlink() {
                             dir_entry.client_state = Some(dir_entry.metadata());
                         }
+                        if follow_links
+                            && dir_entry.path_is_symlink()
+                            && dir_entry.file_type.is_dir()
+                            && loop_ancestor(&dir_entry.path(), path).is_some()
+                        {
+                            // never descend into a directory we are already in
+                            dir_entry.read_children_path = None;
+                        }
+                        if let Some(device_id) = device_id {
+                            // don't read mount points at all, instead of reading everything below them to ignore it
+                            if dir_entry.read_children_path.is_some()
+                                && dir_entry
+                                    .metadata()
+                                    .is_ok_and(|m| !crate::crossdev::is_same_device(device_id, &m))
+                            {
+                                dir_entry.read_children_path = None;
+                            }
+                        }
+                        if let Some(seen) = &seen {
+                            let is_duplicate = dir_entry.read_children_path.is_some()
+                                && dir_entry
+                                    .metadata()
+                                    .is_ok_and(|m| !seen.add(dir_entry.path(), &m));
+                            if is_duplicate {
+                                dir_entry.read_children_path = None;
+                            }
+                        }
                     }
                 })
             })
@@ -153,19 +284,306 @@ impl WalkOptions {
     }
 }
 
+/// Computes the size of files below a traversal root according to the [`WalkOptions`], along with deciding
+/// which files are counted at all.
+pub(crate) struct FileSizes {
+    apparent_size: bool,
+    round_to_fs_blocks: bool,
+    count_hard_links: bool,
+    cross_filesystems: bool,
+    device_id: u64,
+    quirks: FilesystemQuirks,
+    block_size: Option<u64>,
+}
+
+impl FileSizes {
+    /// Prepare for files below `root`, which fails if `root` can't be accessed.
+    pub(crate) fn new(walk_options: &WalkOptions, root: &Path) -> io::Result<Self> {
+        Ok(FileSizes {
+            apparent_size: walk_options.apparent_size,
+            round_to_fs_blocks: walk_options.round_to_fs_blocks,
+            count_hard_links: walk_options.count_hard_links,
+            cross_filesystems: walk_options.cross_filesystems,
+            device_id: crate::crossdev::init(root)?,
+            quirks: FilesystemQuirks::of(root),
+            block_size: walk_options
+                .round_to_fs_blocks
+                .then(|| FilesystemQuirks::block_size(root))
+                .flatten(),
+        })
+    }
+
+    /// Return true if the entry described by `meta` is a file that is counted, i.e. it's on the device of the root
+    /// unless filesystems are crossed, and it wasn't seen before in `inodes` unless hard links are counted.
+    pub(crate) fn counts(&self, meta: &Metadata, inodes: &mut InodeFilter) -> bool {
+        !meta.is_dir()
+            && (self.count_hard_links || self.quirks.no_hard_links || inodes.add(meta))
+            && (self.cross_filesystems || self.is_on_root_device(meta))
+    }
+
+    /// Return true if the entry described by `meta` is on the same device as the root.
+    pub(crate) fn is_on_root_device(&self, meta: &Metadata) -> bool {
+        crate::crossdev::is_same_device(self.device_id, meta)
+    }
+
+    /// The size of the file `entry` described by `meta`, according to the size model of the walk options.
+    pub(crate) fn size_of(&self, entry: &DirEntry, meta: &Metadata) -> io::Result<u64> {
+        if self.apparent_size {
+            Ok(meta.len())
+        } else if self.round_to_fs_blocks {
+            Ok(FilesystemQuirks::size_in_blocks(meta, self.block_size))
+        } else {
+            self.size_on_disk(entry, meta)
+        }
+    }
+
+    /// The amount of bytes the file `entry` described by `meta` takes on disk, whichever size model is used.
+    pub(crate) fn size_on_disk(&self, entry: &DirEntry, meta: &Metadata) -> io::Result<u64> {
+        #[cfg(not(windows))]
+        let size = Path::new(&entry.file_name).size_on_disk_fast(meta);
+        #[cfg(windows)]
+        let size = entry.path().size_on_disk_fast(meta);
+        size.map(|size| self.quirks.size_on_disk(meta, size))
+    }
+}
+
+/// The entries of a traversal, which stop once `deadline` is reached if it is set.
+///
+/// With a deadline the traversal runs on another thread, so the deadline is kept even if reading a directory or
+/// obtaining metadata doesn't return.
+pub(crate) struct EntriesUntil {
+    source: EntrySource,
+    timed_out: bool,
+}
+
+enum EntrySource {
+    Direct(jwalk::DirEntryIter<ClientState>),
+    Deadline(Receiver<Result<DirEntry, jwalk::Error>>, Instant),
+}
+
+impl EntriesUntil {
+    pub(crate) fn new(walk: WalkDir, deadline: Option<Instant>) -> Self {
+        let source = match deadline {
+            None => EntrySource::Direct(walk.into_iter()),
+            Some(deadline) => {
+                let (tx, rx) = mpsc::sync_channel(1024);
+                std::thread::spawn(move || {
+                    for entry in walk {
+                        if tx.send(entry).is_err() {
+                            break;
+                        }
+                    }
+                });
+                EntrySource::Deadline(rx, deadline)
+            }
+        };
+        EntriesUntil {
+            source,
+            timed_out: false,
+        }
+    }
+
+    /// If set, the deadline was reached before all entries were seen.
+    pub(crate) fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+impl Iterator for EntriesUntil {
+    type Item = Result<DirEntry, jwalk::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (entries, deadline) = match &mut self.source {
+            EntrySource::Direct(entries) => return entries.next(),
+            EntrySource::Deadline(entries, deadline) => (entries, *deadline),
+        };
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let entry = match timeout.is_zero() {
+            true => Err(RecvTimeoutError::Timeout),
+            false => entries.recv_timeout(timeout),
+        };
+        match entry {
+            Ok(entry) => Some(entry),
+            Err(RecvTimeoutError::Timeout) => {
+                self.timed_out = true;
+                None
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+/// A handle to pause and resume a running traversal from another thread
+#[derive(Default, Clone, Debug)]
+pub struct Pause(Arc<(Mutex<bool>, Condvar)>);
+
+impl Pause {
+    pub fn set(&self, paused: bool) {
+        let (is_paused, resumed) = &*self.0;
+        *is_paused.lock().expect("no panic while holding the lock") = paused;
+        if !paused {
+            resumed.notify_all();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.0 .0.lock().expect("no panic while holding the lock")
+    }
+
+    fn wait_while_paused(&self) {
+        let (is_paused, resumed) = &*self.0;
+        let _guard = resumed
+            .wait_while(
+                is_paused.lock().expect("no panic while holding the lock"),
+                |paused| *paused,
+            )
+            .expect("no panic while holding the lock");
+    }
+}
+
+/// A symbolic link which points to one of its own ancestors, and was thus not followed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymlinkLoop {
+    /// The path of the symbolic link
+    pub path: PathBuf,
+    /// The ancestor directory the link points back to
+    pub ancestor: PathBuf,
+}
+
+impl SymlinkLoop {
+    /// Obtain the loop from errors in which the symlink target is literally one of its ancestors.
+    pub(crate) fn from_error(err: &jwalk::Error) -> Option<Self> {
+        Some(SymlinkLoop {
+            path: err.path()?.to_owned(),
+            ancestor: err.loop_ancestor()?.to_owned(),
+        })
+    }
+
+    /// Obtain the loop from entries we decided not to descend into while following links.
+    pub(crate) fn from_entry(entry: &DirEntry) -> Option<Self> {
+        if !(entry.path_is_symlink()
+            && entry.file_type.is_dir()
+            && entry.read_children_path.is_none())
+        {
+            return None;
+        }
+        let path = entry.path();
+        Some(SymlinkLoop {
+            ancestor: loop_ancestor(&path, &entry.parent_path)?,
+            path,
+        })
+    }
+}
+
+/// Return the canonical target of `link` if it is `directory` or one of its ancestors.
+///
+/// This also applies to NTFS junctions, which are treated like symbolic links to directories.
+fn loop_ancestor(link: &Path, directory: &Path) -> Option<PathBuf> {
+    let target = link.canonicalize().ok()?;
+    directory
+        .canonicalize()
+        .ok()?
+        .starts_with(&target)
+        .then_some(target)
+}
+
 /// Information we gather during a filesystem walk
 #[derive(Default)]
 pub struct WalkResult {
     /// The amount of io::errors we encountered. Can happen when fetching meta-data, or when reading the directory contents.
     pub num_errors: u64,
+    /// Symbolic links we didn't follow as they point to one of their own ancestors.
+    pub symlink_loops: Vec<SymlinkLoop>,
+    /// Directories we didn't read as they were already seen at another path, like through a bind mount.
+    pub duplicate_directories: Vec<DuplicateDirectory>,
+    /// If set, the total size exceeded the threshold configured by the user.
+    pub threshold_exceeded: bool,
+    /// If set, the walk was aborted before it could finish.
+    pub interrupted: bool,
+    /// If set, the walk was stopped as it took longer than it was given.
+    pub timed_out: bool,
+}
+
+/// The exit codes we promise to scripts, from least to most important.
+///
+/// If multiple conditions apply, the most important one is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// Everything could be read
+    Success = 0,
+    /// Some entries could not be read, which usually means sizes are lower than they should be
+    PartialErrors = 1,
+    /// The total size exceeded the configured threshold
+    ThresholdExceeded = 3,
+    /// The walk was stopped as it took longer than the configured timeout, the code used by `timeout`
+    TimedOut = 124,
+    /// The walk was aborted by the user, the conventional code for SIGINT
+    Interrupted = 130,
 }
 
 impl WalkResult {
-    pub fn to_exit_code(&self) -> i32 {
-        if self.num_errors > 0 {
-            1
+    pub fn exit_code(&self) -> ExitCode {
+        if self.interrupted {
+            ExitCode::Interrupted
+        } else if self.timed_out {
+            ExitCode::TimedOut
+        } else if self.threshold_exceeded {
+            ExitCode::ThresholdExceeded
+        } else if self.num_errors > 0 {
+            ExitCode::PartialErrors
         } else {
-            0
+            ExitCode::Success
         }
     }
+
+    pub fn to_exit_code(&self) -> i32 {
+        self.exit_code() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn byte_formats_cycle_back_to_metric() {
+        let mut format = ByteFormat::MiB;
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            format.cycle();
+            seen.push(format);
+        }
+        use ByteFormat::*;
+        assert_eq!(seen, vec![Metric, Binary, Bytes, GB, Metric]);
+    }
+
+    #[test]
+    fn paths_are_compared_regardless_of_their_normalization_form() {
+        let (composed, decomposed) = (
+            Path::new("caf\u{e9}/r\u{e9}sum\u{e9}"),
+            Path::new("cafe\u{301}/re\u{301}sume\u{301}"),
+        );
+        assert_ne!(composed, decomposed);
+        assert!(same_path(composed, decomposed));
+        assert_eq!(normalized(decomposed), composed);
+        assert!(matches!(normalized(composed), Cow::Borrowed(_)));
+        assert!(!same_path(composed, Path::new("cafe/resume")));
+    }
+
+    #[test]
+    fn pause_blocks_waiters_until_resumed() {
+        let pause = Pause::default();
+        pause.set(true);
+        assert!(pause.is_paused());
+        let waiter = thread::spawn({
+            let pause = pause.clone();
+            move || pause.wait_while_paused()
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished(), "the waiter is blocked");
+        pause.set(false);
+        waiter.join().expect("waiter not to panic");
+        assert!(!pause.is_paused());
+    }
 }
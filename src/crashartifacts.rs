@@ -0,0 +1,268 @@
+use crate::{ByteFormat, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Files left behind by crashing or profiled programs, which tend to be large and are rarely cleaned up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CrashArtifact {
+    CoreDump,
+    HeapProfile,
+    Minidump,
+}
+
+impl CrashArtifact {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrashArtifact::CoreDump => "core dump",
+            CrashArtifact::HeapProfile => "heap profile",
+            CrashArtifact::Minidump => "crash dump",
+        }
+    }
+
+    /// The kind of artifact a file with the given `name` might be, judging by naming conventions only.
+    fn candidate_by_name(name: &str) -> Option<Self> {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        let is_core = name == "core"
+            || name
+                .strip_prefix("core.")
+                .is_some_and(|pid| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()))
+            || (name.len() > ".core".len() && extension.as_deref() == Some("core"));
+        if is_core {
+            return Some(CrashArtifact::CoreDump);
+        }
+        match extension.as_deref() {
+            Some("hprof" | "heapsnapshot") => return Some(CrashArtifact::HeapProfile),
+            Some("dmp" | "mdmp") => return Some(CrashArtifact::Minidump),
+            _ => {}
+        }
+        (name.starts_with("heaptrack.") || name.starts_with("massif.out."))
+            .then_some(CrashArtifact::HeapProfile)
+    }
+
+    /// Return false if `header`, the first bytes of a file, contradicts the file being this kind of artifact.
+    fn matches_header(&self, name: &str, header: &[u8]) -> bool {
+        match self {
+            CrashArtifact::CoreDump => is_core_file_header(header),
+            CrashArtifact::Minidump => {
+                header.starts_with(b"MDMP")
+                    || header.starts_with(b"PAGEDU64")
+                    || header.starts_with(b"PAGEDUMP")
+            }
+            CrashArtifact::HeapProfile if name.ends_with(".hprof") => {
+                header.starts_with(b"JAVA PROFILE ")
+            }
+            CrashArtifact::HeapProfile if name.starts_with("massif.out.") => {
+                header.starts_with(b"desc:")
+            }
+            // heaptrack files are compressed, and heap snapshots are plain JSON
+            CrashArtifact::HeapProfile => true,
+        }
+    }
+}
+
+/// ELF files of type `ET_CORE`, or Mach-O files of type `MH_CORE`
+fn is_core_file_header(header: &[u8]) -> bool {
+    const ET_CORE: u16 = 4;
+    const MH_CORE: u32 = 4;
+    if header.len() >= 18 && header.starts_with(b"\x7fELF") {
+        let e_type = [header[16], header[17]];
+        let e_type = match header[5] {
+            2 => u16::from_be_bytes(e_type),
+            _ => u16::from_le_bytes(e_type),
+        };
+        return e_type == ET_CORE;
+    }
+    if header.len() >= 16 && header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        let file_type = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        return file_type == MH_CORE;
+    }
+    false
+}
+
+/// Determine if the file at `path` is a crash artifact by its name, confirmed by its first bytes if it can be read.
+///
+/// Only files named like an artifact are opened, which keeps this cheap enough to be used for every file.
+pub fn crash_artifact_of(path: &Path) -> Option<CrashArtifact> {
+    let name = path.file_name()?.to_string_lossy();
+    let artifact = CrashArtifact::candidate_by_name(&name)?;
+    let mut header = Vec::with_capacity(32);
+    match fs::File::open(path).and_then(|f| f.take(32).read_to_end(&mut header)) {
+        Ok(_) => artifact.matches_header(&name, &header).then_some(artifact),
+        Err(_) => Some(artifact),
+    }
+}
+
+/// Find all crash artifacts below `paths` and write them to `out`, grouped by kind along with their total size.
+///
+/// Files with multiple hard links are only listed at the first path they are seen at.
+pub fn summarize_crash_artifacts(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut artifacts: Vec<(CrashArtifact, u128, PathBuf)> = Vec::new();
+    let mut inodes = InodeFilter::default();
+    for path in paths {
+        let path = path.as_ref();
+        let sizes = FileSizes::new(&walk_options, path)?;
+        for entry in walk_options.iter_from_path(path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            let m = match &entry.client_state {
+                Some(Ok(m)) if m.is_file() && sizes.counts(m, &mut inodes) => m,
+                _ => continue,
+            };
+            let entry_path = entry.path();
+            if let Some(artifact) = crash_artifact_of(&entry_path) {
+                match sizes.size_of(&entry, m) {
+                    Ok(size) => artifacts.push((artifact, size as u128, entry_path)),
+                    Err(_) => res.num_errors += 1,
+                }
+            }
+        }
+    }
+
+    artifacts.sort_by(|(lk, ls, lp), (rk, rs, rp)| lk.cmp(rk).then(rs.cmp(ls)).then(lp.cmp(rp)));
+    let format = walk_options.byte_format;
+    let mut total = 0;
+    for (kind, group) in &itertools::Itertools::group_by(artifacts.iter(), |(kind, _, _)| *kind) {
+        let group: Vec<_> = group.collect();
+        let group_size: u128 = group.iter().map(|(_, size, _)| size).sum();
+        writeln!(
+            out,
+            "{}s: {} in {} file{}",
+            capitalized(kind.name()),
+            format.display(group_size).to_string().trim(),
+            group.len(),
+            if group.len() == 1 { "" } else { "s" }
+        )?;
+        for (_, size, path) in group {
+            write_size_and_path(&mut out, format, *size, path)?;
+        }
+        writeln!(out)?;
+        total += group_size;
+    }
+    writeln!(
+        out,
+        "{} in {} crash artifact{}",
+        format.display(total).to_string().trim(),
+        artifacts.len(),
+        if artifacts.len() == 1 { "" } else { "s" }
+    )?;
+    Ok(res)
+}
+
+fn write_size_and_path(
+    out: &mut impl io::Write,
+    format: ByteFormat,
+    size: u128,
+    path: &Path,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>byte_column_width$} {}",
+        format.display(size).to_string(),
+        path.display(),
+        byte_column_width = format.width()
+    )
+}
+
+fn capitalized(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+
+    #[test]
+    fn candidates_are_found_by_name() {
+        for (name, expected) in [
+            ("core", Some(CrashArtifact::CoreDump)),
+            ("core.1234", Some(CrashArtifact::CoreDump)),
+            ("firefox.core", Some(CrashArtifact::CoreDump)),
+            ("java_pid42.hprof", Some(CrashArtifact::HeapProfile)),
+            ("heaptrack.app.42.zst", Some(CrashArtifact::HeapProfile)),
+            ("massif.out.42", Some(CrashArtifact::HeapProfile)),
+            ("Heap.heapsnapshot", Some(CrashArtifact::HeapProfile)),
+            ("app.DMP", Some(CrashArtifact::Minidump)),
+            ("core.rs", None),
+            (".core", None),
+            ("score", None),
+            ("dump.txt", None),
+        ] {
+            assert_eq!(CrashArtifact::candidate_by_name(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn headers_confirm_the_kind_of_artifact() {
+        let elf = |e_type: u8| {
+            let mut header = b"\x7fELF\x02\x01\x01".to_vec();
+            header.resize(16, 0);
+            header.extend([e_type, 0]);
+            header
+        };
+        let core = CrashArtifact::CoreDump;
+        assert!(core.matches_header("core", &elf(4)));
+        assert!(!core.matches_header("core", &elf(2)), "an executable");
+        assert!(!core.matches_header("core", b"// core module"));
+        assert!(core.matches_header(
+            "core",
+            &[0xcf, 0xfa, 0xed, 0xfe, 7, 0, 0, 1, 3, 0, 0, 0, 4, 0, 0, 0]
+        ));
+
+        let minidump = CrashArtifact::Minidump;
+        assert!(minidump.matches_header("a.dmp", b"MDMP\x93\xa7"));
+        assert!(!minidump.matches_header("a.dmp", b"PK\x03\x04"));
+
+        let heap = CrashArtifact::HeapProfile;
+        assert!(heap.matches_header("a.hprof", b"JAVA PROFILE 1.0.2\0"));
+        assert!(!heap.matches_header("a.hprof", b"{}"));
+        assert!(heap.matches_header("massif.out.1", b"desc: (none)\n"));
+        assert!(heap.matches_header("a.heapsnapshot", b"{\"snapshot\""));
+    }
+
+    #[test]
+    fn artifacts_are_summarized_by_kind() -> Result<()> {
+        let dir = TempTree::new("crash-artifacts");
+        fs::write(dir.join("app.dmp"), b"MDMP and more")?;
+        fs::write(dir.join("core"), b"not a core dump")?;
+        fs::write(dir.join("massif.out.7"), b"desc: --time-unit=B\n")?;
+        let mut out = Vec::new();
+        let res = summarize_crash_artifacts(&mut out, WalkOptions::for_tests(), Some(&dir));
+        assert_eq!(res?.num_errors, 0);
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "Heap profiles: 20 b in 1 file\n{:>w$} {}\n\nCrash dumps: 13 b in 1 file\n{:>w$} {}\n\n33 b in 2 crash artifacts\n",
+                "20 b",
+                dir.join("massif.out.7").display(),
+                "13 b",
+                dir.join("app.dmp").display(),
+                w = ByteFormat::Bytes.width()
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+/// How much space the device holding a path has, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceSpace {
+    pub used: u64,
+    pub available: u64,
+}
+
+impl DeviceSpace {
+    /// Query the space of the device `path` is on, or `None` if it can't be determined.
+    ///
+    /// Like `df`, space reserved for privileged users counts neither as used nor as available.
+    pub fn of(path: &Path) -> Option<Self> {
+        let stats = fs2::statvfs(path).ok()?;
+        Some(DeviceSpace {
+            used: stats.total_space().saturating_sub(stats.free_space()),
+            available: stats.available_space(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_space_of_existing_paths_is_known() {
+        let space = DeviceSpace::of(&std::env::temp_dir()).expect("the temporary directory exists");
+        assert!(space.used > 0 || space.available > 0);
+        assert_eq!(DeviceSpace::of(Path::new("/does/not/exist")), None);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{
+    load_snapshot, normalized,
+    traverse::{Traversal, TreeIndex},
+    ByteFormat, WalkResult,
+};
+use anyhow::Result;
+use colored::Colorize;
+use petgraph::Direction;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// How the size of a directory changed between two traversals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeChange {
+    pub path: PathBuf,
+    /// The size in the earlier traversal, or 0 if it didn't exist back then
+    pub before: u128,
+    /// The size in the later traversal, or 0 if it doesn't exist anymore
+    pub after: u128,
+}
+
+impl SizeChange {
+    /// The amount of bytes the directory grew by, negative if it shrank.
+    pub fn growth(&self) -> i128 {
+        self.after as i128 - self.before as i128
+    }
+}
+
+/// The sizes all entries of a traversal had in an earlier one, to show how they changed.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    before: Traversal,
+    /// The size and alternate size of each entry in the later traversal that also exists in the earlier one
+    sizes: HashMap<TreeIndex, (u128, u128)>,
+}
+
+impl Baseline {
+    /// Find the sizes in `before` of all entries of `after`, matching them by path.
+    pub fn new(before: Traversal, after: &Traversal) -> Self {
+        let mut baseline = Baseline {
+            before,
+            sizes: HashMap::new(),
+        };
+        baseline.update(after);
+        baseline
+    }
+
+    /// Match the entries of `after` again, after entries were added to or replaced in it.
+    pub fn update(&mut self, after: &Traversal) {
+        let (before, sizes) = (&self.before, &mut self.sizes);
+        sizes.clear();
+        for_each_pair(before, after, |_, before_idx, after_idx, _| {
+            if let (Some(before_idx), Some(after_idx)) = (before_idx, after_idx) {
+                let entry = &before.tree[before_idx];
+                sizes.insert(after_idx, (entry.size, entry.alternate_size));
+            }
+        });
+    }
+
+    /// The earlier size of the entry at `index`, or `None` if it didn't exist back then.
+    pub fn size_of(&self, index: TreeIndex) -> Option<u128> {
+        self.sizes.get(&index).map(|(size, _)| *size)
+    }
+
+    /// Exchange sizes and alternate sizes, along with [`Traversal::swap_size_model()`].
+    pub fn swap_size_model(&mut self) {
+        self.before.swap_size_model();
+        for (size, alternate_size) in self.sizes.values_mut() {
+            std::mem::swap(size, alternate_size);
+        }
+    }
+}
+
+/// All directories whose size differs between `before` and `after`, with the largest change last.
+pub fn size_changes(before: &Traversal, after: &Traversal) -> Vec<SizeChange> {
+    let size_of = |t: &Traversal, idx: Option<TreeIndex>| idx.map_or(0, |idx| t.tree[idx].size);
+    let mut changes = Vec::new();
+    for_each_pair(before, after, |path, before_idx, after_idx, is_dir| {
+        let change = SizeChange {
+            path: path.to_owned(),
+            before: size_of(before, before_idx),
+            after: size_of(after, after_idx),
+        };
+        if is_dir && change.before != change.after {
+            changes.push(change);
+        }
+    });
+    changes.sort_by_key(|change| change.growth().unsigned_abs());
+    changes
+}
+
+/// Format the change from `before` to `after` bytes, like `+1.20 MB` or `-512 b`.
+pub fn display_growth(byte_format: ByteFormat, before: u128, after: u128) -> String {
+    let (sign, bytes) = match after.checked_sub(before) {
+        Some(0) => ('±', 0),
+        Some(grown) => ('+', grown),
+        None => ('-', before - after),
+    };
+    format!(
+        "{}{}",
+        sign,
+        byte_format.display(bytes).to_string().trim_start()
+    )
+}
+
+/// Load the snapshots at `before` and `after` and write all directories that changed in size to `out`,
+/// along with the total change across all inputs.
+pub fn diff_snapshots(
+    mut out: impl io::Write,
+    byte_format: ByteFormat,
+    apparent_size: bool,
+    before: &Path,
+    after: &Path,
+) -> Result<WalkResult> {
+    let (before, after) = (
+        load_snapshot(before, apparent_size)?,
+        load_snapshot(after, apparent_size)?,
+    );
+    let changes = size_changes(&before, &after);
+    let width = byte_format.width() + 1;
+    if changes.is_empty() {
+        writeln!(out, "No directory changed in size")?;
+    }
+    for change in &changes {
+        let growth = display_growth(byte_format, change.before, change.after);
+        let growth = match change.growth() > 0 {
+            true => growth.red(),
+            false => growth.green(),
+        };
+        writeln!(
+            out,
+            "{:>width$} {:>size_width$} {}",
+            growth,
+            byte_format.display(change.after).to_string(),
+            change.path.display().to_string().cyan(),
+            width = width,
+            size_width = byte_format.width()
+        )?;
+    }
+    let (total_before, total_after) = (
+        before.tree[before.root_index].size,
+        after.tree[after.root_index].size,
+    );
+    writeln!(
+        out,
+        "{:>width$} {:>size_width$} total",
+        display_growth(byte_format, total_before, total_after),
+        byte_format.display(total_after).to_string(),
+        width = width,
+        size_width = byte_format.width()
+    )?;
+    Ok(WalkResult::default())
+}
+
+/// Call `visit` with the path of each entry in `before` or `after`, its index in both of them
+/// and whether it is a directory with entries in either of them.
+///
+/// Inputs are matched by their absolute path, all other entries by their name within the matched parent directory,
+/// both regardless of their Unicode normalization form to match scans of the same files made on different platforms.
+fn for_each_pair(
+    before: &Traversal,
+    after: &Traversal,
+    mut visit: impl FnMut(&Path, Option<TreeIndex>, Option<TreeIndex>, bool),
+) {
+    let children = |t: &Traversal, idx: Option<TreeIndex>| -> BTreeMap<PathBuf, TreeIndex> {
+        idx.into_iter()
+            .flat_map(|idx| t.tree.neighbors_directed(idx, Direction::Outgoing))
+            .map(|child| {
+                let name = &t.tree[child].name;
+                let name = match idx == Some(t.root_index) {
+                    true => name.canonicalize().unwrap_or_else(|_| name.clone()),
+                    false => name.clone(),
+                };
+                (normalized(&name).into_owned(), child)
+            })
+            .collect()
+    };
+    let mut stack = vec![(
+        PathBuf::new(),
+        Some(before.root_index),
+        Some(after.root_index),
+    )];
+    while let Some((path, before_idx, after_idx)) = stack.pop() {
+        let mut before_children = children(before, before_idx);
+        let after_children = children(after, after_idx);
+        if !path.as_os_str().is_empty() {
+            let is_dir = !before_children.is_empty() || !after_children.is_empty();
+            visit(&path, before_idx, after_idx, is_dir);
+        }
+        for (name, after_child) in after_children {
+            let before_child = before_children.remove(&name);
+            stack.push((path.join(name), before_child, Some(after_child)));
+        }
+        for (name, before_child) in before_children {
+            stack.push((path.join(name), Some(before_child), None));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::EntryData;
+
+    fn traversal(entries: &[(&str, u128)]) -> Traversal {
+        let mut t = Traversal::default();
+        t.root_index = t.tree.add_node(EntryData::default());
+        let mut indices = HashMap::<PathBuf, TreeIndex>::new();
+        for (path, size) in entries {
+            let path = Path::new(path);
+            let parent = path
+                .parent()
+                .and_then(|parent| indices.get(parent).copied())
+                .unwrap_or(t.root_index);
+            let name = match parent == t.root_index {
+                true => path.to_owned(),
+                false => path.file_name().expect("name").into(),
+            };
+            let idx = t.tree.add_node(EntryData {
+                name,
+                size: *size,
+                ..Default::default()
+            });
+            t.tree.add_edge(parent, idx, ());
+            indices.insert(path.to_owned(), idx);
+        }
+        t
+    }
+
+    #[test]
+    fn directories_are_compared_by_path() {
+        let before = traversal(&[
+            ("/data", 300),
+            ("/data/logs", 200),
+            ("/data/logs/a.log", 200),
+            ("/data/old", 100),
+            ("/data/old/x", 100),
+        ]);
+        let after = traversal(&[
+            ("/data", 1200),
+            ("/data/logs", 1000),
+            ("/data/logs/a.log", 1000),
+            ("/data/new", 200),
+            ("/data/new/y", 200),
+        ]);
+        let change = |path: &str, before, after| SizeChange {
+            path: path.into(),
+            before,
+            after,
+        };
+        assert_eq!(
+            size_changes(&before, &after),
+            vec![
+                change("/data/old", 100, 0),
+                change("/data/new", 0, 200),
+                change("/data/logs", 200, 1000),
+                change("/data", 300, 1200),
+            ],
+            "files aren't listed, and the largest change comes last"
+        );
+
+        let baseline = Baseline::new(before, &after);
+        let size_before = |name: &str| {
+            let idx = after
+                .tree
+                .node_indices()
+                .find(|idx| after.tree[*idx].name == Path::new(name))
+                .expect("present");
+            baseline.size_of(idx)
+        };
+        assert_eq!(size_before("/data"), Some(300));
+        assert_eq!(size_before("a.log"), Some(200));
+        assert_eq!(size_before("new"), None);
+    }
+
+    #[test]
+    fn names_match_regardless_of_their_normalization_form() {
+        let before = traversal(&[("/photos", 10), ("/photos/cafe\u{301}", 10)]);
+        let after = traversal(&[("/photos", 30), ("/photos/caf\u{e9}", 30)]);
+        assert_eq!(
+            size_changes(&before, &after),
+            vec![SizeChange {
+                path: "/photos".into(),
+                before: 10,
+                after: 30
+            }]
+        );
+        assert_eq!(Baseline::new(before, &after).sizes.len(), 2);
+    }
+
+    #[test]
+    fn growth_is_signed() {
+        assert_eq!(display_growth(ByteFormat::Bytes, 10, 1034), "+1024 b");
+        assert_eq!(display_growth(ByteFormat::Metric, 3000, 1000), "-2.00 KB");
+        assert_eq!(display_growth(ByteFormat::Metric, 5, 5), "±0  B");
+    }
+}
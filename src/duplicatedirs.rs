@@ -0,0 +1,140 @@
+use crate::{inodefilter::dev_inode_of, DirEntry};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A directory reachable at more than one path, like through bind mounts, whose content was only counted
+/// at the first of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDirectory {
+    /// The path at which the directory was seen again, and not descended into
+    pub path: PathBuf,
+    /// The path at which the directory was seen first, and counted
+    pub original: PathBuf,
+}
+
+/// The amount of independently locked parts the directories seen first are spread over, so threads rarely wait
+/// for each other
+const SHARDS: usize = 64;
+
+/// The path at which each directory was seen first, by device and inode
+type FirstSeen = HashMap<(u64, u64), Box<Path>>;
+
+#[derive(Debug)]
+struct Seen {
+    /// The directories seen first, each in the shard of its device and inode
+    paths: Vec<Mutex<FirstSeen>>,
+    /// The original path of each directory that was seen again, by the path it was seen at
+    duplicates: Mutex<HashMap<PathBuf, PathBuf>>,
+}
+
+impl Default for Seen {
+    fn default() -> Self {
+        Seen {
+            paths: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            duplicates: Mutex::default(),
+        }
+    }
+}
+
+/// The directories seen during a walk, shared among the threads reading them, to descend into each of them only once
+#[derive(Default, Debug, Clone)]
+pub(crate) struct SeenDirectories(Arc<Seen>);
+
+impl SeenDirectories {
+    /// Remember the directory at `path` described by `metadata`, and return `false` if it was seen before at another path.
+    pub fn add(&self, path: PathBuf, metadata: &fs::Metadata) -> bool {
+        match dev_inode_of(metadata) {
+            Some(dev_inode) => self.add_dev_inode(path, dev_inode),
+            None => true,
+        }
+    }
+
+    fn add_dev_inode(&self, path: PathBuf, dev_inode: (u64, u64)) -> bool {
+        let (dev, inode) = dev_inode;
+        let shard = (inode ^ dev.rotate_left(32)) as usize % SHARDS;
+        let original = {
+            let mut paths = self.0.paths[shard]
+                .lock()
+                .expect("no panic while holding the lock");
+            match paths.get(&dev_inode) {
+                None => {
+                    paths.insert(dev_inode, path.into_boxed_path());
+                    return true;
+                }
+                Some(original) => original.to_path_buf(),
+            }
+        };
+        // overlapping inputs reach the same directory at the same path, which isn't a duplicate
+        if same_location(&original, &path) {
+            return true;
+        }
+        self.0
+            .duplicates
+            .lock()
+            .expect("no panic while holding the lock")
+            .insert(path, original);
+        false
+    }
+
+    /// Return how `entry` duplicates another directory if it wasn't descended into for that reason.
+    pub fn duplicate_of(&self, entry: &DirEntry) -> Option<DuplicateDirectory> {
+        if !(entry.file_type.is_dir() && entry.read_children_path.is_none()) {
+            return None;
+        }
+        self.take_duplicate(&entry.path())
+    }
+
+    fn take_duplicate(&self, path: &Path) -> Option<DuplicateDirectory> {
+        self.0
+            .duplicates
+            .lock()
+            .expect("no panic while holding the lock")
+            .remove(path)
+            .map(|original| DuplicateDirectory {
+                path: path.to_owned(),
+                original,
+            })
+    }
+}
+
+fn same_location(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_seen_again_at_another_path_are_duplicates() {
+        let seen = SeenDirectories::default();
+        assert!(seen.add_dev_inode("/data/a".into(), (1, 10)));
+        assert!(seen.add_dev_inode("/data/b".into(), (1, 11)));
+        assert!(
+            seen.add_dev_inode("/data/a".into(), (1, 10)),
+            "overlapping inputs see the same path again"
+        );
+        assert!(!seen.add_dev_inode("/mnt/a".into(), (1, 10)));
+        assert!(
+            seen.add_dev_inode("/mnt/c".into(), (2, 10)),
+            "inodes are only unique per device"
+        );
+
+        assert_eq!(seen.take_duplicate(Path::new("/data/b")), None);
+        assert_eq!(
+            seen.take_duplicate(Path::new("/mnt/a")),
+            Some(DuplicateDirectory {
+                path: "/mnt/a".into(),
+                original: "/data/a".into()
+            })
+        );
+        assert_eq!(seen.take_duplicate(Path::new("/mnt/a")), None);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{crash_artifact_of, EntriesUntil, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    ffi::OsStr,
+    fs::Metadata,
+    io,
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The amount of entries to show per kind of target
+const MAX_TARGETS: usize = 10;
+/// Logs which haven't been written to for this long are considered old
+const OLD_LOG_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Names of directories holding data that is expected to be recreated if deleted
+const CACHE_DIRECTORIES: &[&str] = &[
+    ".cache",
+    "Caches",
+    "cache",
+    "__pycache__",
+    ".gradle",
+    ".npm",
+];
+/// Where caches, crash artifacts and logs are usually kept within an input or a home directory, to be looked at
+/// before everything else
+const PROBED_LOCATIONS: &[&str] = &[
+    ".cache",
+    "Library/Caches",
+    "Library/Logs",
+    "AppData/Local/Temp",
+    "AppData/Local/CrashDumps",
+    "var/cache",
+    "var/crash",
+    "var/lib/systemd/coredump",
+    "var/log",
+];
+/// The directories within an input holding home directories, whose probed locations are looked at as well
+const HOME_DIRECTORIES: &[&str] = &["home", "Users"];
+/// The share of the time budget to spend on the probed locations at most, the rest is for the inputs
+const PROBE_SHARE: f64 = 0.5;
+
+/// The kind of entries which are likely to free a lot of space if deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Target {
+    LargestFiles,
+    Caches,
+    CrashArtifacts,
+    OldLogs,
+}
+
+impl Target {
+    fn title(&self) -> &'static str {
+        match self {
+            Target::LargestFiles => "Largest files",
+            Target::Caches => "Cache directories",
+            Target::CrashArtifacts => "Core dumps, heap profiles and crash dumps",
+            Target::OldLogs => "Logs unchanged for a week or more",
+        }
+    }
+}
+
+/// Find entries below `paths` which are likely to free a lot of space when deleted, but spend no more than
+/// `time_budget` on the traversal. This is meant for full disks on which a complete traversal would take too long.
+///
+/// The locations which usually hold caches, crash artifacts and logs are looked at first, then the inputs
+/// themselves. Each of them gets an equal share of the time that is left, so a large one doesn't keep the others
+/// from being looked at, and the time is kept even if reading a directory doesn't return. Whatever was found until then is written to `out`, with sizes being lower bounds if a
+/// traversal didn't finish.
+pub fn emergency(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    time_budget: Duration,
+) -> Result<WalkResult> {
+    let start = Instant::now();
+    let now = SystemTime::now();
+    let mut res = WalkResult::default();
+    let mut sizes: BTreeMap<Target, BTreeMap<PathBuf, u128>> = BTreeMap::new();
+    let mut largest = BinaryHeap::<Reverse<(u128, PathBuf)>>::new();
+    let mut inodes = InodeFilter::default();
+    let mut num_entries = 0u64;
+    let mut finished = true;
+
+    let paths: Vec<_> = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+    let probed: Vec<_> = paths
+        .iter()
+        .flat_map(|path| probed_locations_within(path))
+        .collect();
+    let probe_budget = time_budget.mul_f64(PROBE_SHARE);
+    let walks = probed
+        .iter()
+        .map(|path| (path, true))
+        .chain(paths.iter().map(|path| (path, false)));
+    let num_probes = probed.len();
+    for (walk_index, (path, is_probe)) in walks.enumerate() {
+        let (budget, walks_left) = match is_probe {
+            true => (probe_budget, num_probes - walk_index),
+            false => (time_budget, num_probes + paths.len() - walk_index),
+        };
+        let share = budget.saturating_sub(start.elapsed()) / walks_left as u32;
+        let file_sizes = FileSizes::new(&walk_options, path)?;
+        let mut entries = EntriesUntil::new(
+            walk_options.iter_from_path(path),
+            Some(Instant::now() + share),
+        );
+        for entry in entries.by_ref() {
+            num_entries += 1;
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            let m = match &entry.client_state {
+                Some(Ok(m)) if file_sizes.counts(m, &mut inodes) => m,
+                Some(Err(_)) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            let entry_path = entry.path();
+            // what the probes saw is seen again when traversing the inputs
+            if !is_probe && probed.iter().any(|probed| entry_path.starts_with(probed)) {
+                continue;
+            }
+            let size = match file_sizes.size_of(&entry, m) {
+                Ok(size) => size as u128,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            for (target, path) in targets_of(&entry_path, m, now) {
+                *sizes.entry(target).or_default().entry(path).or_default() += size;
+            }
+            if largest.len() < MAX_TARGETS
+                || largest
+                    .peek()
+                    .is_some_and(|Reverse((smallest, _))| size > *smallest)
+            {
+                largest.push(Reverse((size, entry_path)));
+                if largest.len() > MAX_TARGETS {
+                    largest.pop();
+                }
+            }
+        }
+        finished &= !entries.timed_out();
+    }
+    if !largest.is_empty() {
+        sizes.insert(
+            Target::LargestFiles,
+            largest
+                .into_iter()
+                .map(|Reverse((size, path))| (path, size))
+                .collect(),
+        );
+    }
+
+    for (target, sizes) in sizes {
+        writeln!(out, "{}:", target.title())?;
+        let mut sizes: Vec<_> = sizes.into_iter().collect();
+        sizes.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        for (path, size) in sizes.into_iter().take(MAX_TARGETS) {
+            writeln!(
+                out,
+                "{:>byte_column_width$} {}",
+                walk_options.byte_format.display(size).to_string(),
+                path.display(),
+                byte_column_width = walk_options.byte_format.width()
+            )?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(
+        out,
+        "Looked at {} entries in {:.1}s{}",
+        num_entries,
+        start.elapsed().as_secs_f32(),
+        if finished {
+            ""
+        } else {
+            " and stopped early, sizes are lower bounds"
+        }
+    )?;
+    Ok(res)
+}
+
+/// The [`PROBED_LOCATIONS`] that exist below `input` and the home directories within it.
+fn probed_locations_within(input: &Path) -> Vec<PathBuf> {
+    let homes = HOME_DIRECTORIES
+        .iter()
+        .filter_map(|homes| std::fs::read_dir(input.join(homes)).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()));
+    std::iter::once(input.to_owned())
+        .chain(homes)
+        .flat_map(|base| {
+            PROBED_LOCATIONS
+                .iter()
+                .map(move |location| base.join(location))
+        })
+        .filter(|location| {
+            location
+                .symlink_metadata()
+                .is_ok_and(|m| m.file_type().is_dir())
+        })
+        .collect()
+}
+
+/// Return all kinds of targets the file at `path` belongs to, along with the path to report the target with.
+fn targets_of(path: &Path, meta: &Metadata, now: SystemTime) -> Vec<(Target, PathBuf)> {
+    let mut targets = Vec::new();
+    if let Some(cache) = cache_directory_of(path) {
+        targets.push((Target::Caches, cache));
+    }
+    if crash_artifact_of(path).is_some() {
+        targets.push((Target::CrashArtifacts, path.to_owned()));
+    }
+    let is_old = meta
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age >= OLD_LOG_AGE);
+    if is_old && is_log(path) {
+        targets.push((Target::OldLogs, path.to_owned()));
+    }
+    targets
+}
+
+/// The outermost cache directory `path` is contained in, if any.
+fn cache_directory_of(path: &Path) -> Option<PathBuf> {
+    let mut cache = PathBuf::new();
+    let parent = path.parent()?;
+    for component in parent.components() {
+        cache.push(component);
+        if let Component::Normal(name) = component {
+            if CACHE_DIRECTORIES.iter().any(|c| OsStr::new(c) == name) {
+                return Some(cache);
+            }
+        }
+    }
+    None
+}
+
+/// Logs end with `.log`, maybe followed by a rotation number and a compression extension, or are located in
+/// a `log` or `logs` directory and have no other extensions than these.
+fn is_log(path: &Path) -> bool {
+    let in_log_directory = path
+        .parent()
+        .and_then(Path::file_name)
+        .is_some_and(|dir| dir == "log" || dir == "logs");
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut extensions = name.split('.').skip(1);
+    let is_rotation_or_compression = |ext: &str| {
+        ext.bytes().all(|b| b.is_ascii_digit()) || ["gz", "xz", "zst", "bz2", "old"].contains(&ext)
+    };
+    match in_log_directory {
+        true => extensions.all(|ext| ext == "log" || is_rotation_or_compression(ext)),
+        false => extensions.any(|ext| ext.eq_ignore_ascii_case("log")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+
+    #[test]
+    fn cache_directories_are_the_outermost_ones() {
+        assert_eq!(
+            cache_directory_of(Path::new("/home/a/.cache/pip/cache/file")),
+            Some(PathBuf::from("/home/a/.cache"))
+        );
+        assert_eq!(
+            cache_directory_of(Path::new("src/__pycache__/mod.pyc")),
+            Some(PathBuf::from("src/__pycache__"))
+        );
+        assert_eq!(
+            cache_directory_of(Path::new("/home/a/.cache")),
+            None,
+            "a file named like a cache"
+        );
+        assert_eq!(cache_directory_of(Path::new("/home/a/cached/file")), None);
+    }
+
+    #[test]
+    fn logs_are_detected_by_name_or_directory() {
+        for path in [
+            "a/syslog.log",
+            "a/app.log.1",
+            "a/app.log.2.gz",
+            "/var/log/syslog",
+            "/var/log/syslog.2.gz",
+            "a/logs/app",
+        ] {
+            assert!(is_log(Path::new(path)), "{}", path);
+        }
+        for path in ["a/catalog", "a/login.txt", "a/log", "a/logs/read.py"] {
+            assert!(!is_log(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn probed_locations_are_the_existing_ones_within_inputs_and_home_directories() -> io::Result<()>
+    {
+        let root = TempTree::new("emergency-probes");
+        std::fs::create_dir_all(root.join("var/log"))?;
+        std::fs::create_dir_all(root.join("home/a/.cache"))?;
+        std::fs::create_dir_all(root.join("home/b/Library"))?;
+        std::fs::write(root.join("home/b/Library/Caches"), b"not a directory")?;
+        let mut probed = probed_locations_within(&root);
+
+        probed.sort();
+        assert_eq!(
+            probed,
+            vec![root.join("home/a/.cache"), root.join("var/log")]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{json_escape, ByteFormat, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{collections::BTreeMap, io, path::Path};
+
+/// How many files there are of a kind, and how much space they take
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+struct Usage {
+    files: u64,
+    bytes: u128,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: u128) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// The extension `name` is grouped by, lowercased so `JPG` and `jpg` count as the same.
+/// Names without one, including hidden files like `.bashrc`, have none.
+fn extension_of(name: &Path) -> Option<String> {
+    name.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+/// Write the amount of files below `paths` and their total size to `out`. If `by_extension` is set, do so for each
+/// file extension with the largest last, followed by the total. If `json` is set, write it as JSON instead, with
+/// a `null` extension for files without one.
+///
+/// Files with multiple hard links are only counted at the first path they are seen at.
+pub fn file_statistics(
+    out: impl io::Write,
+    walk_options: WalkOptions,
+    by_extension: bool,
+    json: bool,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut usage = BTreeMap::<Option<String>, Usage>::new();
+    let mut inodes = InodeFilter::default();
+    for path in paths {
+        let path = path.as_ref();
+        let sizes = FileSizes::new(&walk_options, path)?;
+        for entry in walk_options.iter_from_path(path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            let bytes = match &entry.client_state {
+                Some(Ok(m)) if sizes.counts(m, &mut inodes) => match sizes.size_of(&entry, m) {
+                    Ok(bytes) => bytes as u128,
+                    Err(_) => {
+                        res.num_errors += 1;
+                        continue;
+                    }
+                },
+                Some(Err(_)) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            let extension = by_extension
+                .then(|| extension_of(Path::new(&entry.file_name)))
+                .flatten();
+            usage.entry(extension).or_default().add(bytes);
+        }
+    }
+    let mut usage: Vec<_> = usage.into_iter().collect();
+    usage.sort_by(|(le, l), (re, r)| l.bytes.cmp(&r.bytes).then(le.cmp(re)));
+    match json {
+        true => write_json(out, by_extension, &usage)?,
+        false => write_table(out, walk_options.byte_format, by_extension, &usage)?,
+    }
+    Ok(res)
+}
+
+fn write_table(
+    mut out: impl io::Write,
+    format: ByteFormat,
+    by_extension: bool,
+    usage: &[(Option<String>, Usage)],
+) -> io::Result<()> {
+    let total = total_of(usage);
+    if by_extension {
+        let files_width = total.files.to_string().len();
+        for (extension, usage) in usage {
+            writeln!(
+                out,
+                "{:>w$} {:>fw$} file{} {}",
+                format.display(usage.bytes).to_string(),
+                usage.files,
+                if usage.files == 1 { " " } else { "s" },
+                extension.as_deref().unwrap_or("(no extension)"),
+                w = format.width(),
+                fw = files_width
+            )?;
+        }
+    }
+    write!(
+        out,
+        "{} in {} file{}",
+        format.display(total.bytes).to_string().trim(),
+        total.files,
+        plural(total.files)
+    )?;
+    let extensions = usage.iter().filter(|(e, _)| e.is_some()).count();
+    match by_extension {
+        true => writeln!(
+            out,
+            " with {} extension{}",
+            extensions,
+            plural(extensions as u64)
+        ),
+        false => writeln!(out),
+    }
+}
+
+fn write_json(
+    mut out: impl io::Write,
+    by_extension: bool,
+    usage: &[(Option<String>, Usage)],
+) -> io::Result<()> {
+    let total = total_of(usage);
+    write!(out, "{{")?;
+    if by_extension {
+        write!(out, "\n  \"extensions\": [")?;
+        for (idx, (extension, usage)) in usage.iter().enumerate() {
+            write!(
+                out,
+                "{}\n    {{\"extension\": {}, \"files\": {}, \"bytes\": {}}}",
+                if idx == 0 { "" } else { "," },
+                match extension {
+                    Some(extension) => format!("\"{}\"", json_escape(extension)),
+                    None => "null".into(),
+                },
+                usage.files,
+                usage.bytes
+            )?;
+        }
+        write!(out, "\n  ],")?;
+    }
+    writeln!(
+        out,
+        "\n  \"total\": {{\"files\": {}, \"bytes\": {}}}\n}}",
+        total.files, total.bytes
+    )
+}
+
+fn total_of(usage: &[(Option<String>, Usage)]) -> Usage {
+    usage
+        .iter()
+        .fold(Usage::default(), |total, (_, usage)| Usage {
+            files: total.files + usage.files,
+            bytes: total.bytes + usage.bytes,
+        })
+}
+
+fn plural(n: u64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+
+    #[test]
+    fn files_are_counted_by_their_case_insensitive_extension() -> Result<()> {
+        let root = TempTree::new("file-statistics");
+        root.file("photos/a.jpg", vec![0; 100])?;
+        root.file("photos/b.JPG", vec![0; 50])?;
+        root.file("movie.mp4", vec![0; 1000])?;
+        root.file(".bashrc", vec![0; 5])?;
+        root.file("Makefile", vec![0; 5])?;
+        let stats = |by_extension, json| -> Result<String> {
+            let mut out = Vec::new();
+            file_statistics(
+                &mut out,
+                WalkOptions::for_tests(),
+                by_extension,
+                json,
+                Some(&root),
+            )?;
+            Ok(String::from_utf8(out)?)
+        };
+
+        let w = ByteFormat::Bytes.width();
+        assert_eq!(
+            stats(true, false)?,
+            format!(
+                "{:>w$} 2 files (no extension)\n{:>w$} 2 files jpg\n{:>w$} 1 file  mp4\n\
+                 1160 b in 5 files with 2 extensions\n",
+                "10 b",
+                "150 b",
+                "1000 b",
+                w = w
+            )
+        );
+        assert_eq!(
+            stats(true, true)?,
+            "{\n  \"extensions\": [\
+             \n    {\"extension\": null, \"files\": 2, \"bytes\": 10},\
+             \n    {\"extension\": \"jpg\", \"files\": 2, \"bytes\": 150},\
+             \n    {\"extension\": \"mp4\", \"files\": 1, \"bytes\": 1000}\
+             \n  ],\n  \"total\": {\"files\": 5, \"bytes\": 1160}\n}\n"
+        );
+        assert_eq!(
+            stats(false, true)?,
+            "{\n  \"total\": {\"files\": 5, \"bytes\": 1160}\n}\n"
+        );
+        Ok(())
+    }
+}
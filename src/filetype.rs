@@ -0,0 +1,157 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+/// The amount of bytes needed to tell all known file types apart
+const HEADER_LEN: usize = 512;
+
+/// The type of a file as determined by its content, which can differ from what its name suggests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+    Zip,
+    SevenZip,
+    Tar,
+    Pdf,
+    Png,
+    Jpeg,
+    Gif,
+    Mp4,
+    Matroska,
+    Sqlite,
+    Elf,
+    /// Anything that looks like UTF-8 encoded text
+    Text,
+}
+
+impl FileType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileType::Gzip => "gzip compressed data",
+            FileType::Zstd => "zstd compressed data",
+            FileType::Xz => "xz compressed data",
+            FileType::Bzip2 => "bzip2 compressed data",
+            FileType::Zip => "zip archive",
+            FileType::SevenZip => "7-zip archive",
+            FileType::Tar => "tar archive",
+            FileType::Pdf => "PDF document",
+            FileType::Png => "PNG image",
+            FileType::Jpeg => "JPEG image",
+            FileType::Gif => "GIF image",
+            FileType::Mp4 => "MP4 video or audio",
+            FileType::Matroska => "Matroska video",
+            FileType::Sqlite => "SQLite database",
+            FileType::Elf => "ELF binary",
+            FileType::Text => "text",
+        }
+    }
+
+    /// The type of file named `name` judging by its extension, if it is one that is reliably used for one type only.
+    pub fn expected_for(name: &str) -> Option<FileType> {
+        let (_, extension) = name.rsplit_once('.')?;
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "gz" | "tgz" => FileType::Gzip,
+            "zst" => FileType::Zstd,
+            "xz" | "txz" => FileType::Xz,
+            "bz2" | "tbz2" => FileType::Bzip2,
+            "zip" | "jar" | "apk" | "whl" | "docx" | "xlsx" | "pptx" | "odt" | "epub" => {
+                FileType::Zip
+            }
+            "7z" => FileType::SevenZip,
+            "tar" => FileType::Tar,
+            "pdf" => FileType::Pdf,
+            "png" => FileType::Png,
+            "jpg" | "jpeg" => FileType::Jpeg,
+            "gif" => FileType::Gif,
+            "mp4" | "m4a" | "m4v" | "mov" => FileType::Mp4,
+            "mkv" | "webm" => FileType::Matroska,
+            "sqlite" | "sqlite3" => FileType::Sqlite,
+            "so" | "ko" => FileType::Elf,
+            "txt" | "log" | "csv" | "json" | "md" | "xml" | "yaml" | "yml" | "toml" | "ini" => {
+                FileType::Text
+            }
+            _ => return None,
+        })
+    }
+
+    /// The type of a file starting with `header`, or `None` if it isn't known or `header` is empty.
+    pub fn detect(header: &[u8]) -> Option<FileType> {
+        const MAGIC: &[(&[u8], FileType)] = &[
+            (b"\x1f\x8b", FileType::Gzip),
+            (b"\x28\xb5\x2f\xfd", FileType::Zstd),
+            (b"\xfd7zXZ\0", FileType::Xz),
+            (b"BZh", FileType::Bzip2),
+            (b"PK\x03\x04", FileType::Zip),
+            (b"PK\x05\x06", FileType::Zip),
+            (b"7z\xbc\xaf\x27\x1c", FileType::SevenZip),
+            (b"%PDF-", FileType::Pdf),
+            (b"\x89PNG\r\n\x1a\n", FileType::Png),
+            (b"\xff\xd8\xff", FileType::Jpeg),
+            (b"GIF87a", FileType::Gif),
+            (b"GIF89a", FileType::Gif),
+            (b"\x1a\x45\xdf\xa3", FileType::Matroska),
+            (b"SQLite format 3\0", FileType::Sqlite),
+            (b"\x7fELF", FileType::Elf),
+        ];
+        if let Some((_, file_type)) = MAGIC.iter().find(|(magic, _)| header.starts_with(magic)) {
+            return Some(*file_type);
+        }
+        if header.get(4..8) == Some(&b"ftyp"[..]) {
+            return Some(FileType::Mp4);
+        }
+        if header.get(257..262) == Some(&b"ustar"[..]) {
+            return Some(FileType::Tar);
+        }
+        let is_text = match std::str::from_utf8(header) {
+            Ok(text) => !text.contains('\0'),
+            // the header may end in the middle of a character
+            Err(err) => err.error_len().is_none() && !header[..err.valid_up_to()].contains(&0),
+        };
+        (!header.is_empty() && is_text).then_some(FileType::Text)
+    }
+}
+
+/// Determine the type of the file at `path` by its first bytes.
+pub fn file_type_of(path: &Path) -> io::Result<Option<FileType>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    fs::File::open(path)?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(FileType::detect(&header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_are_detected_by_their_first_bytes() {
+        let mut tar = vec![0; HEADER_LEN];
+        tar[..8].copy_from_slice(b"file.txt");
+        tar[257..263].copy_from_slice(b"ustar\0");
+        for (header, expected) in [
+            (&b"\x1f\x8b\x08\0"[..], Some(FileType::Gzip)),
+            (b"PK\x03\x04\x14\0", Some(FileType::Zip)),
+            (b"\0\0\0\x20ftypisom", Some(FileType::Mp4)),
+            (&tar[..], Some(FileType::Tar)),
+            (b"2021-03-14 started \xe2\x9c", Some(FileType::Text)),
+            (b"\0\x01\x02\x03", None),
+            (b"", None),
+        ] {
+            assert_eq!(FileType::detect(header), expected, "{:?}", header);
+        }
+    }
+
+    #[test]
+    fn extensions_suggest_a_type() {
+        assert_eq!(FileType::expected_for("app.log"), Some(FileType::Text));
+        assert_eq!(FileType::expected_for("a.tar.GZ"), Some(FileType::Gzip));
+        assert_eq!(FileType::expected_for("libc.so.6"), None);
+        assert_eq!(FileType::expected_for("Makefile"), None);
+    }
+}
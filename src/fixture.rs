@@ -0,0 +1,322 @@
+use crate::{ByteFormat, WalkResult};
+use anyhow::{bail, Context, Result};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The size of the buffer written repeatedly into each generated file
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How the sizes of generated files are spread between their minimum and maximum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeDistribution {
+    /// Every size is equally likely
+    Uniform,
+    /// Small files are much more common than large ones, as in most real directory trees
+    LogUniform,
+}
+
+/// The shape of a synthetic directory tree created by [`create_fixture()`]
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    /// Trees created with the same seed and otherwise equal settings are identical
+    pub seed: u64,
+    /// The amount of directory levels below the root
+    pub depth: u32,
+    /// The amount of directories in each directory above the deepest level
+    pub fan_out: u32,
+    /// The amount of files in each directory, including the root
+    pub files_per_dir: u32,
+    pub min_size: u64,
+    pub max_size: u64,
+    pub distribution: SizeDistribution,
+    /// The amount of symbolic links to randomly chosen files and directories, which may be ancestors of the link
+    pub symlinks: u32,
+    /// The amount of additional hard links to randomly chosen files
+    pub hard_links: u32,
+    /// If set, files are created without writing their content, so they take next to no space on disk
+    pub sparse: bool,
+}
+
+/// What [`create_fixture()`] created
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FixtureSummary {
+    /// The amount of directories below the root
+    pub directories: u64,
+    pub files: u64,
+    /// The apparent size of all files, counting hard-linked ones once
+    pub bytes: u128,
+    pub symlinks: u64,
+    pub hard_links: u64,
+}
+
+/// A small and fast pseudo random number generator (`splitmix64`), so trees don't depend on the platform or a crate.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` to `n`, exclusive, which must not be 0.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn size(&mut self, min: u64, max: u64, distribution: SizeDistribution) -> u64 {
+        let range = max - min;
+        match distribution {
+            SizeDistribution::Uniform => match range.checked_add(1) {
+                Some(n) => min + self.next() % n,
+                None => self.next(),
+            },
+            SizeDistribution::LogUniform => {
+                let fraction = self.next() as f64 / u64::MAX as f64;
+                let log_size = (range as f64 + 1.0).ln() * fraction;
+                min + (log_size.exp() - 1.0).round().min(range as f64) as u64
+            }
+        }
+    }
+}
+
+/// Create the directory tree described by `spec` at `root`, which must not exist or be empty.
+///
+/// Directories are named `d<n>` and files `f<n>.bin`, links are placed into random directories as `link<n>`
+/// and `hardlink<n>.bin`. Symbolic links are relative, so the tree can be moved.
+#[doc(hidden)]
+pub fn create_fixture(root: &Path, spec: &FixtureSpec) -> Result<FixtureSummary> {
+    if spec.min_size > spec.max_size {
+        bail!(
+            "The minimum size of {} bytes is larger than the maximum of {} bytes",
+            spec.min_size,
+            spec.max_size
+        );
+    }
+    if root
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        bail!(
+            "'{}' must be empty to not mix fixtures with other files",
+            root.display()
+        );
+    }
+    fs::create_dir_all(root).with_context(|| format!("Could not create '{}'", root.display()))?;
+
+    let mut rng = Rng(spec.seed);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    // random content, so filesystems that compress can't shrink the files
+    for bytes in chunk.chunks_mut(8) {
+        bytes.copy_from_slice(&rng.next().to_le_bytes()[..bytes.len()]);
+    }
+
+    let mut summary = FixtureSummary::default();
+    // paths relative to `root`, the root itself being the empty path
+    let mut dirs = vec![PathBuf::new()];
+    let mut files = Vec::new();
+    let mut level = vec![PathBuf::new()];
+    for _ in 0..spec.depth {
+        let mut next_level = Vec::new();
+        for parent in &level {
+            for n in 0..spec.fan_out {
+                let dir = parent.join(format!("d{}", n));
+                fs::create_dir(root.join(&dir))?;
+                next_level.push(dir);
+            }
+        }
+        summary.directories += next_level.len() as u64;
+        dirs.extend(next_level.iter().cloned());
+        level = next_level;
+    }
+    for dir in &dirs {
+        for n in 0..spec.files_per_dir {
+            let file = dir.join(format!("f{}.bin", n));
+            let size = rng.size(spec.min_size, spec.max_size, spec.distribution);
+            write_file(&root.join(&file), size, spec.sparse, &chunk)
+                .with_context(|| format!("Could not write '{}'", root.join(&file).display()))?;
+            summary.files += 1;
+            summary.bytes += size as u128;
+            files.push(file);
+        }
+    }
+
+    if spec.hard_links > 0 && files.is_empty() {
+        bail!("Hard links need files to link to");
+    }
+    for n in 0..spec.hard_links {
+        let original = &files[rng.below(files.len())];
+        let link = dirs[rng.below(dirs.len())].join(format!("hardlink{}.bin", n));
+        fs::hard_link(root.join(original), root.join(&link))
+            .with_context(|| format!("Could not create hard link '{}'", link.display()))?;
+        summary.hard_links += 1;
+    }
+
+    let targets: Vec<_> = dirs.iter().skip(1).chain(files.iter()).collect();
+    if spec.symlinks > 0 && targets.is_empty() {
+        bail!("Symbolic links need files or directories to link to");
+    }
+    for n in 0..spec.symlinks {
+        let target = targets[rng.below(targets.len())];
+        let dir = &dirs[rng.below(dirs.len())];
+        let relative_target: PathBuf = dir
+            .components()
+            .map(|_| Path::new(".."))
+            .collect::<PathBuf>()
+            .join(target);
+        let link = root.join(dir).join(format!("link{}", n));
+        symlink(&relative_target, &link)
+            .with_context(|| format!("Could not create symbolic link '{}'", link.display()))?;
+        summary.symlinks += 1;
+    }
+    Ok(summary)
+}
+
+/// Create the tree described by `spec` at `root` like [`create_fixture()`], and write what was created to `out`.
+pub fn generate_fixture(
+    mut out: impl io::Write,
+    byte_format: ByteFormat,
+    root: &Path,
+    spec: &FixtureSpec,
+) -> Result<WalkResult> {
+    let summary = create_fixture(root, spec)?;
+    writeln!(
+        out,
+        "Created {} directories and {} files with {} in '{}', along with {} symbolic and {} hard links",
+        summary.directories,
+        summary.files,
+        byte_format.display(summary.bytes).to_string().trim_start(),
+        root.display(),
+        summary.symlinks,
+        summary.hard_links
+    )?;
+    Ok(WalkResult::default())
+}
+
+fn write_file(path: &Path, size: u64, sparse: bool, chunk: &[u8]) -> io::Result<()> {
+    use io::Write;
+    let mut file = fs::File::create(path)?;
+    if sparse {
+        return file.set_len(size);
+    }
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = remaining.min(chunk.len() as u64) as usize;
+        file.write_all(&chunk[..len])?;
+        remaining -= len as u64;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symbolic links can only be generated on unix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testutils::TempTree, traverse::Traversal, WalkOptions};
+
+    fn spec(seed: u64) -> FixtureSpec {
+        FixtureSpec {
+            seed,
+            depth: 3,
+            fan_out: 3,
+            files_per_dir: 4,
+            min_size: 0,
+            max_size: 100_000,
+            distribution: SizeDistribution::LogUniform,
+            symlinks: if cfg!(unix) { 5 } else { 0 },
+            hard_links: 5,
+            sparse: false,
+        }
+    }
+
+    /// All entries below `root` with their size or link target, sorted by path.
+    fn listing(root: &Path) -> Vec<(PathBuf, u64, Option<PathBuf>)> {
+        let mut entries = Vec::new();
+        let mut stack = vec![root.to_owned()];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir).expect("readable") {
+                let path = entry.expect("entry").path();
+                let meta = path.symlink_metadata().expect("metadata");
+                if meta.is_dir() {
+                    stack.push(path.clone());
+                }
+                let relative = path.strip_prefix(root).expect("below root").to_owned();
+                entries.push((relative, meta.len(), fs::read_link(&path).ok()));
+            }
+        }
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn the_same_seed_creates_the_same_tree() -> Result<()> {
+        let (a, b, c, d) = (
+            TempTree::new("fixture-same-seed-a"),
+            TempTree::new("fixture-same-seed-b"),
+            TempTree::new("fixture-other-seed"),
+            TempTree::new("fixture-twice"),
+        );
+        let summary = create_fixture(&a, &spec(1))?;
+        assert_eq!(create_fixture(&b, &spec(1))?, summary);
+        create_fixture(&c, &spec(2))?;
+
+        assert_eq!(listing(&a), listing(&b));
+        assert_ne!(listing(&a), listing(&c), "other seeds create other trees");
+        assert_eq!(summary.directories, 3 + 9 + 27);
+        assert_eq!(summary.files, 4 * (1 + 3 + 9 + 27));
+        assert!(
+            create_fixture(&d, &spec(1)).is_ok() && create_fixture(&d, &spec(1)).is_err(),
+            "non-empty directories are refused"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn traversals_count_generated_trees_correctly() -> Result<()> {
+        let root = TempTree::new("fixture-traversal");
+        let summary = create_fixture(&root, &spec(7))?;
+        let traversal =
+            Traversal::from_walk(WalkOptions::for_tests(), vec![root.to_owned()], |_| {
+                Ok(false)
+            })?
+            .expect("complete");
+        let symlink_bytes: u128 = listing(&root)
+            .into_iter()
+            .filter(|(_, _, target)| target.is_some())
+            .map(|(_, len, _)| len as u128)
+            .sum();
+
+        assert_eq!(
+            traversal.entries_traversed,
+            1 + summary.directories + summary.files + summary.symlinks + summary.hard_links,
+            "every entry is seen once, without following symbolic links"
+        );
+        assert_eq!(
+            traversal.tree[traversal.root_index].entry_count, traversal.entries_traversed,
+            "entries are counted in each directory"
+        );
+        assert_eq!(traversal.io_errors, 0);
+        assert_eq!(
+            traversal.total_bytes,
+            Some(summary.bytes + symlink_bytes),
+            "hard-linked files are counted once, symbolic links by the length of their target"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use std::{fs::Metadata, path::Path};
+
+/// Properties of the filesystem a traversal root is on which affect how sizes are computed.
+///
+/// FAT and exFAT volumes, as found on SD-cards and USB-drives, don't support hard links and some drivers
+/// synthesize inode numbers, so deduplicating by inode could drop distinct files. Their allocation unit is
+/// a cluster, which isn't always reflected in the amount of blocks reported by the platform.
+/// Timestamps are not affected as they are never used to compute sizes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FilesystemQuirks {
+    /// If set, the filesystem has no notion of hard links and no entry should be deduplicated by inode.
+    pub no_hard_links: bool,
+    /// If set, the size on disk is at least the apparent size rounded up to the smallest unit of allocation.
+    pub rounds_to_allocation_unit: bool,
+}
+
+const FAT_FILESYSTEMS: &[&str] = &["vfat", "msdos", "fat", "exfat", "umsdos"];
+
+impl FilesystemQuirks {
+    /// Determine the quirks of the filesystem `path` is on, assuming none if it can't be determined.
+    #[cfg(target_os = "linux")]
+    pub fn of(path: &Path) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        path.metadata()
+            .ok()
+            .and_then(|m| {
+                let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+                filesystem_type_of_device(&mountinfo, m.dev()).map(Self::for_filesystem_type)
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn of(_path: &Path) -> Self {
+        Self::default()
+    }
+
+    fn for_filesystem_type(fs_type: &str) -> Self {
+        let is_fat = FAT_FILESYSTEMS.contains(&fs_type);
+        FilesystemQuirks {
+            no_hard_links: is_fat,
+            rounds_to_allocation_unit: is_fat,
+        }
+    }
+
+    /// The fundamental block size of the filesystem `path` is on, its cluster size on Windows, if it can be
+    /// determined.
+    pub fn block_size(path: &Path) -> Option<u64> {
+        fs2::allocation_granularity(path)
+            .ok()
+            .filter(|size| *size > 0)
+    }
+
+    /// Return the apparent size of the entry described by `meta` rounded up to `block_size`, if known.
+    pub fn size_in_blocks(meta: &Metadata, block_size: Option<u64>) -> u64 {
+        match block_size {
+            Some(unit) => round_up(meta.len(), unit),
+            None => meta.len(),
+        }
+    }
+
+    /// Return the size on disk of the entry described by `meta`, given the size `reported` by the platform.
+    pub fn size_on_disk(&self, meta: &Metadata, reported: u64) -> u64 {
+        match allocation_unit(meta).filter(|_| self.rounds_to_allocation_unit) {
+            Some(unit) => reported.max(round_up(meta.len(), unit)),
+            None => reported,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn allocation_unit(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    // FAT drivers report the cluster size as preferred block size
+    Some(meta.blksize()).filter(|size| *size > 0)
+}
+
+#[cfg(not(unix))]
+fn allocation_unit(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+fn round_up(size: u64, unit: u64) -> u64 {
+    size.div_ceil(unit) * unit
+}
+
+/// Find the filesystem type of the device `dev` in the contents of `/proc/self/mountinfo`, whose lines look like
+/// `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn filesystem_type_of_device(mountinfo: &str, dev: u64) -> Option<&str> {
+    mount_of_device(mountinfo, dev).map(|(fs_type, _source)| fs_type)
+}
+
+/// Find the filesystem type and source of the device `dev` in the contents of `/proc/self/mountinfo`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn mount_of_device(mountinfo: &str, dev: u64) -> Option<(&str, &str)> {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let device = format!("{}:{}", major, minor);
+    // later mounts of the same device shadow the earlier ones
+    mountinfo
+        .lines()
+        .filter(|line| line.split_whitespace().nth(2) == Some(device.as_str()))
+        .filter_map(|line| {
+            let mut fields = line.split_once(" - ")?.1.split_whitespace();
+            Some((fields.next()?, fields.next()?))
+        })
+        .next_back()
+}
+
+/// The device and type of the filesystem a path is on, to tell which one is affected when crossing mount points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filesystem {
+    /// The source of the mount, like `/dev/sda1`, or the name of a virtual filesystem like `tmpfs`
+    pub device: String,
+    pub fs_type: String,
+}
+
+impl Filesystem {
+    /// Determine the filesystem `path` is on through the mounts of this process, or `None` if it can't be determined.
+    #[cfg(target_os = "linux")]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let dev = path.metadata().ok()?.dev();
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+        mount_of_device(&mountinfo, dev).map(|(fs_type, device)| Filesystem {
+            device: device.into(),
+            fs_type: fs_type.into(),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn of(_path: &Path) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTINFO: &str = "\
+22 1 254:1 / / rw,relatime shared:1 - ext4 /dev/vda1 rw
+40 22 8:17 / /media/sdcard rw,nosuid,nodev,relatime shared:20 - vfat /dev/sdb1 rw,fmask=0022
+41 22 8:33 / /media/usb rw,relatime shared:21 - exfat /dev/sdc1 rw
+";
+
+    #[test]
+    fn it_finds_the_filesystem_type_by_device() {
+        let dev = |major: u64, minor: u64| (major << 8) | minor;
+        assert_eq!(
+            filesystem_type_of_device(MOUNTINFO, dev(254, 1)),
+            Some("ext4")
+        );
+        assert_eq!(
+            filesystem_type_of_device(MOUNTINFO, dev(8, 17)),
+            Some("vfat")
+        );
+        assert_eq!(
+            filesystem_type_of_device(MOUNTINFO, dev(8, 33)),
+            Some("exfat")
+        );
+        assert_eq!(filesystem_type_of_device(MOUNTINFO, dev(8, 1)), None);
+    }
+
+    #[test]
+    fn it_finds_the_source_of_mounts_by_device() {
+        let dev = |major: u64, minor: u64| (major << 8) | minor;
+        assert_eq!(
+            mount_of_device(MOUNTINFO, dev(8, 17)),
+            Some(("vfat", "/dev/sdb1"))
+        );
+        assert_eq!(
+            mount_of_device("22 1 254:1 / / rw - ext4\n", dev(254, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn only_fat_filesystems_have_quirks() {
+        assert_eq!(
+            FilesystemQuirks::for_filesystem_type("ext4"),
+            FilesystemQuirks::default()
+        );
+        for fs_type in ["vfat", "exfat"] {
+            assert_eq!(
+                FilesystemQuirks::for_filesystem_type(fs_type),
+                FilesystemQuirks {
+                    no_hard_links: true,
+                    rounds_to_allocation_unit: true
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn sizes_are_rounded_up_to_the_allocation_unit() {
+        assert_eq!(round_up(0, 4096), 0);
+        assert_eq!(round_up(1, 4096), 4096);
+        assert_eq!(round_up(4096, 4096), 4096);
+        assert_eq!(round_up(40_000, 32_768), 65_536);
+    }
+
+    #[test]
+    fn the_block_size_is_known_for_existing_paths() {
+        assert!(FilesystemQuirks::block_size(&std::env::temp_dir()).is_some());
+        assert_eq!(
+            FilesystemQuirks::block_size(Path::new("/does/not/exist")),
+            None
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The files in each directory whose patterns apply to the entries below it, the latter taking precedence
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+/// A single line of an ignore file
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    /// If set, `pattern` is matched against the path relative to the ignore file instead of the name only
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.strip_prefix('/').unwrap_or(line);
+        (!pattern.is_empty()).then(|| Rule {
+            pattern: pattern.to_owned(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, relative_path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let text = match self.anchored {
+            true => relative_path,
+            false => relative_path.rsplit('/').next().unwrap_or(relative_path),
+        };
+        glob_matches(self.pattern.as_bytes(), text.as_bytes())
+    }
+}
+
+/// The patterns of all ignore files found in a directory, along with those of the directories above it.
+#[derive(Debug)]
+pub(crate) struct IgnoreRules {
+    /// The directory as seen by the traversal, with paths of entries starting with it
+    dir: PathBuf,
+    /// The path of `dir` relative to the directory the ignore files were found in, empty if they are in `dir`
+    prefix: PathBuf,
+    rules: Vec<Rule>,
+    parent: Option<Arc<IgnoreRules>>,
+}
+
+impl IgnoreRules {
+    /// The rules of the directories above `root` up to the enclosing git repository, if there is one.
+    pub(crate) fn above(root: &Path) -> Option<Arc<IgnoreRules>> {
+        let canonical = root.canonicalize().ok()?;
+        let ancestors: Vec<_> = canonical.ancestors().skip(1).collect();
+        let repository = ancestors.iter().position(|dir| dir.join(".git").exists())?;
+        ancestors[..=repository]
+            .iter()
+            .rev()
+            .fold(None, |parent, dir| {
+                let rules = Self::read(dir);
+                if rules.is_empty() {
+                    return parent;
+                }
+                Some(Arc::new(IgnoreRules {
+                    dir: root.to_owned(),
+                    prefix: canonical.strip_prefix(dir).expect("an ancestor").to_owned(),
+                    rules,
+                    parent,
+                }))
+            })
+    }
+
+    /// Add the rules of the ignore files in `dir` to `parent`, or return `parent` if there are none.
+    pub(crate) fn extended(
+        parent: Option<Arc<IgnoreRules>>,
+        dir: &Path,
+    ) -> Option<Arc<IgnoreRules>> {
+        let rules = Self::read(dir);
+        if rules.is_empty() {
+            return parent;
+        }
+        Some(Arc::new(IgnoreRules {
+            dir: dir.to_owned(),
+            prefix: PathBuf::new(),
+            rules,
+            parent,
+        }))
+    }
+
+    /// Return true if the entry at `path` is excluded by the last matching rule of the innermost ignore file.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = path
+            .strip_prefix(&self.dir)
+            .map(|relative| self.prefix.join(relative));
+        if let Ok(relative) = relative {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if let Some(rule) = self
+                .rules
+                .iter()
+                .rev()
+                .find(|rule| rule.matches(&relative, is_dir))
+            {
+                return !rule.negated;
+            }
+        }
+        self.parent
+            .as_ref()
+            .is_some_and(|parent| parent.is_ignored(path, is_dir))
+    }
+
+    fn read(dir: &Path) -> Vec<Rule> {
+        IGNORE_FILES
+            .iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .flat_map(|text| text.lines().filter_map(Rule::parse).collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+/// Match `text` against the glob `pattern` in which `*` and `?` don't match `/`, but `**` does.
+pub fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            rest.is_empty()
+                || (0..=text.len())
+                    .filter(|&i| i == 0 || text[i - 1] == b'/')
+                    .any(|i| glob_matches(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_matches(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            text.first().is_some_and(|c| *c != b'/') && glob_matches(rest, &text[1..])
+        }
+        [b'[', rest @ ..] => match (text.split_first(), class_matches(rest)) {
+            (Some((c, text)), Some((matches, rest))) => matches(*c) && glob_matches(rest, text),
+            (_, None) => text.first() == Some(&b'[') && glob_matches(rest, &text[1..]),
+            (None, Some(_)) => false,
+        },
+        [b'\\', c, rest @ ..] | [c, rest @ ..] => {
+            text.first() == Some(c) && glob_matches(rest, &text[1..])
+        }
+    }
+}
+
+/// Parse the character class at the start of `pattern`, just after its opening `[`, and return a function
+/// testing if a character is part of it along with the pattern after the class, or `None` if it isn't closed.
+fn class_matches(pattern: &[u8]) -> Option<(impl Fn(u8) -> bool + '_, &[u8])> {
+    let (negated, class) = match pattern {
+        [b'!' | b'^', class @ ..] => (true, class),
+        class => (false, class),
+    };
+    // a `]` right at the start is part of the class
+    let end = class.iter().skip(1).position(|c| *c == b']')? + 1;
+    let (class, rest) = (&class[..end], &class[end + 1..]);
+    let matches = move |c: u8| {
+        let mut found = false;
+        let mut i = 0;
+        while i < class.len() {
+            if i + 2 < class.len() && class[i + 1] == b'-' {
+                found |= (class[i]..=class[i + 2]).contains(&c);
+                i += 3;
+            } else {
+                found |= class[i] == c;
+                i += 1;
+            }
+        }
+        found != negated && c != b'/'
+    };
+    Some((matches, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_like_git() {
+        for (pattern, text, expected) in [
+            ("*.o", "main.o", true),
+            ("*.o", "src/main.o", false),
+            ("target", "target", true),
+            ("a/**/b", "a/b", true),
+            ("a/**/b", "a/x/y/b", true),
+            ("**/node_modules", "web/node_modules", true),
+            ("build/**", "build/out/a.bin", true),
+            ("file?.txt", "file1.txt", true),
+            ("file?.txt", "file/.txt", false),
+            ("[a-c]x[!0-9]", "bxy", true),
+            ("[a-c]x[!0-9]", "bx5", false),
+            ("\\#notes", "#notes", true),
+            ("[unclosed", "[unclosed", true),
+        ] {
+            assert_eq!(
+                glob_matches(pattern.as_bytes(), text.as_bytes()),
+                expected,
+                "{} ~ {}",
+                pattern,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn the_last_matching_rule_of_the_innermost_file_decides() -> std::io::Result<()> {
+        let tree = crate::testutils::TempTree::new("ignore-files");
+        let (dir, sub) = (tree.root.clone(), tree.join("sub"));
+        tree.file(".gitignore", "*.log\n/target/\n")?;
+        tree.file("sub/.ignore", "!keep.log\n")?;
+        let outer = IgnoreRules::extended(None, &dir).expect("rules");
+        let inner = IgnoreRules::extended(Some(outer.clone()), &sub).expect("rules");
+
+        assert!(outer.is_ignored(&dir.join("a.log"), false));
+        assert!(outer.is_ignored(&dir.join("target"), true));
+        assert!(
+            !outer.is_ignored(&dir.join("target"), false),
+            "only directories"
+        );
+        assert!(!outer.is_ignored(&sub.join("target"), true), "anchored");
+        assert!(inner.is_ignored(&sub.join("other.log"), false));
+        assert!(!inner.is_ignored(&sub.join("keep.log"), false));
+        Ok(())
+    }
+}
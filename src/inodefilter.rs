@@ -55,6 +55,49 @@ impl InodeFilter {
     }
 }
 
+/// Return the amount of hard links pointing to the inode described by `metadata`, if known on this platform.
+#[cfg(unix)]
+pub fn hard_links_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.nlink())
+}
+
+#[cfg(windows)]
+pub fn hard_links_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::windows::fs::MetadataExt;
+
+    metadata.number_of_links().map(u64::from)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn hard_links_of(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Return the device and inode identifying the file or directory described by `metadata`, if known on this platform.
+#[cfg(unix)]
+pub(crate) fn dev_inode_of(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+pub(crate) fn dev_inode_of(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::windows::fs::MetadataExt;
+
+    Some((
+        metadata.volume_serial_number()? as u64,
+        metadata.file_index()?,
+    ))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn dev_inode_of(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
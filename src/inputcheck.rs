@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+/// How long to wait for an input to respond before assuming it's on a network filesystem that is unreachable
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Make sure `path` can be scanned, or explain why it can't and what to do about it.
+///
+/// This is meant to be used before a traversal, which would otherwise fail with a plain IO error or,
+/// for unreachable network filesystems, hang.
+pub fn check_input(path: &Path) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let owned_path = path.to_owned();
+    // unreachable network filesystems can block indefinitely, so we don't wait for the thread if it doesn't respond
+    std::thread::spawn(move || tx.send(probe(&owned_path)).ok());
+    let problem = match rx.recv_timeout(RESPONSE_TIMEOUT) {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(err)) => describe(path, err),
+        Err(_) => format!(
+            "'{}' did not respond within {}s, it may be on a network filesystem that is unreachable - \
+             check the connection or unmount it",
+            path.display(),
+            RESPONSE_TIMEOUT.as_secs()
+        ),
+    };
+    Err(anyhow!(problem))
+}
+
+/// Check all `paths` and return those that can be scanned, along with the problems of those that can't.
+pub fn usable_inputs(paths: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<anyhow::Error>) {
+    let mut problems = Vec::new();
+    let usable = paths
+        .into_iter()
+        .filter(|path| match check_input(path) {
+            Ok(()) => true,
+            Err(err) => {
+                problems.push(err);
+                false
+            }
+        })
+        .collect();
+    (usable, problems)
+}
+
+fn probe(path: &Path) -> io::Result<()> {
+    if path.metadata()?.is_dir() {
+        fs::read_dir(path)?;
+    }
+    Ok(())
+}
+
+fn describe(path: &Path, err: io::Error) -> String {
+    let path_display = path.display();
+    match err.kind() {
+        ErrorKind::NotFound => match fs::read_link(path) {
+            Ok(target) => format!(
+                "'{}' is a symbolic link to '{}', which doesn't exist",
+                path_display,
+                target.display()
+            ),
+            Err(_) => format!(
+                "'{}' doesn't exist - check for typos, and quote paths containing spaces",
+                path_display
+            ),
+        },
+        ErrorKind::PermissionDenied if path.is_dir() => format!(
+            "'{}' is a directory we are not permitted to read - try again with --sudo",
+            path_display
+        ),
+        ErrorKind::PermissionDenied => format!(
+            "'{}' can't be accessed as one of its parent directories isn't accessible - try again with --sudo",
+            path_display
+        ),
+        ErrorKind::NotADirectory => format!(
+            "'{}' doesn't exist as one of its parents is a file, not a directory",
+            path_display
+        ),
+        ErrorKind::TimedOut
+        | ErrorKind::NotConnected
+        | ErrorKind::HostUnreachable
+        | ErrorKind::StaleNetworkFileHandle => format!(
+            "'{}' is on a network filesystem that isn't reachable ({}) - check the connection or remount it",
+            path_display, err
+        ),
+        _ => format!("'{}' can't be scanned: {}", path_display, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_are_explained() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample-01");
+        assert!(check_input(&fixture).is_ok());
+        assert!(check_input(&fixture.join("a")).is_ok(), "files are fine");
+
+        let missing = fixture.join("missing");
+        assert_eq!(
+            check_input(&missing).unwrap_err().to_string(),
+            format!(
+                "'{}' doesn't exist - check for typos, and quote paths containing spaces",
+                missing.display()
+            )
+        );
+        if cfg!(unix) {
+            let below_file = fixture.join("a").join("b");
+            assert_eq!(
+                check_input(&below_file).unwrap_err().to_string(),
+                format!(
+                    "'{}' doesn't exist as one of its parents is a file, not a directory",
+                    below_file.display()
+                )
+            );
+        }
+
+        let (usable, problems) = usable_inputs(vec![missing, fixture.clone()]);
+        assert_eq!(usable, vec![fixture]);
+        assert_eq!(problems.len(), 1);
+    }
+}
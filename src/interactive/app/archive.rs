@@ -0,0 +1,106 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+/// The compression to use for an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveCompression {
+    None,
+    Zstd,
+}
+
+impl ArchiveCompression {
+    /// Choose the compression by the extension of `path`, like `.tar.zst`, defaulting to [`ArchiveCompression::None`].
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("zst" | "tzst") => ArchiveCompression::Zstd,
+            _ => ArchiveCompression::None,
+        }
+    }
+
+    fn tar(&self) -> Command {
+        let mut cmd = Command::new("tar");
+        if let ArchiveCompression::Zstd = self {
+            cmd.arg("--zstd");
+        }
+        cmd
+    }
+}
+
+/// Use `tar` to write all `paths` into a new archive at `archive` and verify it by reading it back in full,
+/// making sure each of the given paths is contained.
+///
+/// Only if this succeeds it's safe to delete `paths`.
+pub fn create_verified_archive(archive: &Path, paths: &[PathBuf]) -> Result<()> {
+    if archive.exists() {
+        bail!("'{}' already exists", archive.display());
+    }
+    let compression = ArchiveCompression::from_path(archive);
+    run(compression
+        .tar()
+        .arg("-c")
+        .arg("-f")
+        .arg(archive)
+        .arg("--")
+        .args(paths))
+    .context("Could not create archive")?;
+
+    let listing = run(compression.tar().arg("-t").arg("-f").arg(archive))
+        .context("Could not read back archive")?;
+    let archived: BTreeSet<_> = listing.lines().map(|l| member_name(Path::new(l))).collect();
+    if let Some(missing) = paths.iter().find(|p| !archived.contains(&member_name(p))) {
+        bail!("'{}' is missing in the archive", missing.display());
+    }
+    Ok(())
+}
+
+/// Run `cmd` and return its standard output, or fail if it doesn't exit successfully.
+fn run(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output().context("Could not run 'tar'")?;
+    if !output.status.success() {
+        bail!(
+            "'tar' failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The name of `path` within an archive, without the leading `/`, `./` or `../` that `tar` might strip.
+fn member_name(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_is_chosen_by_extension() {
+        for (path, expected) in [
+            ("backup.tar", ArchiveCompression::None),
+            ("backup", ArchiveCompression::None),
+            ("backup.tar.zst", ArchiveCompression::Zstd),
+            ("backup.TZST", ArchiveCompression::Zstd),
+        ] {
+            assert_eq!(ArchiveCompression::from_path(Path::new(path)), expected);
+        }
+    }
+
+    #[test]
+    fn member_names_are_relative() {
+        for path in ["/a/b", "./a/b", "../a/b/", "a/./b"] {
+            assert_eq!(member_name(Path::new(path)), Path::new("a/b"), "{}", path);
+        }
+    }
+}
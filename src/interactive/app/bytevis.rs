@@ -1,24 +1,29 @@
+use crate::interactive::widgets::ColorDepth;
 use dua::{ByteFormat, WalkOptions};
 use std::fmt;
+use tui::style::Color;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum ByteVisualization {
     Percentage,
     Bar,
     LongBar,
+    #[default]
     PercentageAndBar,
+    /// The percentage and a bar drawn with `#`, like `ncdu` does, for terminals without block characters
+    PercentageAndHashBar,
 }
 
 pub struct DisplayByteVisualization {
     format: ByteVisualization,
     percentage: f32,
+    bar_width: usize,
 }
 
-impl Default for ByteVisualization {
-    fn default() -> Self {
-        ByteVisualization::PercentageAndBar
-    }
-}
+/// The length of bars in columns unless configured otherwise
+pub const DEFAULT_BAR_WIDTH: usize = 10;
+/// The bounds of the configurable bar length, to keep enough room for names and the bar meaningful
+pub const BAR_WIDTH_RANGE: std::ops::RangeInclusive<usize> = 2..=60;
 
 impl ByteVisualization {
     pub fn cycle(&mut self) {
@@ -26,14 +31,16 @@ impl ByteVisualization {
         *self = match self {
             Bar => LongBar,
             LongBar => PercentageAndBar,
-            PercentageAndBar => Percentage,
+            PercentageAndBar => PercentageAndHashBar,
+            PercentageAndHashBar => Percentage,
             Percentage => Bar,
         }
     }
-    pub fn display(self, percentage: f32) -> DisplayByteVisualization {
+    pub fn display(self, percentage: f32, bar_width: usize) -> DisplayByteVisualization {
         DisplayByteVisualization {
             format: self,
             percentage,
+            bar_width,
         }
     }
 }
@@ -41,23 +48,31 @@ impl ByteVisualization {
 impl fmt::Display for DisplayByteVisualization {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         use ByteVisualization::*;
-        let Self { format, percentage } = self;
+        let Self {
+            format,
+            percentage,
+            bar_width,
+        } = self;
 
         let percentage = if percentage.is_nan() {
             0.0
         } else {
             *percentage
         };
-        const BAR_SIZE: usize = 10;
         match format {
             Percentage => Self::make_percentage(f, percentage),
             PercentageAndBar => {
                 Self::make_percentage(f, percentage)?;
                 f.write_str(" ")?;
-                Self::make_bar(f, percentage, BAR_SIZE)
+                Self::make_bar(f, percentage, *bar_width)
+            }
+            PercentageAndHashBar => {
+                Self::make_percentage(f, percentage)?;
+                f.write_str(" ")?;
+                Self::make_hash_bar(f, percentage, *bar_width)
             }
-            Bar => Self::make_bar(f, percentage, BAR_SIZE),
-            LongBar => Self::make_bar(f, percentage, 19),
+            Bar => Self::make_bar(f, percentage, *bar_width),
+            LongBar => Self::make_bar(f, percentage, bar_width * 2 - 1),
         }
     }
 }
@@ -95,6 +110,14 @@ impl DisplayByteVisualization {
         }
         Ok(())
     }
+    fn make_hash_bar(
+        f: &mut fmt::Formatter,
+        percentage: f32,
+        length: usize,
+    ) -> Result<(), fmt::Error> {
+        let filled = ((length as f32 * percentage).round() as usize).min(length);
+        write!(f, "{}{}", "#".repeat(filled), " ".repeat(length - filled))
+    }
     fn make_percentage(f: &mut fmt::Formatter, percentage: f32) -> Result<(), fmt::Error> {
         write!(f, " {:>5.01}% ", percentage * 100.0)
     }
@@ -105,13 +128,66 @@ impl DisplayByteVisualization {
 pub struct DisplayOptions {
     pub byte_format: ByteFormat,
     pub byte_vis: ByteVisualization,
+    /// The length of bars in columns, with long bars being about twice as long
+    pub bar_width: usize,
+    /// If set, entries show their share of the parent directory as configured by `byte_vis`
+    pub show_byte_vis: bool,
+    /// If set, shares are of all scanned bytes instead of the parent directory, to see what dominates overall
+    pub share_of_total: bool,
+    pub show_hard_links: bool,
+    /// If set, directories show the amount of entries below them, at any depth
+    pub show_entry_count: bool,
+    /// If set, entries show when they were last modified, which for directories is the newest time of anything in them
+    pub show_mtime: bool,
+    pub show_icons: bool,
+    pub show_size_without_marked: bool,
+    /// If set, the first row of the entries pane summarizes the current directory and stays visible while scrolling
+    pub show_summary_row: bool,
+    /// If set, entries much larger than most of their siblings are highlighted
+    pub highlight_outliers: bool,
+    /// The background color of every other row in the entries pane, if any
+    pub row_shading: Option<Color>,
+    /// The colors the terminal can show, which all others are mapped to
+    pub color_depth: ColorDepth,
+    /// How the sizes are computed, as shown in the details pane
+    pub size_model: &'static str,
 }
 
 impl From<WalkOptions> for DisplayOptions {
-    fn from(WalkOptions { byte_format, .. }: WalkOptions) -> Self {
+    fn from(walk_options: WalkOptions) -> Self {
         DisplayOptions {
-            byte_format,
+            byte_format: walk_options.byte_format,
+            size_model: walk_options.size_model_description(),
             byte_vis: ByteVisualization::default(),
+            bar_width: DEFAULT_BAR_WIDTH,
+            show_byte_vis: true,
+            share_of_total: false,
+            show_hard_links: false,
+            show_entry_count: false,
+            show_mtime: false,
+            show_icons: false,
+            show_size_without_marked: false,
+            show_summary_row: false,
+            highlight_outliers: false,
+            row_shading: None,
+            color_depth: ColorDepth::TrueColor,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_are_shown_as_percentage_and_bar() {
+        let show =
+            |format: ByteVisualization, percentage| format.display(percentage, 4).to_string();
+        assert_eq!(
+            show(ByteVisualization::PercentageAndHashBar, 0.5),
+            "  50.0%  ##  "
+        );
+        assert_eq!(show(ByteVisualization::Bar, 1.0), "████");
+        assert_eq!(show(ByteVisualization::Percentage, f32::NAN), "   0.0% ");
+    }
+}
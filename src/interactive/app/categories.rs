@@ -0,0 +1,161 @@
+use anyhow::{bail, Result};
+use dua::{glob_matches, traverse::Tree};
+use petgraph::Direction;
+use std::{fmt, str::FromStr};
+
+/// A named group of files to sum up the sizes of, configured as `category = <name>: <glob>, <glob>…`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    pub name: String,
+    /// Globs matched against file names, like `*.ckpt`
+    pub patterns: Vec<String>,
+}
+
+/// The amount of files of a [`Category`] and their size across the whole tree
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CategoryTotal {
+    pub name: String,
+    pub bytes: u128,
+    pub files: u64,
+}
+
+impl Category {
+    /// Return true if the file `name` matches any of the patterns of this category.
+    pub fn matches(&self, name: &[u8]) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern.as_bytes(), name))
+    }
+}
+
+impl FromStr for Category {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, patterns) = match s.split_once(':') {
+            Some((name, patterns)) => (name.trim(), patterns),
+            None => bail!("Expected '<name>: <glob>, <glob>…', got '{}'", s),
+        };
+        let patterns: Vec<_> = patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        if name.is_empty() || patterns.is_empty() {
+            bail!("The category '{}' needs a name and at least one glob", s);
+        }
+        Ok(Category {
+            name: name.to_owned(),
+            patterns,
+        })
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.patterns.join(", "))
+    }
+}
+
+/// Sum up the files of `tree` per category, largest first, along with all files no category matched.
+///
+/// Each file counts towards the first of `categories` matching its name.
+pub fn category_totals(
+    tree: &Tree,
+    categories: &[Category],
+) -> (Vec<CategoryTotal>, CategoryTotal) {
+    let mut totals: Vec<_> = categories
+        .iter()
+        .map(|category| CategoryTotal {
+            name: category.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut rest = CategoryTotal {
+        name: "Uncategorized".into(),
+        ..Default::default()
+    };
+    for index in tree.node_indices() {
+        let is_file = tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .next()
+            .is_none()
+            && tree
+                .neighbors_directed(index, Direction::Incoming)
+                .next()
+                .is_some();
+        if !is_file {
+            continue;
+        }
+        let entry = &tree[index];
+        let name = entry.name.file_name().unwrap_or(entry.name.as_os_str());
+        let name = name.to_string_lossy();
+        let total = match categories
+            .iter()
+            .position(|category| category.matches(name.as_bytes()))
+        {
+            Some(position) => &mut totals[position],
+            None => &mut rest,
+        };
+        total.bytes += entry.size;
+        total.files += 1;
+    }
+    totals.sort_by_key(|total| std::cmp::Reverse(total.bytes));
+    (totals, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dua::traverse::EntryData;
+
+    #[test]
+    fn files_count_towards_the_first_matching_category() -> Result<()> {
+        let mut tree = Tree::new();
+        let root = tree.add_node(EntryData::default());
+        let mut add = |parent, name: &str, size| {
+            let index = tree.add_node(EntryData {
+                name: name.into(),
+                size,
+                ..Default::default()
+            });
+            tree.add_edge(parent, index, ());
+            index
+        };
+        let models = add(root, "/data/models", 0);
+        add(models, "a.ckpt", 100);
+        add(models, "b.safetensors", 50);
+        add(models, "notes.txt", 1);
+        add(root, "/data/run.log", 7);
+        let categories = vec![
+            "Logs: *.log, *.txt".parse::<Category>()?,
+            "ML checkpoints: *.ckpt,*.safetensors".parse()?,
+            "Checkpoints again: *.ckpt".parse()?,
+        ];
+
+        let total = |name: &str, bytes, files| CategoryTotal {
+            name: name.into(),
+            bytes,
+            files,
+        };
+        assert_eq!(
+            category_totals(&tree, &categories),
+            (
+                vec![
+                    total("ML checkpoints", 150, 2),
+                    total("Logs", 8, 2),
+                    total("Checkpoints again", 0, 0)
+                ],
+                total("Uncategorized", 0, 0)
+            )
+        );
+        assert_eq!(
+            categories[1].to_string(),
+            "ML checkpoints: *.ckpt, *.safetensors"
+        );
+        assert!("no globs:".parse::<Category>().is_err());
+        assert!("*.log".parse::<Category>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// What [`write_checksum_manifest()`] wrote
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ManifestSummary {
+    pub files: u64,
+    pub bytes: u128,
+    /// The amount of files that couldn't be read, which are missing from the manifest
+    pub unreadable: u64,
+}
+
+/// Write the BLAKE3 checksum and size of all files at or below `paths` to a new file at `manifest`, one line per file
+/// as `<checksum>\t<size>\t<path>`, to later verify that backups contain them.
+///
+/// Files are hashed on all cores, but written in the order they are found in.
+/// Symbolic links aren't followed, and only regular files are part of the manifest.
+pub fn write_checksum_manifest(manifest: &Path, paths: &[PathBuf]) -> Result<ManifestSummary> {
+    if manifest.exists() {
+        bail!("'{}' already exists", manifest.display());
+    }
+    let mut files = Vec::new();
+    for path in paths {
+        collect_files(path, &mut files)
+            .with_context(|| format!("Could not list the files in '{}'", path.display()))?;
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let next = AtomicUsize::new(0);
+    let mut checksums: Vec<(usize, io::Result<(String, u64)>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(files.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut checksums = Vec::new();
+                    let mut buf = vec![0; READ_BUFFER_SIZE];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        match files.get(index) {
+                            Some(path) => checksums.push((index, checksum_of(path, &mut buf))),
+                            None => break checksums,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("hashing to not panic"))
+            .collect()
+    });
+    checksums.sort_by_key(|(index, _)| *index);
+
+    let file = fs::File::create(manifest)
+        .with_context(|| format!("Could not create '{}'", manifest.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut summary = ManifestSummary::default();
+    for (index, checksum) in checksums {
+        match checksum {
+            Ok((checksum, size)) => {
+                writeln!(out, "{}\t{}\t{}", checksum, size, files[index].display())?;
+                summary.files += 1;
+                summary.bytes += size as u128;
+            }
+            Err(_) => summary.unreadable += 1,
+        }
+    }
+    out.flush()?;
+    Ok(summary)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_file() {
+        files.push(path.to_owned());
+    } else if meta.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            collect_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// The BLAKE3 checksum of the file at `path` along with its size, reading it through `buf`.
+fn checksum_of(path: &Path, buf: &mut [u8]) -> io::Result<(String, u64)> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut size = 0;
+    loop {
+        match file.read(buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                size += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((hasher.finalize().to_hex().to_string(), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    fn manifests_list_all_files_below_the_given_paths_in_order() -> Result<()> {
+        let root = WritableFixture::empty("checksum-manifest");
+        fs::create_dir_all(root.join("dir/sub"))?;
+        fs::write(root.join("dir/b"), b"abc")?;
+        fs::write(root.join("dir/sub/a"), b"")?;
+        fs::write(root.join("single"), vec![0; 3])?;
+        let manifest = root.join("manifest.txt");
+
+        let summary = write_checksum_manifest(&manifest, &[root.join("dir"), root.join("single")])?;
+        let written = fs::read_to_string(&manifest)?;
+        let again = write_checksum_manifest(&manifest, &[root.join("single")]);
+
+        assert_eq!(
+            summary,
+            ManifestSummary {
+                files: 3,
+                bytes: 6,
+                unreadable: 0
+            }
+        );
+        let lines: Vec<_> = written
+            .lines()
+            .map(|l| {
+                let fields: Vec<_> = l.split('\t').collect();
+                (fields[0].len(), fields[1].to_owned(), fields[2].to_owned())
+            })
+            .collect();
+        let path = |p: &str| root.join(p).display().to_string();
+        assert_eq!(
+            lines,
+            vec![
+                (64, "3".into(), path("dir/b")),
+                (64, "0".into(), path("dir/sub/a")),
+                (64, "3".into(), path("single")),
+            ]
+        );
+        assert!(written
+            .starts_with("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85\t3\t"));
+        assert!(again.is_err(), "existing manifests aren't overwritten");
+        Ok(())
+    }
+}
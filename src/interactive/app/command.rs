@@ -0,0 +1,368 @@
+use crate::interactive::{EntryFilter, SortMode};
+use crate::options::ByteFormat;
+use anyhow::{anyhow, bail, Result};
+
+/// The names of all commands, for completion
+const COMMANDS: &[&str] = &["cd", "export", "filter", "mark", "set", "sort"];
+const FILTERS: &[&str] = &["hard-linked", "not-hidden", "local"];
+const SORTINGS: &[&str] = &[
+    "descending",
+    "ascending",
+    "growth",
+    "name",
+    "newest",
+    "oldest",
+    "count",
+];
+const OPTIONS: &[&str] = &[
+    "auto-descend",
+    "bar-width",
+    "entry-count",
+    "format",
+    "hard-links",
+    "icons",
+    "mtime",
+    "outliers",
+    "share",
+    "share-of-total",
+    "size-without-marked",
+    "summary",
+];
+/// The amount of commands to remember
+pub const COMMAND_HISTORY_LIMIT: usize = 100;
+
+/// A command typed into the command line, which is opened with `:`
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Enter the directory at the given path relative to the current one, `..` being the parent and `/` the top
+    Cd(String),
+    /// Toggle the mark of the entry with the given name in the current directory, or of the selected one.
+    /// Names containing `*`, `?` or `[` mark all matching entries instead, like `*` does.
+    Mark(Option<String>),
+    /// Toggle the given filter
+    Filter(EntryFilter),
+    Sort(SortMode),
+    /// Write the entries of the current directory to the given file
+    Export(String),
+    Set(Setting),
+}
+
+/// A display option changed by `set`
+#[derive(Debug, PartialEq)]
+pub enum Setting {
+    /// Turn a flag on or off, or toggle it if `None`
+    Flag(Flag, Option<bool>),
+    BarWidth(usize),
+    Format(dua::ByteFormat),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    AutoDescend,
+    HardLinks,
+    EntryCount,
+    Mtime,
+    Icons,
+    SizeWithoutMarked,
+    Summary,
+    Outliers,
+    Share,
+    ShareOfTotal,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command> {
+        let line = line.trim();
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        let required = |what: &str| match args.is_empty() {
+            true => Err(anyhow!("'{}' needs {}", name, what)),
+            false => Ok(args.to_owned()),
+        };
+        Ok(match name {
+            "cd" => Command::Cd(pasted_path(&required("a directory")?).to_owned()),
+            "mark" => Command::Mark((!args.is_empty()).then(|| pasted_path(args).to_owned())),
+            "filter" => Command::Filter(match args {
+                "hard-linked" => EntryFilter::HardLinked,
+                "not-hidden" => EntryFilter::NotHidden,
+                "local" => EntryFilter::LocallyAvailable,
+                _ => bail!(
+                    "Unknown filter '{}', expected one of {}",
+                    args,
+                    FILTERS.join(", ")
+                ),
+            }),
+            "sort" => Command::Sort(match args {
+                "descending" | "" => SortMode::SizeDescending,
+                "ascending" => SortMode::SizeAscending,
+                "growth" => SortMode::GrowthDescending,
+                "name" => SortMode::NameAscending,
+                "newest" => SortMode::MtimeDescending,
+                "oldest" => SortMode::MtimeAscending,
+                "count" => SortMode::CountDescending,
+                _ => bail!(
+                    "Unknown sorting '{}', expected one of {}",
+                    args,
+                    SORTINGS.join(", ")
+                ),
+            }),
+            "export" => Command::Export(required("a file to export to")?),
+            "set" => Command::Set(Setting::parse(args)?),
+            "" => bail!("Type a command, one of {}", COMMANDS.join(", ")),
+            _ => bail!(
+                "Unknown command '{}', expected one of {}",
+                name,
+                COMMANDS.join(", ")
+            ),
+        })
+    }
+}
+
+impl Setting {
+    fn parse(args: &str) -> Result<Setting> {
+        let (option, value) = args.split_once(' ').unwrap_or((args, ""));
+        let value = value.trim();
+        let flag = |flag| {
+            Ok(Setting::Flag(
+                flag,
+                match value {
+                    "" => None,
+                    "on" | "true" | "yes" => Some(true),
+                    "off" | "false" | "no" => Some(false),
+                    _ => bail!("Expected 'on' or 'off' for '{}', got '{}'", option, value),
+                },
+            ))
+        };
+        match option {
+            "auto-descend" => flag(Flag::AutoDescend),
+            "hard-links" => flag(Flag::HardLinks),
+            "entry-count" => flag(Flag::EntryCount),
+            "mtime" => flag(Flag::Mtime),
+            "icons" => flag(Flag::Icons),
+            "size-without-marked" => flag(Flag::SizeWithoutMarked),
+            "summary" => flag(Flag::Summary),
+            "outliers" => flag(Flag::Outliers),
+            "share" => flag(Flag::Share),
+            "share-of-total" => flag(Flag::ShareOfTotal),
+            "bar-width" => value
+                .parse()
+                .map(Setting::BarWidth)
+                .map_err(|_| anyhow!("Expected a number of columns, got '{}'", value)),
+            "format" => value
+                .parse::<ByteFormat>()
+                .map(|format| Setting::Format(format.into()))
+                .map_err(|err| {
+                    anyhow!(
+                        "{}, expected one of {}",
+                        err,
+                        ByteFormat::VARIANTS.join(", ")
+                    )
+                }),
+            _ => bail!(
+                "Unknown option '{}', expected one of {}",
+                option,
+                OPTIONS.join(", ")
+            ),
+        }
+    }
+}
+
+/// `path` without the decoration it may have when copied from elsewhere, like quotes or a `file://` prefix.
+fn pasted_path(path: &str) -> &str {
+    let path = path.trim();
+    let path = ['"', '\'']
+        .iter()
+        .find_map(|quote| path.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(path);
+    path.strip_prefix("file://").unwrap_or(path)
+}
+
+/// `path` without a trailing `:<line>` or `:<line>:<column>`, if it has one.
+pub fn without_line_number(path: &str) -> Option<&str> {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (rest, last) = path.rsplit_once(':')?;
+    if !is_number(last) {
+        return None;
+    }
+    Some(match rest.rsplit_once(':') {
+        Some((file, line)) if is_number(line) => file,
+        _ => rest,
+    })
+}
+
+/// Complete the last word of `input` as far as it is unambiguous, or return `None` if nothing matches.
+///
+/// `names_in` returns the names of the entries in the directory at the given path relative to the current one,
+/// which are used to complete the arguments of `cd` and `mark`.
+pub fn complete(input: &str, names_in: impl FnOnce(&str) -> Vec<String>) -> Option<String> {
+    let (command, args) = match input.split_once(' ') {
+        None => return complete_word(input, "", COMMANDS.iter().copied(), " "),
+        Some((command, args)) => (command, args.trim_start()),
+    };
+    let prefix = &input[..input.len() - args.len()];
+    match command {
+        "cd" | "mark" => {
+            let (dir, partial) = match args.rsplit_once('/') {
+                Some((dir, partial)) => (dir, partial),
+                None => ("", args),
+            };
+            let names = names_in(dir);
+            let prefix = &input[..input.len() - partial.len()];
+            let separator = if command == "cd" { "/" } else { "" };
+            complete_word(partial, prefix, names.iter().map(String::as_str), separator)
+        }
+        "filter" => complete_word(args, prefix, FILTERS.iter().copied(), ""),
+        "sort" => complete_word(args, prefix, SORTINGS.iter().copied(), ""),
+        "set" => match args.split_once(' ') {
+            None => complete_word(args, prefix, OPTIONS.iter().copied(), " "),
+            Some(("format", value)) => complete_word(
+                value.trim_start(),
+                &input[..input.len() - value.trim_start().len()],
+                ByteFormat::VARIANTS.iter().copied(),
+                "",
+            ),
+            Some(("bar-width", _)) => None,
+            Some(_) => complete_word(
+                args.rsplit(' ').next().unwrap_or_default(),
+                &input[..input.rfind(' ').map_or(0, |pos| pos + 1)],
+                ["on", "off"].iter().copied(),
+                "",
+            ),
+        },
+        _ => None,
+    }
+}
+
+/// Put the longest common prefix of all `candidates` starting with `partial` after `prefix`, followed by
+/// `separator` if there is only one candidate.
+fn complete_word<'a>(
+    partial: &str,
+    prefix: &str,
+    candidates: impl Iterator<Item = &'a str>,
+    separator: &str,
+) -> Option<String> {
+    let matches: Vec<_> = candidates.filter(|c| c.starts_with(partial)).collect();
+    let first = matches.first()?;
+    let common_len = matches.iter().skip(1).fold(first.len(), |len, other| {
+        first
+            .chars()
+            .zip(other.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum::<usize>()
+            .min(len)
+    });
+    let completed = &first[..common_len];
+    Some(match matches.len() {
+        1 => format!("{}{}{}", prefix, completed, separator),
+        _ => format!("{}{}", prefix, completed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_with_their_arguments() -> Result<()> {
+        assert_eq!(Command::parse("cd a dir/b")?, Command::Cd("a dir/b".into()));
+        assert_eq!(Command::parse(" mark ")?, Command::Mark(None));
+        assert_eq!(
+            Command::parse("filter local")?,
+            Command::Filter(EntryFilter::LocallyAvailable)
+        );
+        assert_eq!(
+            Command::parse("sort ascending")?,
+            Command::Sort(SortMode::SizeAscending)
+        );
+        assert_eq!(
+            Command::parse("sort growth")?,
+            Command::Sort(SortMode::GrowthDescending)
+        );
+        assert_eq!(
+            Command::parse("sort oldest")?,
+            Command::Sort(SortMode::MtimeAscending)
+        );
+        assert_eq!(
+            Command::parse("set icons off")?,
+            Command::Set(Setting::Flag(Flag::Icons, Some(false)))
+        );
+        assert_eq!(
+            Command::parse("set entry-count")?,
+            Command::Set(Setting::Flag(Flag::EntryCount, None))
+        );
+        assert_eq!(
+            Command::parse("set mtime on")?,
+            Command::Set(Setting::Flag(Flag::Mtime, Some(true)))
+        );
+        assert_eq!(
+            Command::parse("set share-of-total")?,
+            Command::Set(Setting::Flag(Flag::ShareOfTotal, None))
+        );
+        assert_eq!(
+            Command::parse("set format MiB")?,
+            Command::Set(Setting::Format(dua::ByteFormat::MiB))
+        );
+        assert_eq!(
+            Command::parse("set bar-width 12")?,
+            Command::Set(Setting::BarWidth(12))
+        );
+        for invalid in [
+            "",
+            "cd",
+            "export",
+            "rm -rf",
+            "filter all",
+            "set icons maybe",
+        ] {
+            assert!(Command::parse(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pasted_paths_are_stripped_of_decoration() -> Result<()> {
+        assert_eq!(
+            Command::parse("cd '/var/log/my app'")?,
+            Command::Cd("/var/log/my app".into())
+        );
+        assert_eq!(
+            Command::parse("cd file:///tmp/x\n")?,
+            Command::Cd("/tmp/x".into())
+        );
+        assert_eq!(without_line_number("src/main.rs:42"), Some("src/main.rs"));
+        assert_eq!(without_line_number("src/main.rs:42:7"), Some("src/main.rs"));
+        assert_eq!(without_line_number("src/main.rs"), None);
+        assert_eq!(without_line_number("C:"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn completion_extends_the_last_word() {
+        let names = |dir: &str| match dir {
+            "" => vec!["src".to_string(), "sample".into(), "target".into()],
+            "src" => vec!["main.rs".to_string()],
+            _ => vec![],
+        };
+        assert_eq!(complete("ex", names).as_deref(), Some("export "));
+        assert_eq!(complete("s", names).as_deref(), Some("s"), "set or sort");
+        assert_eq!(complete("cd t", names).as_deref(), Some("cd target/"));
+        assert_eq!(complete("cd s", names).as_deref(), Some("cd s"));
+        assert_eq!(complete("cd sr", names).as_deref(), Some("cd src/"));
+        assert_eq!(
+            complete("mark src/m", names).as_deref(),
+            Some("mark src/main.rs")
+        );
+        assert_eq!(complete("set ic", names).as_deref(), Some("set icons "));
+        assert_eq!(
+            complete("set icons of", names).as_deref(),
+            Some("set icons off")
+        );
+        assert_eq!(
+            complete("set format Gi", names).as_deref(),
+            Some("set format GiB")
+        );
+        assert_eq!(complete("filter l", names).as_deref(), Some("filter local"));
+        assert_eq!(complete("cd x", names), None);
+    }
+}
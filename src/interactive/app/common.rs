@@ -1,58 +1,282 @@
 use crate::interactive::path_of;
 use dua::traverse::{EntryData, Tree, TreeIndex};
-use itertools::Itertools;
 use petgraph::Direction;
+use std::{borrow::Cow, path::Path};
 use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Default)]
 pub enum SortMode {
+    #[default]
     SizeDescending,
     SizeAscending,
+    /// By the size of recently modified files, the largest first
+    GrowthDescending,
+    /// Alphabetically by name, ignoring case
+    NameAscending,
+    /// By the time the entry or anything in it was last modified, the newest first
+    MtimeDescending,
+    /// By the time the entry or anything in it was last modified, the oldest first
+    MtimeAscending,
+    /// By the amount of files and directories in the entry at any depth, the most first
+    CountDescending,
 }
 
 impl SortMode {
-    pub fn toggle_size(&mut self) {
+    /// Switch to the next way of sorting, from size in both directions over name and modification time
+    /// to the amount of entries.
+    pub fn cycle(&mut self) {
         use SortMode::*;
         *self = match self {
-            SizeAscending => SizeDescending,
-            SizeDescending => SizeAscending,
+            SizeDescending | GrowthDescending => SizeAscending,
+            SizeAscending => NameAscending,
+            NameAscending => MtimeDescending,
+            MtimeDescending => MtimeAscending,
+            MtimeAscending => CountDescending,
+            CountDescending => SizeDescending,
         }
     }
+
+    /// The column entries are sorted by, along with the direction.
+    pub fn label(&self) -> &'static str {
+        use SortMode::*;
+        match self {
+            SizeDescending => "size ↓",
+            SizeAscending => "size ↑",
+            GrowthDescending => "growth ↓",
+            NameAscending => "name ↑",
+            MtimeDescending => "modified, newest first",
+            MtimeAscending => "modified, oldest first",
+            CountDescending => "entries ↓",
+        }
+    }
+
+    /// Return true if entries are sorted by their modification time.
+    pub fn is_by_mtime(&self) -> bool {
+        matches!(self, SortMode::MtimeDescending | SortMode::MtimeAscending)
+    }
 }
 
-impl Default for SortMode {
-    fn default() -> Self {
-        SortMode::SizeDescending
+/// A way to reduce the entries shown in the entries pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryFilter {
+    /// Only show directories and files with more than one hard link
+    HardLinked,
+    /// Only show entries whose name doesn't start with a dot
+    NotHidden,
+    /// Only show entries which aren't cloud storage placeholders, i.e. whose content is stored locally
+    LocallyAvailable,
+    /// Only show entries whose name contains all characters of the given text in order, ignoring case
+    Name(String),
+}
+
+impl EntryFilter {
+    /// Return true if this filter needs metadata from disk to decide, which is costly for large directories.
+    pub fn needs_metadata(&self) -> bool {
+        use EntryFilter::*;
+        match self {
+            HardLinked | LocallyAvailable => true,
+            NotHidden | Name(_) => false,
+        }
     }
+
+    pub fn matches(&self, entry: &EntryDataBundle) -> bool {
+        use EntryFilter::*;
+        match self {
+            HardLinked => entry.is_dir || entry.hard_links.is_some_and(|n| n > 1),
+            NotHidden => !entry.data.name.to_string_lossy().starts_with('.'),
+            LocallyAvailable => entry.cloud_size.is_none(),
+            Name(query) => name_matches(query, &entry.data.name.to_string_lossy()),
+        }
+    }
+
+    /// A short description of what the filter keeps, for display
+    pub fn label(&self) -> Cow<'_, str> {
+        use EntryFilter::*;
+        match self {
+            HardLinked => "multiple hard links".into(),
+            NotHidden => "no hidden entries".into(),
+            LocallyAvailable => "locally available".into(),
+            Name(query) => format!("name ~ {}", query).into(),
+        }
+    }
+}
+
+/// Return true if all characters of `query` appear in `name` in the same order, so `dlg` finds `debug.log`.
+/// Case and the Unicode normalization form don't matter.
+fn name_matches(query: &str, name: &str) -> bool {
+    let name = folded(name);
+    let mut name_chars = name.chars();
+    folded(query).chars().all(|c| name_chars.any(|n| n == c))
 }
 
+/// Return true if `name` matches `query`, a glob like `core.*` if it has wildcards, or otherwise text within the name.
+/// Case and the Unicode normalization form don't matter.
+pub fn find_matches(query: &str, name: &str) -> bool {
+    let (query, name) = (folded(query), folded(name));
+    match query.contains(['*', '?', '[']) {
+        true => dua::glob_matches(query.as_bytes(), name.as_bytes()),
+        false => name.contains(&query),
+    }
+}
+
+fn folded(s: &str) -> String {
+    dua::normalized(Path::new(s))
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+/// Add `filter` to `filters` unless it's already present, in which case it will be removed.
+/// Returns true if the filter is now active.
+pub fn toggle_filter(filters: &mut Vec<EntryFilter>, filter: EntryFilter) -> bool {
+    match filters.iter().position(|f| *f == filter) {
+        Some(pos) => {
+            filters.remove(pos);
+            false
+        }
+        None => {
+            filters.push(filter);
+            true
+        }
+    }
+}
+
+/// Directories with more entries than this will not have the metadata of their entries queried when listing them.
+/// Instead it's obtained only for the entries that are actually displayed, to keep navigation responsive.
+pub const EAGER_METADATA_LIMIT: usize = 10_000;
+
+#[derive(Clone)]
 pub struct EntryDataBundle {
     pub index: TreeIndex,
     pub data: EntryData,
     pub is_dir: bool,
     pub exists: bool,
+    /// The amount of hard links to this entry, if known
+    pub hard_links: Option<u64>,
+    /// The logical size of the file if it's a cloud storage placeholder, whose content may not be stored locally.
+    /// Its `data.size` is the size on disk then, unless apparent sizes are used.
+    pub cloud_size: Option<u64>,
+    /// The kind of crash artifact this file is, if it is one
+    pub crash_artifact: Option<dua::CrashArtifact>,
+    /// If false, `is_dir`, `exists`, `hard_links`, `cloud_size` and `crash_artifact` are merely estimated from the tree, see [`EntryDataBundle::with_metadata()`].
+    pub has_metadata: bool,
+}
+
+impl EntryDataBundle {
+    fn new(tree: &Tree, index: TreeIndex, data: &EntryData, query_metadata: bool) -> Self {
+        let bundle = EntryDataBundle {
+            index,
+            data: data.clone(),
+            is_dir: tree
+                .neighbors_directed(index, Direction::Outgoing)
+                .next()
+                .is_some(),
+            exists: true,
+            hard_links: None,
+            cloud_size: None,
+            crash_artifact: None,
+            has_metadata: false,
+        };
+        if query_metadata {
+            bundle.with_metadata(tree)
+        } else {
+            bundle
+        }
+    }
+
+    /// Return this bundle with its metadata queried from disk, or unchanged if it already has metadata.
+    pub fn with_metadata(mut self, tree: &Tree) -> Self {
+        if !self.has_metadata {
+            let path = path_of(tree, self.index);
+            let pm = path.symlink_metadata();
+            self.exists = pm.is_ok();
+            self.is_dir = pm.as_ref().is_ok_and(|m| m.is_dir());
+            self.cloud_size = pm
+                .as_ref()
+                .ok()
+                .filter(|m| dua::is_cloud_placeholder(m))
+                .map(|m| m.len());
+            self.crash_artifact = pm
+                .as_ref()
+                .is_ok_and(|m| m.is_file())
+                .then(|| dua::crash_artifact_of(&path))
+                .flatten();
+            self.hard_links = pm.ok().and_then(|m| dua::hard_links_of(&m));
+            self.has_metadata = true;
+        }
+        self
+    }
+}
+
+/// The time the entry or anything in it was last modified, if known.
+fn newest(entry: &EntryDataBundle) -> Option<u32> {
+    entry.data.mtime_range.map(|range| range.newest)
+}
+
+pub fn sorted_entries(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    sorting: SortMode,
+    filters: &[EntryFilter],
+) -> Vec<EntryDataBundle> {
+    partially_sorted_entries(tree, node_idx, sorting, filters, usize::MAX)
 }
 
-pub fn sorted_entries(tree: &Tree, node_idx: TreeIndex, sorting: SortMode) -> Vec<EntryDataBundle> {
+/// Like [`sorted_entries()`], but only the first `count` entries are in order, followed by all others in no
+/// particular order. This is enough for entries which are sorted again soon, like while scanning.
+pub fn partially_sorted_entries(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    sorting: SortMode,
+    filters: &[EntryFilter],
+    count: usize,
+) -> Vec<EntryDataBundle> {
     use SortMode::*;
-    tree.neighbors_directed(node_idx, Direction::Outgoing)
+    let query_metadata = filters.iter().any(EntryFilter::needs_metadata)
+        || tree
+            .neighbors_directed(node_idx, Direction::Outgoing)
+            .nth(EAGER_METADATA_LIMIT)
+            .is_none();
+    // names are lowercased only once per entry instead of once per comparison
+    let name_key = |entry: &EntryDataBundle| {
+        (sorting == NameAscending).then(|| entry.data.name.to_string_lossy().to_lowercase())
+    };
+    let mut entries: Vec<_> = tree
+        .neighbors_directed(node_idx, Direction::Outgoing)
         .filter_map(|idx| {
-            tree.node_weight(idx).map(|w| {
-                let p = path_of(tree, idx);
-                let pm = p.symlink_metadata();
-                EntryDataBundle {
-                    index: idx,
-                    data: w.clone(),
-                    exists: pm.is_ok(),
-                    is_dir: pm.ok().map_or(false, |m| m.is_dir()),
-                }
-            })
-        })
-        .sorted_by(|l, r| match sorting {
-            SizeDescending => r.data.size.cmp(&l.data.size),
-            SizeAscending => l.data.size.cmp(&r.data.size),
+            tree.node_weight(idx)
+                .map(|w| EntryDataBundle::new(tree, idx, w, query_metadata))
         })
-        .collect()
+        .filter(|entry| filters.iter().all(|f| f.matches(entry)))
+        .map(|entry| (name_key(&entry), entry))
+        .collect();
+    let compare = |(l_name, l): &(Option<String>, EntryDataBundle),
+                   (r_name, r): &(Option<String>, EntryDataBundle)| match sorting
+    {
+        SizeDescending => r.data.size.cmp(&l.data.size),
+        SizeAscending => l.data.size.cmp(&r.data.size),
+        GrowthDescending => {
+            (r.data.recent_size, r.data.size).cmp(&(l.data.recent_size, l.data.size))
+        }
+        NameAscending => l_name
+            .cmp(r_name)
+            .then_with(|| l.data.name.as_os_str().cmp(r.data.name.as_os_str())),
+        // entries without a modification time come last either way
+        MtimeDescending => newest(r).cmp(&newest(l)),
+        MtimeAscending => newest(l)
+            .is_none()
+            .cmp(&newest(r).is_none())
+            .then_with(|| newest(l).cmp(&newest(r))),
+        CountDescending => {
+            (r.data.entry_count, r.data.size).cmp(&(l.data.entry_count, l.data.size))
+        }
+    };
+    if count < entries.len() {
+        entries.select_nth_unstable_by(count, compare);
+        entries[..count].sort_by(compare);
+    } else {
+        entries.sort_by(compare);
+    }
+    entries.into_iter().map(|(_, entry)| entry).collect()
 }
 
 pub fn fit_string_graphemes_with_ellipsis(
@@ -91,6 +315,198 @@ pub fn fit_string_graphemes_with_ellipsis(
 mod tests {
     use super::*;
 
+    #[test]
+    fn entries_are_sorted_by_name_modification_time_and_count() {
+        use dua::traverse::MtimeRange;
+        let mut tree = Tree::new();
+        let root = tree.add_node(EntryData::default());
+        for (name, newest, entry_count) in [
+            ("b", Some(20), 3),
+            ("C", None, 1_000_000),
+            ("a", Some(10), 0),
+        ] {
+            let idx = tree.add_node(EntryData {
+                name: name.into(),
+                entry_count,
+                mtime_range: newest.map(|newest| MtimeRange {
+                    oldest: newest,
+                    newest,
+                }),
+                ..Default::default()
+            });
+            tree.add_edge(root, idx, ());
+        }
+        let names = |sorting| {
+            sorted_entries(&tree, root, sorting, &[])
+                .into_iter()
+                .map(|e| e.data.name.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(SortMode::NameAscending),
+            vec!["a", "b", "C"],
+            "ignoring case"
+        );
+        assert_eq!(
+            names(SortMode::MtimeDescending),
+            vec!["b", "a", "C"],
+            "unknown times come last"
+        );
+        assert_eq!(names(SortMode::MtimeAscending), vec!["a", "b", "C"]);
+        assert_eq!(
+            names(SortMode::CountDescending),
+            vec!["C", "b", "a"],
+            "the most entries first"
+        );
+    }
+
+    #[test]
+    fn partially_sorted_entries_start_like_sorted_entries() {
+        let mut tree = Tree::new();
+        let root = tree.add_node(EntryData::default());
+        for size in [5, 1, 9, 3, 7, 2, 8] {
+            let idx = tree.add_node(EntryData {
+                name: format!("{}", size).into(),
+                size,
+                ..Default::default()
+            });
+            tree.add_edge(root, idx, ());
+        }
+        let sizes = |entries: Vec<EntryDataBundle>| {
+            entries.into_iter().map(|e| e.data.size).collect::<Vec<_>>()
+        };
+        let sorted = sizes(sorted_entries(&tree, root, SortMode::SizeDescending, &[]));
+        assert_eq!(sorted, vec![9, 8, 7, 5, 3, 2, 1]);
+        let partial = sizes(partially_sorted_entries(
+            &tree,
+            root,
+            SortMode::SizeDescending,
+            &[],
+            3,
+        ));
+        assert_eq!(partial[..3], sorted[..3]);
+        assert_eq!(partial.len(), sorted.len(), "no entry is left out");
+        assert_eq!(
+            sizes(partially_sorted_entries(
+                &tree,
+                root,
+                SortMode::SizeDescending,
+                &[],
+                100
+            )),
+            sorted
+        );
+    }
+
+    #[test]
+    fn toggle_filter_adds_and_removes() {
+        let mut filters = Vec::new();
+        assert!(toggle_filter(&mut filters, EntryFilter::HardLinked));
+        assert_eq!(filters, vec![EntryFilter::HardLinked]);
+        assert!(!toggle_filter(&mut filters, EntryFilter::HardLinked));
+        assert!(filters.is_empty());
+    }
+
+    #[test]
+    fn hard_linked_filter_keeps_directories() {
+        let bundle = |is_dir, hard_links| EntryDataBundle {
+            index: TreeIndex::new(0),
+            data: Default::default(),
+            is_dir,
+            exists: true,
+            hard_links,
+            cloud_size: None,
+            crash_artifact: None,
+            has_metadata: true,
+        };
+        let filter = EntryFilter::HardLinked;
+        assert!(filter.matches(&bundle(true, Some(2))));
+        assert!(filter.matches(&bundle(true, None)));
+        assert!(filter.matches(&bundle(false, Some(2))));
+        assert!(!filter.matches(&bundle(false, Some(1))));
+        assert!(!filter.matches(&bundle(false, None)));
+    }
+
+    #[test]
+    fn locally_available_filter_drops_cloud_placeholders() {
+        let bundle = |cloud_size| EntryDataBundle {
+            index: TreeIndex::new(0),
+            data: Default::default(),
+            is_dir: false,
+            exists: true,
+            hard_links: None,
+            cloud_size,
+            crash_artifact: None,
+            has_metadata: true,
+        };
+        let filter = EntryFilter::LocallyAvailable;
+        assert!(filter.needs_metadata());
+        assert!(filter.matches(&bundle(None)));
+        assert!(!filter.matches(&bundle(Some(0))));
+        assert!(!filter.matches(&bundle(Some(1024))));
+    }
+
+    #[test]
+    fn not_hidden_filter_drops_dot_entries() {
+        let bundle = |name: &str| EntryDataBundle {
+            index: TreeIndex::new(0),
+            data: EntryData {
+                name: name.into(),
+                ..Default::default()
+            },
+            is_dir: false,
+            exists: true,
+            hard_links: None,
+            cloud_size: None,
+            crash_artifact: None,
+            has_metadata: false,
+        };
+        let filter = EntryFilter::NotHidden;
+        assert!(filter.matches(&bundle("visible")));
+        assert!(filter.matches(&bundle("not.hidden")));
+        assert!(!filter.matches(&bundle(".hidden")));
+    }
+
+    #[test]
+    fn name_filter_matches_characters_in_order() {
+        let matches = |query: &str, name: &str| {
+            EntryFilter::Name(query.into()).matches(&EntryDataBundle {
+                index: TreeIndex::new(0),
+                data: EntryData {
+                    name: name.into(),
+                    ..Default::default()
+                },
+                is_dir: false,
+                exists: true,
+                hard_links: None,
+                cloud_size: None,
+                crash_artifact: None,
+                has_metadata: false,
+            })
+        };
+        assert!(matches("log", "debug.log"), "substrings");
+        assert!(matches("dlg", "debug.log"), "characters in order");
+        assert!(!matches("gld", "debug.log"));
+        assert!(matches("LOG", "Debug.log"), "regardless of case");
+        assert!(matches("caf\u{e9}", "cafe\u{301}.txt"), "and normalization");
+        assert!(matches("", "anything"));
+    }
+
+    #[test]
+    fn find_matches_globs_or_text() {
+        assert!(find_matches("core.*", "core.1234"));
+        assert!(
+            !find_matches("core.*", "score.1234"),
+            "globs match whole names"
+        );
+        assert!(
+            find_matches("Core", "hardcore.txt"),
+            "text is found anywhere"
+        );
+        assert!(!find_matches("dlg", "debug.log"), "but not spread out");
+        assert!(find_matches("caf\u{e9}.*", "Cafe\u{301}.txt"));
+    }
+
     #[test]
     fn fit_string_inputs() {
         assert_eq!(
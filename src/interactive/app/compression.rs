@@ -0,0 +1,140 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// The amount of files to sample at most
+const MAX_SAMPLES: usize = 32;
+/// The amount of bytes to read from each sampled file, which is also the size of the extents btrfs compresses individually
+const SAMPLE_SIZE: u64 = 128 * 1024;
+/// Compressors to try in order, along with their arguments. zstd at level 3 is what btrfs uses by default.
+const COMPRESSORS: &[(&str, &[&str])] = &[("zstd", &["-3", "-c"]), ("gzip", &["-6", "-c"])];
+
+/// How well a sample of files compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionEstimate {
+    pub compressor: &'static str,
+    pub sampled_files: usize,
+    pub sampled_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+impl CompressionEstimate {
+    /// The amount of bytes likely saved if all `total_bytes` compressed like the sample.
+    pub fn savings(&self, total_bytes: u128) -> u128 {
+        match self.sampled_bytes {
+            0 => 0,
+            sampled => total_bytes * (sampled - self.compressed_bytes) as u128 / sampled as u128,
+        }
+    }
+}
+
+/// Compress the beginning of some of the given `files`, spread evenly, to estimate how well all of them would compress.
+///
+/// Like transparent filesystem compression, data that doesn't get smaller is counted as stored uncompressed.
+pub fn estimate_compression(files: &[PathBuf]) -> Result<CompressionEstimate> {
+    let (compressor, args) = COMPRESSORS
+        .iter()
+        .find(|(program, _)| Command::new(program).arg("--version").output().is_ok())
+        .context("Neither 'zstd' nor 'gzip' could be found")?;
+    let mut estimate = CompressionEstimate {
+        compressor,
+        sampled_files: 0,
+        sampled_bytes: 0,
+        compressed_bytes: 0,
+    };
+    for path in sample_evenly(files, MAX_SAMPLES) {
+        let mut sample = Vec::new();
+        match fs::File::open(path).and_then(|f| f.take(SAMPLE_SIZE).read_to_end(&mut sample)) {
+            Ok(0) | Err(_) => continue,
+            Ok(_) => {}
+        }
+        let sample_len = sample.len() as u64;
+        let compressed = compressed_len(compressor, args, sample)?;
+        estimate.sampled_files += 1;
+        estimate.sampled_bytes += sample_len;
+        estimate.compressed_bytes += compressed.min(sample_len);
+    }
+    Ok(estimate)
+}
+
+fn compressed_len(program: &str, args: &[&str], input: Vec<u8>) -> Result<u64> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Could not run '{}'", program))?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    // write from another thread as the output pipe could fill up before all input is consumed
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let len = io::copy(
+        &mut child.stdout.take().expect("piped stdout"),
+        &mut io::sink(),
+    )?;
+    writer.join().expect("no panic")?;
+    if !child.wait()?.success() {
+        bail!("'{}' failed to compress", program);
+    }
+    Ok(len)
+}
+
+/// Pick at most `n` items from `items`, evenly spaced and including the first one.
+fn sample_evenly<T>(items: &[T], n: usize) -> impl Iterator<Item = &T> {
+    let step = items.len().div_ceil(n.max(1)).max(1);
+    items.iter().step_by(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    fn samples_are_spread_evenly() {
+        let items: Vec<_> = (0..10).collect();
+        let sample = |n| sample_evenly(&items, n).copied().collect::<Vec<_>>();
+        assert_eq!(sample(32), items, "all items if there are few");
+        assert_eq!(sample(5), vec![0, 2, 4, 6, 8]);
+        assert_eq!(sample(3), vec![0, 4, 8]);
+        assert_eq!(sample(1), vec![0]);
+        assert_eq!(sample_evenly(&[] as &[u8], 3).count(), 0);
+    }
+
+    #[test]
+    fn savings_are_extrapolated_from_the_sample() {
+        let estimate = CompressionEstimate {
+            compressor: "zstd",
+            sampled_files: 2,
+            sampled_bytes: 1000,
+            compressed_bytes: 250,
+        };
+        assert_eq!(estimate.savings(10_000), 7500);
+        assert_eq!(
+            CompressionEstimate {
+                sampled_bytes: 0,
+                compressed_bytes: 0,
+                ..estimate
+            }
+            .savings(10_000),
+            0,
+            "nothing could be sampled"
+        );
+    }
+
+    #[test]
+    fn repetitive_files_compress_well() -> Result<()> {
+        let dir = WritableFixture::empty("compression-estimate");
+        let path = dir.join("estimate.txt");
+        fs::write(&path, "all work and no play\n".repeat(10_000))?;
+        let estimate = estimate_compression(&[path.clone(), path.with_extension("missing")])?;
+        assert_eq!(estimate.sampled_files, 1, "unreadable files are skipped");
+        assert_eq!(estimate.sampled_bytes, SAMPLE_SIZE);
+        assert!(estimate.savings(100) > 90, "{:?}", estimate);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use crate::interactive::Category;
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// Preferences of the interactive mode which are kept across sessions, stored as `key = value` lines.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The length of the bars in the entries pane, in columns
+    pub bar_width: Option<usize>,
+    /// The groups of files to sum up in the categories pane, in the order they are matched
+    pub categories: Vec<Category>,
+    /// A shell command to run for each entry before deleting it, which is kept if the command fails
+    pub before_delete: Option<String>,
+    /// The file to append each deleted entry to, along with its size and the time of deletion
+    pub deletion_log: Option<PathBuf>,
+    /// Paths which must never be deleted, nor the directories containing them
+    pub protected: Vec<PathBuf>,
+    /// The amount of threads removing files at once while deleting directories, or 0 for one per logical processor
+    pub deletion_workers: Option<usize>,
+}
+
+impl Config {
+    /// The path to the configuration file within the configuration directory of the user, if it can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                if cfg!(windows) {
+                    std::env::var_os("APPDATA").map(PathBuf::from)
+                } else {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                }
+            })?;
+        Some(config_dir.join("dua-cli").join("config"))
+    }
+
+    /// Read the configuration at `path`, which is empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Config> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text)
+                .with_context(|| format!("Invalid configuration in '{}'", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err)
+                .with_context(|| format!("Could not read configuration at '{}'", path.display())),
+        }
+    }
+
+    /// Set the bar width in the configuration at `path` to `width`, creating the file and its parent directories as
+    /// needed. Only the `bar-width` line is rewritten, all other lines are kept as they are.
+    pub fn store_bar_width(path: &Path, width: usize) -> Result<()> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                String::from("# Written by dua interactive mode\n")
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Could not read configuration at '{}'", path.display())
+                })
+            }
+        };
+        fs::write(path, with_bar_width(&text, width))
+            .with_context(|| format!("Could not write configuration to '{}'", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Config> {
+        let mut config = Config::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => bail!("Expected 'key = value', got '{}'", line),
+            };
+            // Keys of newer versions are ignored so downgrading doesn't break anything
+            if key == "bar-width" {
+                config.bar_width = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid bar-width '{}'", value))?,
+                );
+            } else if key == "category" {
+                config.categories.push(value.parse()?);
+            } else if key == "deletion-log" {
+                config.deletion_log =
+                    Some(PathBuf::from(value)).filter(|p| !p.as_os_str().is_empty());
+            } else if key == "deletion-workers" {
+                config.deletion_workers = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid deletion-workers '{}'", value))?,
+                );
+            } else if key == "protected" {
+                config.protected.push(value.into());
+            } else if key == "before-delete" {
+                config.before_delete = Some(value.into()).filter(|c: &String| !c.is_empty());
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// `text` with the value of each `bar-width` line replaced by `width`, or with such a line appended if there is none.
+fn with_bar_width(text: &str, width: usize) -> String {
+    let line = format!("bar-width = {}", width);
+    let mut found = false;
+    let mut out = String::with_capacity(text.len() + line.len() + 1);
+    for current in text.split_inclusive('\n') {
+        let is_bar_width = current
+            .split_once('=')
+            .is_some_and(|(key, _)| key.trim() == "bar-width");
+        if is_bar_width {
+            found = true;
+            out.push_str(&line);
+            out.push_str(&current[current.trim_end_matches(['\r', '\n']).len()..]);
+        } else {
+            out.push_str(current);
+        }
+    }
+    if !found {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_is_parsed_from_text() -> Result<()> {
+        let config = Config {
+            bar_width: Some(14),
+            categories: vec!["Images: *.png, *.jpg".parse()?, "Logs: *.log".parse()?],
+            before_delete: Some("rsync -a \"$1\" backup:/archive/".into()),
+            deletion_log: Some("/var/log/dua-deletions.log".into()),
+            protected: vec!["/".into(), "/home".into()],
+            deletion_workers: Some(16),
+        };
+        let text = "# preferences\n\
+                    bar-width = 14\n\
+                    category = Images: *.png, *.jpg\n\
+                    category = Logs: *.log\n\
+                    before-delete = rsync -a \"$1\" backup:/archive/\n\
+                    deletion-log = /var/log/dua-deletions.log\n\
+                    deletion-workers = 16\n\
+                    protected = /\n\
+                    protected = /home\n";
+        assert_eq!(Config::parse(text)?, config);
+        assert_eq!(Config::parse("")?, Config::default());
+        assert_eq!(
+            Config::parse("  bar-width=3 \n\nunknown = key\n")?.bar_width,
+            Some(3)
+        );
+        assert_eq!(Config::parse("before-delete =")?.before_delete, None);
+        assert!(Config::parse("bar-width = wide").is_err());
+        assert!(Config::parse("bar-width").is_err());
+        assert!(Config::parse("deletion-workers = -1").is_err());
+        assert!(Config::parse("category = *.log").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn storing_the_bar_width_keeps_all_other_lines() {
+        let text =
+            "# my settings\r\nunknown = key\r\n  bar-width = 3\r\nprotected = /home\n# the end";
+        assert_eq!(
+            with_bar_width(text, 20),
+            "# my settings\r\nunknown = key\r\nbar-width = 20\r\nprotected = /home\n# the end"
+        );
+        assert_eq!(
+            with_bar_width("protected = /\n# the end", 20),
+            "protected = /\n# the end\nbar-width = 20\n"
+        );
+        assert_eq!(with_bar_width("", 20), "bar-width = 20\n");
+    }
+}
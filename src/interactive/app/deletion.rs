@@ -0,0 +1,153 @@
+use crate::interactive::widgets::format_utc_rfc3339;
+use anyhow::{Context, Result};
+use crosstermion::input::Key;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Allows to stop a running deletion of marked entries, which only learns about the keys pressed meanwhile through
+/// another thread, as it blocks the one handling them.
+#[derive(Debug, Default, Clone)]
+pub struct DeletionAbort(Arc<DeletionAbortState>);
+
+#[derive(Debug, Default)]
+struct DeletionAbortState {
+    running: AtomicBool,
+    aborted: AtomicBool,
+}
+
+impl DeletionAbort {
+    pub fn start(&self) {
+        self.0.running.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the deletion, and forget that it was aborted so the next one runs.
+    pub fn finish(&self) {
+        self.0.running.store(false, Ordering::SeqCst);
+        self.0.aborted.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.0.running.load(Ordering::SeqCst)
+    }
+
+    pub fn abort(&self) {
+        self.0.aborted.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.0.aborted.load(Ordering::SeqCst)
+    }
+}
+
+/// Forward all `keys` to the returned channel, except for `Esc` while a deletion is running, which aborts it instead.
+pub fn forward_keys_aborting_deletions(keys: Receiver<Key>, abort: DeletionAbort) -> Receiver<Key> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for key in keys {
+            if matches!(key, Key::Esc) && abort.is_running() {
+                abort.abort();
+                continue;
+            }
+            if tx.send(key).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// A file each deleted entry is appended to as `<time>\t<size>\t<path>` line, to audit deletions later
+#[derive(Debug)]
+pub struct DeletionLog {
+    path: PathBuf,
+    file: fs::File,
+}
+
+impl DeletionLog {
+    /// Open the log at `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Could not open the deletion log at '{}'", path.display()))?;
+        Ok(DeletionLog {
+            path: path.to_owned(),
+            file,
+        })
+    }
+
+    /// Record that `deleted` was deleted just now, freeing `size` bytes.
+    pub fn record(&mut self, deleted: &Path, size: u128) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs().min(u32::MAX as u64) as u32);
+        self.record_at(now, deleted, size)
+    }
+
+    fn record_at(&mut self, secs_since_epoch: u32, deleted: &Path, size: u128) -> Result<()> {
+        // one write per line, so lines of concurrent sessions don't interleave
+        let line = format!(
+            "{}\t{}\t{}\n",
+            format_utc_rfc3339(secs_since_epoch),
+            size,
+            deleted.display()
+        );
+        self.file
+            .write_all(line.as_bytes())
+            .with_context(|| format!("Could not write to '{}'", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    fn deletions_are_appended_to_the_log() -> Result<()> {
+        let dir = WritableFixture::empty("deletion-log");
+        let path = dir.join("log");
+        DeletionLog::open(&path)?.record_at(1_615_734_545, Path::new("/data/old"), 1234)?;
+        DeletionLog::open(&path)?.record_at(0, Path::new("a b"), 0)?;
+        let log = fs::read_to_string(&path)?;
+
+        assert_eq!(
+            log,
+            "2021-03-14T15:09:05Z\t1234\t/data/old\n1970-01-01T00:00:00Z\t0\ta b\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escape_aborts_running_deletions_instead_of_being_forwarded() {
+        let abort = DeletionAbort::default();
+        let (tx, keys) = mpsc::channel();
+        let forwarded = forward_keys_aborting_deletions(keys, abort.clone());
+
+        tx.send(Key::Esc).unwrap();
+        assert!(
+            matches!(forwarded.recv(), Ok(Key::Esc)),
+            "without deletion it's a normal key"
+        );
+        assert!(!abort.is_aborted());
+
+        abort.start();
+        tx.send(Key::Esc).unwrap();
+        tx.send(Key::Char('j')).unwrap();
+        assert!(matches!(forwarded.recv(), Ok(Key::Char('j'))));
+        assert!(abort.is_aborted());
+
+        abort.finish();
+        assert!(!abort.is_aborted(), "the next deletion isn't aborted");
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// What deleting an entry would do, as found by [`dry_run_deletion()`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DryRun {
+    /// The amount of files and symbolic links that would be removed
+    pub files: u64,
+    /// The amount of directories that would be removed, which are those without failures below them
+    pub directories: u64,
+    /// The apparent size of the files that would be removed
+    pub bytes: u128,
+    /// The paths that couldn't be removed along with the reason, in the order they were found
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+impl DryRun {
+    pub fn add(&mut self, other: DryRun) {
+        self.files += other.files;
+        self.directories += other.directories;
+        self.bytes += other.bytes;
+        self.failures.extend(other.failures);
+    }
+}
+
+/// The user we run as, to tell whether permissions allow removing something
+#[derive(Debug, Clone, Copy)]
+struct User {
+    uid: u32,
+    gid: u32,
+}
+
+impl User {
+    /// On linux, `/proc/self` belongs to the effective user and group of the process. Elsewhere they are unknown.
+    #[cfg(unix)]
+    fn current() -> Option<User> {
+        use std::os::unix::fs::MetadataExt;
+        fs::metadata("/proc/self").ok().map(|m| User {
+            uid: m.uid(),
+            gid: m.gid(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn current() -> Option<User> {
+        None
+    }
+}
+
+/// Visit `path` and everything below it like a deletion would, without removing anything, and check if the
+/// permissions allow removing each entry.
+///
+/// Supplementary groups of the user are unknown, so entries only removable through one of them are reported as failing.
+pub fn dry_run_deletion(path: &Path) -> DryRun {
+    let parent = match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Some(Path::new(".")),
+        parent => parent,
+    };
+    let parent = parent.and_then(|p| fs::metadata(p).ok());
+    let mut report = DryRun::default();
+    visit(path, parent.as_ref(), User::current(), &mut report);
+    report
+}
+
+/// Returns `true` if `path` and everything below it could be removed.
+fn visit(
+    path: &Path,
+    parent: Option<&fs::Metadata>,
+    user: Option<User>,
+    report: &mut DryRun,
+) -> bool {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return true,
+        Err(err) => {
+            report.failures.push((path.to_owned(), err.to_string()));
+            return false;
+        }
+    };
+    let mut removable = match parent.and_then(|parent| removal_blocked(parent, &meta, user)) {
+        Some(reason) => {
+            report.failures.push((path.to_owned(), reason.into()));
+            false
+        }
+        None => true,
+    };
+    if meta.is_dir() {
+        // like a deletion, we try to remove everything below directories we can't remove ourselves
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries {
+                    match entry {
+                        Ok(entry) => removable &= visit(&entry.path(), Some(&meta), user, report),
+                        Err(err) => {
+                            report.failures.push((path.to_owned(), err.to_string()));
+                            removable = false;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                report.failures.push((path.to_owned(), err.to_string()));
+                removable = false;
+            }
+        }
+        if removable {
+            report.directories += 1;
+        }
+    } else if removable {
+        report.files += 1;
+        report.bytes += meta.len() as u128;
+    }
+    removable
+}
+
+/// Why the permissions of the `parent` directory prevent removing `entry`, if they do.
+#[cfg(unix)]
+fn removal_blocked(
+    parent: &fs::Metadata,
+    entry: &fs::Metadata,
+    user: Option<User>,
+) -> Option<&'static str> {
+    use std::os::unix::fs::MetadataExt;
+    const STICKY: u32 = 0o1000;
+    let mode = parent.mode();
+    let user = match user {
+        Some(user) if user.uid == 0 => return None,
+        Some(user) => user,
+        None => {
+            return (mode & 0o222 == 0).then_some("its directory is read-only");
+        }
+    };
+    let write_and_search = if user.uid == parent.uid() {
+        mode >> 6 & 0o3
+    } else if user.gid == parent.gid() {
+        mode >> 3 & 0o3
+    } else {
+        mode & 0o3
+    };
+    if write_and_search != 0o3 {
+        return Some("its directory isn't writable");
+    }
+    if mode & STICKY != 0 && user.uid != entry.uid() && user.uid != parent.uid() {
+        return Some("its directory is sticky and it belongs to someone else");
+    }
+    None
+}
+
+/// Files which are read-only can't be removed on Windows.
+#[cfg(not(unix))]
+fn removal_blocked(
+    _parent: &fs::Metadata,
+    entry: &fs::Metadata,
+    _user: Option<User>,
+) -> Option<&'static str> {
+    (!entry.is_dir() && entry.permissions().readonly()).then_some("it is read-only")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    #[cfg(unix)]
+    fn permissions_of_directories_decide_what_would_fail() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = WritableFixture::empty("dry-run");
+        fs::create_dir_all(root.join("open"))?;
+        fs::create_dir_all(root.join("locked/sub"))?;
+        fs::write(root.join("open/a"), b"abc")?;
+        fs::write(root.join("locked/b"), b"de")?;
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o755))?;
+        fs::set_permissions(root.join("open"), fs::Permissions::from_mode(0o777))?;
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o755))?;
+        let parent = fs::metadata(&root)?;
+        // someone else than the owner of the files, who are created by the user running the tests
+        let other = Some(User {
+            uid: u32::MAX - 1,
+            gid: u32::MAX - 1,
+        });
+
+        let mut open = DryRun::default();
+        visit(&root.join("open"), None, other, &mut open);
+        let mut locked = DryRun::default();
+        visit(&root.join("locked"), Some(&parent), other, &mut locked);
+        let mut as_owner = DryRun::default();
+        visit(&root.join("locked"), None, User::current(), &mut as_owner);
+
+        assert_eq!(
+            open,
+            DryRun {
+                files: 1,
+                directories: 1,
+                bytes: 3,
+                failures: Vec::new()
+            }
+        );
+        assert_eq!(locked.files + locked.directories, 0);
+        let mut failures: Vec<_> = locked.failures.into_iter().map(|(p, _)| p).collect();
+        failures.sort();
+        assert_eq!(
+            failures,
+            vec![
+                root.join("locked"),
+                root.join("locked/b"),
+                root.join("locked/sub")
+            ],
+            "nothing in a directory without write permission can be removed, nor the directory itself"
+        );
+        assert_eq!(
+            (
+                as_owner.files,
+                as_owner.directories,
+                as_owner.failures.len()
+            ),
+            (1, 2, 0)
+        );
+        Ok(())
+    }
+}
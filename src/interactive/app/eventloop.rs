@@ -1,30 +1,33 @@
 use crate::interactive::{
-    sorted_entries,
-    widgets::{MainWindow, MainWindowProps},
-    ByteVisualization, CursorDirection, CursorMode, DisplayOptions, EntryDataBundle, MarkEntryMode,
-    SortMode,
+    forward_keys_aborting_deletions, partially_sorted_entries, path_of, sorted_entries, title,
+    widgets::{color_depth_from_env, row_shading_from_env, MainWindow, MainWindowProps},
+    ByteVisualization, Category, Config, CursorDirection, CursorMode, DeletionAbort, DeletionLog,
+    DisplayOptions, EntryDataBundle, EntryFilter, MarkEntryMode, ProtectedPaths, SortMode,
+    BAR_WIDTH_RANGE,
 };
 use anyhow::Result;
 use crosstermion::input::{key_input_channel, Key};
 use dua::{
+    load_snapshot,
     traverse::{Traversal, TreeIndex},
-    WalkOptions, WalkResult,
+    Baseline, Filesystem, WalkOptions, WalkResult,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
 };
-use std::{collections::BTreeMap, path::PathBuf};
 use tui::backend::Backend;
 use tui_react::Terminal;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub enum FocussedPane {
+    #[default]
     Main,
     Help,
     Mark,
-}
-
-impl Default for FocussedPane {
-    fn default() -> Self {
-        FocussedPane::Main
-    }
+    Trash,
+    Find,
+    Suggestions,
 }
 
 #[derive(Default)]
@@ -33,10 +36,105 @@ pub struct AppState {
     pub selected: Option<TreeIndex>,
     pub entries: Vec<EntryDataBundle>,
     pub sorting: SortMode,
+    pub filters: Vec<EntryFilter>,
+    /// The index into `filters` of the chip selected for removal
+    pub selected_filter: Option<usize>,
     pub message: Option<String>,
     pub focussed: FocussedPane,
     pub bookmarks: BTreeMap<TreeIndex, TreeIndex>,
     pub is_scanning: bool,
+    /// If set, the entries were loaded from a snapshot or listed from a bucket rather than scanned, so nothing on disk
+    /// is changed through them
+    pub is_read_only: bool,
+    /// If set, the scan is paused until it is resumed by the user
+    pub is_paused: bool,
+    /// If set, entering a directory descends through all directories that contain only a single directory
+    pub auto_descend: bool,
+    /// Entries the user wants to revisit or report, independently of marks for deletion
+    pub flagged: BTreeSet<TreeIndex>,
+    /// The prompt the user is currently typing into, which receives all keys
+    pub prompt: Option<Prompt>,
+    /// Where to persist preferences changed during the session, if anywhere
+    pub config_path: Option<PathBuf>,
+    /// The groups of files to sum up in the categories pane, as configured
+    pub categories: Vec<Category>,
+    /// The command to run for each entry before deleting it, as configured
+    pub before_delete: Option<String>,
+    /// The paths which must not be marked or deleted, as configured
+    pub protected: ProtectedPaths,
+    /// The amount of threads removing files at once while deleting directories as configured, or 0 for one per
+    /// logical processor
+    pub deletion_workers: usize,
+    /// If set, rescanning all inputs only reads directories whose modification time changed since the scan
+    pub trust_mtime: bool,
+    /// The directory proposed to move marked entries into, if set on the command line
+    pub move_to: Option<PathBuf>,
+    /// Commands run from the command line, the most recent one last
+    pub command_history: Vec<String>,
+    /// The directory we just ascended from, highlighted in its parent until the next key is pressed
+    pub came_from: Option<TreeIndex>,
+    /// The sizes of entries in an earlier scan, if we compare against one
+    pub baseline: Option<Baseline>,
+    /// The amount of bytes after which deleting marked entries pauses, leaving the remaining ones marked
+    pub deletion_cap: Option<u128>,
+    /// The entry to delete once its name was typed into the [`PromptKind::QuickDelete`] prompt, along with its
+    /// path and size as shown in it
+    pub quick_delete: Option<(TreeIndex, String)>,
+    /// The amount and size of the marked entries to delete once confirmed in the [`PromptKind::ConfirmDeletion`]
+    /// prompt, as shown in it
+    pub pending_deletion: Option<String>,
+    /// Raised while deleting marked entries to stop before the next file
+    pub deletion_abort: DeletionAbort,
+    /// Where deleted entries are recorded, as configured
+    pub deletion_log: Option<DeletionLog>,
+    /// Why the deletion log couldn't be written since this was last shown, to not stay unnoticed
+    pub deletion_log_error: Option<anyhow::Error>,
+    /// The directory whose entries show their exact amount of bytes, until another directory is shown
+    pub exact_sizes_of: Option<TreeIndex>,
+    /// The filesystem the current root is on, determined once for each root it is shown for
+    pub root_filesystem: Option<(TreeIndex, Option<Filesystem>)>,
+    /// The entry whose path in the footer is scrolled, and by how many characters towards its beginning
+    pub path_scroll: Option<(TreeIndex, usize)>,
+}
+
+/// What the text typed into a [`Prompt`] will be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// Mark all files larger than the given size below the current directory
+    MarkLargerThan,
+    /// Mark all entries of the current directory matching the given glob, or at any depth if it contains a `/`
+    MarkGlob,
+    /// Write the entries of the current directory to the given file
+    Export,
+    /// Archive all marked entries into the given file, then delete them
+    Archive,
+    /// Move all marked entries into the given directory
+    MoveTo,
+    /// Write the size and BLAKE3 checksum of all marked files to the given file
+    Checksums,
+    /// Run the given [`Command`][crate::interactive::Command]
+    Command,
+    /// Mark the entry at the given path, which may be outside of the scanned paths
+    MarkPath,
+    /// Pause deletions of marked entries once they freed the given size
+    DeletionCap,
+    /// Only show entries whose name matches the given text, updated while typing
+    NameFilter,
+    /// List all entries of the tree whose name matches the given text or glob
+    Find,
+    /// Delete the entry in [`AppState::quick_delete`] if its name was typed
+    QuickDelete,
+    /// Delete all marked entries, as summarized in [`AppState::pending_deletion`], if `yes` was typed
+    ConfirmDeletion,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    /// The text typed so far
+    pub input: String,
+    /// The position in [`AppState::command_history`] the input was recalled from, if any
+    pub history_position: Option<usize>,
 }
 
 pub enum ProcessingResult {
@@ -55,10 +153,21 @@ impl AppState {
     where
         B: Backend,
     {
+        if self
+            .root_filesystem
+            .as_ref()
+            .is_none_or(|(root, _)| *root != self.root)
+        {
+            let path = path_of(&traversal.tree, self.root);
+            let filesystem = (!path.as_os_str().is_empty())
+                .then(|| Filesystem::of(&path))
+                .flatten();
+            self.root_filesystem = Some((self.root, filesystem));
+        }
         let props = MainWindowProps {
-            traversal: &traversal,
+            traversal,
             display,
-            state: &self,
+            state: self,
         };
         draw_window(window, props, terminal)
     }
@@ -68,6 +177,7 @@ impl AppState {
         window: &mut MainWindow,
         traversal: &mut Traversal,
         display: &mut DisplayOptions,
+        walk_options: &mut WalkOptions,
         terminal: &mut Terminal<B>,
         keys: impl Iterator<Item = Key>,
     ) -> Result<ProcessingResult>
@@ -79,6 +189,12 @@ impl AppState {
 
         self.draw(window, traversal, *display, terminal)?;
         for key in keys {
+            if self.prompt.is_some() {
+                self.process_prompt_key(key, window, traversal, display, walk_options, terminal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            self.came_from = None;
             self.reset_message();
             match key {
                 Char('?') => self.toggle_help_pane(window),
@@ -88,18 +204,20 @@ impl AppState {
                 Ctrl('c') => {
                     return Ok(ProcessingResult::ExitRequested(WalkResult {
                         num_errors: traversal.io_errors,
+                        ..Default::default()
                     }))
                 }
                 Char('q') | Esc => match self.focussed {
                     Main => {
                         return Ok(ProcessingResult::ExitRequested(WalkResult {
                             num_errors: traversal.io_errors,
+                            ..Default::default()
                         }))
                     }
                     Mark => self.focussed = Main,
-                    Help => {
+                    Help | Trash | Find | Suggestions => {
                         self.focussed = Main;
-                        window.help_pane = None
+                        window.close_side_panes();
                     }
                 },
                 _ => {}
@@ -109,6 +227,11 @@ impl AppState {
                 FocussedPane::Mark => {
                     self.dispatch_to_mark_pane(key, window, traversal, *display, terminal)
                 }
+                FocussedPane::Trash => self.dispatch_to_trash_pane(key, window),
+                FocussedPane::Find => self.dispatch_to_find_pane(key, window, traversal),
+                FocussedPane::Suggestions => {
+                    self.dispatch_to_suggestions_pane(key, window, traversal)
+                }
                 FocussedPane::Help => {
                     window
                         .help_pane
@@ -140,8 +263,14 @@ impl AppState {
                         self.exit_node_with_traversal(traversal)
                     }
                     Char('o') | Char('l') | Char('\n') | Right => {
-                        self.enter_node_with_traversal(traversal)
+                        self.enter_node_scanning_mounts(window, traversal, walk_options)
+                    }
+                    Char('J') => {
+                        self.enter_sibling_with_traversal(CursorDirection::Down, traversal)
                     }
+                    Char('K') => self.enter_sibling_with_traversal(CursorDirection::Up, traversal),
+                    Char('T') => self.toggle_auto_descend(),
+                    Char('e') => self.toggle_exact_sizes(),
                     Char('H') => self.change_entry_selection(CursorDirection::ToTop),
                     Char('G') => self.change_entry_selection(CursorDirection::ToBottom),
                     Ctrl('u') | PageUp => self.change_entry_selection(CursorDirection::PageUp),
@@ -149,7 +278,66 @@ impl AppState {
                     Char('j') | Down => self.change_entry_selection(CursorDirection::Down),
                     Ctrl('d') | PageDown => self.change_entry_selection(CursorDirection::PageDown),
                     Char('s') => self.cycle_sorting(traversal),
+                    Char('w') => self.toggle_sort_by_growth(traversal),
                     Char('g') => display.byte_vis.cycle(),
+                    Char('v') => display.show_byte_vis = !display.show_byte_vis,
+                    Char('V') => {
+                        display.share_of_total = !display.share_of_total;
+                        self.message = Some(
+                            match display.share_of_total {
+                                true => "Showing shares of all scanned bytes",
+                                false => "Showing shares of the parent directory",
+                            }
+                            .into(),
+                        );
+                    }
+                    Char('<') => self.change_bar_width(display, -1),
+                    Char('>') => self.change_bar_width(display, 1),
+                    Char('b') => {
+                        display.byte_format.cycle();
+                        self.message = Some(format!("Showing sizes as {:?}", display.byte_format));
+                    }
+                    Char('n') => display.show_hard_links = !display.show_hard_links,
+                    Char('c') => display.show_entry_count = !display.show_entry_count,
+                    Char('m') => display.show_mtime = !display.show_mtime,
+                    Char('S') => display.show_summary_row = !display.show_summary_row,
+                    Char('a') => display.highlight_outliers = !display.highlight_outliers,
+                    Char('t') => {
+                        display.show_size_without_marked = !display.show_size_without_marked
+                    }
+                    Char('N') => self.toggle_filter(EntryFilter::HardLinked, traversal),
+                    Char('.') => self.toggle_filter(EntryFilter::NotHidden, traversal),
+                    Char('C') => self.toggle_filter(EntryFilter::LocallyAvailable, traversal),
+                    Char('f') => self.cycle_filter_selection(),
+                    Delete => self.remove_selected_filter(traversal),
+                    Char('E') => self.rescan_permission_denied(traversal, walk_options),
+                    Char('r') => self.refresh_selected(window, traversal, walk_options),
+                    Char('R') => {
+                        self.rescan_all(window, traversal, *display, walk_options, terminal)
+                    }
+                    Char('A') => self.toggle_apparent_size(traversal, walk_options, display),
+                    Char('L') => self.toggle_diagnostics_pane(window, traversal),
+                    Char('B') => self.toggle_categories_pane(window, traversal),
+                    Char('U') => self.open_suggestions_pane(window, traversal),
+                    Char('i') => self.toggle_details_pane(window),
+                    Char('W') => self.open_trash_pane(window),
+                    Char('p') => self.toggle_pause(traversal),
+                    Char('F') => self.toggle_flag(traversal),
+                    Char('Z') => {
+                        self.message = Some("Compressing samples...".into());
+                        self.draw(window, traversal, *display, terminal)?;
+                        self.estimate_compression_savings(traversal, display.byte_format)
+                    }
+                    Char('M') => self.open_prompt(PromptKind::MarkLargerThan),
+                    Char('*') => self.open_prompt(PromptKind::MarkGlob),
+                    Char('X') => self.open_prompt(PromptKind::Export),
+                    Char(':') => self.open_prompt(PromptKind::Command),
+                    Char('+') => self.open_prompt(PromptKind::MarkPath),
+                    Char('/') => self.open_name_filter(),
+                    Char('D') => self.open_quick_delete(traversal, display.byte_format),
+                    Char('[') => self.scroll_selected_path(traversal, true),
+                    Char(']') => self.scroll_selected_path(traversal, false),
+                    Ctrl('f') => self.open_prompt(PromptKind::Find),
                     _ => {}
                 },
             };
@@ -157,6 +345,7 @@ impl AppState {
         }
         Ok(ProcessingResult::Finished(WalkResult {
             num_errors: traversal.io_errors,
+            ..Default::default()
         }))
     }
 }
@@ -170,17 +359,45 @@ where
     B: Backend,
 {
     let area = terminal.pre_render()?;
+    let color_depth = props.display.color_depth;
     window.render(props, area, terminal.current_buffer_mut());
+    color_depth.apply(terminal.current_buffer_mut());
     terminal.post_render()?;
     Ok(())
 }
 
+/// Options of the interactive mode which don't affect the traversal itself
+#[derive(Default)]
+pub struct InteractiveOptions {
+    /// The initial value of [`AppState::auto_descend`]
+    pub auto_descend: bool,
+    /// If set, show Nerd Font icons in front of each entry
+    pub icons: bool,
+    /// If set, shade every other row of the entries pane if the terminal supports it
+    pub row_shading: bool,
+    /// The configuration file to read preferences from, and to store them in when changed
+    pub config_path: Option<PathBuf>,
+    /// If set, the snapshot to show instead of scanning the input paths
+    pub snapshot: Option<PathBuf>,
+    /// If set, a tree built without scanning, like from the objects of a bucket, to show instead of the input paths
+    pub listing: Option<Traversal>,
+    /// If set, the snapshot of an earlier scan to show the growth of each entry against
+    pub compare: Option<PathBuf>,
+    /// If set, the directory proposed to move marked entries into
+    pub move_to: Option<PathBuf>,
+    /// Why some of the inputs given by the user are not scanned, to be shown once the scan is done
+    pub skipped_inputs: Vec<String>,
+    /// The initial value of [`AppState::trust_mtime`]
+    pub trust_mtime: bool,
+}
+
 /// State and methods representing the interactive disk usage analyser for the terminal
 pub struct TerminalApp {
     pub traversal: Traversal,
     pub display: DisplayOptions,
     pub state: AppState,
     pub window: MainWindow,
+    pub walk_options: WalkOptions,
 }
 
 type KeyboardInputAndApp = (std::sync::mpsc::Receiver<Key>, TerminalApp);
@@ -196,11 +413,26 @@ impl TerminalApp {
                 &mut self.window,
                 &mut self.traversal,
                 &mut self.display,
+                &mut self.walk_options,
                 terminal,
                 std::iter::once(Key::Alt('\r')),
             )
             .ok();
     }
+    pub fn rescan_permission_denied<B>(&mut self, terminal: &mut Terminal<B>)
+    where
+        B: Backend,
+    {
+        self.state
+            .rescan_permission_denied(&mut self.traversal, &self.walk_options);
+        self.state
+            .draw(&mut self.window, &self.traversal, self.display, terminal)
+            .ok();
+    }
+    /// Handle all `keys` until the user asks to quit or there are no more keys.
+    ///
+    /// This blocks on `keys` and only wakes up to handle input, so an idle session doesn't use any CPU
+    /// as long as the iterator blocks as well, like the one of the key input channel does.
     pub fn process_events<B>(
         &mut self,
         terminal: &mut Terminal<B>,
@@ -213,6 +445,7 @@ impl TerminalApp {
             &mut self.window,
             &mut self.traversal,
             &mut self.display,
+            &mut self.walk_options,
             terminal,
             keys,
         )? {
@@ -225,6 +458,7 @@ impl TerminalApp {
         options: WalkOptions,
         input_paths: Vec<PathBuf>,
         mode: Interaction,
+        mut interactive_options: InteractiveOptions,
     ) -> Result<Option<KeyboardInputAndApp>>
     where
         B: Backend,
@@ -233,13 +467,35 @@ impl TerminalApp {
         terminal.clear()?;
         let mut display: DisplayOptions = options.clone().into();
         display.byte_vis = ByteVisualization::PercentageAndBar;
+        display.show_icons = interactive_options.icons;
+        display.row_shading = interactive_options
+            .row_shading
+            .then(row_shading_from_env)
+            .flatten();
+        display.color_depth = color_depth_from_env();
+        let config = match interactive_options.config_path.as_deref() {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        if let Some(width) = config.bar_width {
+            display.bar_width = width.clamp(*BAR_WIDTH_RANGE.start(), *BAR_WIDTH_RANGE.end());
+        }
         let mut window = MainWindow::default();
+        let deletion_abort = DeletionAbort::default();
+        let protected = ProtectedPaths::new(&config.protected);
+        let deletion_log = config
+            .deletion_log
+            .as_deref()
+            .map(DeletionLog::open)
+            .transpose()?;
         let keys_rx = match mode {
             Interaction::None => {
                 let (_, keys_rx) = std::sync::mpsc::channel();
                 keys_rx
             }
-            Interaction::Full => key_input_channel(),
+            Interaction::Full => {
+                forward_keys_aborting_deletions(key_input_channel(), deletion_abort.clone())
+            }
         };
 
         let fetch_buffered_key_events = || {
@@ -250,54 +506,130 @@ impl TerminalApp {
             keys
         };
 
+        let set_title = matches!(mode, Interaction::Full);
+        let listing = interactive_options.listing.take();
+        let description = match (interactive_options.snapshot.as_ref(), listing.as_ref()) {
+            (Some(snapshot), _) => title::describe(std::slice::from_ref(snapshot)),
+            (None, Some(listing)) => title::describe(
+                &listing
+                    .tree
+                    .neighbors_directed(listing.root_index, petgraph::Direction::Outgoing)
+                    .map(|idx| listing.tree[idx].name.clone())
+                    .collect::<Vec<_>>(),
+            ),
+            (None, None) => title::describe(&input_paths),
+        };
+        let earlier = match interactive_options.compare.as_deref() {
+            Some(snapshot) => Some(load_snapshot(snapshot, options.apparent_size)?),
+            None => None,
+        };
         let mut state = None::<AppState>;
         let mut received_events = false;
-        let traversal = Traversal::from_walk(options, input_paths, |traversal| {
-            let s = match state.as_mut() {
-                Some(s) => {
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
-                    if !received_events {
-                        s.selected = s.entries.get(0).map(|b| b.index);
-                    }
-                    s
-                }
-                None => {
-                    state = Some({
-                        let sorting = Default::default();
-                        let entries =
-                            sorted_entries(&traversal.tree, traversal.root_index, sorting);
-                        AppState {
-                            root: traversal.root_index,
-                            sorting,
-                            selected: entries.get(0).map(|b| b.index),
-                            entries,
-                            is_scanning: true,
-                            ..Default::default()
+        let mut walk_options = options.clone();
+        let is_read_only = interactive_options.snapshot.is_some() || listing.is_some();
+        let traversal = match (interactive_options.snapshot.as_deref(), listing) {
+            (Some(snapshot), _) => Some(load_snapshot(snapshot, options.apparent_size)?),
+            (None, Some(listing)) => Some(listing),
+            (None, None) => Traversal::from_walk(options, input_paths, |traversal| {
+                let s = match state.as_mut() {
+                    Some(s) => {
+                        // entries are sorted again on the next update, so only those that can be seen are sorted
+                        let shown = s
+                            .selected
+                            .and_then(|selected| s.entries.iter().position(|b| b.index == selected))
+                            .unwrap_or(0)
+                            + terminal
+                                .backend
+                                .size()
+                                .map_or(0, |area| area.height as usize);
+                        s.entries = partially_sorted_entries(
+                            &traversal.tree,
+                            s.root,
+                            s.sorting,
+                            &s.filters,
+                            shown,
+                        );
+                        if !received_events {
+                            s.selected = s.entries.first().map(|b| b.index);
                         }
-                    });
-                    state.as_mut().expect("state to be present, we just set it")
+                        s
+                    }
+                    None => {
+                        state = Some({
+                            let sorting = Default::default();
+                            let entries =
+                                sorted_entries(&traversal.tree, traversal.root_index, sorting, &[]);
+                            AppState {
+                                root: traversal.root_index,
+                                sorting,
+                                selected: entries.first().map(|b| b.index),
+                                entries,
+                                is_scanning: true,
+                                auto_descend: interactive_options.auto_descend,
+                                config_path: interactive_options.config_path.clone(),
+                                categories: config.categories.clone(),
+                                before_delete: config.before_delete.clone(),
+                                protected: protected.clone(),
+                                deletion_workers: config.deletion_workers.unwrap_or_default(),
+                                ..Default::default()
+                            }
+                        });
+                        state.as_mut().expect("state to be present, we just set it")
+                    }
+                };
+                s.reset_message(); // force "scanning" to appear
+                if set_title {
+                    title::set(&format!(
+                        "dua: scanning {} ({} entries)",
+                        description, traversal.entries_traversed
+                    ));
                 }
-            };
-            s.reset_message(); // force "scanning" to appear
-            let events = fetch_buffered_key_events();
-            received_events |= !events.is_empty();
+                let events = fetch_buffered_key_events();
+                received_events |= !events.is_empty();
 
-            let should_exit = match s.process_events(
-                &mut window,
-                traversal,
-                &mut display,
-                terminal,
-                events.into_iter(),
-            )? {
-                ProcessingResult::ExitRequested(_) => true,
-                ProcessingResult::Finished(_) => false,
-            };
-            Ok(should_exit)
-        })?;
+                let mut should_exit = match s.process_events(
+                    &mut window,
+                    traversal,
+                    &mut display,
+                    &mut walk_options,
+                    terminal,
+                    events.into_iter(),
+                )? {
+                    ProcessingResult::ExitRequested(_) => true,
+                    ProcessingResult::Finished(_) => false,
+                };
+                // Workers stop reading directories once paused, so we wait here for keys instead of for them.
+                while s.is_paused && !should_exit {
+                    let key = match keys_rx.recv() {
+                        Ok(key) => key,
+                        Err(_) => break,
+                    };
+                    should_exit = matches!(
+                        s.process_events(
+                            &mut window,
+                            traversal,
+                            &mut display,
+                            &mut walk_options,
+                            terminal,
+                            std::iter::once(key),
+                        )?,
+                        ProcessingResult::ExitRequested(_)
+                    );
+                }
+                if s.is_paused {
+                    s.is_paused = false;
+                    traversal.pause.set(false);
+                }
+                Ok(should_exit)
+            })?,
+        };
         let traversal = match traversal {
             Some(t) => t,
             None => return Ok(None),
         };
+        if set_title {
+            title::set(&format!("dua: {}", description));
+        }
 
         Ok(Some((keys_rx, {
             let mut app = TerminalApp {
@@ -305,26 +637,51 @@ impl TerminalApp {
                     let mut s = state.unwrap_or_else(|| {
                         let sorting = Default::default();
                         let root = traversal.root_index;
-                        let entries = sorted_entries(&traversal.tree, root, sorting);
+                        let entries = sorted_entries(&traversal.tree, root, sorting, &[]);
                         AppState {
                             root,
                             entries,
                             sorting,
+                            auto_descend: interactive_options.auto_descend,
+                            config_path: interactive_options.config_path.clone(),
+                            categories: config.categories.clone(),
+                            before_delete: config.before_delete.clone(),
+                            protected: protected.clone(),
+                            deletion_workers: config.deletion_workers.unwrap_or_default(),
                             ..Default::default()
                         }
                     });
                     s.is_scanning = false;
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
+                    s.is_read_only = is_read_only;
+                    s.deletion_abort = deletion_abort;
+                    s.deletion_log = deletion_log;
+                    s.move_to = interactive_options.move_to.take();
+                    s.trust_mtime = interactive_options.trust_mtime;
+                    s.baseline = earlier.map(|earlier| Baseline::new(earlier, &traversal));
+                    if !interactive_options.skipped_inputs.is_empty() {
+                        s.message = Some(format!(
+                            "Skipped {} input{}: {}",
+                            interactive_options.skipped_inputs.len(),
+                            if interactive_options.skipped_inputs.len() == 1 {
+                                ""
+                            } else {
+                                "s"
+                            },
+                            interactive_options.skipped_inputs.join("; ")
+                        ));
+                    }
+                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting, &s.filters);
                     s.selected = if received_events {
-                        s.selected.or_else(|| s.entries.get(0).map(|b| b.index))
+                        s.selected.or_else(|| s.entries.first().map(|b| b.index))
                     } else {
-                        s.entries.get(0).map(|b| b.index)
+                        s.entries.first().map(|b| b.index)
                     };
                     s
                 },
                 display,
                 traversal,
                 window,
+                walk_options,
             };
             app.refresh_view(terminal);
             app
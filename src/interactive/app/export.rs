@@ -0,0 +1,114 @@
+use dua::json_escape;
+use std::{io, path::Path};
+
+/// The format to write exported entries in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One line per entry with its size in bytes and its path separated by a tab, like `du -b`
+    Du,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Choose the format by the extension of `path`, defaulting to [`ExportFormat::Du`].
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => ExportFormat::Csv,
+            Some("json") => ExportFormat::Json,
+            _ => ExportFormat::Du,
+        }
+    }
+}
+
+/// Write `entries` made of paths and their size in bytes to `out` in the given `format`.
+pub fn write_entries<P: AsRef<Path>>(
+    out: &mut impl io::Write,
+    format: ExportFormat,
+    entries: impl IntoIterator<Item = (P, u128)>,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Du => {
+            for (path, bytes) in entries {
+                writeln!(out, "{}\t{}", bytes, path.as_ref().display())?;
+            }
+        }
+        ExportFormat::Csv => {
+            writeln!(out, "bytes,path")?;
+            for (path, bytes) in entries {
+                let path = path.as_ref().to_string_lossy();
+                writeln!(out, "{},\"{}\"", bytes, path.replace('"', "\"\""))?;
+            }
+        }
+        ExportFormat::Json => {
+            write!(out, "[")?;
+            for (idx, (path, bytes)) in entries.into_iter().enumerate() {
+                write!(
+                    out,
+                    "{}\n  {{\"path\": \"{}\", \"bytes\": {}}}",
+                    if idx == 0 { "" } else { "," },
+                    json_escape(&path.as_ref().to_string_lossy()),
+                    bytes
+                )?;
+            }
+            writeln!(out, "\n]")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        write_entries(
+            &mut out,
+            format,
+            vec![("dir/a \"b\"", 1500), ("dir/c\\d", 3)],
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn it_picks_the_format_by_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("x.CSV")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("x.json")),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("x.txt")),
+            ExportFormat::Du
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("x")), ExportFormat::Du);
+    }
+
+    #[test]
+    fn it_writes_entries_in_all_formats() {
+        assert_eq!(
+            exported(ExportFormat::Du),
+            "1500\tdir/a \"b\"\n3\tdir/c\\d\n"
+        );
+        assert_eq!(
+            exported(ExportFormat::Csv),
+            "bytes,path\n1500,\"dir/a \"\"b\"\"\"\n3,\"dir/c\\d\"\n"
+        );
+        assert_eq!(
+            exported(ExportFormat::Json),
+            "[\n  {\"path\": \"dir/a \\\"b\\\"\", \"bytes\": 1500},\n  {\"path\": \"dir/c\\\\d\", \"bytes\": 3}\n]\n"
+        );
+        let mut out = Vec::new();
+        write_entries(&mut out, ExportFormat::Json, Vec::<(&str, u128)>::new()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[\n]\n");
+    }
+}
@@ -1,17 +1,44 @@
 use crate::interactive::{
-    app::FocussedPane::*,
-    path_of, sorted_entries,
-    widgets::{HelpPane, MainWindow, MarkMode, MarkPane},
-    AppState, DisplayOptions, EntryDataBundle,
+    absolute_path_of,
+    app::FocussedPane::{self, *},
+    category_totals, complete, create_verified_archive, dry_run_deletion, estimate_compression,
+    find_matches, lexically_absolute, list_trash, move_entry, path_of, relocation_targets,
+    restore_from_trash, run_before_delete, sorted_entries, suggest_cleanups, symlink_farms,
+    toggle_filter,
+    widgets::{
+        CategoriesPane, DetailsPane, DiagnosticsPane, FindPane, FoundEntry, HelpPane, MainWindow,
+        MarkMode, MarkPane, SuggestionAction, SuggestionsPane, TrashPane, PATH_SCROLL_STEP,
+    },
+    without_line_number, write_checksum_manifest, write_entries, AppState, Command, Config,
+    DisplayOptions, DryRun, EntryDataBundle, EntryFilter, ExportFormat, Flag, Prompt, PromptKind,
+    Setting, SortMode, BAR_WIDTH_RANGE, COMMAND_HISTORY_LIMIT,
 };
 use crosstermion::input::Key;
-use dua::traverse::{Traversal, TreeIndex};
+use dua::{
+    display_growth, glob_matches, normalized, same_path,
+    traverse::{MtimeRange, Traversal, Tree, TreeIndex, RECENT_PERIOD_DAYS},
+    ByteFormat, WalkOptions,
+};
 use itertools::Itertools;
 use petgraph::{visit::Bfs, Direction};
-use std::{fs, io, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 use tui::backend::Backend;
 use tui_react::Terminal;
 
+/// The amount of entries to list at most when finding them in the whole tree, to keep it responsive
+const MAX_FIND_RESULTS: usize = 10_000;
+/// How often the progress of deleting marked entries is shown
+const DELETION_REFRESH_RATE: Duration = Duration::from_millis(100);
+
 #[derive(Copy, Clone)]
 pub enum CursorMode {
     Advance,
@@ -55,8 +82,51 @@ impl AppState {
     }
 
     pub fn exit_node_with_traversal(&mut self, traversal: &Traversal) {
-        let entries = self.entries_for_exit_node(traversal);
+        let previous_root = self.root;
+        let mut entries = self.entries_for_exit_node(traversal);
+        if self.auto_descend {
+            // ascend through the chain of single-child directories we may have tunneled through
+            while let Some((parent_idx, parent_entries)) = &entries {
+                match traversal
+                    .tree
+                    .neighbors_directed(*parent_idx, Direction::Incoming)
+                    .next()
+                {
+                    Some(grandparent_idx) if parent_entries.len() == 1 => {
+                        self.bookmarks.insert(grandparent_idx, *parent_idx);
+                        entries = Some((
+                            grandparent_idx,
+                            sorted_entries(
+                                &traversal.tree,
+                                grandparent_idx,
+                                self.sorting,
+                                &self.filters,
+                            ),
+                        ));
+                    }
+                    _ => break,
+                }
+            }
+        }
         self.exit_node(entries);
+        self.select_where_we_came_from(previous_root, &traversal.tree);
+    }
+
+    /// If `previous_root` is below the current root, select and highlight the entry leading to it
+    /// so it's easy to see where we were.
+    fn select_where_we_came_from(&mut self, previous_root: TreeIndex, tree: &Tree) {
+        let came_from = std::iter::successors(Some(previous_root), |idx| {
+            tree.neighbors_directed(*idx, Direction::Incoming).next()
+        })
+        .tuple_windows()
+        .find(|(_, parent)| *parent == self.root)
+        .map(|(child, _)| child)
+        .filter(|child| self.entries.iter().any(|e| e.index == *child));
+        if let Some(came_from) = came_from {
+            self.selected = Some(came_from);
+            self.bookmarks.insert(self.root, came_from);
+            self.came_from = Some(came_from);
+        }
     }
 
     fn entries_for_exit_node(
@@ -70,7 +140,7 @@ impl AppState {
             .map(|parent_idx| {
                 (
                     parent_idx,
-                    sorted_entries(&traversal.tree, parent_idx, self.sorting),
+                    sorted_entries(&traversal.tree, parent_idx, self.sorting, &self.filters),
                 )
             })
     }
@@ -84,7 +154,7 @@ impl AppState {
                     .bookmarks
                     .get(&parent_idx)
                     .copied()
-                    .or_else(|| self.entries.get(0).map(|b| b.index));
+                    .or_else(|| self.entries.first().map(|b| b.index));
             }
             None => self.message = Some("Top level reached".into()),
         }
@@ -97,14 +167,47 @@ impl AppState {
         self.selected.map(|previously_selected| {
             (
                 previously_selected,
-                sorted_entries(&traversal.tree, previously_selected, self.sorting),
+                sorted_entries(
+                    &traversal.tree,
+                    previously_selected,
+                    self.sorting,
+                    &self.filters,
+                ),
             )
         })
     }
 
     pub fn enter_node_with_traversal(&mut self, traversal: &Traversal) {
-        let new_entries = self.entries_for_enter_node(traversal);
-        self.enter_node(new_entries)
+        let mut new_entries = self.entries_for_enter_node(traversal);
+        let (previous_root, previously_selected) = (self.root, self.selected);
+        if self.auto_descend {
+            // tunnel through chains of directories which contain nothing but another directory
+            while let Some((idx, entries)) = &new_entries {
+                match entries.as_slice() {
+                    [only_child]
+                        if traversal
+                            .tree
+                            .neighbors_directed(only_child.index, Direction::Outgoing)
+                            .next()
+                            .is_some() =>
+                    {
+                        let child_idx = only_child.index;
+                        self.bookmarks.insert(*idx, child_idx);
+                        new_entries = Some((
+                            child_idx,
+                            sorted_entries(&traversal.tree, child_idx, self.sorting, &self.filters),
+                        ));
+                    }
+                    _ => break,
+                }
+            }
+        }
+        self.enter_node(new_entries);
+        if let Some(previously_selected) = previously_selected {
+            if self.root != previous_root {
+                self.bookmarks.insert(previous_root, previously_selected);
+            }
+        }
     }
 
     pub fn enter_node(&mut self, entries_at_selected: Option<(TreeIndex, Vec<EntryDataBundle>)>) {
@@ -131,6 +234,122 @@ impl AppState {
         }
     }
 
+    /// Make the next or previous sibling directory of the current root the new root, depending on `direction`.
+    pub fn enter_sibling_with_traversal(
+        &mut self,
+        direction: CursorDirection,
+        traversal: &Traversal,
+    ) {
+        let parent_idx = match traversal
+            .tree
+            .neighbors_directed(self.root, Direction::Incoming)
+            .next()
+        {
+            Some(idx) => idx,
+            None => {
+                self.message = Some("The top level has no siblings".into());
+                return;
+            }
+        };
+        let siblings = sorted_entries(&traversal.tree, parent_idx, self.sorting, &self.filters);
+        let position = match siblings.iter().position(|b| b.index == self.root) {
+            Some(pos) => pos,
+            None => return,
+        };
+        let has_children = |b: &&EntryDataBundle| {
+            traversal
+                .tree
+                .neighbors_directed(b.index, Direction::Outgoing)
+                .next()
+                .is_some()
+        };
+        let sibling = match direction {
+            CursorDirection::Up => siblings[..position].iter().rev().find(has_children),
+            _ => siblings[position + 1..].iter().find(has_children),
+        };
+        match sibling {
+            Some(sibling) => {
+                let sibling = sibling.index;
+                self.bookmarks.insert(parent_idx, sibling);
+                self.root = sibling;
+                self.entries =
+                    sorted_entries(&traversal.tree, sibling, self.sorting, &self.filters);
+                self.selected = self
+                    .bookmarks
+                    .get(&sibling)
+                    .copied()
+                    .filter(|selected| self.entries.iter().any(|b| b.index == *selected))
+                    .or_else(|| self.entries.first().map(|b| b.index));
+            }
+            None => self.message = Some("There is no further sibling directory".into()),
+        }
+    }
+
+    pub fn toggle_auto_descend(&mut self) {
+        self.auto_descend = !self.auto_descend;
+        self.message = Some(
+            if self.auto_descend {
+                "Entering directories tunnels through single-child directories"
+            } else {
+                "Entering directories descends one level at a time"
+            }
+            .into(),
+        );
+    }
+
+    /// Show the exact amount of bytes of the entries in the current directory, or go back to the byte format.
+    pub fn toggle_exact_sizes(&mut self) {
+        self.exact_sizes_of = match self.exact_sizes_of {
+            Some(root) if root == self.root => None,
+            _ => Some(self.root),
+        };
+        self.message = Some(
+            match self.exact_sizes_of {
+                Some(_) => "Showing exact sizes in this directory",
+                None => "Showing sizes in the byte format",
+            }
+            .into(),
+        );
+    }
+
+    /// Switch between apparent sizes and disk usage, which is possible only once the scan is complete.
+    pub fn toggle_apparent_size(
+        &mut self,
+        traversal: &mut Traversal,
+        walk_options: &mut WalkOptions,
+        display: &mut DisplayOptions,
+    ) {
+        if self.is_scanning {
+            self.message = Some("Sizes can be switched once the scan is done".into());
+            return;
+        }
+        traversal.swap_size_model();
+        if let Some(baseline) = self.baseline.as_mut() {
+            baseline.swap_size_model();
+        }
+        walk_options.apparent_size = !walk_options.apparent_size;
+        display.size_model = walk_options.size_model_description();
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+        self.message = Some(format!("Showing {}", display.size_model));
+    }
+
+    /// Make bars `delta` columns longer, trading space with the entry names, and remember it for the next session.
+    pub fn change_bar_width(&mut self, display: &mut DisplayOptions, delta: isize) {
+        let (min, max) = (*BAR_WIDTH_RANGE.start(), *BAR_WIDTH_RANGE.end());
+        display.bar_width = display
+            .bar_width
+            .saturating_add_signed(delta)
+            .clamp(min, max);
+        let stored = self
+            .config_path
+            .as_deref()
+            .map(|path| Config::store_bar_width(path, display.bar_width));
+        self.message = Some(match stored {
+            Some(Err(err)) => format!("Could not remember the bar width: {:#}", err),
+            _ => format!("Bars are {} columns wide", display.bar_width),
+        });
+    }
+
     pub fn change_entry_selection(&mut self, direction: CursorDirection) {
         let entries = &self.entries;
         let next_selected_pos = match self.selected {
@@ -152,41 +371,753 @@ impl AppState {
     }
 
     pub fn cycle_sorting(&mut self, traversal: &Traversal) {
-        self.sorting.toggle_size();
-        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
+        self.sorting.cycle();
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
     }
 
-    pub fn reset_message(&mut self) {
+    /// Scroll the path of the selected entry in the footer towards its beginning, or back towards its end.
+    pub fn scroll_selected_path(&mut self, traversal: &Traversal, towards_beginning: bool) {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return,
+        };
+        let scroll = self
+            .path_scroll
+            .filter(|(scrolled, _)| *scrolled == index)
+            .map_or(0, |(_, scroll)| scroll);
+        let path_len = absolute_path_of(&traversal.tree, index)
+            .to_string_lossy()
+            .chars()
+            .count();
+        let scroll = match towards_beginning {
+            true => (scroll + PATH_SCROLL_STEP).min(path_len),
+            false => scroll.saturating_sub(PATH_SCROLL_STEP),
+        };
+        self.path_scroll = Some((index, scroll));
+    }
+
+    /// Sort by the size of recently modified files, or by size again if we already do.
+    pub fn toggle_sort_by_growth(&mut self, traversal: &Traversal) {
+        self.sorting = match self.sorting {
+            SortMode::GrowthDescending => SortMode::SizeDescending,
+            _ => SortMode::GrowthDescending,
+        };
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+        self.message = Some(match self.sorting {
+            SortMode::GrowthDescending => format!(
+                "Sorting by the size of files modified in the {} days before the scan",
+                RECENT_PERIOD_DAYS
+            ),
+            _ => "Sorting by size".into(),
+        });
+    }
+
+    pub fn toggle_filter(&mut self, filter: EntryFilter, traversal: &Traversal) {
+        let is_active = toggle_filter(&mut self.filters, filter.clone());
+        self.refresh_filtered_entries(traversal);
+        self.message = Some(match (filter, is_active) {
+            (EntryFilter::HardLinked, true) => "Showing only files with multiple hard links".into(),
+            (EntryFilter::HardLinked, false) => "Showing files regardless of hard links".into(),
+            (EntryFilter::NotHidden, true) => "Hiding entries starting with a dot".into(),
+            (EntryFilter::NotHidden, false) => "Showing hidden entries".into(),
+            (EntryFilter::LocallyAvailable, true) => {
+                "Hiding cloud placeholders not stored locally".into()
+            }
+            (EntryFilter::LocallyAvailable, false) => "Showing cloud placeholders".into(),
+            (EntryFilter::Name(query), true) => format!("Showing only names matching '{}'", query),
+            (EntryFilter::Name(_), false) => "Showing entries regardless of their name".into(),
+        });
+    }
+
+    /// Open the prompt for the name filter with its current text, to change it.
+    pub fn open_name_filter(&mut self) {
+        let query = self.filters.iter().find_map(|f| match f {
+            EntryFilter::Name(query) => Some(query.clone()),
+            _ => None,
+        });
+        self.open_prompt(PromptKind::NameFilter);
+        if let (Some(prompt), Some(query)) = (self.prompt.as_mut(), query) {
+            prompt.input = query;
+            self.reset_message();
+        }
+    }
+
+    /// Only show entries whose name matches `query`, replacing the previous name filter, or remove it if `query` is empty.
+    pub fn set_name_filter(&mut self, query: &str, traversal: &Traversal) {
+        let position = self
+            .filters
+            .iter()
+            .position(|f| matches!(f, EntryFilter::Name(_)));
+        match (position, query.is_empty()) {
+            (Some(position), true) => {
+                self.filters.remove(position);
+            }
+            (Some(position), false) => self.filters[position] = EntryFilter::Name(query.into()),
+            (None, true) => return,
+            (None, false) => self.filters.push(EntryFilter::Name(query.into())),
+        }
+        self.refresh_filtered_entries(traversal);
+    }
+
+    pub fn cycle_filter_selection(&mut self) {
+        self.selected_filter = match self.selected_filter {
+            None if !self.filters.is_empty() => Some(0),
+            Some(idx) if idx + 1 < self.filters.len() => Some(idx + 1),
+            _ => None,
+        };
+    }
+
+    pub fn remove_selected_filter(&mut self, traversal: &Traversal) {
+        if let Some(idx) = self.selected_filter.filter(|idx| *idx < self.filters.len()) {
+            let filter = self.filters.remove(idx);
+            self.selected_filter = match self.filters.len() {
+                0 => None,
+                len => Some(idx.min(len - 1)),
+            };
+            self.refresh_filtered_entries(traversal);
+            self.message = Some(format!("Removed filter '{}'", filter.label()));
+        }
+    }
+
+    fn refresh_filtered_entries(&mut self, traversal: &Traversal) {
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+        if self
+            .selected
+            .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
+            .is_none()
+        {
+            self.selected = self.entries.first().map(|e| e.index);
+        }
+        if self
+            .selected_filter
+            .is_some_and(|idx| idx >= self.filters.len())
+        {
+            self.selected_filter = None;
+        }
+    }
+
+    pub fn rescan_permission_denied(
+        &mut self,
+        traversal: &mut Traversal,
+        walk_options: &WalkOptions,
+    ) {
+        if self.refuse_read_only("Rescanning") {
+            return;
+        }
+        let indices: Vec<_> = traversal
+            .permission_denied
+            .iter()
+            .copied()
+            .filter(|idx| traversal.tree.node_weight(*idx).is_some())
+            .collect();
+        if indices.is_empty() {
+            self.message = Some("There are no unreadable directories".into());
+            return;
+        }
+        let paths: Vec<_> = indices
+            .iter()
+            .map(|idx| path_of(&traversal.tree, *idx))
+            .collect();
+        match dua::privileged::sizes_of(walk_options, &paths) {
+            Ok(sizes) => {
+                for (idx, (bytes, errors)) in indices.iter().zip(sizes) {
+                    traversal.set_privileged_size(*idx, bytes);
+                    traversal.io_errors = traversal.io_errors.saturating_sub(1) + errors;
+                }
+                traversal.permission_denied.clear();
+                self.entries =
+                    sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+                self.message = Some(format!(
+                    "Re-scanned {} unreadable directories with sudo",
+                    indices.len()
+                ));
+            }
+            Err(err) => self.message = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// Discard the tree and traverse all inputs again, showing the progress like during the initial scan.
+    /// If [`trust_mtime`](Self::trust_mtime) is set, only directories modified since the scan are traversed again
+    /// instead. The current directory, marks and flags are carried over to the new tree by path, as far as these
+    /// still exist.
+    ///
+    /// Keys pressed in the meantime are handled once the scan is done.
+    pub fn rescan_all<B>(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        display: DisplayOptions,
+        walk_options: &WalkOptions,
+        terminal: &mut Terminal<B>,
+    ) where
+        B: Backend,
+    {
+        if self.is_scanning {
+            self.message = Some("A scan is already running".into());
+            return;
+        }
+        if self.refuse_read_only("Rescanning") {
+            return;
+        }
+        let cwd = match std::env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(err) => {
+                self.message = Some(format!("Could not rescan: {}", err));
+                return;
+            }
+        };
+        let inputs: Vec<_> = traversal
+            .tree
+            .neighbors_directed(traversal.root_index, Direction::Outgoing)
+            .map(|idx| traversal.tree[idx].name.clone())
+            .collect();
+        let path_of = |idx| cwd.join(path_of(&traversal.tree, idx));
+        // the top of the tree has no path, but is the top of the new tree as well
+        let location_of = |idx| (idx != traversal.root_index).then(|| path_of(idx));
+        let root = location_of(self.root);
+        let selected = self.selected.map(path_of);
+        let flagged_paths: Vec<_> = self.flagged_paths(traversal);
+        let exact_sizes_of = self.exact_sizes_of.map(location_of);
+        let bookmarked: Vec<_> = self
+            .bookmarks
+            .iter()
+            .map(|(&root, &selected)| (location_of(root), path_of(selected)))
+            .collect();
+
+        // what refers to the current tree is put aside while the new one is shown, and put back if the scan fails
+        let mark_pane = window.mark_pane.take();
+        let flagged = std::mem::take(&mut self.flagged);
+        let bookmarks = std::mem::take(&mut self.bookmarks);
+        let (root_before, selected_before) = (self.root, self.selected);
+        let exact_sizes_before = self.exact_sizes_of.take();
+        let came_from_before = self.came_from.take();
+        let path_scroll_before = self.path_scroll.take();
+        let root_filesystem_before = self.root_filesystem.take();
+        let entries_before = std::mem::take(&mut self.entries);
+        let mut failure = None;
+        self.is_scanning = true;
+        let (sorting, filters) = (self.sorting, self.filters.clone());
+        let rescanned = match self.trust_mtime {
+            // unchanged entries keep their indices, and the others are found by path like after a full rescan
+            true => traversal.refresh_changed(walk_options.clone()).map(Some),
+            false => Traversal::from_walk(walk_options.clone(), inputs, |t| {
+                self.root = t.root_index;
+                self.entries = sorted_entries(&t.tree, t.root_index, sorting, &filters);
+                self.selected = self.entries.first().map(|b| b.index);
+                self.reset_message();
+                self.draw(window, t, display, terminal)?;
+                Ok(false)
+            })
+            .map(|rescanned| {
+                *traversal = rescanned.expect("traversals that aren't aborted run to completion");
+                None
+            }),
+        };
+        self.is_scanning = false;
+        let refreshed = match rescanned {
+            Ok(refreshed) => refreshed,
+            // the tree is still consistent as each entry is refreshed at once, but some of it is outdated
+            Err(err) if self.trust_mtime => {
+                failure = Some(err);
+                None
+            }
+            Err(err) => {
+                window.mark_pane = mark_pane;
+                self.flagged = flagged;
+                self.bookmarks = bookmarks;
+                self.root = root_before;
+                self.selected = selected_before;
+                self.entries = entries_before;
+                self.exact_sizes_of = exact_sizes_before;
+                self.came_from = came_from_before;
+                self.path_scroll = path_scroll_before;
+                self.root_filesystem = root_filesystem_before;
+                self.message = Some(format!("Could not rescan: {:#}", err));
+                return;
+            }
+        };
+        let marked: Vec<_> = mark_pane
+            .map(|pane| {
+                pane.marked()
+                    .values()
+                    .map(|mark| (cwd.join(&mark.path), mark.is_dir))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let tree = &traversal.tree;
+        let top = traversal.root_index;
+        let resolve = |location: Option<PathBuf>| match location {
+            Some(path) => resolve_on_disk(tree, &path),
+            None => Some(top),
+        };
+        self.root = resolve(root).unwrap_or(top);
+        self.entries = sorted_entries(tree, self.root, self.sorting, &self.filters);
+        self.selected = selected
+            .and_then(|path| resolve_on_disk(tree, &path))
+            .filter(|idx| self.entries.iter().any(|b| b.index == *idx))
+            .or_else(|| self.entries.first().map(|b| b.index));
+        for (path, is_dir) in marked {
+            if let Some(index) = resolve_on_disk(tree, &path) {
+                let pane = window.mark_pane.take().unwrap_or_default();
+                window.mark_pane = pane.toggle_index(index, tree, is_dir, false);
+            }
+        }
+        self.flagged = flagged_paths
+            .iter()
+            .filter_map(|path| resolve_on_disk(tree, &cwd.join(path)))
+            .collect();
+        self.exact_sizes_of = exact_sizes_of.and_then(resolve);
+        self.bookmarks = bookmarked
+            .into_iter()
+            .filter_map(|(root, selected)| {
+                Some((resolve(root)?, resolve_on_disk(tree, &selected)?))
+            })
+            .collect();
+        if let Some(baseline) = self.baseline.as_mut() {
+            baseline.update(traversal);
+        }
+        let total = walk_options
+            .byte_format
+            .display(traversal.total_bytes.unwrap_or_default())
+            .to_string();
+        self.message = Some(match (failure, refreshed) {
+            (Some(err), _) => format!("Could not rescan: {:#}", err),
+            (None, Some(refreshed)) => format!(
+                "Rescanned {} changed entr{}, {} in total",
+                refreshed,
+                if refreshed == 1 { "y" } else { "ies" },
+                total.trim_start()
+            ),
+            (None, None) => format!(
+                "Rescanned {} entries, {} in total",
+                traversal.entries_traversed,
+                total.trim_start()
+            ),
+        });
+    }
+
+    /// Traverse the selected entry once more to pick up changes made to it since the scan.
+    pub fn refresh_selected(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        walk_options: &WalkOptions,
+    ) {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return,
+        };
+        if self.is_scanning {
+            self.message = Some("Entries can be refreshed once the scan is done".into());
+            return;
+        }
+        if self.refuse_read_only("Refreshing entries") {
+            return;
+        }
+        let path = path_of(&traversal.tree, index);
+        if path.symlink_metadata().is_err() {
+            let mut removed = Vec::new();
+            let mut bfs = Bfs::new(&traversal.tree, index);
+            while let Some(idx) = bfs.next(&traversal.tree) {
+                removed.push(idx);
+            }
+            self.delete_entries_in_traversal(index, traversal);
+            self.forget_refreshed_entries(window, traversal, &removed);
+            self.message = Some(format!("'{}' doesn't exist anymore", path.display()));
+            return;
+        }
+        let size_before = traversal.tree.node_weight(index).map_or(0, |e| e.size);
+        let removed = match traversal.refresh(walk_options.clone(), index, path.clone()) {
+            Ok(removed) => removed,
+            Err(err) => {
+                self.message = Some(format!("Could not scan '{}': {:#}", path.display(), err));
+                return;
+            }
+        };
+        self.forget_refreshed_entries(window, traversal, &removed);
+        let size = traversal.tree.node_weight(index).map_or(0, |e| e.size);
+        self.message = Some(format!(
+            "Refreshed '{}', now {} ({})",
+            path.display(),
+            walk_options
+                .byte_format
+                .display(size)
+                .to_string()
+                .trim_start(),
+            display_growth(walk_options.byte_format, size_before, size)
+        ));
+    }
+
+    /// Enter the selected directory, but if it's a mount point that was skipped as the traversal stays on the
+    /// filesystems of its inputs, scan it first to add what's on it to the tree. Mount points on it are skipped as well.
+    pub fn enter_node_scanning_mounts(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        walk_options: &WalkOptions,
+    ) {
+        let index = match self.selected {
+            Some(index) if traversal.skipped_mounts.contains(&index) => index,
+            _ => return self.enter_node_with_traversal(traversal),
+        };
         if self.is_scanning {
+            self.message = Some("Mount points can be scanned once the scan is done".into());
+            return;
+        }
+        let path = path_of(&traversal.tree, index);
+        let removed = match traversal.refresh(walk_options.clone(), index, path.clone()) {
+            Ok(removed) => removed,
+            Err(err) => {
+                self.message = Some(format!("Could not scan '{}': {:#}", path.display(), err));
+                return;
+            }
+        };
+        self.forget_refreshed_entries(window, traversal, &removed);
+        let size = traversal.tree.node_weight(index).map_or(0, |e| e.size);
+        self.message = Some(format!(
+            "Scanned the mount point '{}', which holds {}",
+            path.display(),
+            walk_options
+                .byte_format
+                .display(size)
+                .to_string()
+                .trim_start(),
+        ));
+        self.enter_node_with_traversal(traversal);
+    }
+
+    /// Drop everything we know about the `removed` entries after they were replaced by a refresh.
+    /// Marks are kept on the entries that replaced them at the same path, if there are any.
+    fn forget_refreshed_entries(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+        removed: &[TreeIndex],
+    ) {
+        for index in removed {
+            self.flagged.remove(index);
+        }
+        self.bookmarks
+            .retain(|root, selected| !removed.contains(root) && !removed.contains(selected));
+        if let Some(pane) = window.mark_pane.take() {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let marked: Vec<_> = removed
+                .iter()
+                .filter_map(|index| pane.marked().get(index))
+                .map(|mark| (cwd.join(&mark.path), mark.is_dir))
+                .collect();
+            window.mark_pane = pane.unmark_removed(removed);
+            for (path, is_dir) in marked {
+                if let Some(index) = resolve_on_disk(&traversal.tree, &path) {
+                    let pane = window.mark_pane.take().unwrap_or_default();
+                    window.mark_pane = pane.toggle_index(index, &traversal.tree, is_dir, false);
+                }
+            }
+        }
+        if let Some(baseline) = self.baseline.as_mut() {
+            baseline.update(traversal);
+        }
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+    }
+
+    pub fn reset_message(&mut self) {
+        if let Some(Prompt { kind, input, .. }) = &self.prompt {
+            self.message = Some(match kind {
+                PromptKind::MarkLargerThan => format!(
+                    "Mark files larger than: {}_ (e.g. 100MB, <enter> = mark, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::MarkGlob => format!(
+                    "Mark entries matching: {}_ (e.g. *.log, target/ or **/*.tmp for all depths, <enter> = mark, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::Export => format!(
+                    "Export this directory to: {}_ (.csv, .json or du-style text, <enter> = export, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::Archive => format!(
+                    "Archive marked entries to: {}_ (.tar or .tar.zst, <enter> = archive and delete, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::MoveTo => format!(
+                    "Move marked entries into: {}_ (keeping their relative location, <enter> = move, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::Checksums => format!(
+                    "Write checksums of marked files to: {}_ (BLAKE3 and size of each file, <enter> = write, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::MarkPath => format!(
+                    "Mark the path: {}_ (scanned if outside of this scan, <enter> = mark, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::DeletionCap => format!(
+                    "Free at most: {}_ per deletion, then pause (e.g. 50GB, empty = no limit, <enter> = set, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::NameFilter => format!(
+                    "/{}_ (names containing these characters in order, <enter> = keep, <esc> = clear)",
+                    input
+                ),
+                PromptKind::Find => format!(
+                    "Find in the whole tree: {}_ (text or a glob like core.*, <enter> = find, <esc> = cancel)",
+                    input
+                ),
+                PromptKind::QuickDelete => format!(
+                    "Delete {} permanently? Type its name to confirm: {}_ (<enter> = delete, <esc> = cancel)",
+                    self.quick_delete.as_ref().map_or("nothing", |(_, what)| what.as_str()),
+                    input
+                ),
+                PromptKind::ConfirmDeletion => format!(
+                    "Permanently delete {}? Type 'yes' to confirm: {}_ (<esc> = cancel)",
+                    self.pending_deletion.as_deref().unwrap_or("nothing"),
+                    input
+                ),
+                PromptKind::Command => format!(
+                    ":{}_ (<tab> = complete, ↑/↓ = history, <enter> = run, <esc> = cancel)",
+                    input
+                ),
+            });
+        } else if self.is_scanning && self.is_paused {
+            self.message = Some("-> scan paused, p = resume <-".into());
+        } else if self.is_scanning {
             self.message = Some("-> scanning <-".into());
         } else {
             self.message = None;
         }
     }
 
+    pub fn toggle_pause(&mut self, traversal: &Traversal) {
+        if !self.is_scanning {
+            self.message = Some("There is no scan to pause".into());
+            return;
+        }
+        self.is_paused = !self.is_paused;
+        traversal.pause.set(self.is_paused);
+        self.reset_message();
+    }
+
     pub fn toggle_help_pane(&mut self, window: &mut MainWindow) {
-        self.focussed = match self.focussed {
+        match self.focussed {
             Main | Mark => {
+                window.close_side_panes();
                 window.help_pane = Some(HelpPane::default());
-                Help
+                self.focus_side_pane(window, Help);
+            }
+            Help | Trash | Find | Suggestions => {
+                window.close_side_panes();
+                self.focussed = Main;
+            }
+        }
+    }
+
+    /// Give the focus to the side pane `focussed`, which was just opened in `window`, taking it from the mark pane.
+    fn focus_side_pane(&mut self, window: &mut MainWindow, focussed: FocussedPane) {
+        if let Some(p) = window.mark_pane.as_mut() {
+            p.set_focus(false)
+        };
+        self.focussed = focussed;
+    }
+
+    pub fn toggle_diagnostics_pane(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        window.diagnostics_pane = match window.diagnostics_pane {
+            Some(_) => None,
+            None => {
+                window.close_side_panes();
+                Some(DiagnosticsPane::new(symlink_farms(&traversal.tree)))
+            }
+        };
+    }
+
+    /// Show how much space the files of each configured category take up, as of now, or hide it again.
+    pub fn toggle_categories_pane(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        window.categories_pane = match window.categories_pane {
+            Some(_) => None,
+            None => {
+                window.close_side_panes();
+                let (totals, rest) = category_totals(&traversal.tree, &self.categories);
+                let config_path = self
+                    .config_path
+                    .as_ref()
+                    .map(|path| path.display().to_string());
+                Some(CategoriesPane::new(totals, rest, config_path))
+            }
+        };
+    }
+
+    pub fn toggle_details_pane(&mut self, window: &mut MainWindow) {
+        window.details_pane = match window.details_pane {
+            Some(_) => None,
+            None => {
+                window.close_side_panes();
+                Some(DetailsPane::default())
+            }
+        };
+    }
+
+    /// Show the entries of the trash bin and focus them, or explain why that's not possible.
+    pub fn open_trash_pane(&mut self, window: &mut MainWindow) {
+        match list_trash() {
+            Ok(entries) => {
+                window.close_side_panes();
+                window.trash_pane = Some(TrashPane::new(entries));
+                self.focus_side_pane(window, Trash);
+            }
+            Err(err) => self.message = Some(format!("Could not list the trash bin: {:#}", err)),
+        }
+    }
+
+    pub fn dispatch_to_trash_pane(&mut self, key: Key, window: &mut MainWindow) {
+        let pane = match window.trash_pane.as_mut() {
+            Some(pane) => pane,
+            None => return,
+        };
+        if let Some(entries) = pane.process_events(key) {
+            let num_entries = entries.len();
+            self.message = Some(match restore_from_trash(entries.clone()) {
+                Ok(()) => format!(
+                    "Restored {} entr{} to their original location",
+                    num_entries,
+                    if num_entries == 1 { "y" } else { "ies" }
+                ),
+                Err(err) => {
+                    pane.reinsert(entries);
+                    format!("Could not restore: {:#}", err)
+                }
+            });
+        }
+    }
+
+    /// List all entries of the tree whose name matches `query` in a pane, the largest first, and focus it.
+    pub fn find_entries(&mut self, query: &str, window: &mut MainWindow, traversal: &Traversal) {
+        if query.is_empty() {
+            return self.reset_message();
+        }
+        let tree = &traversal.tree;
+        let mut results = Vec::new();
+        let mut bfs = Bfs::new(tree, traversal.root_index);
+        while let Some(index) = bfs.next(tree) {
+            if let Some(entry) = tree.node_weight(index) {
+                if index != traversal.root_index
+                    && find_matches(query, &entry.name.to_string_lossy())
+                {
+                    results.push((index, entry.size));
+                }
+            }
+        }
+        let num_matches = results.len();
+        if num_matches == 0 {
+            self.message = Some(format!("Nothing matches '{}'", query));
+            return;
+        }
+        results.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        results.truncate(MAX_FIND_RESULTS);
+        let results = results
+            .into_iter()
+            .map(|(index, size)| FoundEntry {
+                index,
+                path: path_of(tree, index),
+                size,
+            })
+            .collect();
+        window.close_side_panes();
+        window.find_pane = Some(FindPane::new(query.into(), results, num_matches));
+        self.focus_side_pane(window, Find);
+    }
+
+    /// Show the chosen result of the find pane in its directory, and focus the entries pane for it.
+    pub fn dispatch_to_find_pane(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let index = match window
+            .find_pane
+            .as_mut()
+            .and_then(|p| p.process_events(key))
+        {
+            Some(found) => found.index,
+            None => return,
+        };
+        if traversal.tree.node_weight(index).is_none() {
+            self.message = Some("The entry is no longer part of the tree".into());
+            return;
+        }
+        self.focussed = Main;
+        self.reveal_entry(index, traversal);
+    }
+
+    /// List the entries that can likely be cleaned up in a pane and focus it, or explain that there are none.
+    pub fn open_suggestions_pane(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        let suggestions = suggest_cleanups(traversal);
+        if suggestions.is_empty() {
+            self.message = Some("Nothing looks like it could be cleaned up safely".into());
+            return;
+        }
+        window.close_side_panes();
+        window.suggestions_pane = Some(SuggestionsPane::new(suggestions));
+        self.focus_side_pane(window, Suggestions);
+    }
+
+    /// Show or mark the suggestion chosen in the suggestions pane.
+    pub fn dispatch_to_suggestions_pane(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let action = match window
+            .suggestions_pane
+            .as_mut()
+            .and_then(|p| p.process_events(key))
+        {
+            Some(action) => action,
+            None => return,
+        };
+        let index = match action {
+            SuggestionAction::Show(index) | SuggestionAction::ToggleMark { index, .. } => index,
+        };
+        if traversal.tree.node_weight(index).is_none() {
+            self.message = Some("The entry is no longer part of the tree".into());
+            return;
+        }
+        match action {
+            SuggestionAction::Show(index) => {
+                self.focussed = Main;
+                self.reveal_entry(index, traversal);
             }
-            Help => {
-                window.help_pane = None;
-                Main
+            SuggestionAction::ToggleMark { index, is_dir } => {
+                if !is_marked(window, index) && self.refuse_protected(&traversal.tree, index) {
+                    return;
+                }
+                window.mark_pane = window.mark_pane.take().unwrap_or_default().toggle_index(
+                    index,
+                    &traversal.tree,
+                    is_dir,
+                    true,
+                );
             }
         }
     }
+
     pub fn cycle_focus(&mut self, window: &mut MainWindow) {
         if let Some(p) = window.mark_pane.as_mut() {
             p.set_focus(false)
         };
+        let has_find_pane = window.find_pane.is_some();
+        let has_suggestions_pane = window.suggestions_pane.is_some();
         self.focussed = match (self.focussed, &window.help_pane, &mut window.mark_pane) {
+            (Trash, _, _) => Main,
             (Main, Some(_), _) => Help,
-            (Help, _, Some(ref mut pane)) => {
+            (Main, None, _) if has_find_pane => Find,
+            (Main, None, _) if has_suggestions_pane => Suggestions,
+            (Help | Find | Suggestions, _, Some(ref mut pane)) => {
                 pane.set_focus(true);
                 Mark
             }
-            (Help, _, None) => Main,
+            (Help | Find | Suggestions, _, None) => Main,
             (Mark, _, _) => Main,
             (Main, None, None) => Main,
             (Main, None, Some(ref mut pane)) => {
@@ -206,50 +1137,94 @@ impl AppState {
     ) where
         B: Backend,
     {
+        // only needed to show the progress of trashing
+        #[cfg(not(feature = "trash-move"))]
+        let _ = terminal;
         let res = window.mark_pane.take().and_then(|p| p.process_events(key));
         window.mark_pane = match res {
             Some((pane, mode)) => match mode {
+                Some(MarkMode::Delete | MarkMode::Archive | MarkMode::Move)
+                    if self.refuse_read_only("Changing entries on disk")
+                        || self.refuse_protected_marks(&pane) =>
+                {
+                    Some(pane)
+                }
+                #[cfg(feature = "trash-move")]
+                Some(MarkMode::Trash)
+                    if self.refuse_read_only("Changing entries on disk")
+                        || self.refuse_protected_marks(&pane) =>
+                {
+                    Some(pane)
+                }
                 Some(MarkMode::Delete) => {
-                    self.message = Some("Deleting entries...".to_string());
-                    let mut entries_deleted = 0;
-                    let res = pane.iterate_deletable_items(|mut pane, entry_to_delete| {
-                        window.mark_pane = Some(pane);
-                        self.draw(window, traversal, display, terminal).ok();
-                        pane = window.mark_pane.take().expect("option to be filled");
-                        match self.delete_entry(entry_to_delete, traversal) {
-                            Ok(ed) => {
-                                entries_deleted += ed;
-                                self.message =
-                                    Some(format!("Deleted {} entries...", entries_deleted));
-                                Ok(pane)
-                            }
-                            Err(c) => Err((pane, c)),
-                        }
-                    });
-                    self.message = None;
-                    res
+                    self.confirm_deletion(&pane, traversal, display);
+                    Some(pane)
                 }
                 #[cfg(feature = "trash-move")]
                 Some(MarkMode::Trash) => {
                     self.message = Some("Trashing entries...".to_string());
                     let mut entries_trashed = 0;
-                    let res = pane.iterate_deletable_items(|mut pane, entry_to_trash| {
-                        window.mark_pane = Some(pane);
-                        self.draw(window, traversal, display, terminal).ok();
-                        pane = window.mark_pane.take().expect("option to be filled");
-                        match self.trash_entry(entry_to_trash, traversal) {
-                            Ok(ed) => {
-                                entries_trashed += ed;
-                                self.message =
-                                    Some(format!("Trashed {} entries...", entries_trashed));
-                                Ok(pane)
+                    let cap = self.deletion_cap;
+                    let (res, bytes_trashed) = pane.iterate_deletable_items(
+                        cap,
+                        || false,
+                        |mut pane, entry_to_trash| {
+                            window.mark_pane = Some(pane);
+                            self.draw(window, traversal, display, terminal).ok();
+                            pane = window.mark_pane.take().expect("option to be filled");
+                            match self.trash_entry(entry_to_trash, traversal) {
+                                Ok(ed) => {
+                                    entries_trashed += ed;
+                                    self.message =
+                                        Some(format!("Trashed {} entries...", entries_trashed));
+                                    Ok(pane)
+                                }
+                                Err(c) => Err((pane, c)),
                             }
-                            Err(c) => Err((pane, c)),
-                        }
-                    });
+                        },
+                    );
                     self.message = None;
+                    self.report_paused_deletion(
+                        res.as_ref(),
+                        cap,
+                        bytes_trashed,
+                        display,
+                        "Ctrl + t trashes",
+                    );
                     res
                 }
+                Some(MarkMode::Archive) => {
+                    self.open_prompt(PromptKind::Archive);
+                    Some(pane)
+                }
+                Some(MarkMode::Move) => {
+                    self.open_prompt(PromptKind::MoveTo);
+                    if let (Some(prompt), Some(dir)) = (self.prompt.as_mut(), &self.move_to) {
+                        prompt.input = dir.display().to_string();
+                        self.reset_message();
+                    }
+                    Some(pane)
+                }
+                Some(MarkMode::Checksums) => {
+                    self.open_prompt(PromptKind::Checksums);
+                    Some(pane)
+                }
+                Some(MarkMode::DryRun) => {
+                    let mut pane = pane;
+                    self.dry_run_marked_entries(&mut pane, display.byte_format);
+                    Some(pane)
+                }
+                Some(MarkMode::SetDeletionCap) => {
+                    self.open_prompt(PromptKind::DeletionCap);
+                    Some(pane)
+                }
+                Some(MarkMode::Reveal(index)) => {
+                    let mut pane = pane;
+                    pane.set_focus(false);
+                    self.focussed = Main;
+                    self.reveal_entry(index, traversal);
+                    Some(pane)
+                }
                 None => Some(pane),
             },
             None => None,
@@ -259,15 +1234,291 @@ impl AppState {
         }
     }
 
-    pub fn delete_entry(
-        &mut self,
-        index: TreeIndex,
-        traversal: &mut Traversal,
-    ) -> Result<usize, usize> {
+    /// Tell that `action` isn't possible if the entries were loaded from a snapshot or a bucket listing, and don't
+    /// reflect what's on disk.
+    fn refuse_read_only(&mut self, action: &str) -> bool {
+        if self.is_read_only {
+            self.message = Some(format!(
+                "{} isn't possible, as the entries were loaded from a snapshot or listing",
+                action
+            ));
+        }
+        self.is_read_only
+    }
+
+    /// Tell why the entry at `index` must not be marked for deletion, if it is protected or contains a protected path.
+    fn refuse_protected(&mut self, tree: &Tree, index: TreeIndex) -> bool {
+        match self.protected.refusal(&path_of(tree, index)) {
+            Some(refusal) => {
+                self.message = Some(refusal);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tell why the entries marked in `pane` must not be deleted, if any of them would remove a protected path,
+    /// even though they were marked before they became protected.
+    fn refuse_protected_marks(&mut self, pane: &MarkPane) -> bool {
+        let refusals: Vec<_> = pane
+            .marked()
+            .values()
+            .filter_map(|mark| self.protected.refusal(&mark.path))
+            .collect();
+        match refusals.as_slice() {
+            [] => return false,
+            [refusal] => self.message = Some(format!("Nothing was deleted: {}", refusal)),
+            [refusal, others @ ..] => {
+                self.message = Some(format!(
+                "Nothing was deleted: {}, and {} more marked entr{} would remove protected paths",
+                refusal,
+                others.len(),
+                if others.len() == 1 { "y" } else { "ies" }
+            ))
+            }
+        }
+        true
+    }
+
+    /// Ask to confirm deleting all entries marked in `pane`, telling how many there are and how much they free.
+    fn confirm_deletion(
+        &mut self,
+        pane: &MarkPane,
+        traversal: &Traversal,
+        display: DisplayOptions,
+    ) {
+        let format = display.byte_format;
+        let num_entries = pane.marked().len();
+        let mut summary = format!(
+            "{} marked entr{} with {}",
+            num_entries,
+            if num_entries == 1 { "y" } else { "ies" },
+            format
+                .display(pane.marked_bytes_within(&traversal.tree, traversal.root_index))
+                .to_string()
+                .trim_start()
+        );
+        if let Some(cap) = self.deletion_cap {
+            summary.push_str(&format!(
+                ", pausing after freeing {}",
+                format.display(cap).to_string().trim_start()
+            ));
+        }
+        if let Some(command) = &self.before_delete {
+            summary.push_str(&format!(", running '{}' before each", command));
+        }
+        self.pending_deletion = Some(summary);
+        self.open_prompt(PromptKind::ConfirmDeletion);
+    }
+
+    /// Delete the marked entries summarized by [`AppState::confirm_deletion()`] if `confirmation` is `yes`.
+    pub fn delete_marked_entries<B>(
+        &mut self,
+        confirmation: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        display: DisplayOptions,
+        terminal: &mut Terminal<B>,
+    ) where
+        B: Backend,
+    {
+        let confirmation = confirmation.trim();
+        if self.pending_deletion.take().is_none() {
+            return;
+        }
+        if confirmation.is_empty() {
+            self.message = Some("Nothing was deleted, as nothing was typed".into());
+            return;
+        }
+        if !confirmation.eq_ignore_ascii_case("yes") {
+            self.message = Some(format!(
+                "Nothing was deleted, as '{}' isn't 'yes'",
+                confirmation
+            ));
+            return;
+        }
+        let pane = match window.mark_pane.take() {
+            Some(pane) => pane,
+            None => return,
+        };
+        let cap = self.deletion_cap;
+        let abort = self.deletion_abort.clone();
+        let before_delete = self.before_delete.clone();
+        let workers = self.deletion_workers();
+        let mut hook_failures = Vec::new();
+        let (mut files_deleted, mut bytes_freed) = (0, 0);
+        let mut last_drawn = None::<Instant>;
+        self.message = Some("Deleting entries...".to_string());
+        abort.start();
+        let delete_fn = |pane, entry_to_delete| {
+            if traversal.tree.node_weight(entry_to_delete).is_none() {
+                // it was deleted along with a marked ancestor
+                return Ok(pane);
+            }
+            window.mark_pane = Some(pane);
+            let path_to_delete = path_of(&traversal.tree, entry_to_delete);
+            if let Some(command) = before_delete.as_deref() {
+                self.message = Some(format!(
+                    "Running '{}' for '{}' before deleting it, <esc> aborts",
+                    command,
+                    path_to_delete.display()
+                ));
+                self.draw(window, traversal, display, terminal).ok();
+            }
+            let res = remove_from_disk(
+                before_delete.as_deref(),
+                path_to_delete,
+                workers,
+                &mut |path, size| {
+                    files_deleted += 1;
+                    bytes_freed += size as u128;
+                    if last_drawn.is_none_or(|t| t.elapsed() >= DELETION_REFRESH_RATE) {
+                        self.message = Some(deletion_progress(
+                            path,
+                            files_deleted,
+                            bytes_freed,
+                            display.byte_format,
+                        ));
+                        self.draw(window, traversal, display, terminal).ok();
+                        last_drawn = Some(Instant::now());
+                    }
+                    !abort.is_aborted()
+                },
+            );
+            let pane = window.mark_pane.take().expect("option to be filled");
+            match res {
+                Ok(()) => {
+                    self.log_deletion(entry_to_delete, traversal);
+                    if self.deletion_log_error.is_some() {
+                        // deletions must not go unrecorded
+                        abort.abort();
+                    }
+                    self.delete_entries_in_traversal(entry_to_delete, traversal);
+                    Ok(pane)
+                }
+                Err(RemovalFailure::Refused(err)) => {
+                    hook_failures.push(err);
+                    Err((pane, 1))
+                }
+                Err(RemovalFailure::Io(c)) => Err((pane, c)),
+            }
+        };
+        let (res, bytes_deleted) =
+            pane.iterate_deletable_items(cap, || abort.is_aborted(), delete_fn);
+        let aborted = abort.is_aborted();
+        abort.finish();
+        self.message = None;
+        if aborted {
+            self.message = Some(format!(
+                "Aborted after deleting {} files with {} - {} entries remain marked",
+                files_deleted,
+                display
+                    .byte_format
+                    .display(bytes_freed)
+                    .to_string()
+                    .trim_start(),
+                res.as_ref().map_or(0, |p| p.marked().len())
+            ));
+        } else {
+            self.report_paused_deletion(
+                res.as_ref(),
+                cap,
+                bytes_deleted,
+                display,
+                "Ctrl + r deletes",
+            );
+        }
+        self.report_before_delete_failures(&hook_failures);
+        self.report_deletion_log_error();
+        window.mark_pane = res;
+        if window.mark_pane.is_none() {
+            self.focussed = Main;
+        }
+    }
+
+    /// The amount of threads removing files at once while deleting a directory, as configured.
+    fn deletion_workers(&self) -> usize {
+        match self.deletion_workers {
+            0 => num_cpus::get(),
+            n => n,
+        }
+    }
+
+    /// Append the entry at `index`, which was just deleted from disk, to the deletion log if there is one.
+    fn log_deletion(&mut self, index: TreeIndex, traversal: &Traversal) {
+        if let Some(log) = self.deletion_log.as_mut() {
+            let size = traversal.tree.node_weight(index).map_or(0, |e| e.size);
+            if let Err(err) = log.record(&absolute_path_of(&traversal.tree, index), size) {
+                self.deletion_log_error.get_or_insert(err);
+            }
+        }
+    }
+
+    /// Tell which marked entries were kept as the before-delete command failed for them, if any.
+    fn report_before_delete_failures(&mut self, failures: &[anyhow::Error]) {
+        if let Some(last_failure) = failures.last() {
+            self.message = Some(format!(
+                "Kept {} marked entr{} as the before-delete command failed: {:#}",
+                failures.len(),
+                if failures.len() == 1 { "y" } else { "ies" },
+                last_failure
+            ));
+        }
+    }
+
+    /// Tell after the current message if the deletion log couldn't be written.
+    fn report_deletion_log_error(&mut self) {
+        if let Some(err) = self.deletion_log_error.take() {
+            self.message = Some(match self.message.take() {
+                Some(message) => format!(
+                    "{} - but deletions are missing from the log: {:#}",
+                    message, err
+                ),
+                None => format!("Deletions are missing from the log: {:#}", err),
+            });
+        }
+    }
+
+    /// Tell how to continue if a deletion of marked entries stopped at `cap` after `bytes_deleted`,
+    /// leaving the entries of `pane` marked.
+    fn report_paused_deletion(
+        &mut self,
+        pane: Option<&MarkPane>,
+        cap: Option<u128>,
+        bytes_deleted: u128,
+        display: DisplayOptions,
+        continue_with: &str,
+    ) {
+        if let (Some(pane), Some(cap)) = (pane, cap) {
+            if bytes_deleted >= cap {
+                self.message =
+                    Some(format!(
+                    "Paused after freeing {} of {} - {} entries remain marked, {} the next batch",
+                    display.byte_format.display(bytes_deleted).to_string().trim_start(),
+                    display.byte_format.display(cap).to_string().trim_start(),
+                    pane.marked().len(),
+                    continue_with
+                ));
+            }
+        }
+    }
+
+    /// Delete the entry at `index` from disk and from `traversal`, once the before-delete command allows it.
+    pub fn delete_entry(
+        &mut self,
+        index: TreeIndex,
+        traversal: &mut Traversal,
+    ) -> Result<usize, RemovalFailure> {
         let mut entries_deleted = 0;
         if let Some(_entry) = traversal.tree.node_weight(index) {
             let path_to_delete = path_of(&traversal.tree, index);
-            delete_directory_recursively(path_to_delete)?;
+            remove_from_disk(
+                self.before_delete.as_deref(),
+                path_to_delete,
+                self.deletion_workers(),
+                &mut |_, _| true,
+            )?;
+            self.log_deletion(index, traversal);
             entries_deleted = self.delete_entries_in_traversal(index, traversal);
         }
         Ok(entries_deleted)
@@ -304,10 +1555,13 @@ impl AppState {
         let mut bfs = Bfs::new(&traversal.tree, index);
         while let Some(nx) = bfs.next(&traversal.tree) {
             traversal.tree.remove_node(nx);
+            self.flagged.remove(&nx);
             traversal.entries_traversed -= 1;
             entries_deleted += 1;
         }
-        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
+        // sizes of ancestors must be current before they are copied into the entries to show
+        self.recompute_sizes_recursively(parent_idx, traversal);
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
         if traversal.tree.node_weight(self.root).is_none() {
             self.set_root(traversal.root_index, traversal);
         }
@@ -316,28 +1570,58 @@ impl AppState {
             .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
             .is_none()
         {
-            self.selected = self.entries.get(0).map(|e| e.index);
+            self.selected = self.entries.first().map(|e| e.index);
         }
-        self.recompute_sizes_recursively(parent_idx, traversal);
         entries_deleted
     }
 
+    /// Make the parent of the entry at `index` the new root and select the entry, if the filters don't hide it.
+    pub fn reveal_entry(&mut self, index: TreeIndex, traversal: &Traversal) {
+        let parent_idx = match traversal
+            .tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next()
+        {
+            Some(parent_idx) => parent_idx,
+            None => return,
+        };
+        if parent_idx != self.root {
+            if let Some(selected) = self.selected {
+                self.bookmarks.insert(self.root, selected);
+            }
+            self.set_root(parent_idx, traversal);
+        }
+        self.selected = match self.entries.iter().any(|b| b.index == index) {
+            true => Some(index),
+            false => {
+                self.message = Some("The entry is hidden by the active filters".into());
+                self.entries.first().map(|b| b.index)
+            }
+        };
+    }
+
     fn set_root(&mut self, root: TreeIndex, traversal: &Traversal) {
         self.root = root;
-        self.entries = sorted_entries(&traversal.tree, root, self.sorting);
+        self.entries = sorted_entries(&traversal.tree, root, self.sorting, &self.filters);
     }
 
     fn recompute_sizes_recursively(&mut self, mut index: TreeIndex, traversal: &mut Traversal) {
         loop {
-            traversal
-                .tree
-                .node_weight_mut(index)
-                .expect("valid index")
-                .size = traversal
+            let mtime_range = MtimeRange::of_children(&traversal.tree, index);
+            let size = traversal
                 .tree
                 .neighbors_directed(index, Direction::Outgoing)
                 .filter_map(|idx| traversal.tree.node_weight(idx).map(|w| w.size))
                 .sum();
+            let alternate_size = traversal.alternate_size_of_children(index);
+            let recent_size = traversal.recent_size_of_children(index);
+            let entry_count = traversal.entry_count_of_children(index);
+            let entry = traversal.tree.node_weight_mut(index).expect("valid index");
+            entry.size = size;
+            entry.alternate_size = alternate_size;
+            entry.recent_size = recent_size;
+            entry.entry_count = entry_count;
+            entry.mtime_range = mtime_range;
             match traversal
                 .tree
                 .neighbors_directed(index, Direction::Incoming)
@@ -353,6 +1637,859 @@ impl AppState {
             .map(|w| w.size);
     }
 
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt {
+            kind,
+            input: String::new(),
+            history_position: None,
+        });
+        self.reset_message();
+    }
+
+    pub fn process_prompt_key<B>(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        display: &mut DisplayOptions,
+        walk_options: &WalkOptions,
+        terminal: &mut Terminal<B>,
+    ) where
+        B: Backend,
+    {
+        use crosstermion::input::Key::*;
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let is_command = prompt.kind == PromptKind::Command;
+        match key {
+            Char('\n') => {
+                return match prompt.kind {
+                    PromptKind::MarkLargerThan => {
+                        self.mark_files_larger_than(&prompt.input, window, traversal)
+                    }
+                    PromptKind::MarkGlob => {
+                        self.mark_matching(prompt.input.trim(), window, traversal)
+                    }
+                    PromptKind::Export => self.export_entries(prompt.input.trim(), traversal),
+                    PromptKind::Archive => {
+                        self.archive_marked_entries(prompt.input.trim(), window, traversal)
+                    }
+                    PromptKind::MoveTo => self.move_marked_entries(
+                        prompt.input.trim(),
+                        window,
+                        traversal,
+                        walk_options,
+                    ),
+                    PromptKind::Checksums => {
+                        self.write_checksums(prompt.input.trim(), window, display.byte_format)
+                    }
+                    PromptKind::Command => self.run_command(
+                        prompt.input.trim(),
+                        window,
+                        traversal,
+                        display,
+                        walk_options,
+                    ),
+                    PromptKind::MarkPath => {
+                        self.mark_path(prompt.input.trim(), window, traversal, walk_options)
+                    }
+                    PromptKind::DeletionCap => {
+                        self.set_deletion_cap(prompt.input.trim(), walk_options)
+                    }
+                    PromptKind::NameFilter => self.reset_message(),
+                    PromptKind::Find => self.find_entries(prompt.input.trim(), window, traversal),
+                    PromptKind::QuickDelete => {
+                        self.quick_delete_entry(&prompt.input, window, traversal)
+                    }
+                    PromptKind::ConfirmDeletion => self.delete_marked_entries(
+                        &prompt.input,
+                        window,
+                        traversal,
+                        *display,
+                        terminal,
+                    ),
+                }
+            }
+            Esc | Ctrl('c') if prompt.kind == PromptKind::NameFilter => {
+                self.set_name_filter("", traversal);
+                return self.reset_message();
+            }
+            Esc | Ctrl('c') => return self.reset_message(),
+            Backspace => {
+                prompt.input.pop();
+            }
+            Char('\t') if is_command => {
+                let tree = &traversal.tree;
+                let root = self.root;
+                let names_in = |dir: &str| {
+                    resolve_relative(tree, root, dir)
+                        .map(|dir| {
+                            tree.neighbors_directed(dir, Direction::Outgoing)
+                                .filter_map(|idx| tree.node_weight(idx))
+                                .map(|e| e.name.to_string_lossy().into_owned())
+                                .sorted()
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+                if let Some(completed) = complete(&prompt.input, names_in) {
+                    prompt.input = completed;
+                }
+            }
+            Up if is_command && !self.command_history.is_empty() => {
+                let position = prompt
+                    .history_position
+                    .unwrap_or(self.command_history.len())
+                    .saturating_sub(1);
+                prompt.history_position = Some(position);
+                prompt.input = self.command_history[position].clone();
+            }
+            Down if is_command => {
+                prompt.history_position = prompt
+                    .history_position
+                    .map(|position| position + 1)
+                    .filter(|position| *position < self.command_history.len());
+                prompt.input = prompt
+                    .history_position
+                    .map(|position| self.command_history[position].clone())
+                    .unwrap_or_default();
+            }
+            Char(c) => prompt.input.push(c),
+            _ => {}
+        }
+        if prompt.kind == PromptKind::NameFilter {
+            self.set_name_filter(&prompt.input, traversal);
+        }
+        self.prompt = Some(prompt);
+        self.reset_message();
+    }
+
+    /// Parse and run `line` as typed into the command line, and remember it in the history.
+    pub fn run_command(
+        &mut self,
+        line: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        display: &mut DisplayOptions,
+        walk_options: &WalkOptions,
+    ) {
+        if line.is_empty() {
+            return self.reset_message();
+        }
+        if self.command_history.last().map(String::as_str) != Some(line) {
+            self.command_history.push(line.to_owned());
+            if self.command_history.len() > COMMAND_HISTORY_LIMIT {
+                self.command_history.remove(0);
+            }
+        }
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(err) => {
+                self.message = Some(format!("{:#}", err));
+                return;
+            }
+        };
+        self.reset_message();
+        match command {
+            Command::Cd(path) => match resolve(&traversal.tree, self.root, &path) {
+                Some(dir) if dir == self.root => {}
+                Some(dir)
+                    if traversal
+                        .tree
+                        .neighbors_directed(dir, Direction::Outgoing)
+                        .next()
+                        .is_some() =>
+                {
+                    if let Some(selected) = self.selected {
+                        self.bookmarks.insert(self.root, selected);
+                    }
+                    let previous_root = self.root;
+                    self.set_root(dir, traversal);
+                    self.selected = self
+                        .bookmarks
+                        .get(&dir)
+                        .copied()
+                        .or_else(|| self.entries.first().map(|b| b.index));
+                    self.select_where_we_came_from(previous_root, &traversal.tree);
+                }
+                Some(file_or_empty_dir) => self.reveal_entry(file_or_empty_dir, traversal),
+                None => self.message = Some(format!("There is no scanned entry at '{}'", path)),
+            },
+            Command::Mark(Some(pattern)) if pattern.contains(['*', '?', '[']) => {
+                self.mark_matching(&pattern, window, traversal)
+            }
+            Command::Mark(name) => {
+                let index = match name {
+                    Some(name) => match resolve(&traversal.tree, self.root, &name) {
+                        Some(index) => index,
+                        None if Path::new(&name).is_absolute() => {
+                            return self.mark_path(&name, window, traversal, walk_options)
+                        }
+                        None => {
+                            self.message = Some(format!("There is no entry at '{}'", name));
+                            return;
+                        }
+                    },
+                    None => match self.selected {
+                        Some(index) => index,
+                        None => return,
+                    },
+                };
+                let is_dir = traversal
+                    .tree
+                    .neighbors_directed(index, Direction::Outgoing)
+                    .next()
+                    .is_some()
+                    || path_of(&traversal.tree, index).is_dir();
+                if !is_marked(window, index) && self.refuse_protected(&traversal.tree, index) {
+                    return;
+                }
+                window.mark_pane = window.mark_pane.take().unwrap_or_default().toggle_index(
+                    index,
+                    &traversal.tree,
+                    is_dir,
+                    true,
+                );
+            }
+            Command::Filter(filter) => self.toggle_filter(filter, traversal),
+            Command::Sort(sorting) => {
+                self.sorting = sorting;
+                self.entries =
+                    sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+            }
+            Command::Export(path) => self.export_entries(&path, traversal),
+            Command::Set(Setting::Flag(flag, value)) => {
+                let current = match flag {
+                    Flag::AutoDescend => &mut self.auto_descend,
+                    Flag::HardLinks => &mut display.show_hard_links,
+                    Flag::EntryCount => &mut display.show_entry_count,
+                    Flag::Mtime => &mut display.show_mtime,
+                    Flag::Icons => &mut display.show_icons,
+                    Flag::SizeWithoutMarked => &mut display.show_size_without_marked,
+                    Flag::Summary => &mut display.show_summary_row,
+                    Flag::Outliers => &mut display.highlight_outliers,
+                    Flag::Share => &mut display.show_byte_vis,
+                    Flag::ShareOfTotal => &mut display.share_of_total,
+                };
+                *current = value.unwrap_or(!*current);
+            }
+            Command::Set(Setting::BarWidth(width)) => {
+                let delta = width as isize - display.bar_width as isize;
+                self.change_bar_width(display, delta)
+            }
+            Command::Set(Setting::Format(format)) => {
+                display.byte_format = format;
+                self.message = Some(format!("Showing sizes as {:?}", display.byte_format));
+            }
+        }
+    }
+
+    /// Write the entries of the current directory as currently sorted and filtered to the file at `path`,
+    /// in a format according to its extension.
+    pub fn export_entries(&mut self, path: &str, traversal: &Traversal) {
+        let entries = self
+            .entries
+            .iter()
+            .map(|b| (path_of(&traversal.tree, b.index), b.data.size));
+        self.message = Some(
+            match fs::File::create(path).and_then(|file| {
+                let mut out = io::BufWriter::new(file);
+                write_entries(&mut out, ExportFormat::from_path(Path::new(path)), entries)?;
+                io::Write::flush(&mut out)
+            }) {
+                Ok(()) => format!("Exported {} entries to '{}'", self.entries.len(), path),
+                Err(err) => format!("Could not export to '{}': {}", path, err),
+            },
+        );
+    }
+
+    /// Archive all marked entries to the file at `path` and delete them, but only if the archive could be verified.
+    pub fn archive_marked_entries(
+        &mut self,
+        path: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let pane = match window.mark_pane.take() {
+            Some(pane) => pane,
+            None => return,
+        };
+        let marked: Vec<_> = pane.marked().values().map(|m| m.path.clone()).collect();
+        let paths: Vec<_> = marked
+            .iter()
+            .filter(|p| {
+                !marked
+                    .iter()
+                    .any(|other| p.starts_with(other) && p != &other)
+            })
+            .cloned()
+            .collect();
+        let archive = lexically_absolute(Path::new(path));
+        if let Some(marked) = paths
+            .iter()
+            .find(|marked| archive.starts_with(lexically_absolute(marked)))
+        {
+            window.mark_pane = Some(pane);
+            self.message = Some(format!(
+                "Nothing was archived, as '{}' would be deleted along with '{}'",
+                path,
+                marked.display()
+            ));
+            return;
+        }
+        if let Err(err) = create_verified_archive(Path::new(path), &paths) {
+            window.mark_pane = Some(pane);
+            self.message = Some(format!(
+                "Nothing was deleted, archiving to '{}' failed: {:#}",
+                path, err
+            ));
+            return;
+        }
+        let mut entries_deleted = 0;
+        let mut hook_failures = Vec::new();
+        // the archive contains all marked entries, so they are deleted regardless of the cap
+        (window.mark_pane, _) = pane.iterate_deletable_items(
+            None,
+            || false,
+            |pane, entry_to_delete| match self.delete_entry(entry_to_delete, traversal) {
+                Ok(ed) => {
+                    entries_deleted += ed;
+                    Ok(pane)
+                }
+                Err(RemovalFailure::Refused(err)) => {
+                    hook_failures.push(err);
+                    Err((pane, 1))
+                }
+                Err(RemovalFailure::Io(c)) => Err((pane, c)),
+            },
+        );
+        if window.mark_pane.is_none() {
+            self.focussed = Main;
+        }
+        self.message = Some(format!(
+            "Archived {} entries to '{}' and deleted {} entries",
+            paths.len(),
+            path,
+            entries_deleted
+        ));
+        self.report_before_delete_failures(&hook_failures);
+        self.report_deletion_log_error();
+    }
+
+    /// Move all marked entries into the directory at `target_dir`, where they keep their location relative to each
+    /// other. Entries below other marked entries move along with them.
+    pub fn move_marked_entries(
+        &mut self,
+        target_dir: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        walk_options: &WalkOptions,
+    ) {
+        let pane = match window.mark_pane.take() {
+            Some(pane) => pane,
+            None => return,
+        };
+        if target_dir.is_empty() {
+            window.mark_pane = Some(pane);
+            self.message = Some("Nothing was moved, as no directory was given".into());
+            return;
+        }
+        let target_dir = lexically_absolute(Path::new(target_dir));
+        let marked: Vec<_> = pane
+            .marked()
+            .iter()
+            .map(|(index, m)| (*index, m.path.clone()))
+            .collect();
+        let paths: Vec<_> = marked.iter().map(|(_, path)| path.clone()).collect();
+        if let Some(path) = paths
+            .iter()
+            .find(|path| target_dir.starts_with(lexically_absolute(path)))
+        {
+            window.mark_pane = Some(pane);
+            self.message = Some(format!(
+                "Nothing was moved, as '{}' would be moved into itself",
+                path.display()
+            ));
+            return;
+        }
+        let targets: BTreeMap<_, _> = marked
+            .iter()
+            .map(|(index, _)| *index)
+            .zip(relocation_targets(&paths, &target_dir))
+            .collect();
+        let is_below_other_mark = |path: &Path| {
+            paths
+                .iter()
+                .any(|other| path.starts_with(other) && path != other)
+        };
+        let (mut entries_moved, mut failure) = (0, None);
+        (window.mark_pane, _) = pane.iterate_deletable_items(
+            None,
+            || false,
+            |pane, index| {
+                let path = match traversal.tree.node_weight(index) {
+                    Some(_) => path_of(&traversal.tree, index),
+                    None => return Ok(pane),
+                };
+                if is_below_other_mark(&path) {
+                    return Ok(pane);
+                }
+                match move_entry(&path, &targets[&index]) {
+                    Ok(()) => {
+                        entries_moved += self.delete_entries_in_traversal(index, traversal);
+                        Ok(pane)
+                    }
+                    Err(err) => {
+                        failure.get_or_insert(err);
+                        Err((pane, 1))
+                    }
+                }
+            },
+        );
+        if window.mark_pane.is_none() {
+            self.focussed = Main;
+        }
+        let refreshed = match self.is_scanning {
+            true => None,
+            false => target_dir
+                .ancestors()
+                .find_map(|dir| resolve_on_disk(&traversal.tree, dir)),
+        };
+        if let Some(index) = refreshed {
+            let path = path_of(&traversal.tree, index);
+            if let Ok(removed) = traversal.refresh(walk_options.clone(), index, path) {
+                self.forget_refreshed_entries(window, traversal, &removed);
+            }
+        }
+        self.message = Some(match failure {
+            Some(err) => format!(
+                "Moved {} entries into '{}', the ones that failed remain marked: {:#}",
+                entries_moved,
+                target_dir.display(),
+                err
+            ),
+            None => format!(
+                "Moved {} entries into '{}'",
+                entries_moved,
+                target_dir.display()
+            ),
+        });
+    }
+
+    /// Write the checksums of all files at or below the marked entries to the manifest at `path`.
+    pub fn write_checksums(&mut self, path: &str, window: &MainWindow, format: ByteFormat) {
+        let marked: Vec<_> = window
+            .mark_pane
+            .iter()
+            .flat_map(|pane| pane.marked().values().map(|m| m.path.clone()))
+            .collect();
+        let paths: Vec<_> = marked
+            .iter()
+            .filter(|p| {
+                !marked
+                    .iter()
+                    .any(|other| p.starts_with(other) && p != &other)
+            })
+            .cloned()
+            .collect();
+        self.message = Some(match write_checksum_manifest(Path::new(path), &paths) {
+            Ok(summary) => format!(
+                "Wrote the checksums of {} files with {} to '{}'{}",
+                summary.files,
+                format.display(summary.bytes).to_string().trim_start(),
+                path,
+                match summary.unreadable {
+                    0 => String::new(),
+                    n => format!(", {} files could not be read and are missing", n),
+                }
+            ),
+            Err(err) => format!("Could not write checksums to '{}': {:#}", path, err),
+        });
+    }
+
+    /// Check what deleting the marked entries of `pane` would do without deleting anything, and show next to each
+    /// entry why it would fail.
+    fn dry_run_marked_entries(&mut self, pane: &mut MarkPane, format: ByteFormat) {
+        let marked: Vec<_> = pane
+            .marked()
+            .iter()
+            .map(|(index, mark)| (*index, mark.path.clone()))
+            .collect();
+        let mut total = DryRun::default();
+        let mut failing_entries = 0;
+        for (index, path) in &marked {
+            let within_other_marked = marked
+                .iter()
+                .any(|(_, other)| path.starts_with(other) && path != other);
+            let failure = if within_other_marked {
+                None
+            } else {
+                let run = dry_run_deletion(path);
+                let failure =
+                    run.failures
+                        .first()
+                        .map(|(first, reason)| match run.failures.len() {
+                            1 => format!("'{}': {}", first.display(), reason),
+                            n => format!("{} paths, like '{}': {}", n, first.display(), reason),
+                        });
+                total.add(run);
+                failure
+            };
+            failing_entries += failure.is_some() as usize;
+            pane.set_dry_run_failure(*index, failure);
+        }
+        self.message = Some(format!(
+            "Dry run: deleting would remove {} files and {} directories with {}{}",
+            total.files,
+            total.directories,
+            format.display(total.bytes).to_string().trim_start(),
+            match failing_entries {
+                0 => ", and nothing would fail".to_string(),
+                1 => ", but one marked entry would fail as shown".to_string(),
+                n => format!(", but {} marked entries would fail as shown", n),
+            }
+        ));
+    }
+
+    /// Mark the entry at `input`, a path on disk, and scan it first if it isn't part of the traversal yet.
+    /// It is added as another top-level entry then, so it can be deleted like any other marked entry.
+    pub fn mark_path(
+        &mut self,
+        input: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        walk_options: &WalkOptions,
+    ) {
+        let path = match fs::canonicalize(input) {
+            Ok(path) => path,
+            Err(err) => {
+                self.message = Some(format!("Could not mark '{}': {}", input, err));
+                return;
+            }
+        };
+        let index = match resolve_on_disk(&traversal.tree, &path) {
+            Some(index) => index,
+            None if self.is_scanning => {
+                self.message = Some("Paths can be added once the scan is done".into());
+                return;
+            }
+            None => match traversal.add_input(walk_options.clone(), path.clone()) {
+                Ok(index) => {
+                    self.entries =
+                        sorted_entries(&traversal.tree, self.root, self.sorting, &self.filters);
+                    index
+                }
+                Err(err) => {
+                    self.message = Some(format!("Could not scan '{}': {:#}", path.display(), err));
+                    return;
+                }
+            },
+        };
+        if self.refuse_protected(&traversal.tree, index) {
+            return;
+        }
+        let is_dir = path.is_dir();
+        let pane = window.mark_pane.take().unwrap_or_default();
+        window.mark_pane = pane.toggle_index(index, &traversal.tree, is_dir, false);
+        self.message = Some(format!(
+            "Marked '{}' ({})",
+            path.display(),
+            walk_options
+                .byte_format
+                .display(traversal.tree.node_weight(index).map_or(0, |e| e.size))
+                .to_string()
+                .trim_start()
+        ));
+    }
+
+    /// Mark all files below the current root which are larger than the size described by `input`, like '100MB'.
+    /// Make deletions of marked entries pause once they freed `input`, a size like `50GB`, or never if it's empty.
+    pub fn set_deletion_cap(&mut self, input: &str, walk_options: &WalkOptions) {
+        if input.is_empty() {
+            self.deletion_cap = None;
+            self.message = Some("Deletions free all marked entries at once".into());
+            return;
+        }
+        let cap = match byte_unit::Byte::from_str(input) {
+            Ok(cap) if cap.get_bytes() > 0 => cap.get_bytes(),
+            Ok(_) => {
+                self.message = Some("The size to free per deletion must not be 0".into());
+                return;
+            }
+            Err(err) => {
+                self.message = Some(format!("Invalid size {:?}: {}", input, err));
+                return;
+            }
+        };
+        self.deletion_cap = Some(cap);
+        self.message = Some(format!(
+            "Deletions pause after freeing {}, in the order entries were marked",
+            walk_options
+                .byte_format
+                .display(cap)
+                .to_string()
+                .trim_start()
+        ));
+    }
+
+    pub fn mark_files_larger_than(
+        &mut self,
+        input: &str,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let threshold = match byte_unit::Byte::from_str(input.trim()) {
+            Ok(threshold) => threshold.get_bytes(),
+            Err(err) => {
+                self.message = Some(format!("Invalid size {:?}: {}", input, err));
+                return;
+            }
+        };
+        let tree = &traversal.tree;
+        let (mut num_marked, mut num_protected) = (0, 0);
+        let mut pane = window.mark_pane.take().unwrap_or_default();
+        let mut bfs = Bfs::new(tree, self.root);
+        while let Some(idx) = bfs.next(tree) {
+            let is_file = tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .next()
+                .is_none();
+            if idx != self.root
+                && is_file
+                && tree.node_weight(idx).is_some_and(|e| e.size > threshold)
+                && !pane.marked().contains_key(&idx)
+            {
+                if self.protected.touched_by(&path_of(tree, idx)).is_some() {
+                    num_protected += 1;
+                    continue;
+                }
+                num_marked += 1;
+                pane = pane
+                    .toggle_index(idx, tree, false, false)
+                    .expect("at least one entry to be marked");
+            }
+        }
+        window.mark_pane = (!pane.marked().is_empty()).then_some(pane);
+        self.message = Some(format!(
+            "Marked {} file{} larger than {}{}",
+            num_marked,
+            if num_marked == 1 { "" } else { "s" },
+            input.trim(),
+            skipped_protected(num_protected)
+        ));
+    }
+
+    /// Ask to confirm deleting the selected entry by typing its name, without marking it first.
+    pub fn open_quick_delete(&mut self, traversal: &Traversal, format: ByteFormat) {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return,
+        };
+        if self.is_scanning {
+            self.message = Some("Entries can be deleted once the scan is done".into());
+            return;
+        }
+        if self.refuse_read_only("Deleting entries")
+            || self.refuse_protected(&traversal.tree, index)
+        {
+            return;
+        }
+        let size = traversal.tree.node_weight(index).map_or(0, |e| e.size);
+        self.quick_delete = Some((
+            index,
+            format!(
+                "'{}' ({})",
+                path_of(&traversal.tree, index).display(),
+                format.display(size).to_string().trim_start()
+            ),
+        ));
+        self.open_prompt(PromptKind::QuickDelete);
+    }
+
+    /// Delete the entry chosen with [`AppState::open_quick_delete()`] if `confirmation` is its name.
+    pub fn quick_delete_entry(
+        &mut self,
+        confirmation: &str,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let (index, what) = match self.quick_delete.take() {
+            Some(entry) => entry,
+            None => return,
+        };
+        let name = match traversal.tree.node_weight(index) {
+            Some(entry) => entry.name.clone(),
+            None => return,
+        };
+        let name_matches = name
+            .file_name()
+            .map_or(name.as_os_str(), |file_name| file_name)
+            .to_string_lossy()
+            == confirmation;
+        if !name_matches {
+            self.message = Some(format!(
+                "Nothing was deleted, as '{}' isn't the name of {}",
+                confirmation, what
+            ));
+            return;
+        }
+        let mut removed = Vec::new();
+        let mut bfs = Bfs::new(&traversal.tree, index);
+        while let Some(idx) = bfs.next(&traversal.tree) {
+            removed.push(idx);
+        }
+        self.message = Some(match self.delete_entry(index, traversal) {
+            Ok(_) => {
+                self.bookmarks.retain(|root, selected| {
+                    !removed.contains(root) && !removed.contains(selected)
+                });
+                if let Some(pane) = window.mark_pane.take() {
+                    window.mark_pane = pane.unmark_removed(&removed);
+                }
+                format!("Deleted {}", what)
+            }
+            Err(RemovalFailure::Refused(err)) => format!(
+                "Nothing was deleted, as the before-delete command failed for {}: {:#}",
+                what, err
+            ),
+            Err(RemovalFailure::Io(_)) => format!("Could not delete all of {}", what),
+        });
+        self.report_deletion_log_error();
+    }
+
+    /// Mark all entries of the current directory whose name matches the glob `pattern`, like `*.log`.
+    ///
+    /// Patterns containing a `/` are matched against paths relative to the current directory instead, so `**/*.log`
+    /// marks matching entries at any depth. A trailing `/` only matches directories, like `target/`.
+    pub fn mark_matching(&mut self, pattern: &str, window: &mut MainWindow, traversal: &Traversal) {
+        let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+        if pattern.is_empty() {
+            return self.reset_message();
+        }
+        let recursive = pattern.contains('/');
+        let tree = &traversal.tree;
+        let (mut num_marked, mut num_protected) = (0, 0);
+        let mut pane = window.mark_pane.take().unwrap_or_default();
+        let mut stack: Vec<_> = tree
+            .neighbors_directed(self.root, Direction::Outgoing)
+            .map(|idx| (idx, PathBuf::new()))
+            .collect();
+        while let Some((idx, parent)) = stack.pop() {
+            let entry = match tree.node_weight(idx) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let path = parent.join(&entry.name);
+            let text = match recursive {
+                true => path.to_string_lossy(),
+                false => entry.name.to_string_lossy(),
+            };
+            let has_children = tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .next()
+                .is_some();
+            if glob_matches(pattern.as_bytes(), text.as_bytes()) {
+                let is_dir = has_children || path_of(tree, idx).is_dir();
+                if !dirs_only || is_dir {
+                    if self.protected.touched_by(&path_of(tree, idx)).is_some() {
+                        num_protected += 1;
+                    } else if !pane.marked().contains_key(&idx) {
+                        num_marked += 1;
+                        pane = pane
+                            .toggle_index(idx, tree, is_dir, false)
+                            .expect("at least one entry to be marked");
+                    }
+                    continue;
+                }
+            }
+            if recursive && has_children {
+                stack.extend(
+                    tree.neighbors_directed(idx, Direction::Outgoing)
+                        .map(|child| (child, path.clone())),
+                );
+            }
+        }
+        window.mark_pane = (!pane.marked().is_empty()).then_some(pane);
+        let what = if dirs_only { "directories" } else { "entries" };
+        self.message = Some(match num_marked {
+            0 => format!("No unmarked {} match '{}'", what, pattern),
+            1 => format!("Marked 1 entry matching '{}'", pattern),
+            n => format!("Marked {} {} matching '{}'", n, what, pattern),
+        });
+        if let Some(message) = self.message.as_mut() {
+            message.push_str(&skipped_protected(num_protected));
+        }
+    }
+
+    /// Estimate how much space compressing the selected entry, or the current directory if nothing is selected,
+    /// would save by compressing a sample of its files.
+    pub fn estimate_compression_savings(&mut self, traversal: &Traversal, format: ByteFormat) {
+        let tree = &traversal.tree;
+        let index = self.selected.unwrap_or(self.root);
+        let mut files = Vec::new();
+        let mut bfs = Bfs::new(tree, index);
+        while let Some(idx) = bfs.next(tree) {
+            let is_file = tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .next()
+                .is_none();
+            if is_file && tree.node_weight(idx).is_some_and(|e| e.size > 0) {
+                files.push(path_of(tree, idx));
+            }
+        }
+        let name = path_of(tree, index);
+        let total_bytes = tree.node_weight(index).map_or(0, |e| e.size);
+        self.message = Some(match estimate_compression(&files) {
+            Ok(estimate) if estimate.sampled_files == 0 => {
+                format!("There is nothing to compress in {}", name.display())
+            }
+            Ok(estimate) => {
+                let savings = estimate.savings(total_bytes);
+                format!(
+                    "Compressing {} with {} would save about {} ({:.0}%), judging by {} sampled file{}",
+                    name.display(),
+                    estimate.compressor,
+                    format.display(savings).to_string().trim(),
+                    savings as f64 * 100.0 / total_bytes.max(1) as f64,
+                    estimate.sampled_files,
+                    if estimate.sampled_files == 1 { "" } else { "s" }
+                )
+            }
+            Err(err) => format!("Could not estimate compression savings: {:#}", err),
+        });
+    }
+
+    /// Flag the selected entry for follow-up, or remove its flag.
+    pub fn toggle_flag(&mut self, traversal: &Traversal) {
+        if let Some(index) = self.selected {
+            let name = path_of(&traversal.tree, index);
+            self.message = Some(if self.flagged.insert(index) {
+                format!(
+                    "Flagged {}, {} flagged in total",
+                    name.display(),
+                    self.flagged.len()
+                )
+            } else {
+                self.flagged.remove(&index);
+                format!("Removed flag from {}", name.display())
+            });
+        }
+    }
+
+    /// The paths of all flagged entries, in the order they were discovered.
+    pub fn flagged_paths(&self, traversal: &Traversal) -> Vec<PathBuf> {
+        self.flagged
+            .iter()
+            .filter(|idx| traversal.tree.node_weight(**idx).is_some())
+            .map(|idx| path_of(&traversal.tree, *idx))
+            .collect()
+    }
+
     pub fn mark_entry(
         &mut self,
         cursor: CursorMode,
@@ -361,11 +2498,19 @@ impl AppState {
         traversal: &Traversal,
     ) {
         if let Some(index) = self.selected {
+            if !is_marked(window, index)
+                && (self.refuse_read_only("Marking entries for deletion")
+                    || self.refuse_protected(&traversal.tree, index))
+            {
+                return;
+            }
             let is_dir = self
                 .entries
                 .iter()
                 .find(|e| e.index == index)
                 .unwrap()
+                .clone()
+                .with_metadata(&traversal.tree)
                 .is_dir;
             let should_toggle = match mode {
                 MarkEntryMode::Toggle => true,
@@ -384,6 +2529,88 @@ impl AppState {
     }
 }
 
+fn is_marked(window: &MainWindow, index: TreeIndex) -> bool {
+    window
+        .mark_pane
+        .as_ref()
+        .is_some_and(|pane| pane.marked().contains_key(&index))
+}
+
+fn skipped_protected(num_protected: usize) -> String {
+    match num_protected {
+        0 => String::new(),
+        1 => ", skipping 1 protected entry".into(),
+        n => format!(", skipping {} protected entries", n),
+    }
+}
+
+/// The entry at `path` as typed or pasted into the command line, which is either one of the scanned paths on disk,
+/// or a path relative to `root` as understood by [`resolve_relative()`].
+///
+/// Paths copied from logs often carry a line number, like `src/main.rs:42`, which is ignored if needed.
+fn resolve(tree: &Tree, root: TreeIndex, path: &str) -> Option<TreeIndex> {
+    let resolve_path = |path: &str| {
+        Some(Path::new(path))
+            .filter(|p| p.is_absolute())
+            .and_then(|p| resolve_on_disk(tree, p))
+            .or_else(|| resolve_relative(tree, root, path))
+    };
+    resolve_path(path).or_else(|| without_line_number(path).and_then(resolve_path))
+}
+
+/// The entry at the absolute `path` on disk, if it is below one of the scanned paths.
+/// Names are compared regardless of their Unicode normalization form.
+pub(crate) fn resolve_on_disk(tree: &Tree, path: &Path) -> Option<TreeIndex> {
+    let top = tree.externals(Direction::Incoming).next()?;
+    let cwd = std::env::current_dir().ok();
+    let path = normalized(path);
+    tree.neighbors_directed(top, Direction::Outgoing)
+        .find_map(|input_idx| {
+            let input = &tree.node_weight(input_idx)?.name;
+            let candidates = [
+                cwd.as_ref().map(|cwd| cwd.join(input)),
+                input.canonicalize().ok(),
+            ];
+            let relative = candidates
+                .iter()
+                .flatten()
+                .find_map(|input| path.strip_prefix(normalized(input)).ok())?;
+            let relative = relative.to_str()?;
+            resolve_relative(tree, input_idx, relative)
+        })
+}
+
+/// The entry at the `/`-separated `path` relative to `root`, with `..` referring to the parent and a leading `/`
+/// to the top of the tree. Names match regardless of their Unicode normalization form.
+fn resolve_relative(tree: &Tree, root: TreeIndex, path: &str) -> Option<TreeIndex> {
+    let mut index = match path.starts_with('/') {
+        true => tree.externals(Direction::Incoming).next()?,
+        false => root,
+    };
+    for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        index = match component {
+            ".." => tree.neighbors_directed(index, Direction::Incoming).next()?,
+            name => tree
+                .neighbors_directed(index, Direction::Outgoing)
+                .find(|idx| {
+                    tree.node_weight(*idx)
+                        .is_some_and(|e| same_path(&e.name, Path::new(name)))
+                })?,
+        };
+    }
+    Some(index)
+}
+
+/// The message shown while deleting marked entries, after `files` with `bytes` were deleted and the last one was `path`.
+fn deletion_progress(path: &Path, files: u64, bytes: u128, format: ByteFormat) -> String {
+    format!(
+        "Deleting '{}' - {} files with {} freed so far, <esc> aborts",
+        path.display(),
+        files,
+        format.display(bytes).to_string().trim_start()
+    )
+}
+
 fn into_error_count(res: Result<(), io::Error>) -> usize {
     match res.map_err(io_err_to_usize) {
         Ok(_) => 0,
@@ -400,42 +2627,140 @@ fn io_err_to_usize(err: io::Error) -> usize {
 }
 
 // TODO: could use jwalk for this
+/// Why an entry wasn't removed from disk, or not entirely
+pub enum RemovalFailure {
+    /// The before-delete command failed for it, so nothing was removed
+    Refused(anyhow::Error),
+    /// The amount of files and directories below it that couldn't be removed
+    Io(usize),
+}
+
+/// Run the `before_delete` command for `path` if there is one, and only if it succeeds delete it as
+/// [`delete_directory_recursively()`] does. All deletions go through here to honor the command.
+fn remove_from_disk(
+    before_delete: Option<&str>,
+    path: PathBuf,
+    workers: usize,
+    progress: &mut dyn FnMut(&Path, u64) -> bool,
+) -> Result<(), RemovalFailure> {
+    if let Some(command) = before_delete {
+        run_before_delete(command, &path).map_err(RemovalFailure::Refused)?;
+    }
+    delete_directory_recursively(path, workers, progress).map_err(RemovalFailure::Io)
+}
+
 // see https://github.com/Byron/dua-cli/issues/43
-fn delete_directory_recursively(path: PathBuf) -> Result<(), usize> {
-    let mut files_or_dirs = vec![path];
-    let mut dirs = Vec::new();
+/// Delete `path` along with everything below it, calling `progress` with the path and size of each removed file.
+/// Deleting stops with the next file once `progress` returns `false`, leaving the rest in place.
+///
+/// Directories are read on this thread while up to `workers` threads remove the files found in them, as removing
+/// many small files is bound by the latency of the storage. `progress` is only called on this thread.
+fn delete_directory_recursively(
+    path: PathBuf,
+    workers: usize,
+    progress: &mut dyn FnMut(&Path, u64) -> bool,
+) -> Result<(), usize> {
+    let workers = match path.symlink_metadata() {
+        Ok(meta) if meta.is_dir() => workers.max(1),
+        _ => 1, // there is only one file to remove
+    };
+    let stop = AtomicBool::new(false);
+    let (files_tx, files_rx) = mpsc::sync_channel::<(PathBuf, u64)>(workers * 64);
+    let files_rx = Mutex::new(files_rx);
+    let (removed_tx, removed_rx) = mpsc::channel::<Result<(PathBuf, u64), usize>>();
     let mut num_errors = 0;
-    while let Some(path) = files_or_dirs.pop() {
-        let assume_symlink_to_try_deletion = true;
-        let is_symlink = path
-            .symlink_metadata()
-            .map(|m| m.file_type().is_symlink())
-            .unwrap_or(assume_symlink_to_try_deletion);
-        if is_symlink {
-            // do not follow symlinks
-            num_errors += into_error_count(fs::remove_file(&path));
-            continue;
-        }
-        match fs::read_dir(&path) {
-            Ok(iterator) => {
-                dirs.push(path);
-                for entry in iterator {
-                    match entry.map_err(io_err_to_usize) {
-                        Ok(entry) => files_or_dirs.push(entry.path()),
-                        Err(c) => num_errors += c,
-                    }
+    let mut stopped = false;
+    // returns the amount of errors
+    let mut on_removed = |res: Result<(PathBuf, u64), usize>, stopped: &mut bool| match res {
+        Ok((path, size)) => {
+            // files still being removed once stopped are reported, but can't stop anything anymore
+            if !progress(&path, size) && !*stopped {
+                *stopped = true;
+                stop.store(true, Ordering::SeqCst);
+            }
+            0
+        }
+        Err(c) => c,
+    };
+    let mut dirs = Vec::new();
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let (files_rx, removed_tx, stop) = (&files_rx, removed_tx.clone(), &stop);
+            scope.spawn(move || loop {
+                let next = files_rx
+                    .lock()
+                    .expect("no panic while holding the lock")
+                    .recv();
+                let (path, size) = match next {
+                    Ok(file) => file,
+                    Err(_) => break,
+                };
+                if stop.load(Ordering::SeqCst) {
+                    continue;
                 }
+                let res = fs::remove_file(&path)
+                    .map(|_| (path, size))
+                    .map_err(io_err_to_usize);
+                if removed_tx.send(res).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(removed_tx);
+
+        let mut files_or_dirs = vec![path];
+        while let Some(path) = files_or_dirs.pop() {
+            for res in removed_rx.try_iter() {
+                num_errors += on_removed(res, &mut stopped);
             }
-            Err(ref e) if e.kind() == io::ErrorKind::Other => {
-                // assume file, save IOps
-                num_errors += into_error_count(fs::remove_file(path));
-                continue;
+            if stopped {
+                break;
             }
-            Err(_) => {
-                num_errors += 1;
+            let assume_symlink_to_try_deletion = true;
+            let metadata = path.symlink_metadata();
+            let is_symlink = metadata
+                .as_ref()
+                .map(|m| m.file_type().is_symlink())
+                .unwrap_or(assume_symlink_to_try_deletion);
+            let size = metadata.map_or(0, |m| m.len());
+            if is_symlink {
+                // do not follow symlinks
+                files_tx
+                    .send((path, size))
+                    .expect("workers to run until the sender is dropped");
                 continue;
             }
-        };
+            match fs::read_dir(&path) {
+                Ok(iterator) => {
+                    dirs.push(path);
+                    for entry in iterator {
+                        match entry.map_err(io_err_to_usize) {
+                            Ok(entry) => files_or_dirs.push(entry.path()),
+                            Err(c) => num_errors += c,
+                        }
+                    }
+                }
+                Err(ref e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::Other | io::ErrorKind::NotADirectory
+                    ) =>
+                {
+                    // assume file, save IOps
+                    files_tx
+                        .send((path, size))
+                        .expect("workers to run until the sender is dropped");
+                }
+                Err(_) => num_errors += 1,
+            };
+        }
+        drop(files_tx);
+        for res in removed_rx {
+            num_errors += on_removed(res, &mut stopped);
+        }
+    });
+    if stopped {
+        return Err(num_errors);
     }
 
     for dir in dirs.into_iter().rev() {
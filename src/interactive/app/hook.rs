@@ -0,0 +1,61 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Run the shell `command` configured as `before-delete` for the entry at `path`, which is passed as `$1`
+/// (or appended on Windows), like `rsync -a "$1" backup:/archive/`.
+///
+/// Only if this succeeds the entry may be deleted. The output of the command is captured to not disturb
+/// the terminal, and the last line of its error output is part of the error.
+pub fn run_before_delete(command: &str, path: &Path) -> Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command).arg(path);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).arg("dua").arg(path);
+        cmd
+    };
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Could not run '{}'", command))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+            Some(line) => bail!(
+                "'{}' failed with {}: {}",
+                command,
+                output.status,
+                line.trim()
+            ),
+            None => bail!("'{}' failed with {}", command, output.status),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    #[cfg(unix)]
+    fn the_path_is_passed_as_first_argument_and_failures_are_errors() -> Result<()> {
+        let dir = WritableFixture::empty("before-delete");
+        let copy = dir.join("copy");
+
+        run_before_delete(&format!("echo \"$1\" > '{}'", copy.display()), &dir)?;
+        let written = std::fs::read_to_string(&copy)?;
+        let failure = run_before_delete("echo nope >&2; exit 3", &dir);
+
+        assert_eq!(written.trim_end(), dir.display().to_string());
+        let err = failure.expect_err("non-zero exit codes fail").to_string();
+        assert!(err.ends_with(": nope"), "{}", err);
+        Ok(())
+    }
+}
@@ -1,12 +1,43 @@
+mod archive;
 mod bytevis;
+mod categories;
+mod checksums;
+mod command;
 mod common;
+mod compression;
+mod config;
+mod deletion;
+mod dryrun;
 mod eventloop;
+mod export;
 mod handlers;
+mod hook;
+mod protected;
+mod relocate;
+mod suggestions;
+mod symlinkfarms;
+pub mod title;
+mod trashbin;
 
+pub use archive::*;
 pub use bytevis::*;
+pub use categories::*;
+pub use checksums::*;
+pub use command::*;
 pub use common::*;
+pub use compression::*;
+pub use config::*;
+pub use deletion::*;
+pub use dryrun::*;
 pub use eventloop::*;
+pub use export::*;
 pub use handlers::*;
+pub use hook::*;
+pub use protected::*;
+pub use relocate::*;
+pub use suggestions::*;
+pub use symlinkfarms::*;
+pub use trashbin::*;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
@@ -0,0 +1,125 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Paths which must never be deleted, as configured with `protected = <path>` lines.
+///
+/// Deleting a directory which contains one of them would delete it too, so these directories are protected as well.
+#[derive(Debug, Default, Clone)]
+pub struct ProtectedPaths(Vec<PathBuf>);
+
+impl ProtectedPaths {
+    pub fn new(paths: &[PathBuf]) -> Self {
+        ProtectedPaths(
+            paths
+                .iter()
+                .map(|path| {
+                    path.canonicalize()
+                        .unwrap_or_else(|_| lexically_absolute(path))
+                })
+                .collect(),
+        )
+    }
+
+    /// The protected path deleting `path` would remove, which is `path` itself or one below it.
+    pub fn touched_by(&self, path: &Path) -> Option<&Path> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let path = resolved(path);
+        self.0
+            .iter()
+            .find(|protected| protected.starts_with(&path))
+            .map(PathBuf::as_path)
+    }
+
+    /// Why `path` must not be deleted, if deleting it would remove a protected path.
+    pub fn refusal(&self, path: &Path) -> Option<String> {
+        let protected = self.touched_by(path)?;
+        Some(if protected == resolved(path) {
+            format!("'{}' is protected from deletion", path.display())
+        } else {
+            format!(
+                "'{}' must not be deleted as it contains the protected '{}'",
+                path.display(),
+                protected.display()
+            )
+        })
+    }
+}
+
+/// `path` as absolute path with symbolic links resolved, except for the last component as deleting a link only
+/// removes the link.
+fn resolved(path: &Path) -> PathBuf {
+    let path = lexically_absolute(path);
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map_or_else(|_| path.clone(), |parent| parent.join(name)),
+        _ => path,
+    }
+}
+
+pub(crate) fn lexically_absolute(path: &Path) -> PathBuf {
+    let path = match path.is_relative() {
+        true => std::env::current_dir().map_or(path.to_owned(), |cwd| cwd.join(path)),
+        false => path.to_owned(),
+    };
+    let mut absolute = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    absolute
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    fn protected_paths_and_their_ancestors_are_refused() -> std::io::Result<()> {
+        let fixture = WritableFixture::empty("protected-paths");
+        let root = fixture.canonicalize()?;
+        std::fs::create_dir_all(root.join("keep/below"))?;
+        std::fs::create_dir_all(root.join("other"))?;
+        let protected = ProtectedPaths::new(&[root.join("other/../keep")]);
+
+        assert_eq!(
+            protected.touched_by(&root.join("keep")),
+            Some(root.join("keep").as_path())
+        );
+        assert!(protected.touched_by(&root).is_some(), "it contains it");
+        assert!(protected.touched_by(&root.join("other/..")).is_some());
+        assert_eq!(protected.touched_by(&root.join("keep/below")), None);
+        assert_eq!(protected.touched_by(&root.join("other")), None);
+        assert_eq!(ProtectedPaths::default().touched_by(&root), None);
+        assert_eq!(
+            protected.refusal(&root.join("keep")),
+            Some(format!(
+                "'{}' is protected from deletion",
+                root.join("keep").display()
+            ))
+        );
+        assert_eq!(protected.refusal(&root.join("other")), None);
+        #[cfg(unix)]
+        {
+            let link = root.join("other/link");
+            std::os::unix::fs::symlink(&root, &link)?;
+            assert!(
+                protected.touched_by(&link.join("keep")).is_some(),
+                "links leading to it are resolved"
+            );
+            assert_eq!(
+                protected.touched_by(&link),
+                None,
+                "deleting the link itself leaves its target alone"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use crate::interactive::app::lexically_absolute;
+use anyhow::{bail, Context, Result};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where each of `paths` goes when moving them into `target_dir`, which keeps their location relative to the
+/// deepest directory containing all of them. Moving a single entry puts it right into `target_dir`.
+pub fn relocation_targets(paths: &[PathBuf], target_dir: &Path) -> Vec<PathBuf> {
+    let paths: Vec<_> = paths.iter().map(|p| lexically_absolute(p)).collect();
+    let mut common = match paths.first().and_then(|p| p.parent()) {
+        Some(parent) => parent.to_owned(),
+        None => return Vec::new(),
+    };
+    for path in &paths[1..] {
+        while !path.starts_with(&common) || path == &common {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    paths
+        .iter()
+        .map(|path| {
+            target_dir.join(
+                path.strip_prefix(&common)
+                    .expect("the common directory to contain all paths"),
+            )
+        })
+        .collect()
+}
+
+/// Move the entry at `from` to `to`, creating the directories leading to it. Across filesystems, it's copied and
+/// only removed once the copy is complete, otherwise the partial copy is removed again.
+pub fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    if to.symlink_metadata().is_ok() {
+        bail!("'{}' already exists", to.display());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create '{}'", parent.display()))?;
+    }
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {}
+        Err(err) => {
+            return Err(err).with_context(|| {
+                format!("Could not move '{}' to '{}'", from.display(), to.display())
+            })
+        }
+    }
+    if let Err(err) = copy_recursively(from, to) {
+        remove(to).ok();
+        return Err(err)
+            .with_context(|| format!("Could not copy '{}' to '{}'", from.display(), to.display()));
+    }
+    remove(from).with_context(|| {
+        format!(
+            "Copied '{}' to '{}', but could not remove it",
+            from.display(),
+            to.display()
+        )
+    })
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = from.symlink_metadata()?;
+    if meta.file_type().is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+        #[cfg(not(unix))]
+        return fs::copy(from, to).map(|_| ());
+    }
+    if !meta.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    fs::set_permissions(to, meta.permissions())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match path.symlink_metadata()?.is_dir() {
+        true => fs::remove_dir_all(path),
+        false => fs::remove_file(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::WritableFixture;
+
+    #[test]
+    fn entries_keep_their_location_below_their_common_directory() {
+        let target = Path::new("/quarantine");
+        assert_eq!(
+            relocation_targets(&["/data/a/big".into()], target),
+            vec![PathBuf::from("/quarantine/big")]
+        );
+        assert_eq!(
+            relocation_targets(
+                &["/data/a/x".into(), "/data/b/c/y".into(), "/data/a".into()],
+                target
+            ),
+            vec![
+                PathBuf::from("/quarantine/a/x"),
+                "/quarantine/b/c/y".into(),
+                "/quarantine/a".into()
+            ]
+        );
+        assert_eq!(
+            relocation_targets(&["/data/a".into(), "/other/b".into()], target),
+            vec![
+                PathBuf::from("/quarantine/data/a"),
+                "/quarantine/other/b".into()
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_are_moved_into_new_directories_but_never_replace_existing_ones() -> Result<()> {
+        let root = WritableFixture::empty("move-entry");
+        fs::create_dir_all(root.join("data/dir/sub"))?;
+        fs::write(root.join("data/dir/sub/file"), "content")?;
+        fs::write(root.join("data/other"), "other")?;
+
+        move_entry(&root.join("data/dir"), &root.join("moved/deeply/dir"))?;
+        assert_eq!(
+            fs::read_to_string(root.join("moved/deeply/dir/sub/file"))?,
+            "content"
+        );
+        assert!(!root.join("data/dir").exists());
+
+        let err = move_entry(&root.join("data/other"), &root.join("moved/deeply/dir"))
+            .expect_err("the target exists");
+        assert!(err.to_string().ends_with("already exists"));
+        assert!(root.join("data/other").exists(), "nothing was moved");
+
+        copy_recursively(&root.join("moved"), &root.join("copy"))?;
+        assert_eq!(
+            fs::read_to_string(root.join("copy/deeply/dir/sub/file"))?,
+            "content"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use crate::interactive::path_of;
+use dua::traverse::{Traversal, Tree, TreeIndex, RECENT_PERIOD_DAYS};
+use petgraph::Direction;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The most suggestions we keep, the largest ones
+const MAX_SUGGESTIONS: usize = 1_000;
+
+/// Directories which are caches by convention, compared case-insensitively
+const CACHE_NAMES: &[&str] = &[
+    ".cache",
+    "cache",
+    "caches",
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+];
+
+/// Directories that tools recreate from the lockfiles next to them, along with what they contain
+const BUILD_OUTPUTS: &[(&str, &[&str], &str)] = &[
+    ("target", &["Cargo.lock"], "Rust build output"),
+    (
+        "node_modules",
+        &["package-lock.json", "yarn.lock", "pnpm-lock.yaml"],
+        "Installed packages",
+    ),
+    (
+        ".venv",
+        &["poetry.lock", "Pipfile.lock", "uv.lock"],
+        "Python virtual environment",
+    ),
+];
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    ".zip", ".tar", ".tar.gz", ".tgz", ".tar.xz", ".tar.zst", ".7z", ".rar", ".iso", ".dmg",
+];
+const COMPRESSION_EXTENSIONS: &[&str] = &[".gz", ".xz", ".bz2", ".zst"];
+
+/// An entry that can likely be deleted without losing anything of value, and why we think so
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    pub size: u128,
+    pub is_dir: bool,
+    pub rationale: String,
+}
+
+/// Find entries of `traversal` which look like they are safe to clean up, the largest first.
+///
+/// These are caches nothing was written to recently, build outputs and installed packages next to the lockfile
+/// they are recreated from, rotated logs, and archives with the same name and size as another one.
+/// Nothing below a suggested directory is suggested on its own.
+pub fn suggest_cleanups(traversal: &Traversal) -> Vec<Suggestion> {
+    let tree = &traversal.tree;
+    let mut suggestions = Vec::new();
+    let mut archives = BTreeMap::<(u128, String), Vec<TreeIndex>>::new();
+    let mut stack: Vec<_> = children(tree, traversal.root_index).collect();
+    while let Some(index) = stack.pop() {
+        let entry = &tree[index];
+        if entry.size == 0 {
+            continue;
+        }
+        let name = name_of(&entry.name);
+        let is_dir = children(tree, index).next().is_some();
+        let rationale = match is_dir {
+            true => directory_rationale(traversal, index, &name),
+            false => {
+                if let Some(key) = archive_key(&name) {
+                    archives.entry((entry.size, key)).or_default().push(index);
+                }
+                is_rotated_log(&name).then(|| "Rotated log, which is no longer written to".into())
+            }
+        };
+        match rationale {
+            Some(rationale) => suggestions.push(Suggestion {
+                index,
+                path: path_of(tree, index),
+                size: entry.size,
+                is_dir,
+                rationale,
+            }),
+            None => stack.extend(children(tree, index)),
+        }
+    }
+
+    for duplicates in archives.into_values().filter(|d| d.len() > 1) {
+        let mut duplicates: Vec<_> = duplicates
+            .into_iter()
+            .map(|index| (path_of(tree, index), index))
+            .collect();
+        duplicates
+            .sort_by(|(a, _), (b, _)| (a.as_os_str().len(), a).cmp(&(b.as_os_str().len(), b)));
+        let (original, _) = duplicates.remove(0);
+        suggestions.extend(duplicates.into_iter().map(|(path, index)| Suggestion {
+            index,
+            size: tree[index].size,
+            is_dir: false,
+            rationale: format!(
+                "Archive with the same name and size as '{}'",
+                original.display()
+            ),
+            path,
+        }));
+    }
+    suggestions.sort_by_key(|s| std::cmp::Reverse(s.size));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+fn directory_rationale(traversal: &Traversal, index: TreeIndex, name: &str) -> Option<String> {
+    let tree = &traversal.tree;
+    if CACHE_NAMES.contains(&name.to_lowercase().as_str()) {
+        let is_old = tree[index]
+            .mtime_range
+            .is_some_and(|range| range.newest < traversal.recent_since);
+        return is_old.then(|| {
+            format!(
+                "Cache which nothing was written to in the last {} days",
+                RECENT_PERIOD_DAYS
+            )
+        });
+    }
+    let (_, lockfiles, what) = BUILD_OUTPUTS.iter().find(|(dir, _, _)| *dir == name)?;
+    let parent = tree.neighbors_directed(index, Direction::Incoming).next()?;
+    let lockfile = children(tree, parent)
+        .map(|sibling| name_of(&tree[sibling].name))
+        .find(|sibling| lockfiles.contains(&sibling.as_str()))?;
+    Some(format!(
+        "{}, which can be recreated from '{}' next to it",
+        what, lockfile
+    ))
+}
+
+/// Return true for names like `syslog.1`, `app.log.2.gz`, `app.log-20240101` or `app.log.gz`.
+fn is_rotated_log(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let mut name = name.as_str();
+    let is_compressed = match COMPRESSION_EXTENSIONS
+        .iter()
+        .find(|ext| name.ends_with(*ext))
+    {
+        Some(ext) => {
+            name = &name[..name.len() - ext.len()];
+            true
+        }
+        None => false,
+    };
+    let has_number_suffix = |separator: char| {
+        name.rsplit_once(separator).is_some_and(|(stem, suffix)| {
+            stem.contains("log") && !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit())
+        })
+    };
+    has_number_suffix('.') || has_number_suffix('-') || (is_compressed && name.ends_with(".log"))
+}
+
+/// The name archives have regardless of copies being numbered, like `photos (1).zip`, if `name` is one.
+fn archive_key(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    let ext = ARCHIVE_EXTENSIONS
+        .iter()
+        .filter(|ext| name.ends_with(*ext))
+        .max_by_key(|ext| ext.len())?;
+    let stem = &name[..name.len() - ext.len()];
+    let stem = match stem.rsplit_once(" (") {
+        Some((original, number))
+            if number
+                .strip_suffix(')')
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            original
+        }
+        _ => stem.strip_suffix(" - copy").unwrap_or(stem),
+    };
+    Some(format!("{}{}", stem, ext))
+}
+
+fn children(tree: &Tree, index: TreeIndex) -> impl Iterator<Item = TreeIndex> + '_ {
+    tree.neighbors_directed(index, Direction::Outgoing)
+}
+
+fn name_of(name: &Path) -> String {
+    name.file_name()
+        .unwrap_or(name.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dua::traverse::{EntryData, MtimeRange};
+
+    #[test]
+    fn likely_safe_cleanups_are_suggested_with_their_rationale() {
+        let mut t = Traversal {
+            recent_since: 1_000,
+            ..Default::default()
+        };
+        t.root_index = t.tree.add_node(EntryData::default());
+        let root = t.root_index;
+        let tree = &mut t.tree;
+        let mut add = |parent: TreeIndex, name: &str, size: u128, newest: u32| {
+            let index = tree.add_node(EntryData {
+                name: name.into(),
+                size,
+                mtime_range: Some(MtimeRange { oldest: 0, newest }),
+                ..Default::default()
+            });
+            tree.add_edge(parent, index, ());
+            index
+        };
+        let project = add(root, "/work/project", 691, 2_000);
+        let target = add(project, "target", 500, 2_000);
+        add(target, "debug.bin", 500, 2_000);
+        add(project, "Cargo.lock", 1, 0);
+        let lone = add(project, "other", 70, 0);
+        let lone_target = add(lone, "target", 50, 0);
+        add(lone_target, "kept.bin", 50, 0);
+        let old_cache = add(project, ".cache", 40, 10);
+        add(old_cache, "blob", 40, 10);
+        let fresh_cache = add(project, "cache", 30, 2_000);
+        add(fresh_cache, "fresh", 30, 2_000);
+        add(project, "app.log.1", 20, 0);
+        add(project, "app.log", 20, 0);
+        add(project, "photos.zip", 10, 0);
+        add(lone, "Photos (1).zip", 10, 0);
+        add(lone, "photos.zip.part", 10, 0);
+
+        let suggested: Vec<_> = suggest_cleanups(&t)
+            .into_iter()
+            .map(|s| (s.path.to_string_lossy().into_owned(), s.size, s.rationale))
+            .collect();
+        assert_eq!(
+            suggested,
+            vec![
+                (
+                    "/work/project/target".into(),
+                    500,
+                    "Rust build output, which can be recreated from 'Cargo.lock' next to it".into()
+                ),
+                (
+                    "/work/project/.cache".into(),
+                    40,
+                    "Cache which nothing was written to in the last 7 days".into()
+                ),
+                (
+                    "/work/project/app.log.1".into(),
+                    20,
+                    "Rotated log, which is no longer written to".into()
+                ),
+                (
+                    "/work/project/other/Photos (1).zip".into(),
+                    10,
+                    "Archive with the same name and size as '/work/project/photos.zip'".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rotated_logs_are_recognized_by_their_suffix() {
+        for rotated in ["syslog.1", "app.log.2.gz", "app.log-20240101", "APP.LOG.xz"] {
+            assert!(is_rotated_log(rotated), "{}", rotated);
+        }
+        for current in ["app.log", "archive.1", "catalog", "v1.2"] {
+            assert!(!is_rotated_log(current), "{}", current);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::interactive::{app::resolve_on_disk, path_of};
+use dua::traverse::{Tree, TreeIndex};
+use petgraph::Direction;
+use std::{collections::HashSet, fs, path::PathBuf};
+
+/// Directories with fewer links than this aren't worth summarizing
+const MIN_LINKS: u64 = 8;
+/// The share of entries in a directory which have to be symbolic links for it to be a farm of them
+const MIN_LINK_SHARE: f64 = 0.8;
+
+/// A directory consisting mostly of symbolic links, like the references into a nix store or the alternatives
+/// of a system, which takes almost no space itself while pointing to a lot of data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymlinkFarm {
+    pub path: PathBuf,
+    /// The amount of symbolic links directly in the directory
+    pub links: u64,
+    /// The amount of all entries directly in the directory
+    pub entries: u64,
+    /// The size of the distinct targets of the links, each counted once. Targets within the scanned tree have
+    /// their size as scanned, files elsewhere their apparent size.
+    pub target_bytes: u128,
+    /// The amount of links whose target doesn't exist
+    pub dangling: u64,
+    /// The amount of distinct directories the links point to which weren't scanned, and are not part of `target_bytes`
+    pub unscanned_targets: u64,
+}
+
+/// Find all directories in `tree` consisting mostly of symbolic links, those pointing to the most data first.
+pub fn symlink_farms(tree: &Tree) -> Vec<SymlinkFarm> {
+    let mut farms: Vec<_> = tree
+        .node_indices()
+        .filter_map(|index| symlink_farm(tree, index))
+        .collect();
+    farms.sort_by(|a, b| {
+        b.target_bytes
+            .cmp(&a.target_bytes)
+            .then(a.path.cmp(&b.path))
+    });
+    farms
+}
+
+fn symlink_farm(tree: &Tree, index: TreeIndex) -> Option<SymlinkFarm> {
+    let (mut entries, mut links) = (0, Vec::new());
+    for child in tree.neighbors_directed(index, Direction::Outgoing) {
+        entries += 1;
+        if tree[child].is_symlink {
+            links.push(child);
+        }
+    }
+    let num_links = links.len() as u64;
+    if num_links < MIN_LINKS || (num_links as f64) < entries as f64 * MIN_LINK_SHARE {
+        return None;
+    }
+    let mut farm = SymlinkFarm {
+        path: path_of(tree, index),
+        links: num_links,
+        entries,
+        target_bytes: 0,
+        dangling: 0,
+        unscanned_targets: 0,
+    };
+    let mut seen = HashSet::new();
+    for link in links {
+        let target = match path_of(tree, link).canonicalize() {
+            Ok(target) => target,
+            Err(_) => {
+                farm.dangling += 1;
+                continue;
+            }
+        };
+        if !seen.insert(target.clone()) {
+            continue;
+        }
+        match resolve_on_disk(tree, &target) {
+            Some(target) => farm.target_bytes += tree[target].size,
+            None => match fs::metadata(&target) {
+                Ok(meta) if meta.is_dir() => farm.unscanned_targets += 1,
+                Ok(meta) => farm.target_bytes += meta.len() as u128,
+                Err(_) => farm.dangling += 1,
+            },
+        }
+    }
+    Some(farm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::app::tests::utils::{test_walk_options, WritableFixture};
+    use dua::{traverse::Traversal, TraversalSorting, WalkOptions};
+
+    #[test]
+    #[cfg(unix)]
+    fn directories_of_mostly_links_are_summarized_by_their_targets() -> anyhow::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let fixture = WritableFixture::empty("symlink-farms");
+        let root = fixture.canonicalize()?;
+        fs::create_dir_all(root.join("store/pkg"))?;
+        fs::create_dir_all(root.join("farm"))?;
+        fs::create_dir_all(root.join("few"))?;
+        for n in 0..10 {
+            fs::write(root.join(format!("store/{}", n)), vec![0; 100])?;
+        }
+        fs::write(root.join("store/pkg/lib"), vec![0; 1000])?;
+        for n in 0..7 {
+            symlink(
+                root.join(format!("store/{}", n)),
+                root.join(format!("farm/{}", n)),
+            )?;
+            symlink(
+                root.join(format!("store/{}", n)),
+                root.join(format!("few/{}", n)),
+            )?;
+        }
+        symlink(root.join("store/0"), root.join("farm/again"))?;
+        symlink(root.join("store/pkg"), root.join("farm/pkg"))?;
+        symlink(root.join("nowhere"), root.join("farm/dangling"))?;
+        fs::write(root.join("farm/regular"), vec![0; 5])?;
+
+        let traversal = Traversal::from_walk(
+            WalkOptions {
+                sorting: TraversalSorting::None,
+                cross_filesystems: true,
+                ..test_walk_options()
+            },
+            vec![root.clone()],
+            |_| Ok(false),
+        )?
+        .expect("traversals without updates run to completion");
+        let farms = symlink_farms(&traversal.tree);
+
+        assert_eq!(
+            farms,
+            vec![SymlinkFarm {
+                path: root.join("farm"),
+                links: 10,
+                entries: 11,
+                target_bytes: 7 * 100 + 1000,
+                dangling: 1,
+                unscanned_targets: 0,
+            }],
+            "links to the same target count once, and too few links aren't a farm"
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use crosstermion::input::Key;
 use pretty_assertions::assert_eq;
 use std::ffi::OsString;
 
@@ -10,7 +11,7 @@ use crate::interactive::{
         },
         FIXTURE_PATH,
     },
-    SortMode,
+    EntryFilter, SortMode,
 };
 
 #[test]
@@ -77,6 +78,29 @@ fn simple_user_journey_read_only() -> Result<()> {
         );
         // when hitting the S key again
         app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+        assert_eq!(
+            app.state.sorting,
+            SortMode::NameAscending,
+            "it sets the sort mode to alphabetical"
+        );
+        assert_eq!(
+            node_by_index(&app, app.state.entries[0].index),
+            node_by_name(&app, fixture_str(short_root)),
+            "it recomputes the cached entries"
+        );
+        // when hitting the S key until it wraps around
+        app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+        assert_eq!(app.state.sorting, SortMode::MtimeDescending);
+        app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+        assert_eq!(app.state.sorting, SortMode::MtimeAscending);
+        app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+        assert_eq!(app.state.sorting, SortMode::CountDescending);
+        assert_eq!(
+            node_by_index(&app, app.state.entries[0].index),
+            node_by_name(&app, fixture_str(short_root)),
+            "it puts the entry with the most files and directories first"
+        );
+        app.process_events(&mut terminal, into_keys(b"s".iter()))?;
         assert_eq!(
             app.state.sorting,
             SortMode::SizeDescending,
@@ -176,9 +200,10 @@ fn simple_user_journey_read_only() -> Result<()> {
                 "it marks only a single node",
             );
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it marks the selected node"
             );
             assert_eq!(
@@ -216,9 +241,10 @@ fn simple_user_journey_read_only() -> Result<()> {
             );
 
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it leaves the first selected entry marked"
             );
         }
@@ -274,3 +300,760 @@ fn simple_user_journey_read_only() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn sibling_navigation() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    // enter sample-01/dir/sub
+    app.process_events(&mut terminal, into_keys(b"oojo".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "sub"),
+        "it entered 'sub'"
+    );
+
+    // when moving to the next sibling
+    app.process_events(&mut terminal, into_keys(b"J".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "empty-dir"),
+        "it skips files and enters the next sibling directory"
+    );
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, ".gitkeep")),
+        "it selects the first entry of the sibling"
+    );
+
+    // when there is no further sibling
+    app.process_events(&mut terminal, into_keys(b"J".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "empty-dir"),
+        "it stays in the last sibling directory"
+    );
+
+    // when moving to the previous sibling
+    app.process_events(&mut terminal, into_keys(b"K".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "sub"),
+        "it enters the previous sibling directory"
+    );
+
+    // when going up
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "sub")),
+        "the sibling we came from is selected in the parent"
+    );
+    Ok(())
+}
+
+#[test]
+fn auto_descend_through_single_child_directories() -> Result<()> {
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture(&["single-child-chain"])?;
+    app.state.auto_descend = true;
+
+    // enter the fixture root, then 'a'
+    app.process_events(&mut terminal, into_keys(b"oo".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "c"),
+        "it tunnels through 'a' and 'b' which only contain another directory"
+    );
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "file")));
+
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, fixture_str("single-child-chain")),
+        "it ascends through the chain as well"
+    );
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "a")),
+        "the directory the chain started at is selected"
+    );
+
+    // without auto-descend
+    app.process_events(&mut terminal, into_keys(b"To".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "a"),
+        "it descends one level at a time"
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_chips_can_be_removed_individually() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let hidden = index_by_name(&app, ".hidden.666");
+    assert!(app.state.entries.iter().any(|e| e.index == hidden));
+
+    app.process_events(&mut terminal, into_keys(b"N.".iter()))?;
+    assert_eq!(
+        app.state.filters,
+        vec![EntryFilter::HardLinked, EntryFilter::NotHidden]
+    );
+    assert_eq!(app.state.selected_filter, None, "no chip is selected yet");
+
+    app.process_events(&mut terminal, vec![Key::Char('f'), Key::Delete].into_iter())?;
+    assert_eq!(
+        app.state.filters,
+        vec![EntryFilter::NotHidden],
+        "the first chip was removed"
+    );
+    assert_eq!(
+        app.state.selected_filter,
+        Some(0),
+        "the next chip is selected"
+    );
+    assert!(
+        !app.state.entries.iter().any(|e| e.index == hidden),
+        "the remaining filter still applies"
+    );
+
+    app.process_events(&mut terminal, vec![Key::Delete].into_iter())?;
+    assert!(app.state.filters.is_empty());
+    assert_eq!(app.state.selected_filter, None);
+    assert!(app.state.entries.iter().any(|e| e.index == hidden));
+    Ok(())
+}
+
+#[test]
+fn names_are_filtered_while_typing() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let names = |app: &crate::interactive::app::TerminalApp| {
+        let mut names: Vec<_> = app
+            .state
+            .entries
+            .iter()
+            .map(|e| e.data.name.to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    app.process_events(&mut terminal, into_keys(b"o/d".iter()))?;
+    assert_eq!(
+        names(&app),
+        vec![".hidden.666", "dir"],
+        "entries are filtered with the first character"
+    );
+    app.process_events(&mut terminal, into_keys(b"ir\n".iter()))?;
+    assert_eq!(names(&app), vec!["dir"]);
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "dir")));
+    assert_eq!(
+        app.state.filters,
+        vec![EntryFilter::Name("dir".into())],
+        "the filter is kept"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"/".iter()))?;
+    assert_eq!(
+        app.state.prompt.as_ref().map(|p| p.input.as_str()),
+        Some("dir"),
+        "it can be changed later"
+    );
+    app.process_events(&mut terminal, vec![Key::Esc].into_iter())?;
+    assert!(app.state.filters.is_empty(), "escape clears it");
+    assert_eq!(names(&app).len(), 6);
+    Ok(())
+}
+
+#[test]
+fn entries_matching_a_glob_are_marked() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let is_marked = |app: &crate::interactive::app::TerminalApp, name: &str| {
+        app.window
+            .mark_pane
+            .as_ref()
+            .is_some_and(|p| p.marked().contains_key(&index_by_name(app, name)))
+    };
+    app.process_events(&mut terminal, into_keys(b"o**.b\n".iter()))?;
+    assert!(is_marked(&app, "z123.b"));
+    assert!(!is_marked(&app, "b.empty"), "names must match as a whole");
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Marked 1 entry matching '*.b'")
+    );
+
+    app.process_events(&mut terminal, into_keys(b":mark dir-*\n".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("No unmarked entries match 'dir-*'"),
+        "only the current directory is searched"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"***/dir-*.*b\n".iter()))?;
+    for name in ["dir-a.1mb", "dir-a.kb", "dir-sub-a.256kb"] {
+        assert!(is_marked(&app, name), "{} is marked at any depth", name);
+    }
+    app.process_events(&mut terminal, into_keys(b"***/*empty*/\n".iter()))?;
+    assert!(is_marked(&app, "empty-dir"));
+    assert!(!is_marked(&app, "b.empty"), "only directories match");
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(5)
+    );
+    Ok(())
+}
+
+#[test]
+fn the_path_of_the_selected_entry_scrolls_in_the_footer() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"o[[]".iter()))?;
+    let selected = app.state.selected.expect("selection");
+    assert_eq!(app.state.path_scroll, Some((selected, 16)));
+
+    app.process_events(&mut terminal, into_keys(b"j[".iter()))?;
+    let selected = app.state.selected.expect("selection");
+    assert_eq!(
+        app.state.path_scroll,
+        Some((selected, 16)),
+        "each entry starts out showing the end of its path"
+    );
+    app.process_events(&mut terminal, into_keys(b"]]".iter()))?;
+    assert_eq!(app.state.path_scroll, Some((selected, 0)));
+    Ok(())
+}
+
+#[test]
+fn entries_found_in_the_whole_tree_can_be_shown() -> Result<()> {
+    use crate::interactive::app::FocussedPane;
+
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(
+        &mut terminal,
+        Some(Key::Ctrl('f'))
+            .into_iter()
+            .chain(into_keys(b"*.256KB\n".iter())),
+    )?;
+    assert!(app.window.find_pane.is_some());
+    assert!(matches!(app.state.focussed, FocussedPane::Find));
+
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let file = index_by_name(&app, "dir-sub-a.256kb");
+    assert_eq!(app.state.root, index_by_name(&app, "sub"));
+    assert_eq!(app.state.selected, Some(file));
+    assert!(
+        matches!(app.state.focussed, FocussedPane::Main),
+        "the entries pane is focussed to continue from there"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"\tq".iter()))?;
+    assert!(app.window.find_pane.is_none(), "the results can be closed");
+    assert!(matches!(app.state.focussed, FocussedPane::Main));
+
+    app.process_events(
+        &mut terminal,
+        Some(Key::Ctrl('f'))
+            .into_iter()
+            .chain(into_keys(b"no such entry\n".iter())),
+    )?;
+    assert!(app.window.find_pane.is_none());
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Nothing matches 'no such entry'")
+    );
+    Ok(())
+}
+
+#[test]
+fn idle_sessions_are_only_redrawn_for_input() -> Result<()> {
+    use crate::interactive::app::tests::utils::DrawCountingBackend;
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = DrawCountingBackend::terminal()?;
+    app.process_events(&mut terminal, into_keys(b"jk".iter()))?;
+    let draws_without_waiting = terminal.backend.draws;
+
+    let mut terminal = DrawCountingBackend::terminal()?;
+    let (keys_tx, keys_rx) = mpsc::channel();
+    let idle_time = Duration::from_millis(200);
+    let sender = thread::spawn(move || {
+        thread::sleep(idle_time);
+        keys_tx.send(Key::Char('j')).ok();
+        thread::sleep(idle_time);
+        keys_tx.send(Key::Char('k')).ok();
+    });
+    let start = Instant::now();
+    app.process_events(&mut terminal, keys_rx.into_iter())?;
+    sender.join().expect("sender not to panic");
+
+    assert!(start.elapsed() >= idle_time * 2, "it waited for the keys");
+    assert_eq!(
+        terminal.backend.draws, draws_without_waiting,
+        "it draws once per key, and never while waiting for one"
+    );
+    Ok(())
+}
+
+#[test]
+fn pausing_requires_a_running_scan() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"p".iter()))?;
+    assert!(!app.state.is_paused, "a finished scan can't be paused");
+    assert!(!app.traversal.pause.is_paused());
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("There is no scan to pause")
+    );
+    Ok(())
+}
+
+#[test]
+fn bars_can_be_made_longer_and_shorter_within_bounds() -> Result<()> {
+    use crate::interactive::{BAR_WIDTH_RANGE, DEFAULT_BAR_WIDTH};
+
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b">><".iter()))?;
+    assert_eq!(app.display.bar_width, DEFAULT_BAR_WIDTH + 1);
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Bars are 11 columns wide")
+    );
+
+    app.process_events(&mut terminal, into_keys([b'<'; 64].iter()))?;
+    assert_eq!(app.display.bar_width, *BAR_WIDTH_RANGE.start());
+    app.process_events(&mut terminal, into_keys([b'>'; 64].iter()))?;
+    assert_eq!(app.display.bar_width, *BAR_WIDTH_RANGE.end());
+    Ok(())
+}
+
+#[test]
+fn marked_bytes_are_counted_once_per_subtree() -> Result<()> {
+    use crate::interactive::widgets::MarkPane;
+
+    let (_terminal, app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let tree = &app.traversal.tree;
+    let (dir, sub, a) = (
+        index_by_name(&app, "dir"),
+        index_by_name(&app, "sub"),
+        index_by_name(&app, "a"),
+    );
+    let pane = MarkPane::default()
+        .toggle_index(sub, tree, true, true)
+        .and_then(|p| p.toggle_index(dir, tree, true, true))
+        .and_then(|p| p.toggle_index(a, tree, false, true))
+        .expect("marked entries");
+
+    let size_of = |name| node_by_name(&app, name).size;
+    assert_eq!(
+        pane.marked_bytes_within(tree, app.traversal.root_index),
+        size_of("dir") + size_of("a"),
+        "'sub' is within the marked 'dir' and not counted again"
+    );
+    assert_eq!(
+        pane.marked_bytes_within(tree, dir),
+        size_of("dir"),
+        "only marked entries within the root are counted"
+    );
+    assert_eq!(
+        pane.marked_bytes_within(tree, sub),
+        size_of("sub"),
+        "all of 'sub' goes with 'dir'"
+    );
+    Ok(())
+}
+
+#[test]
+fn marked_entries_can_be_revealed_in_their_directory() -> Result<()> {
+    use crate::interactive::FocussedPane;
+
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture(&["single-child-chain"])?;
+    // enter 'c' and mark 'file', then go back to the top
+    app.process_events(&mut terminal, into_keys(b"oooo uuuu".iter()))?;
+    assert_eq!(app.state.root, app.traversal.root_index);
+
+    app.process_events(&mut terminal, into_keys(b"\to".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "c"),
+        "the parent of the marked entry becomes the root"
+    );
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "file")));
+    assert!(matches!(app.state.focussed, FocussedPane::Main));
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "the entry stays marked"
+    );
+    Ok(())
+}
+
+#[test]
+fn flagging_entries_is_independent_of_marking() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    // enter the fixture root, flag the first entry, flag and unflag the second one
+    app.process_events(&mut terminal, into_keys(b"oFjFF".iter()))?;
+    let first = app.state.entries[0].index;
+    assert_eq!(
+        app.state.flagged.iter().copied().collect::<Vec<_>>(),
+        vec![first]
+    );
+    assert!(
+        app.window.mark_pane.is_none(),
+        "flagged entries are not marked"
+    );
+    assert_eq!(
+        app.state.flagged_paths(&app.traversal),
+        vec![crate::interactive::path_of(&app.traversal.tree, first)]
+    );
+    Ok(())
+}
+
+#[test]
+fn files_larger_than_a_size_can_be_marked_at_once() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    // typing into the prompt doesn't trigger other commands
+    app.process_events(
+        &mut terminal,
+        into_keys(b"oMq".iter()).chain(Some(Key::Backspace)),
+    )?;
+    assert_eq!(
+        app.state.prompt.as_ref().map(|p| p.input.as_str()),
+        Some("")
+    );
+    app.process_events(&mut terminal, into_keys(b"1000\n".iter()))?;
+    assert_eq!(app.state.prompt, None);
+
+    let mut marked: Vec<_> = app
+        .window
+        .mark_pane
+        .as_ref()
+        .map(|p| p.marked().values().map(|m| m.path.clone()).collect())
+        .unwrap_or_default();
+    marked.sort();
+    let fixture = std::path::PathBuf::from(fixture_str("sample-01"));
+    assert_eq!(
+        marked,
+        vec![
+            fixture.join("dir").join("dir-a.1mb"),
+            fixture.join("dir").join("dir-a.kb"),
+            fixture.join("dir").join("sub").join("dir-sub-a.256kb"),
+        ],
+        "only files strictly larger than 1000 bytes are marked, in all subdirectories"
+    );
+    Ok(())
+}
+
+#[test]
+fn commands_can_be_completed_recalled_and_run() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"o:cd d\tsu\t".iter()))?;
+    assert_eq!(
+        app.state.prompt.as_ref().map(|p| p.input.as_str()),
+        Some("cd dir/sub/")
+    );
+    app.process_events(&mut terminal, into_keys(b"\n".iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "sub"));
+
+    app.process_events(&mut terminal, into_keys(b":".iter()).chain(Some(Key::Up)))?;
+    assert_eq!(
+        app.state.prompt.as_ref().map(|p| p.input.as_str()),
+        Some("cd dir/sub/"),
+        "the previous command is recalled"
+    );
+    app.process_events(
+        &mut terminal,
+        Some(Key::Esc)
+            .into_iter()
+            .chain(into_keys(b":cd ..\n:set icons\n".iter())),
+    )?;
+    assert_eq!(app.state.root, index_by_name(&app, "dir"));
+    assert!(app.display.show_icons);
+    assert_eq!(app.state.command_history.len(), 3);
+
+    app.process_events(&mut terminal, into_keys(b":frobnicate\n".iter()))?;
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Unknown command 'frobnicate'")));
+    Ok(())
+}
+
+#[test]
+fn the_modification_time_column_and_totals_without_marked_entries_are_toggled_separately(
+) -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"m".iter()))?;
+    assert!(app.display.show_mtime);
+    assert!(!app.display.show_size_without_marked);
+
+    app.process_events(&mut terminal, into_keys(b"mt".iter()))?;
+    assert!(!app.display.show_mtime);
+    assert!(app.display.show_size_without_marked);
+    Ok(())
+}
+
+#[test]
+fn apparent_sizes_and_disk_usage_can_be_switched() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let root = app.traversal.root_index;
+    let (apparent, disk_usage) = {
+        let entry = node_by_index(&app, root);
+        (entry.size, entry.alternate_size)
+    };
+    assert_eq!(apparent, 1_259_070, "the fixture uses apparent sizes");
+
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert!(!app.walk_options.apparent_size);
+    assert_eq!(node_by_index(&app, root).size, disk_usage);
+    assert_eq!(app.traversal.total_bytes, Some(disk_usage));
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Showing allocated 512-byte blocks (st_blocks), like du")
+    );
+
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert_eq!(node_by_index(&app, root).size, apparent);
+    Ok(())
+}
+
+#[test]
+fn pasted_paths_on_disk_are_resolved_against_the_scanned_tree() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let file = std::fs::canonicalize(fixture_str("sample-01"))?
+        .join("dir")
+        .join("sub")
+        .join("dir-sub-a.256kb");
+    let line = format!(":cd '{}:12'\n", file.display());
+    app.process_events(&mut terminal, into_keys(line.as_bytes().iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "sub"));
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir-sub-a.256kb")),
+        "files are selected in their directory"
+    );
+
+    app.process_events(&mut terminal, into_keys(b":cd /nowhere\n".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("There is no scanned entry at '/nowhere'")
+    );
+    Ok(())
+}
+
+#[test]
+fn paths_outside_of_the_scan_can_be_marked() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let total_bytes = app.traversal.total_bytes;
+    let outside = std::fs::canonicalize(fixture_str("sample-02"))?.join("dir");
+    let line = format!("+{}\n", outside.display());
+    app.process_events(&mut terminal, into_keys(line.as_bytes().iter()))?;
+
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "the scanned path is marked"
+    );
+    let marked = app
+        .window
+        .mark_pane
+        .as_ref()
+        .and_then(|p| p.marked().keys().next().copied())
+        .expect("a marked entry");
+    assert_eq!(node_by_index(&app, marked).name, outside);
+    assert!(
+        app.traversal.total_bytes > total_bytes,
+        "its size counts towards the total"
+    );
+    assert!(
+        app.state.entries.iter().any(|e| e.index == marked),
+        "it is listed next to the other inputs"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"+/nowhere\n".iter()))?;
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Could not mark '/nowhere'")));
+    Ok(())
+}
+
+#[test]
+fn ascending_selects_and_highlights_the_directory_we_came_from() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    // select something other than `dir` first, which would be remembered for its parent
+    app.process_events(&mut terminal, into_keys(b"oj:cd dir/sub\n".iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "sub"));
+
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "dir"));
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "sub")),
+        "not the first and largest entry"
+    );
+    assert_eq!(app.state.came_from, Some(index_by_name(&app, "sub")));
+
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir")),
+        "not the entry selected before jumping into 'sub'"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"j".iter()))?;
+    assert_eq!(
+        app.state.came_from, None,
+        "the highlight lasts until the next key"
+    );
+
+    app.process_events(&mut terminal, into_keys(b":cd dir/sub\n:cd ../..\n".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir")),
+        "the same applies to cd"
+    );
+    assert_eq!(app.state.came_from, Some(index_by_name(&app, "dir")));
+    Ok(())
+}
+
+#[test]
+fn rescanning_everything_keeps_the_current_directory_marks_and_flags() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let (total_bytes, node_count) = (app.traversal.total_bytes, app.traversal.tree.node_count());
+    app.process_events(&mut terminal, into_keys(b"o:cd dir/sub\nG FG".iter()))?;
+    let selected_name = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .selected
+            .map(|idx| node_by_index(app, idx).name.clone())
+    };
+    let selected_before = selected_name(&app);
+
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(app.traversal.total_bytes, total_bytes);
+    assert_eq!(app.traversal.tree.node_count(), node_count);
+    assert!(!app.state.is_scanning);
+    assert_eq!(app.state.root, index_by_name(&app, "sub"));
+    assert_eq!(selected_name(&app), selected_before);
+    let selected = app.state.selected.expect("a selection");
+    assert_eq!(
+        app.window
+            .mark_pane
+            .as_ref()
+            .map(|p| p.marked().keys().copied().collect::<Vec<_>>()),
+        Some(vec![selected])
+    );
+    assert_eq!(
+        app.state.flagged.iter().copied().collect::<Vec<_>>(),
+        vec![selected]
+    );
+    Ok(())
+}
+
+#[test]
+fn skipped_mount_points_are_scanned_when_entered() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let total_bytes = app.traversal.total_bytes;
+    let sub = index_by_name(&app, "sub");
+    let size = node_by_index(&app, sub).size;
+    // pretend `sub` is a mount point on another filesystem, which appears empty
+    let children: Vec<_> = app
+        .traversal
+        .tree
+        .neighbors_directed(sub, petgraph::Direction::Outgoing)
+        .collect();
+    for child in children {
+        app.traversal.tree.remove_node(child);
+    }
+    app.traversal.tree[sub].size = 0;
+    app.traversal.skipped_mounts.push(sub);
+
+    app.process_events(&mut terminal, into_keys(b":cd dir\n".iter()))?;
+    app.state.selected = Some(sub);
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+
+    assert_eq!(app.state.root, sub, "the mount point is entered");
+    assert_eq!(app.state.entries.len(), 1, "its contents are listed");
+    assert_eq!(node_by_index(&app, sub).size, size);
+    assert_eq!(app.traversal.total_bytes, total_bytes);
+    assert!(app.traversal.skipped_mounts.is_empty());
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Scanned the mount point")));
+    Ok(())
+}
+
+#[test]
+fn exact_sizes_are_shown_for_the_current_directory_only() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    app.process_events(&mut terminal, into_keys(b"oe".iter()))?;
+    let root = app.state.root;
+    assert_eq!(app.state.exact_sizes_of, Some(root));
+
+    app.process_events(&mut terminal, into_keys(b":cd dir\n".iter()))?;
+    assert_ne!(
+        app.state.exact_sizes_of,
+        Some(app.state.root),
+        "other directories use the byte format"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"ue".iter()))?;
+    assert_eq!(app.state.root, root);
+    assert_eq!(app.state.exact_sizes_of, None, "it toggles back");
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "s3")]
+fn bucket_listings_are_read_only() -> Result<()> {
+    use crate::interactive::{
+        app::{
+            tests::utils::{new_test_terminal, test_walk_options},
+            InteractiveOptions, TerminalApp,
+        },
+        Interaction,
+    };
+    use dua::ListedObject;
+
+    let url = "s3://bucket/logs/".parse()?;
+    let listing = dua::objects_to_tree(
+        &url,
+        vec![ListedObject {
+            key: "logs/app.log".into(),
+            size: 100,
+            modified: None,
+        }],
+    );
+    let mut terminal = new_test_terminal()?;
+    let mut app = TerminalApp::initialize(
+        &mut terminal,
+        test_walk_options(),
+        Vec::new(),
+        Interaction::None,
+        InteractiveOptions {
+            listing: Some(listing),
+            ..Default::default()
+        },
+    )?
+    .map(|(_, app)| app)
+    .expect("the listing to be shown");
+    assert!(app.state.is_read_only);
+
+    app.process_events(&mut terminal, into_keys(b"od".iter()))?;
+    assert!(app.window.mark_pane.is_none(), "objects can't be marked");
+    app.process_events(&mut terminal, into_keys(b"r".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Refreshing entries isn't possible, as the entries were loaded from a snapshot or listing")
+    );
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::interactive::app::tests::utils::{
-    initialized_app_and_terminal_from_paths, into_keys, WritableFixture,
+    delete_marked_entries_keys, initialized_app_and_terminal_from_paths, into_keys,
+    test_walk_options, WritableFixture,
 };
 use anyhow::Result;
 use pretty_assertions::assert_eq;
@@ -8,7 +9,8 @@ use pretty_assertions::assert_eq;
 #[cfg(not(target_os = "windows"))] // it stopped working here, don't know if it's truly broken or if it's the test. Let's wait for windows users to report.
 fn basic_user_journey_with_deletion() -> Result<()> {
     let fixture = WritableFixture::from("sample-02");
-    let (mut terminal, mut app) = initialized_app_and_terminal_from_paths(&[fixture.root.clone()])?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
 
     // With a selection of items
     app.process_events(&mut terminal, into_keys(b"doddd".iter()))?;
@@ -24,11 +26,7 @@ fn basic_user_journey_with_deletion() -> Result<()> {
     // When selecting the marker window and pressing the combination to delete entries
     app.process_events(
         &mut terminal,
-        vec![
-            crosstermion::input::Key::Char('\t'),
-            crosstermion::input::Key::Ctrl('r'),
-        ]
-        .into_iter(),
+        into_keys(b"\t".iter()).chain(delete_marked_entries_keys()),
     )?;
     assert!(
         app.window.mark_pane.is_none(),
@@ -45,3 +43,990 @@ fn basic_user_journey_with_deletion() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn deleting_updates_the_sizes_of_shown_ancestors_and_totals() -> Result<()> {
+    use crate::interactive::app::tests::utils::{index_by_name, node_by_name};
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let (total_before, entries_before) =
+        (app.traversal.total_bytes, app.traversal.entries_traversed);
+    let file_size = node_by_name(&app, "dir-sub-a.256kb").size;
+    let dir_size = node_by_name(&app, "dir").size;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/sub/dir-sub-a.256kb\n\t".iter()).chain(delete_marked_entries_keys()),
+    )?;
+    assert!(!fixture.root.join("dir/sub/dir-sub-a.256kb").exists());
+    assert_eq!(
+        app.traversal.total_bytes,
+        total_before.map(|total| total - file_size)
+    );
+    assert_eq!(app.traversal.entries_traversed, entries_before - 1);
+    let dir = index_by_name(&app, "dir");
+    assert_eq!(
+        app.state
+            .entries
+            .iter()
+            .find(|e| e.index == dir)
+            .map(|e| e.data.size),
+        Some(dir_size - file_size),
+        "the entry of the parent directory shown in the list is updated as well"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn marked_entries_are_deleted_only_once_confirmed() -> Result<()> {
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let file = fixture.root.join("dir/dir-a.1mb");
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/dir-a.1mb\n:mark a\n\t".iter()).chain(Some(Key::Ctrl('r'))),
+    )?;
+    assert!(
+        app.state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.starts_with("Permanently delete 2 marked entries with")),
+        "it tells what would be deleted"
+    );
+    app.process_events(&mut terminal, Some(Key::Esc).into_iter())?;
+    assert!(file.exists(), "escape cancels");
+
+    app.process_events(
+        &mut terminal,
+        [Key::Ctrl('r'), Key::Char('\n')].iter().copied(),
+    )?;
+    assert!(file.exists(), "just <enter> cancels as well");
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Nothing was deleted, as nothing was typed")));
+
+    app.process_events(
+        &mut terminal,
+        Some(Key::Ctrl('r'))
+            .into_iter()
+            .chain(into_keys(b"no\n".iter())),
+    )?;
+    assert!(file.exists(), "anything but 'yes' cancels");
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2)
+    );
+
+    app.process_events(
+        &mut terminal,
+        Some(Key::Ctrl('r'))
+            .into_iter()
+            .chain(into_keys(b"yes\n".iter())),
+    )?;
+    assert!(!file.exists() && !fixture.root.join("a").exists());
+    assert!(app.window.mark_pane.is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn aborted_deletions_keep_the_remaining_entries_marked() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    // as if <esc> was pressed before the first file was deleted
+    app.state.deletion_abort.abort();
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/dir-a.1mb\n:mark a\n\t".iter()).chain(delete_marked_entries_keys()),
+    )?;
+    assert!(fixture.root.join("dir/dir-a.1mb").exists() && fixture.root.join("a").exists());
+    assert!(
+        app.state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.starts_with("Aborted after deleting 0 files")
+                && m.ends_with("2 entries remain marked")),
+        "it tells what was left"
+    );
+    assert!(
+        !app.state.deletion_abort.is_aborted(),
+        "the next deletion runs"
+    );
+
+    app.process_events(&mut terminal, delete_marked_entries_keys().into_iter())?;
+    assert!(!fixture.root.join("a").exists());
+    assert!(app.window.mark_pane.is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn entries_are_kept_if_the_before_delete_command_fails_for_them() -> Result<()> {
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.before_delete = Some(r#"test "$(basename "$1")" != a"#.into());
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/dir-a.1mb\n:mark a\n\t".iter()).chain(Some(Key::Ctrl('r'))),
+    )?;
+    assert!(
+        app.state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.contains("running 'test")),
+        "the confirmation mentions the command"
+    );
+    app.process_events(&mut terminal, into_keys(b"yes\n".iter()))?;
+    assert!(!fixture.root.join("dir/dir-a.1mb").exists());
+    assert!(fixture.root.join("a").exists(), "the command failed for it");
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1)
+    );
+    assert!(
+        app.state.message.as_deref().is_some_and(
+            |m| m.starts_with("Kept 1 marked entry as the before-delete command failed")
+        ),
+        "it tells why entries are left"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn protected_paths_and_directories_containing_them_are_not_marked_or_deleted() -> Result<()> {
+    use crate::interactive::app::ProtectedPaths;
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.protected = ProtectedPaths::new(&[fixture.root.join("dir/sub")]);
+
+    app.process_events(&mut terminal, into_keys(b"o:mark dir\n".iter()))?;
+    assert!(app.window.mark_pane.is_none());
+    let message = app.state.message.clone().unwrap_or_default();
+    assert!(
+        message.contains("must not be deleted as it contains the protected '")
+            && message.ends_with("sub'"),
+        "{}",
+        message
+    );
+
+    app.process_events(&mut terminal, into_keys(b":mark a\n".iter()))?;
+    // marks made before the path became protected are refused when deleting
+    app.state.protected = ProtectedPaths::new(&[fixture.root.join("a")]);
+    app.process_events(
+        &mut terminal,
+        into_keys(b"\t".iter()).chain(Some(Key::Ctrl('r'))),
+    )?;
+    assert!(app.state.prompt.is_none(), "there is nothing to confirm");
+    assert!(app.state.message.as_deref().is_some_and(
+        |m| m.starts_with("Nothing was deleted: ") && m.ends_with("is protected from deletion")
+    ));
+    assert!(fixture.root.join("a").exists());
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn dry_runs_tell_what_would_be_deleted_without_deleting_it() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir\n:mark dir/sub\n:mark a\n\tn".iter()),
+    )?;
+    assert!(fixture.root.join("dir/sub").exists() && fixture.root.join("a").exists());
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(3),
+        "everything stays marked"
+    );
+    let message = app.state.message.clone().unwrap_or_default();
+    assert!(
+        message.starts_with("Dry run: deleting would remove 5 files and 3 directories with ")
+            && message.ends_with(", and nothing would fail"),
+        "{}",
+        message
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn deleted_entries_are_appended_to_the_deletion_log() -> Result<()> {
+    use crate::interactive::DeletionLog;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let log = fixture.root.join("deletions.log");
+    app.state.deletion_log = Some(DeletionLog::open(&log)?);
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/dir-a.1mb\n:mark a\n\t".iter()).chain(delete_marked_entries_keys()),
+    )?;
+    let lines: Vec<_> = std::fs::read_to_string(&log)?
+        .lines()
+        .map(|line| {
+            let fields: Vec<_> = line.split('\t').collect();
+            (fields[0].ends_with('Z'), fields[2].to_owned())
+        })
+        .collect();
+    let path = |p: &str| fixture.root.join(p).display().to_string();
+    assert_eq!(
+        lines,
+        vec![(true, path("dir/dir-a.1mb")), (true, path("a"))],
+        "each deleted entry is recorded in the order of deletion"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn deletions_pause_once_they_freed_the_cap() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/dir-a.1mb\n:mark a\n:mark z123.b\n\tc1\n".iter())
+            .chain(delete_marked_entries_keys()),
+    )?;
+    assert!(!fixture.root.join("dir/dir-a.1mb").exists());
+    assert!(
+        fixture.root.join("a").exists() && fixture.root.join("z123.b").exists(),
+        "entries are deleted in the order they were marked until the cap is reached"
+    );
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2),
+        "the remaining entries stay marked"
+    );
+    assert!(
+        app.state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.starts_with("Paused after freeing")),
+        "it tells how to continue"
+    );
+
+    app.process_events(&mut terminal, delete_marked_entries_keys().into_iter())?;
+    assert!(!fixture.root.join("a").exists(), "the next batch");
+    assert!(fixture.root.join("z123.b").exists());
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"c\n".iter()).chain(delete_marked_entries_keys()),
+    )?;
+    assert!(
+        !fixture.root.join("z123.b").exists(),
+        "without a cap, everything is deleted"
+    );
+    assert!(app.window.mark_pane.is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn the_before_delete_command_is_run_for_quick_deletions_and_archived_entries_as_well() -> Result<()>
+{
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.before_delete = Some("echo refused >&2; exit 3".into());
+
+    app.process_events(&mut terminal, into_keys(b"o:cd dir\nHjDsub\n".iter()))?;
+    assert!(
+        fixture.root.join("dir/sub").exists(),
+        "the command failed for it"
+    );
+    assert!(app.state.message.as_deref().is_some_and(|m| m
+        .starts_with("Nothing was deleted, as the before-delete command failed")
+        && m.ends_with(": refused")));
+
+    let archive = fixture.root.with_extension("tar");
+    app.process_events(
+        &mut terminal,
+        into_keys(b":mark sub\n\t".iter())
+            .chain(Some(Key::Ctrl('a')))
+            .chain(into_keys(
+                format!("{}\n", archive.display()).as_bytes().iter(),
+            )),
+    )?;
+    assert!(archive.exists(), "archiving happens before deleting");
+    assert!(fixture.root.join("dir/sub").exists());
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "it stays marked"
+    );
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Kept 1 marked entry as the before-delete command failed")));
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn the_selected_entry_is_deleted_once_its_name_was_typed() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"o:cd dir\n:mark sub\nHjD".iter()))?;
+    let message = app.state.message.clone().unwrap_or_default();
+    assert!(
+        message.contains(&format!(
+            "'{}' (",
+            fixture.root.join("dir").join("sub").display()
+        )),
+        "the path and size are shown: {}",
+        message
+    );
+
+    app.process_events(&mut terminal, into_keys(b"su\n".iter()))?;
+    assert!(
+        fixture.root.join("dir/sub").exists(),
+        "a partial name doesn't confirm"
+    );
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Nothing was deleted")));
+
+    app.process_events(&mut terminal, into_keys(b"Dsub\n".iter()))?;
+    assert!(!fixture.root.join("dir/sub").exists());
+    assert!(fixture.root.join("dir/dir-a.1mb").exists(), "nothing else");
+    assert!(
+        app.window.mark_pane.is_none(),
+        "marks of deleted entries are forgotten"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn deleting_from_a_generated_tree_leaves_links_and_everything_else_intact() -> Result<()> {
+    use std::path::{Path, PathBuf};
+
+    fn listing(root: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = jwalk::WalkDir::new(root)
+            .skip_hidden(false)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    let fixture = WritableFixture::generated(
+        "generated-deletion",
+        &dua::FixtureSpec {
+            seed: 42,
+            depth: 3,
+            fan_out: 3,
+            files_per_dir: 5,
+            min_size: 0,
+            max_size: 64 * 1024,
+            distribution: dua::SizeDistribution::LogUniform,
+            symlinks: 20,
+            hard_links: 20,
+            sparse: false,
+        },
+    );
+    let deleted = fixture.root.join("d1");
+    let expected: Vec<_> = listing(&fixture.root)
+        .into_iter()
+        .filter(|path| !path.starts_with(&deleted))
+        .collect();
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.deletion_workers = 4;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark d1\n\t".iter()).chain(delete_marked_entries_keys()),
+    )?;
+    assert!(app.window.mark_pane.is_none());
+    assert_eq!(
+        listing(&fixture.root),
+        expected,
+        "symbolic links into the deleted directory aren't followed, and hard links elsewhere are kept"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn marked_entries_are_deleted_only_after_archiving_them() -> Result<()> {
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let archive = fixture.root.with_extension("tar");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"d\t".iter()))?;
+    let keys_to_archive_to = |path: &std::path::Path| {
+        Some(Key::Ctrl('a'))
+            .into_iter()
+            .chain(path.to_str().unwrap().chars().map(Key::Char))
+            .chain(Some(Key::Char('\n')))
+            .collect::<Vec<_>>()
+    };
+
+    let unwritable = fixture
+        .root
+        .with_extension("no-such-dir")
+        .join("archive.tar");
+    app.process_events(&mut terminal, keys_to_archive_to(&unwritable).into_iter())?;
+    assert!(
+        app.window.mark_pane.is_some(),
+        "entries stay marked if the archive can't be created"
+    );
+    assert!(fixture.as_ref().is_dir(), "and nothing is deleted");
+
+    let within = fixture.root.join("dir").join("archive.tar");
+    app.process_events(&mut terminal, keys_to_archive_to(&within).into_iter())?;
+    assert!(
+        app.window.mark_pane.is_some(),
+        "entries stay marked if the archive would be deleted with them"
+    );
+    assert!(!within.exists(), "no archive is created within them");
+    assert!(fixture.as_ref().is_dir(), "and nothing is deleted");
+
+    app.process_events(&mut terminal, keys_to_archive_to(&archive).into_iter())?;
+    assert!(
+        app.window.mark_pane.is_none(),
+        "all entries were archived and deleted"
+    );
+    assert!(
+        !fixture.as_ref().is_dir(),
+        "the directory should have been deleted"
+    );
+    let listing = std::process::Command::new("tar")
+        .arg("-tf")
+        .arg(&archive)
+        .output()?;
+    assert!(
+        String::from_utf8(listing.stdout)?.contains("sample-01/dir/sub/dir-sub-a.256kb"),
+        "the archive contains everything below the marked directory"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_loops_are_detected_when_following_links() -> Result<()> {
+    use dua::{traverse::Traversal, WalkOptions};
+
+    let fixture = WritableFixture::from("single-child-chain");
+    let link = fixture.root.join("a").join("b").join("loop");
+    std::os::unix::fs::symlink("..", &link)?;
+
+    let traversal = Traversal::from_walk(
+        WalkOptions {
+            cross_filesystems: true,
+            follow_links: true,
+            ..test_walk_options()
+        },
+        vec![fixture.root.clone()],
+        |_| Ok(false),
+    )?
+    .expect("traversal to finish");
+
+    assert_eq!(traversal.symlink_loops.len(), 1, "the link is detected");
+    assert_eq!(traversal.symlink_loops[0].path, link);
+    assert_eq!(traversal.io_errors, 0, "loops are not counted as IO errors");
+    assert_eq!(
+        traversal.total_bytes,
+        Some(7),
+        "the files behind the loop are counted only once"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn refreshing_replaces_the_diagnostics_and_errors_found_below_the_entry() -> Result<()> {
+    use dua::{traverse::Traversal, WalkOptions};
+
+    let fixture = WritableFixture::from("single-child-chain");
+    let link = fixture.root.join("a").join("b").join("loop");
+    std::os::unix::fs::symlink("..", &link)?;
+    let walk_options = WalkOptions {
+        cross_filesystems: true,
+        follow_links: true,
+        ..test_walk_options()
+    };
+    let mut traversal =
+        Traversal::from_walk(walk_options.clone(), vec![fixture.root.clone()], |_| {
+            Ok(false)
+        })?
+        .expect("traversal to finish");
+    let a = traversal
+        .tree
+        .node_indices()
+        .find(|idx| traversal.tree[*idx].name == std::path::Path::new("a"))
+        .expect("a to be scanned");
+    let b = traversal
+        .tree
+        .neighbors_directed(a, petgraph::Direction::Outgoing)
+        .next()
+        .expect("b below a");
+    // pretend reading 'b' failed during the first traversal
+    traversal.read_errors.push(b);
+    traversal.io_errors += 1;
+
+    for _ in 0..2 {
+        traversal.refresh(walk_options.clone(), a, fixture.root.join("a"))?;
+    }
+    assert_eq!(
+        traversal.symlink_loops.len(),
+        1,
+        "the loop is found once no matter how often it's refreshed"
+    );
+    assert_eq!(traversal.symlink_loops[0].path, link);
+    assert_eq!(
+        traversal.io_errors, 0,
+        "the error was replaced by a good read"
+    );
+    assert!(traversal.read_errors.is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn refreshing_the_selected_directory_picks_up_external_changes() -> Result<()> {
+    use crate::interactive::app::tests::utils::{index_by_name, node_by_index, node_by_name};
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let total_before = app.traversal.total_bytes.expect("complete scan");
+    let removed_size = node_by_name(&app, "dir-sub-a.256kb").size;
+    let dir_size = node_by_name(&app, "dir").size;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/sub/dir-sub-a.256kb\n:cd dir\nu".iter()),
+    )?;
+    let dir = index_by_name(&app, "dir");
+    assert_eq!(app.state.selected, Some(dir));
+    std::fs::remove_file(fixture.root.join("dir/sub/dir-sub-a.256kb"))?;
+    std::fs::write(fixture.root.join("dir/added"), vec![0; 1000])?;
+
+    app.process_events(&mut terminal, into_keys(b"r".iter()))?;
+    assert_eq!(app.state.selected, Some(dir), "the selection is kept");
+    assert_eq!(
+        node_by_index(&app, dir).size,
+        dir_size - removed_size + 1000
+    );
+    assert_eq!(
+        app.traversal.total_bytes,
+        Some(total_before - removed_size + 1000),
+        "ancestors are updated"
+    );
+    assert_eq!(node_by_name(&app, "added").size, 1000);
+    assert!(
+        app.traversal
+            .tree
+            .node_weights()
+            .all(|e| e.name != std::path::Path::new("dir-sub-a.256kb")),
+        "deleted files are gone"
+    );
+    assert!(
+        app.window.mark_pane.is_none(),
+        "marks of entries that are gone are dropped"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn refreshing_a_directory_that_is_gone_forgets_the_marks_below_it() -> Result<()> {
+    use crate::interactive::app::tests::utils::index_by_name;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/sub/dir-sub-a.256kb\n:cd dir/sub\nu".iter()),
+    )?;
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    let dir = index_by_name(&app, "dir");
+    assert_eq!(app.state.selected, Some(dir));
+    assert!(!app.state.bookmarks.is_empty());
+    std::fs::remove_dir_all(fixture.root.join("dir"))?;
+
+    app.process_events(&mut terminal, into_keys(b"r".iter()))?;
+    assert!(
+        app.window.mark_pane.is_none(),
+        "the mark of the file within the directory is gone with it"
+    );
+    assert!(
+        app.state.bookmarks.iter().all(|(root, selected)| app
+            .traversal
+            .tree
+            .node_weight(*root)
+            .is_some()
+            && app.traversal.tree.node_weight(*selected).is_some()),
+        "no bookmark refers to entries that were removed"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn refreshing_a_directory_keeps_the_marks_of_entries_that_are_still_there() -> Result<()> {
+    use crate::interactive::app::tests::utils::index_by_name;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/sub/dir-sub-a.256kb\n:cd dir/sub\nu".iter()),
+    )?;
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "sub")));
+
+    app.process_events(&mut terminal, into_keys(b"r".iter()))?;
+    let file = index_by_name(&app, "dir-sub-a.256kb");
+    let marked: Vec<_> = app
+        .window
+        .mark_pane
+        .as_ref()
+        .map(|p| p.marked().keys().copied().collect())
+        .unwrap_or_default();
+    assert_eq!(
+        marked,
+        vec![file],
+        "the file is marked once more after its parent was refreshed"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn suggested_cleanups_can_be_marked_from_their_pane() -> Result<()> {
+    use crate::interactive::app::tests::utils::index_by_name;
+
+    let fixture = WritableFixture::from("sample-01");
+    std::fs::create_dir(fixture.root.join("target"))?;
+    std::fs::write(fixture.root.join("target/debug.rlib"), vec![0; 1000])?;
+    std::fs::write(fixture.root.join("Cargo.lock"), b"lock")?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"U".iter()))?;
+    assert!(
+        app.window.suggestions_pane.is_some(),
+        "the build output is suggested"
+    );
+    app.process_events(&mut terminal, into_keys(b"x".iter()))?;
+    let target = index_by_name(&app, "target");
+    assert_eq!(
+        app.window
+            .mark_pane
+            .as_ref()
+            .map(|p| p.marked().keys().copied().collect::<Vec<_>>()),
+        Some(vec![target])
+    );
+
+    app.process_events(&mut terminal, into_keys(b"q".iter()))?;
+    assert!(app.window.suggestions_pane.is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn checksums_of_marked_files_are_written_to_a_manifest() -> Result<()> {
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let manifest_dir = WritableFixture::empty("journey-checksums");
+    let manifest = manifest_dir.join("manifest.txt");
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark dir/sub\n:mark a\n\t".iter())
+            .chain(Some(Key::Ctrl('e')))
+            .chain(into_keys(
+                format!("{}\n", manifest.display()).as_bytes().iter(),
+            )),
+    )?;
+    let written = std::fs::read_to_string(&manifest)?;
+    assert_eq!(
+        written.lines().count(),
+        2,
+        "the file in the marked directory and the marked file"
+    );
+    assert!(written.lines().all(|l| l.split('\t').count() == 3));
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Wrote the checksums of 2 files")));
+    assert!(
+        fixture.root.join("a").exists(),
+        "nothing is deleted along the way"
+    );
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2)
+    );
+    Ok(())
+}
+
+#[test]
+fn marked_entries_are_moved_into_a_directory_keeping_their_relative_location() -> Result<()> {
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let quarantine = fixture.root.join("quarantine");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b"o:mark a\n:mark dir/sub\n:mark dir/sub/dir-sub-a.256kb\n\t".iter()),
+    )?;
+    let keys_to_move_into = |path: &std::path::Path| {
+        Some(Key::Ctrl('v'))
+            .into_iter()
+            .chain(path.to_str().unwrap().chars().map(Key::Char))
+            .chain(Some(Key::Char('\n')))
+            .collect::<Vec<_>>()
+    };
+    app.process_events(
+        &mut terminal,
+        keys_to_move_into(&fixture.root.join("dir/sub/inside")).into_iter(),
+    )?;
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(3),
+        "nothing is moved into one of the marked entries"
+    );
+    assert!(fixture.root.join("dir/sub/dir-sub-a.256kb").is_file());
+
+    app.state.move_to = Some(quarantine.clone());
+    app.process_events(
+        &mut terminal,
+        vec![Key::Ctrl('v'), Key::Char('\n')].into_iter(),
+    )?;
+    assert!(
+        app.window.mark_pane.is_none(),
+        "all entries were moved, the file along with its directory"
+    );
+    assert!(!fixture.root.join("a").exists());
+    assert!(!fixture.root.join("dir/sub").exists());
+    assert_eq!(std::fs::read(quarantine.join("a"))?.len(), 256);
+    assert!(quarantine.join("dir/sub/dir-sub-a.256kb").is_file());
+    assert!(
+        fixture.root.join("dir/1000bytes").is_file(),
+        "the rest stays"
+    );
+
+    let moved = app
+        .traversal
+        .tree
+        .node_indices()
+        .find(|idx| app.traversal.tree[*idx].name == std::path::Path::new("quarantine"))
+        .expect("the target within the scan to be scanned");
+    assert_eq!(app.traversal.tree[moved].size, 256 + 256_000);
+    Ok(())
+}
+
+#[test]
+fn rescanning_everything_finds_the_entries_with_exact_sizes_and_bookmarks_in_the_new_tree(
+) -> Result<()> {
+    use crate::interactive::app::tests::utils::index_by_name;
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.process_events(&mut terminal, into_keys(b"o:cd dir/sub\neu".iter()))?;
+    // shift the indices of all entries scanned after it
+    std::fs::write(fixture.root.join("dir/aaa"), b"new")?;
+
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    let (dir, sub) = (index_by_name(&app, "dir"), index_by_name(&app, "sub"));
+    assert_eq!(app.state.root, dir);
+    assert_eq!(app.state.exact_sizes_of, Some(sub));
+    assert_eq!(
+        app.state.bookmarks.get(&dir),
+        Some(&sub),
+        "entering the directory once again selects where we came from"
+    );
+    assert_eq!(app.state.path_scroll, None);
+    assert!(app
+        .state
+        .root_filesystem
+        .as_ref()
+        .is_none_or(|(index, _)| *index == dir));
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn rescanning_everything_only_reads_directories_modified_since_the_scan_unless_mtimes_are_not_trusted(
+) -> Result<()> {
+    use crate::interactive::app::tests::utils::index_by_name;
+    use std::time::{Duration, SystemTime};
+
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.trust_mtime = true;
+    // pretend the fixture was written well before it was scanned
+    let scanned_at = SystemTime::now() + Duration::from_secs(60);
+    app.traversal.started_at = Some(scanned_at);
+    let untouched = index_by_name(&app, "dir-a.1mb");
+    std::fs::write(fixture.root.join("dir/sub/new"), b"new")?;
+    std::fs::File::open(fixture.root.join("dir/sub"))?
+        .set_modified(scanned_at + Duration::from_secs(60))?;
+    // a change the modification time of the directory doesn't tell about
+    std::fs::write(fixture.root.join("dir/empty-dir/unseen"), b"unseen")?;
+
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Rescanned 1 changed entry, 1.26 MB in total")
+    );
+    assert_eq!(index_by_name(&app, "dir-a.1mb"), untouched);
+    assert_eq!(app.traversal.tree[index_by_name(&app, "new")].size, 3);
+    assert!(app
+        .traversal
+        .tree
+        .node_indices()
+        .all(|idx| app.traversal.tree[idx].name != *"unseen"));
+
+    app.state.trust_mtime = false;
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(app.traversal.tree[index_by_name(&app, "unseen")].size, 6);
+    Ok(())
+}
+
+#[test]
+fn rescanning_inputs_that_are_gone_keeps_the_current_tree_and_marks() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_paths(&[
+        fixture.root.join("dir"),
+        fixture.root.join("a"),
+    ])?;
+    let node_count = app.traversal.tree.node_count();
+    app.process_events(&mut terminal, into_keys(b"d".iter()))?;
+    std::fs::remove_file(fixture.root.join("a"))?;
+
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert!(app
+        .state
+        .message
+        .as_deref()
+        .is_some_and(|m| m.starts_with("Could not rescan")));
+    assert!(!app.state.is_scanning);
+    assert_eq!(app.traversal.tree.node_count(), node_count);
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "the session goes on with everything still marked"
+    );
+    assert_eq!(app.state.entries.len(), 2);
+    Ok(())
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn nothing_on_disk_is_changed_through_a_loaded_snapshot() -> Result<()> {
+    use crate::interactive::app::{
+        tests::utils::{index_by_name, new_test_terminal},
+        InteractiveOptions, TerminalApp,
+    };
+    use crate::interactive::Interaction;
+    use crosstermion::input::Key;
+
+    let fixture = WritableFixture::from("sample-01");
+    let snapshot = fixture.root.with_extension("snapshot");
+    let walk_options = test_walk_options();
+    dua::save_snapshot(walk_options.clone(), vec![fixture.root.clone()], &snapshot)?;
+    let mut terminal = new_test_terminal()?;
+    let app = TerminalApp::initialize(
+        &mut terminal,
+        walk_options,
+        Vec::new(),
+        Interaction::None,
+        InteractiveOptions {
+            snapshot: Some(snapshot.clone()),
+            ..Default::default()
+        },
+    )?
+    .map(|(_, app)| app);
+    let mut app = app.expect("the snapshot to be loaded");
+    assert!(app.state.is_read_only);
+
+    app.process_events(&mut terminal, into_keys(b"oddx D".iter()))?;
+    assert!(app.window.mark_pane.is_none(), "nothing can be marked");
+    assert!(app.state.prompt.is_none(), "nor deleted right away");
+
+    let dir = index_by_name(&app, "dir");
+    app.window.mark_pane = app
+        .window
+        .mark_pane
+        .take()
+        .unwrap_or_default()
+        .toggle_index(dir, &app.traversal.tree, true, false);
+    for key in [Key::Ctrl('r'), Key::Ctrl('v'), Key::Ctrl('a')] {
+        app.process_events(&mut terminal, vec![Key::Char('\t'), key].into_iter())?;
+        assert!(app.state.prompt.is_none(), "marked entries aren't changed");
+        assert!(app
+            .state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.contains("loaded from a snapshot")));
+        app.state.focussed = Default::default();
+    }
+    assert!(app.window.mark_pane.is_some());
+
+    let entries = app.traversal.entries_traversed;
+    app.process_events(&mut terminal, into_keys(b"rR".iter()))?;
+    assert_eq!(
+        app.traversal.entries_traversed, entries,
+        "nothing is scanned again"
+    );
+    assert!(
+        fixture.root.join("dir").is_dir(),
+        "the entries on disk are untouched"
+    );
+    Ok(())
+}
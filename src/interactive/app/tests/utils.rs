@@ -12,7 +12,9 @@ use std::{
     fmt,
     fs::{copy, create_dir_all, remove_dir, remove_file},
     io::ErrorKind,
+    ops::Deref,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 use tui::backend::TestBackend;
 use tui_react::Terminal;
@@ -25,12 +27,19 @@ pub fn into_keys<'a>(
     bytes.map(|b| crosstermion::input::Key::Char(std::char::from_u32(*b as u32).unwrap()))
 }
 
+/// The keys deleting all marked entries while the mark pane has focus, confirmed by typing `yes`.
+pub fn delete_marked_entries_keys() -> Vec<crosstermion::input::Key> {
+    std::iter::once(crosstermion::input::Key::Ctrl('r'))
+        .chain(into_keys(b"yes\n".iter()))
+        .collect()
+}
+
 pub fn node_by_index(app: &TerminalApp, id: TreeIndex) -> &EntryData {
     app.traversal.tree.node_weight(id).unwrap()
 }
 
 pub fn node_by_name(app: &TerminalApp, name: impl AsRef<OsStr>) -> &EntryData {
-    node_by_index(app, index_by_name(&app, name))
+    node_by_index(app, index_by_name(app, name))
 }
 
 pub fn index_by_name_and_size(
@@ -63,16 +72,31 @@ pub fn index_by_name(app: &TerminalApp, name: impl AsRef<OsStr>) -> TreeIndex {
     index_by_name_and_size(app, name, None)
 }
 
+/// A directory no other test uses, which is deleted along with everything in it when dropped.
 pub struct WritableFixture {
     pub root: PathBuf,
 }
 
 impl Drop for WritableFixture {
     fn drop(&mut self) {
-        delete_recursive(&self.root).ok();
+        if let Some(unique_dir) = self.root.parent() {
+            delete_recursive(unique_dir).ok();
+        }
     }
 }
 
+/// A path called `name` within a new directory used by no other test.
+fn unique_temp_path(name: &str) -> PathBuf {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let dir = temp_dir().join("dua-unit").join(format!(
+        "{}-{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
 fn delete_recursive(path: impl AsRef<Path>) -> Result<()> {
     let mut files: Vec<_> = Vec::new();
     let mut dirs: Vec<_> = Vec::new();
@@ -83,7 +107,8 @@ fn delete_recursive(path: impl AsRef<Path>) -> Result<()> {
     {
         let entry: DirEntry<_> = entry?;
         let p = entry.path();
-        match p.is_dir() {
+        // symlinks to directories are removed like files
+        match entry.file_type().is_dir() {
             true => dirs.push(p),
             false => files.push(p),
         }
@@ -97,9 +122,7 @@ fn delete_recursive(path: impl AsRef<Path>) -> Result<()> {
                 .sorted_by_key(|p| p.components().count())
                 .rev()
                 .map(|d| {
-                    remove_dir(d)
-                        .with_context(|| format!("Could not delete '{}'", d.display()))
-                        .map_err(Error::from)
+                    remove_dir(d).with_context(|| format!("Could not delete '{}'", d.display()))
                 }),
         )
         .collect::<Result<_, _>>()
@@ -135,24 +158,59 @@ fn copy_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Er
 
 impl From<&'static str> for WritableFixture {
     fn from(fixture_name: &str) -> Self {
-        const TEMP_TLD_DIRNAME: &'static str = "dua-unit";
-
         let src = fixture(fixture_name);
-        let dst = temp_dir().join(TEMP_TLD_DIRNAME);
-        create_dir_all(&dst).unwrap();
-
-        let dst = dst.join(fixture_name);
+        let dst = unique_temp_path(fixture_name);
         copy_recursive(src, &dst).unwrap();
         WritableFixture { root: dst }
     }
 }
 
+impl WritableFixture {
+    /// A synthetic tree created according to `spec` in a directory called `name`.
+    pub fn generated(name: &str, spec: &dua::FixtureSpec) -> Self {
+        let root = unique_temp_path(name);
+        dua::create_fixture(&root, spec).unwrap();
+        WritableFixture { root }
+    }
+
+    /// An empty directory called `name`.
+    pub fn empty(name: &str) -> Self {
+        let root = unique_temp_path(name);
+        create_dir_all(&root).unwrap();
+        WritableFixture { root }
+    }
+}
+
 impl AsRef<Path> for WritableFixture {
     fn as_ref(&self) -> &Path {
         &self.root
     }
 }
 
+impl Deref for WritableFixture {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.root
+    }
+}
+
+/// The walk options of apps initialized for tests: on a single thread with apparent sizes, sorted by name and
+/// without crossing filesystems.
+pub fn test_walk_options() -> WalkOptions {
+    WalkOptions {
+        threads: 1,
+        byte_format: ByteFormat::Metric,
+        apparent_size: true,
+        count_hard_links: false,
+        sorting: TraversalSorting::AlphabeticalByFileName,
+        cross_filesystems: false,
+        follow_links: false,
+        round_to_fs_blocks: false,
+        respect_ignore_files: false,
+    }
+}
+
 pub fn fixture(p: impl AsRef<Path>) -> PathBuf {
     Path::new(FIXTURE_PATH).join(p)
 }
@@ -171,16 +229,10 @@ pub fn initialized_app_and_terminal_with_closure(
     let input_paths = fixture_paths.iter().map(|c| convert(c.as_ref())).collect();
     let app = TerminalApp::initialize(
         &mut terminal,
-        WalkOptions {
-            threads: 1,
-            byte_format: ByteFormat::Metric,
-            apparent_size: true,
-            count_hard_links: false,
-            sorting: TraversalSorting::AlphabeticalByFileName,
-            cross_filesystems: false,
-        },
+        test_walk_options(),
         input_paths,
         Interaction::None,
+        Default::default(),
     )?
     .map(|(_, app)| app);
     Ok((
@@ -193,6 +245,52 @@ pub fn new_test_terminal() -> std::io::Result<Terminal<TestBackend>> {
     Terminal::new(TestBackend::new(40, 20))
 }
 
+/// A [`TestBackend`] which counts how often the screen was drawn to.
+pub struct DrawCountingBackend {
+    inner: TestBackend,
+    pub draws: usize,
+}
+
+impl DrawCountingBackend {
+    pub fn terminal() -> std::io::Result<Terminal<Self>> {
+        Terminal::new(DrawCountingBackend {
+            inner: TestBackend::new(40, 20),
+            draws: 0,
+        })
+    }
+}
+
+impl tui::backend::Backend for DrawCountingBackend {
+    fn draw<'a, I>(&mut self, content: I) -> std::io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a tui::buffer::Cell)>,
+    {
+        self.draws += 1;
+        self.inner.draw(content)
+    }
+    fn hide_cursor(&mut self) -> std::io::Result<()> {
+        self.inner.hide_cursor()
+    }
+    fn show_cursor(&mut self) -> std::io::Result<()> {
+        self.inner.show_cursor()
+    }
+    fn get_cursor(&mut self) -> std::io::Result<(u16, u16)> {
+        self.inner.get_cursor()
+    }
+    fn set_cursor(&mut self, x: u16, y: u16) -> std::io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.inner.clear()
+    }
+    fn size(&self) -> std::io::Result<tui::layout::Rect> {
+        self.inner.size()
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 pub fn initialized_app_and_terminal_from_paths(
     fixture_paths: &[PathBuf],
 ) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
@@ -210,6 +308,7 @@ pub fn initialized_app_and_terminal_from_fixture(
 
 pub fn sample_01_tree() -> Tree {
     let mut t = Tree::new();
+    let mut links = Vec::new();
     {
         let mut add_node = make_add_node(&mut t);
         #[cfg(not(windows))]
@@ -224,9 +323,10 @@ pub fn sample_01_tree() -> Tree {
                 add_node("a", 256, Some(s));
                 add_node("b.empty", 0, Some(s));
                 #[cfg(not(windows))]
-                add_node("c.lnk", 1, Some(s));
+                let link = add_node("c.lnk", 1, Some(s));
                 #[cfg(windows)]
-                add_node("c.lnk", 0, Some(s));
+                let link = add_node("c.lnk", 0, Some(s));
+                links.push(link);
                 let d = add_node("dir", 1258024, Some(s));
                 {
                     add_node("1000bytes", 1000, Some(d));
@@ -245,7 +345,10 @@ pub fn sample_01_tree() -> Tree {
             }
         }
     }
-    t
+    for link in links {
+        t[link].is_symlink = true;
+    }
+    with_entry_counts(t)
 }
 
 pub fn sample_02_tree() -> Tree {
@@ -279,7 +382,21 @@ pub fn sample_02_tree() -> Tree {
             }
         }
     }
-    t
+    with_entry_counts(t)
+}
+
+/// Set the amount of entries below each directory of `tree`, as a traversal would.
+fn with_entry_counts(mut tree: Tree) -> Tree {
+    let indices: Vec<_> = tree.node_indices().collect();
+    for idx in indices {
+        let mut dfs = petgraph::visit::Dfs::new(&tree, idx);
+        let mut count = 0;
+        while dfs.next(&tree).is_some() {
+            count += 1;
+        }
+        tree[idx].entry_count = count - 1;
+    }
+    tree
 }
 
 pub fn make_add_node<'a>(
@@ -289,7 +406,12 @@ pub fn make_add_node<'a>(
         let n = t.add_node(EntryData {
             name: PathBuf::from(name),
             size,
+            alternate_size: 0,
             metadata_io_error: false,
+            mtime_range: None,
+            recent_size: 0,
+            entry_count: 0,
+            is_symlink: false,
         });
         if let Some(from) = maybe_from_idx {
             t.add_edge(from, n, ());
@@ -298,6 +420,21 @@ pub fn make_add_node<'a>(
     }
 }
 
+/// Assert that all entries in `tree` know their modification times and remove them along with their disk usage
+/// and recent sizes, as all depend on the checkout.
+pub fn without_checkout_specifics(mut tree: Tree) -> Tree {
+    for entry in tree.node_weights_mut() {
+        assert!(
+            entry.mtime_range.take().is_some(),
+            "'{}' has a modification time",
+            entry.name.display()
+        );
+        entry.alternate_size = 0;
+        entry.recent_size = 0;
+    }
+    tree
+}
+
 pub fn debug(item: impl fmt::Debug) -> String {
     format!("{:?}", item)
 }
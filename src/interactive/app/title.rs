@@ -0,0 +1,32 @@
+//! Control the title of the terminal window using xterm escape sequences, which most terminals understand
+//! and the others ignore.
+use std::{io, io::Write, path::PathBuf};
+
+/// Save the current title on the terminal's title stack, to be restored with [`restore()`].
+pub fn save() {
+    write_escape("\x1b[22;0t");
+}
+
+/// Restore the title saved with [`save()`].
+pub fn restore() {
+    write_escape("\x1b[23;0t");
+}
+
+pub fn set(title: &str) {
+    write_escape(&format!("\x1b]0;{}\x07", title));
+}
+
+/// A short description of the scanned `paths` for use in the title.
+pub fn describe(paths: &[PathBuf]) -> String {
+    match paths {
+        [] => ".".into(),
+        [path] => path.display().to_string(),
+        paths => format!("{} paths", paths.len()),
+    }
+}
+
+fn write_escape(sequence: &str) {
+    let mut err = io::stderr();
+    err.write_all(sequence.as_bytes()).ok();
+    err.flush().ok();
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use std::{ffi::OsString, path::PathBuf};
+
+/// An entry in the trash bin of the operating system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedEntry {
+    /// The platform specific identifier of the entry within the trash bin
+    pub id: OsString,
+    pub original_path: PathBuf,
+    /// Seconds since the unix epoch at which the entry was moved to the trash bin
+    pub time_deleted: i64,
+}
+
+#[cfg(all(
+    feature = "trash-move",
+    any(
+        target_os = "windows",
+        all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        )
+    )
+))]
+mod platform {
+    use super::TrashedEntry;
+    use anyhow::Result;
+
+    pub fn list() -> Result<Vec<TrashedEntry>> {
+        Ok(trash::os_limited::list()?
+            .into_iter()
+            .map(|item| TrashedEntry {
+                original_path: item.original_path(),
+                id: item.id,
+                time_deleted: item.time_deleted,
+            })
+            .collect())
+    }
+
+    pub fn restore(entries: Vec<TrashedEntry>) -> Result<()> {
+        let items = entries.into_iter().map(|entry| trash::TrashItem {
+            id: entry.id,
+            name: entry
+                .original_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            original_parent: entry
+                .original_path
+                .parent()
+                .map(ToOwned::to_owned)
+                .unwrap_or_default(),
+            time_deleted: entry.time_deleted,
+        });
+        Ok(trash::os_limited::restore_all(items)?)
+    }
+}
+
+#[cfg(not(all(
+    feature = "trash-move",
+    any(
+        target_os = "windows",
+        all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        )
+    )
+)))]
+mod platform {
+    use super::TrashedEntry;
+    use anyhow::{bail, Result};
+
+    pub fn list() -> Result<Vec<TrashedEntry>> {
+        bail!("Browsing the trash bin isn't supported on this platform")
+    }
+
+    pub fn restore(_entries: Vec<TrashedEntry>) -> Result<()> {
+        bail!("Restoring from the trash bin isn't supported on this platform")
+    }
+}
+
+/// All entries in the trash bin, the most recently deleted first.
+pub fn list_trash() -> Result<Vec<TrashedEntry>> {
+    let mut entries = platform::list()?;
+    entries.sort_by_key(|e| std::cmp::Reverse(e.time_deleted));
+    Ok(entries)
+}
+
+/// Move all `entries` out of the trash bin and back to their original location.
+pub fn restore_from_trash(entries: Vec<TrashedEntry>) -> Result<()> {
+    platform::restore(entries)
+}
@@ -28,5 +28,14 @@ mod utils {
                 acc
             })
     }
+
+    /// The path of the entry at `node_idx` like [`path_of()`], made absolute if the input it is in was relative.
+    pub fn absolute_path_of(tree: &Tree, node_idx: TreeIndex) -> PathBuf {
+        let path = path_of(tree, node_idx);
+        match path.is_relative() {
+            true => std::env::current_dir().map_or(path.clone(), |cwd| cwd.join(&path)),
+            false => path,
+        }
+    }
 }
-pub use utils::path_of;
+pub use utils::{absolute_path_of, path_of};
@@ -0,0 +1,110 @@
+use crate::interactive::CategoryTotal;
+use dua::ByteFormat;
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+
+/// Shows how many bytes the files of each configured category take up across the whole tree
+#[derive(Default, Clone)]
+pub struct CategoriesPane {
+    totals: Vec<CategoryTotal>,
+    /// All files no category matched
+    rest: CategoryTotal,
+    /// Where categories are configured, to point there if there are none yet
+    config_path: Option<String>,
+}
+
+pub struct CategoriesPaneProps {
+    pub border_style: Style,
+    pub format: ByteFormat,
+}
+
+impl CategoriesPane {
+    pub fn new(
+        totals: Vec<CategoryTotal>,
+        rest: CategoryTotal,
+        config_path: Option<String>,
+    ) -> Self {
+        CategoriesPane {
+            totals,
+            rest,
+            config_path,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        props: impl Borrow<CategoriesPaneProps>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let CategoriesPaneProps {
+            border_style,
+            format,
+        } = props.borrow();
+
+        let block = Block::default()
+            .title(format!("Categories: {}", self.totals.len()))
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let all_bytes: u128 = self.totals.iter().map(|t| t.bytes).sum::<u128>() + self.rest.bytes;
+        let lines: Vec<_> = if self.totals.is_empty() {
+            vec![
+                Spans::from(" No categories are configured. Add lines like"),
+                Spans::from("   category = ML checkpoints: *.ckpt, *.safetensors"),
+                Spans::from(format!(
+                    " to {}",
+                    self.config_path
+                        .as_deref()
+                        .unwrap_or("the configuration file")
+                )),
+            ]
+        } else {
+            self.totals
+                .iter()
+                .chain(std::iter::once(&self.rest))
+                .map(|total| {
+                    let share = match all_bytes {
+                        0 => 0.0,
+                        all => total.bytes as f64 / all as f64 * 100.0,
+                    };
+                    Spans::from(vec![
+                        Span::styled(
+                            format!(
+                                "{:>byte_column_width$}",
+                                format.display(total.bytes).to_string(),
+                                byte_column_width = format.width()
+                            ),
+                            Style {
+                                fg: Color::Green.into(),
+                                ..Default::default()
+                            },
+                        ),
+                        Span::from(format!(" {:>5.1}% ", share)),
+                        Span::styled(
+                            total.name.clone(),
+                            Style {
+                                fg: Color::Cyan.into(),
+                                ..Default::default()
+                            },
+                        ),
+                        Span::from(format!(
+                            " ({} file{})",
+                            total.files,
+                            if total.files == 1 { "" } else { "s" }
+                        )),
+                    ])
+                })
+                .collect()
+        };
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
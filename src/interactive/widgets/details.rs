@@ -0,0 +1,184 @@
+use dua::{file_type_of, traverse::MtimeRange, ByteFormat, FileType};
+use std::{
+    borrow::Borrow,
+    path::{Path, PathBuf},
+};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+
+/// Shows additional information about the selected entry
+#[derive(Default, Clone)]
+pub struct DetailsPane {
+    /// The file the type was last determined for, to avoid reading it on every render
+    file_type: Option<(PathBuf, Option<FileType>)>,
+}
+
+pub struct DetailsPaneProps<'a> {
+    pub border_style: Style,
+    pub format: ByteFormat,
+    pub size_model: &'static str,
+    pub path: &'a Path,
+    pub size: u128,
+    pub is_dir: bool,
+    /// `None` if the modification times aren't known (yet)
+    pub mtime_range: Option<MtimeRange>,
+}
+
+impl DetailsPane {
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<DetailsPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let DetailsPaneProps {
+            border_style,
+            format,
+            size_model,
+            path,
+            size,
+            is_dir,
+            mtime_range,
+        } = props.borrow();
+
+        let block = Block::default()
+            .title("Details")
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let line = |label: &str, value: String| {
+            Spans::from(vec![
+                Span::styled(
+                    format!(" {:<15}", label),
+                    Style {
+                        fg: Color::Green.into(),
+                        ..Default::default()
+                    },
+                ),
+                Span::from(value),
+            ])
+        };
+        let time_or_unknown = |secs: Option<u32>| secs.map_or_else(|| "unknown".into(), format_utc);
+        let mut lines = vec![
+            line("Path", path.display().to_string()),
+            line("Size", format.display(*size).to_string().trim().to_owned()),
+            line("Counted as", size_model.to_string()),
+        ];
+        if *is_dir {
+            lines.push(line(
+                "Oldest file",
+                time_or_unknown(mtime_range.map(|r| r.oldest)),
+            ));
+            lines.push(line(
+                "Newest file",
+                time_or_unknown(mtime_range.map(|r| r.newest)),
+            ));
+        } else {
+            lines.push(line(
+                "Modified",
+                time_or_unknown(mtime_range.map(|r| r.newest)),
+            ));
+            lines.push(self.file_type_line(path, line));
+        }
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    /// The type of the file at `path` by its content, and the one its name suggests if that is different.
+    fn file_type_line<'a>(
+        &mut self,
+        path: &Path,
+        line: impl Fn(&str, String) -> Spans<'a>,
+    ) -> Spans<'a> {
+        let file_type = match &self.file_type {
+            Some((cached_path, file_type)) if cached_path == path => *file_type,
+            _ => {
+                let file_type = file_type_of(path).ok().flatten();
+                self.file_type = Some((path.to_owned(), file_type));
+                file_type
+            }
+        };
+        let detected = match file_type {
+            Some(file_type) => file_type,
+            None => return line("Type", "unknown".into()),
+        };
+        let mut spans = line("Type", detected.name().into());
+        let expected = path
+            .file_name()
+            .and_then(|name| FileType::expected_for(&name.to_string_lossy()));
+        if let Some(expected) = expected.filter(|expected| *expected != detected) {
+            spans.0.push(Span::styled(
+                format!(" (named like {})", expected.name()),
+                Style {
+                    fg: Color::Red.into(),
+                    ..Default::default()
+                },
+            ));
+        }
+        spans
+    }
+}
+
+/// Format `secs` since the unix epoch as UTC date and time, like `2021-03-14 15:09`.
+pub(super) fn format_utc(secs: u32) -> String {
+    let ((year, month, day), secs_of_day) = utc_date(secs);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60
+    )
+}
+
+/// Format `secs` since the unix epoch as UTC date and time with seconds as in RFC 3339, like `2021-03-14T15:09:00Z`.
+pub(crate) fn format_utc_rfc3339(secs: u32) -> String {
+    let ((year, month, day), secs_of_day) = utc_date(secs);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// The year, month and day of `secs` since the unix epoch, along with the seconds into that day.
+fn utc_date(secs: u32) -> ((i64, i64, i64), u32) {
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Convert days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    ((year, month, day), secs_of_day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_utc, format_utc_rfc3339};
+
+    #[test]
+    fn timestamps_are_formatted_as_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00", "leap day");
+        assert_eq!(format_utc(1_615_734_540), "2021-03-14 15:09");
+        assert_eq!(format_utc(u32::MAX), "2106-02-07 06:28");
+        assert_eq!(format_utc_rfc3339(1_615_734_545), "2021-03-14T15:09:05Z");
+    }
+}
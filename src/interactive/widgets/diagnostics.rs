@@ -0,0 +1,129 @@
+use crate::interactive::SymlinkFarm;
+use dua::{ByteFormat, DuplicateDirectory, SymlinkLoop};
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+
+/// Lists everything noteworthy we found during the traversal which doesn't show up in the entries themselves
+#[derive(Default, Clone)]
+pub struct DiagnosticsPane {
+    /// Directories of mostly symbolic links, found when the pane was opened
+    symlink_farms: Vec<SymlinkFarm>,
+}
+
+pub struct DiagnosticsPaneProps<'a> {
+    pub border_style: Style,
+    pub symlink_loops: &'a [SymlinkLoop],
+    pub duplicate_directories: &'a [DuplicateDirectory],
+    pub format: ByteFormat,
+}
+
+impl DiagnosticsPane {
+    pub fn new(symlink_farms: Vec<SymlinkFarm>) -> Self {
+        DiagnosticsPane { symlink_farms }
+    }
+}
+
+impl DiagnosticsPane {
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<DiagnosticsPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let DiagnosticsPaneProps {
+            border_style,
+            symlink_loops,
+            duplicate_directories,
+            format,
+        } = props.borrow();
+
+        let block = Block::default()
+            .title(format!(
+                "Symlink loops: {}, duplicate directories: {}, symlink farms: {}",
+                symlink_loops.len(),
+                duplicate_directories.len(),
+                self.symlink_farms.len()
+            ))
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let path_style = Style {
+            fg: Color::Cyan.into(),
+            ..Default::default()
+        };
+        let mut lines: Vec<_> = if symlink_loops.is_empty() {
+            vec![Spans::from(" No symlink loops were detected")]
+        } else {
+            symlink_loops
+                .iter()
+                .map(|SymlinkLoop { path, ancestor }| {
+                    Spans::from(vec![
+                        Span::styled(format!(" {}", path.display()), path_style),
+                        Span::from(format!(" -> {}", ancestor.display())),
+                    ])
+                })
+                .collect()
+        };
+        lines.push(Spans::default());
+        if duplicate_directories.is_empty() {
+            lines.push(Spans::from(" No directory was seen at more than one path"));
+        } else {
+            lines.push(Spans::from(
+                " Seen again, like through bind mounts, and only counted at the first path:",
+            ));
+            lines.extend(duplicate_directories.iter().map(
+                |DuplicateDirectory { path, original }| {
+                    Spans::from(vec![
+                        Span::styled(format!(" {}", path.display()), path_style),
+                        Span::from(format!(" = {}", original.display())),
+                    ])
+                },
+            ));
+        }
+        lines.push(Spans::default());
+        if self.symlink_farms.is_empty() {
+            lines.push(Spans::from(
+                " No directory consists mostly of symbolic links",
+            ));
+        } else {
+            lines.push(Spans::from(
+                " Mostly symbolic links, with the size of what they point to:",
+            ));
+            lines.extend(self.symlink_farms.iter().map(|farm| {
+                let mut details = format!(
+                    " {} of {} entries are links to {}",
+                    farm.links,
+                    farm.entries,
+                    format.display(farm.target_bytes).to_string().trim_start()
+                );
+                if farm.unscanned_targets > 0 {
+                    details.push_str(&format!(
+                        ", and {} unscanned director{}",
+                        farm.unscanned_targets,
+                        if farm.unscanned_targets == 1 {
+                            "y"
+                        } else {
+                            "ies"
+                        }
+                    ));
+                }
+                if farm.dangling > 0 {
+                    details.push_str(&format!(", {} dangling", farm.dangling));
+                }
+                Spans::from(vec![
+                    Span::styled(format!(" {}", farm.path.display()), path_style),
+                    Span::from(details),
+                ])
+            }));
+        }
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
@@ -1,17 +1,28 @@
 use crate::interactive::{
     path_of,
-    widgets::{entry_color, EntryMarkMap},
-    DisplayOptions, EntryDataBundle,
+    widgets::{
+        color_depth, entry_color, format_utc, icon_for, ColorDepth, EntryMarkMap,
+        COLOR_ROW_SHADING, COLOR_ROW_SHADING_256,
+    },
+    DisplayOptions, EntryDataBundle, SortMode,
+};
+use dua::{
+    display_growth,
+    traverse::{Tree, TreeIndex, RECENT_PERIOD_DAYS},
+    Baseline,
 };
-use dua::traverse::{Tree, TreeIndex};
 use itertools::Itertools;
-use std::{borrow::Borrow, path::Path};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::BTreeSet,
+    path::Path,
+};
 use tui::{
     buffer::Buffer,
     layout::Rect,
     style::{Color, Modifier, Style},
     text::Span,
-    widgets::{Block, Borders},
+    widgets::{Block, Borders, Widget},
 };
 use tui_react::util::rect::line_bound;
 use tui_react::{
@@ -20,13 +31,33 @@ use tui_react::{
     List, ListProps,
 };
 
+/// Entries more than this many times larger than the median of all entries shown with them are outliers
+const OUTLIER_FACTOR: u128 = 3;
+/// The least amount of entries needed for their median to tell what's unusual among them
+const MIN_ENTRIES_FOR_OUTLIERS: usize = 4;
+
+/// Directories of which at least this share was modified recently are growing fast, if it's large enough to matter
+const GROWING_FAST_SHARE: f64 = 0.5;
+const GROWING_FAST_MIN_BYTES: u128 = 1024 * 1024;
+
 pub struct EntriesProps<'a> {
     pub tree: &'a Tree,
     pub root: TreeIndex,
     pub display: DisplayOptions,
     pub selected: Option<TreeIndex>,
     pub entries: &'a [EntryDataBundle],
+    /// How `entries` are sorted, to show which column drives it
+    pub sorting: SortMode,
     pub marked: Option<&'a EntryMarkMap>,
+    pub flagged: &'a BTreeSet<TreeIndex>,
+    /// The entry to draw attention to, like the directory we just came from
+    pub highlighted: Option<TreeIndex>,
+    /// The sizes of an earlier scan, to show how much each entry grew since
+    pub baseline: Option<&'a Baseline>,
+    /// If set, sizes are shown as exact amount of bytes instead of in the byte format
+    pub exact_sizes: bool,
+    /// The directory currently being scanned, if the scan is still running
+    pub in_progress: Option<TreeIndex>,
     pub border_style: Style,
     pub is_focussed: bool,
 }
@@ -48,8 +79,14 @@ impl Entries {
             root,
             display,
             entries,
+            sorting,
             selected,
             marked,
+            flagged,
+            highlighted,
+            baseline,
+            exact_sizes,
+            in_progress,
             border_style,
             is_focussed,
         } = props.borrow();
@@ -61,7 +98,42 @@ impl Entries {
                 .is_none()
         };
 
-        let total: u128 = entries.iter().map(|b| b.data.size).sum();
+        // directories whose contents are not entirely known yet
+        let partial: Vec<_> = std::iter::successors(*in_progress, |idx| {
+            tree.neighbors_directed(*idx, petgraph::Incoming).next()
+        })
+        .collect();
+
+        let total: u128 = match display.share_of_total {
+            true => std::iter::successors(Some(*root), |idx| {
+                tree.neighbors_directed(*idx, petgraph::Incoming).next()
+            })
+            .last()
+            .map(|top| {
+                tree.neighbors_directed(top, petgraph::Outgoing)
+                    .map(|idx| tree[idx].size)
+                    .sum()
+            })
+            .unwrap_or_default(),
+            false => entries.iter().map(|b| b.data.size).sum(),
+        };
+        let format_size = |size: u128| match exact_sizes {
+            true => exact_bytes(size),
+            false => display.byte_format.display(size).to_string(),
+        };
+        let byte_column_width = match exact_sizes {
+            true => entries
+                .iter()
+                .map(|b| exact_bytes(b.data.size).len())
+                .max()
+                .unwrap_or_default()
+                .max(display.byte_format.width()),
+            false => display.byte_format.width(),
+        };
+        let median = display
+            .highlight_outliers
+            .then(|| median_for_outliers(entries.iter().map(|b| b.data.size)))
+            .flatten();
         let title = match path_of(tree, *root).to_string_lossy().to_string() {
             ref p if p.is_empty() => Path::new(".")
                 .canonicalize()
@@ -70,13 +142,19 @@ impl Entries {
             p => p,
         };
         let title = format!(
-            " {} ({} item{}) ",
+            " {} ({} item{}{}, by {}) ",
             title,
             entries.len(),
             match entries.len() {
                 1 => "",
                 _ => "s",
-            }
+            },
+            if partial.contains(root) {
+                " so far, partial"
+            } else {
+                ""
+            },
+            sorting.label()
         );
         let block = Block::default()
             .title(title.as_str())
@@ -90,81 +168,286 @@ impl Entries {
                 .unwrap_or(0)
         });
 
+        let (list_area, block) = if display.show_summary_row {
+            let inner = block.inner(area);
+            block.render(area, buf);
+            draw_text_nowrap_fn(
+                line_bound(inner, 0),
+                buf,
+                summary(tree, *root, entries.len(), display),
+                |_, _, _| Style {
+                    fg: Color::Cyan.into(),
+                    add_modifier: Modifier::BOLD,
+                    ..Default::default()
+                },
+            );
+            let list_area = Rect {
+                y: inner.y + inner.height.min(1),
+                height: inner.height.saturating_sub(1),
+                ..inner
+            };
+            (list_area, None)
+        } else {
+            (area, Some(block))
+        };
+        let first_visible_entry = visible_offset(
+            list.offset,
+            entry_in_view,
+            block
+                .as_ref()
+                .map_or(list_area, |b| b.inner(list_area))
+                .height as usize,
+        );
+
         let props = ListProps {
-            block: Some(block),
+            block,
             entry_in_view,
         };
-        let lines = entries.iter().map(
-            |EntryDataBundle {
-                 index: node_idx,
-                 data: w,
-                 is_dir,
-                 exists,
-             }| {
-                let mut style = Style::default();
-                let is_selected = if let Some(idx) = selected {
-                    *idx == *node_idx
-                } else {
-                    false
-                };
-                if is_selected {
-                    style.add_modifier.insert(Modifier::REVERSED);
-                }
-                if *is_focussed & is_selected {
-                    style.add_modifier.insert(Modifier::BOLD);
-                }
-
-                let bytes = Span::styled(
+        let lines = entries.iter().enumerate().map(|(position, bundle)| {
+            if position < first_visible_entry {
+                // skipped by the list anyway, so don't spend time on formatting it
+                return Vec::new();
+            }
+            let bundle = if bundle.has_metadata {
+                Cow::Borrowed(bundle)
+            } else {
+                Cow::Owned(bundle.clone().with_metadata(tree))
+            };
+            let EntryDataBundle {
+                index: node_idx,
+                data: w,
+                is_dir,
+                exists,
+                hard_links,
+                cloud_size,
+                crash_artifact,
+                ..
+            } = bundle.as_ref();
+            let mut style = Style::default();
+            let is_selected = if let Some(idx) = selected {
+                *idx == *node_idx
+            } else {
+                false
+            };
+            if position % 2 == 1 && !is_selected {
+                style.bg = display.row_shading;
+            }
+            if is_selected {
+                style.add_modifier.insert(Modifier::REVERSED);
+            }
+            if *is_focussed & is_selected {
+                style.add_modifier.insert(Modifier::BOLD);
+            }
+            if *highlighted == Some(*node_idx) {
+                style.add_modifier.insert(Modifier::UNDERLINED);
+            }
+
+            let bytes = Span::styled(
+                format!(
+                    "{:>byte_column_width$}",
+                    format_size(w.size), // we would have to impl alignment/padding ourselves otherwise...
+                    byte_column_width = byte_column_width
+                ),
+                Style {
+                    fg: Color::Green.into(),
+                    ..style
+                },
+            );
+            let growth = baseline.map(|baseline| {
+                let size_before = baseline.size_of(*node_idx);
+                Span::styled(
                     format!(
-                        "{:>byte_column_width$}",
-                        display.byte_format.display(w.size).to_string(), // we would have to impl alignment/padding ourselves otherwise...
-                        byte_column_width = display.byte_format.width()
+                        " {:>growth_column_width$}",
+                        match size_before {
+                            Some(size_before) =>
+                                display_growth(display.byte_format, size_before, w.size),
+                            None => "new".into(),
+                        },
+                        growth_column_width = display.byte_format.width() + 1
                     ),
                     Style {
-                        fg: Color::Green.into(),
+                        fg: match size_before {
+                            Some(size_before) if size_before == w.size => style.fg,
+                            Some(size_before) if size_before > w.size => Color::Green.into(),
+                            _ => Color::Red.into(),
+                        },
                         ..style
                     },
-                );
-                let fraction = w.size as f32 / total as f32;
-                let should_avoid_showing_a_big_reversed_bar = fraction > 0.9;
-                let local_style = if should_avoid_showing_a_big_reversed_bar {
-                    style.remove_modifier(Modifier::REVERSED)
+                )
+            });
+            let fraction = w.size as f32 / total as f32;
+            let should_avoid_showing_a_big_reversed_bar = fraction > 0.9;
+            let local_style = if should_avoid_showing_a_big_reversed_bar {
+                style.remove_modifier(Modifier::REVERSED)
+            } else {
+                style
+            };
+
+            let left_bar = Span::styled(" |", local_style);
+            let percentage = Span::styled(
+                format!("{}", display.byte_vis.display(fraction, display.bar_width)),
+                local_style,
+            );
+            let right_bar = Span::styled("| ", local_style);
+            let hard_links = display.show_hard_links.then(|| {
+                Span::styled(
+                    match hard_links {
+                        Some(n) if !*is_dir => format!("{:>5} ", n),
+                        _ => format!("{:>5} ", ""),
+                    },
+                    Style {
+                        fg: match hard_links {
+                            Some(n) if *n > 1 && !*is_dir => Color::Magenta.into(),
+                            _ => style.fg,
+                        },
+                        ..style
+                    },
+                )
+            });
+
+            let entry_count = (display.show_entry_count || *sorting == SortMode::CountDescending)
+                .then(|| {
+                    Span::styled(
+                        match is_dir {
+                            true => format!("{:>9} ", w.entry_count),
+                            false => format!("{:>9} ", ""),
+                        },
+                        Style {
+                            fg: Color::Cyan.into(),
+                            ..style
+                        },
+                    )
+                });
+            let modified = (display.show_mtime || sorting.is_by_mtime()).then(|| {
+                Span::styled(
+                    format!(
+                        "{:<16} ",
+                        w.mtime_range
+                            .map_or_else(String::new, |range| format_utc(range.newest))
+                    ),
+                    Style {
+                        fg: Color::Cyan.into(),
+                        ..style
+                    },
+                )
+            });
+
+            let name_style = {
+                let is_marked = marked.map(|m| m.contains_key(node_idx)).unwrap_or(false);
+                let fg = if !exists {
+                    // non-existing - always red!
+                    Some(Color::Red)
                 } else {
-                    style
+                    entry_color(style.fg, !*is_dir, is_marked)
                 };
-
-                let left_bar = Span::styled(" |", local_style);
-                let percentage = Span::styled(
-                    format!("{}", display.byte_vis.display(fraction)),
-                    local_style,
-                );
-                let right_bar = Span::styled("| ", local_style);
-
-                let name = Span::styled(
-                    fill_background_to_right(
-                        format!(
-                            "{prefix}{}",
-                            w.name.to_string_lossy(),
-                            prefix = if *is_dir && !is_top(*root) { "/" } else { " " }
-                        ),
-                        area.width,
+                Style { fg, ..style }
+            };
+            let icon = display
+                .show_icons
+                .then(|| Span::styled(format!("{} ", icon_for(&w.name, *is_dir)), name_style));
+            let placeholder = cloud_size.map(|cloud_size| {
+                Span::styled(
+                    format!(
+                        "☁ {} ",
+                        display
+                            .byte_format
+                            .display(cloud_size as u128)
+                            .to_string()
+                            .trim_start()
                     ),
-                    {
-                        let is_marked = marked.map(|m| m.contains_key(node_idx)).unwrap_or(false);
-                        let fg = if !exists {
-                            // non-existing - always red!
-                            Some(Color::Red)
-                        } else {
-                            entry_color(style.fg, !*is_dir, is_marked)
-                        };
-                        Style { fg, ..style }
+                    Style {
+                        fg: Color::Blue.into(),
+                        ..style
                     },
-                );
-                vec![bytes, left_bar, percentage, right_bar, name]
-            },
-        );
+                )
+            });
+            let crash_artifact = crash_artifact.map(|artifact| {
+                Span::styled(
+                    format!("✖ {} ", artifact.name()),
+                    Style {
+                        fg: Color::Red.into(),
+                        ..style
+                    },
+                )
+            });
+            let outlier = median
+                .filter(|median| w.size > median * OUTLIER_FACTOR)
+                .map(|median| {
+                    Span::styled(
+                        format!("▲{}× ", w.size / median),
+                        Style {
+                            fg: Color::Magenta.into(),
+                            add_modifier: style.add_modifier | Modifier::BOLD,
+                            ..style
+                        },
+                    )
+                });
+            let growing_fast = (*is_dir && is_growing_fast(w.size, w.recent_size)).then(|| {
+                Span::styled(
+                    format!(
+                        "↗ {}/day ",
+                        display
+                            .byte_format
+                            .display(w.recent_size / RECENT_PERIOD_DAYS as u128)
+                            .to_string()
+                            .trim_start()
+                    ),
+                    Style {
+                        fg: Color::LightRed.into(),
+                        ..style
+                    },
+                )
+            });
+            let flag = flagged.contains(node_idx).then(|| {
+                Span::styled(
+                    "⚑ ",
+                    Style {
+                        fg: Color::Yellow.into(),
+                        ..style
+                    },
+                )
+            });
+            let partial_badge = partial.contains(node_idx).then(|| {
+                Span::styled(
+                    "partial ",
+                    Style {
+                        fg: Color::Yellow.into(),
+                        add_modifier: style.add_modifier | Modifier::ITALIC,
+                        ..style
+                    },
+                )
+            });
+            let name = Span::styled(
+                fill_background_to_right(
+                    format!(
+                        "{prefix}{}",
+                        w.name.to_string_lossy(),
+                        prefix = if *is_dir && !is_top(*root) { "/" } else { " " }
+                    ),
+                    area.width,
+                ),
+                name_style,
+            );
+            let mut columns = vec![bytes];
+            columns.extend(growth);
+            if display.show_byte_vis {
+                columns.extend([left_bar, percentage, right_bar]);
+            }
+            columns.extend(hard_links);
+            columns.extend(modified);
+            columns.extend(entry_count);
+            columns.extend(icon);
+            columns.extend(placeholder);
+            columns.extend(crash_artifact);
+            columns.extend(outlier);
+            columns.extend(growing_fast);
+            columns.extend(flag);
+            columns.extend(partial_badge);
+            columns.push(name);
+            columns
+        });
 
-        list.render(props, lines, area, buf);
+        list.render(props, lines, list_area, buf);
 
         if *is_focussed {
             let help_text = " . = o|.. = u ── ⇊ = CTRL+d|↓ = j|⇈ = CTRL+u|↑ = k ";
@@ -195,3 +478,186 @@ impl Entries {
         }
     }
 }
+
+/// The background color for every other row, if the terminal we are running in supports a subtle one.
+pub fn row_shading_from_env() -> Option<Color> {
+    row_shading(
+        std::env::var("TERM").ok().as_deref(),
+        std::env::var("COLORTERM").ok().as_deref(),
+        std::env::var_os("NO_COLOR").is_some(),
+    )
+}
+
+fn row_shading(term: Option<&str>, colorterm: Option<&str>, no_color: bool) -> Option<Color> {
+    match color_depth(term, colorterm, no_color) {
+        ColorDepth::TrueColor => Some(COLOR_ROW_SHADING),
+        ColorDepth::Indexed256 => Some(COLOR_ROW_SHADING_256),
+        _ => None,
+    }
+}
+
+/// A line describing the directory at `root` with `num_entries` visible entries, for the pinned summary row.
+fn summary(tree: &Tree, root: TreeIndex, num_entries: usize, display: &DisplayOptions) -> String {
+    let size_of = |idx| tree.node_weight(idx).map_or(0, |e| e.size);
+    let size = size_of(root);
+    let share_of_parent = tree
+        .neighbors_directed(root, petgraph::Incoming)
+        .next()
+        .map(size_of)
+        .filter(|parent_size| *parent_size > 0)
+        .map(|parent_size| {
+            format!(
+                " | {:.1}% of parent",
+                size as f64 / parent_size as f64 * 100.0
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        " Σ {} | {} item{}{}",
+        display.byte_format.display(size).to_string().trim_start(),
+        num_entries,
+        if num_entries == 1 { "" } else { "s" },
+        share_of_parent
+    )
+}
+
+/// Return true if so much of a directory of `size` bytes was modified recently that it will likely fill up again soon.
+fn is_growing_fast(size: u128, recent_size: u128) -> bool {
+    recent_size >= GROWING_FAST_MIN_BYTES && recent_size as f64 >= size as f64 * GROWING_FAST_SHARE
+}
+
+/// The median of `sizes` to find outliers with, or `None` if there are too few of them or most are empty.
+fn median_for_outliers(sizes: impl Iterator<Item = u128>) -> Option<u128> {
+    let mut sizes: Vec<_> = sizes.collect();
+    if sizes.len() < MIN_ENTRIES_FOR_OUTLIERS {
+        return None;
+    }
+    let middle = sizes.len() / 2;
+    let (_, median, _) = sizes.select_nth_unstable(middle);
+    Some(*median).filter(|median| *median > 0)
+}
+
+/// `bytes` with thousands separators, like `1,234,567 b`.
+fn exact_bytes(bytes: u128) -> String {
+    let digits = bytes.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 + 2);
+    for (pos, digit) in digits.chars().enumerate() {
+        if pos > 0 && (digits.len() - pos).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(" b");
+    grouped
+}
+
+/// Compute the offset of the first visible entry the same way the `List` widget does.
+fn visible_offset(offset: usize, entry_in_view: Option<usize>, height: usize) -> usize {
+    match entry_in_view {
+        Some(pos) if offset + height.saturating_sub(1) < pos => pos + 1 - height.max(1),
+        Some(pos) if offset > pos => pos,
+        Some(_) => offset,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        exact_bytes, is_growing_fast, median_for_outliers, row_shading, summary, visible_offset,
+    };
+    use crate::interactive::DisplayOptions;
+    use dua::traverse::{EntryData, Tree};
+
+    #[test]
+    fn exact_bytes_are_grouped_by_thousands() {
+        assert_eq!(exact_bytes(0), "0 b");
+        assert_eq!(exact_bytes(999), "999 b");
+        assert_eq!(exact_bytes(1000), "1,000 b");
+        assert_eq!(exact_bytes(123_456_789), "123,456,789 b");
+    }
+
+    #[test]
+    fn visible_offset_follows_the_entry_in_view() {
+        assert_eq!(visible_offset(0, None, 10), 0);
+        assert_eq!(visible_offset(0, Some(9), 10), 0, "last entry of the page");
+        assert_eq!(visible_offset(0, Some(10), 10), 1, "scrolls down by one");
+        assert_eq!(visible_offset(5, Some(3), 10), 3, "scrolls up to the entry");
+        assert_eq!(visible_offset(5, Some(7), 10), 5, "keeps the offset");
+        assert_eq!(visible_offset(0, Some(3), 0), 3, "no height");
+    }
+
+    #[test]
+    fn outliers_are_found_relative_to_the_median() {
+        assert_eq!(
+            median_for_outliers(vec![10, 900, 12, 11, 9].into_iter()),
+            Some(11)
+        );
+        assert_eq!(
+            median_for_outliers(vec![10, 900, 12].into_iter()),
+            None,
+            "too few to tell"
+        );
+        assert_eq!(
+            median_for_outliers(vec![0, 0, 0, 5].into_iter()),
+            None,
+            "mostly empty"
+        );
+    }
+
+    #[test]
+    fn directories_grow_fast_if_much_of_them_was_modified_recently() {
+        const MB: u128 = 1024 * 1024;
+        assert!(is_growing_fast(10 * MB, 6 * MB));
+        assert!(!is_growing_fast(10 * MB, 2 * MB), "mostly old files");
+        assert!(!is_growing_fast(MB / 2, MB / 2), "too small to matter");
+    }
+
+    #[test]
+    fn summary_describes_the_current_directory() {
+        let mut tree = Tree::new();
+        let mut add = |size| {
+            tree.add_node(EntryData {
+                size,
+                ..Default::default()
+            })
+        };
+        let (parent, root) = (add(4000), add(1000));
+        tree.add_edge(parent, root, ());
+        let display = DisplayOptions::from(dua::WalkOptions {
+            apparent_size: false,
+            sorting: dua::TraversalSorting::None,
+            ..crate::interactive::app::tests::utils::test_walk_options()
+        });
+        assert_eq!(
+            summary(&tree, root, 3, &display),
+            " Σ 1000  B | 3 items | 25.0% of parent"
+        );
+        assert_eq!(
+            summary(&tree, parent, 1, &display),
+            " Σ 4.00 KB | 1 item",
+            "the top-level has no parent"
+        );
+    }
+
+    #[test]
+    fn row_shading_depends_on_color_support() {
+        use crate::interactive::widgets::{COLOR_ROW_SHADING, COLOR_ROW_SHADING_256};
+        assert_eq!(
+            row_shading(Some("xterm-256color"), Some("truecolor"), false),
+            Some(COLOR_ROW_SHADING)
+        );
+        assert_eq!(
+            row_shading(Some("screen-256color"), None, false),
+            Some(COLOR_ROW_SHADING_256)
+        );
+        assert_eq!(row_shading(Some("xterm"), None, false), None, "16 colors");
+        assert_eq!(row_shading(Some("dumb"), Some("truecolor"), false), None);
+        assert_eq!(row_shading(None, None, false), None);
+        assert_eq!(
+            row_shading(Some("xterm-256color"), Some("truecolor"), true),
+            None,
+            "NO_COLOR is respected"
+        );
+    }
+}
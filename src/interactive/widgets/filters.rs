@@ -0,0 +1,66 @@
+use crate::interactive::EntryFilter;
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Paragraph, Widget},
+};
+
+pub struct FilterChips;
+
+pub struct FilterChipsProps<'a> {
+    pub filters: &'a [EntryFilter],
+    pub selected: Option<usize>,
+}
+
+impl FilterChips {
+    pub fn render<'a>(
+        &self,
+        props: impl Borrow<FilterChipsProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let FilterChipsProps { filters, selected } = props.borrow();
+
+        let mut spans = vec![Span::from(" Filters: ")];
+        for (idx, filter) in filters.iter().enumerate() {
+            let is_selected = *selected == Some(idx);
+            spans.push(Span::styled(
+                format!(
+                    " {} {} ",
+                    if is_selected { "✕" } else { "•" },
+                    filter.label()
+                ),
+                Style {
+                    fg: Color::Black.into(),
+                    bg: if is_selected {
+                        Color::LightRed
+                    } else {
+                        Color::Cyan
+                    }
+                    .into(),
+                    add_modifier: if is_selected {
+                        Modifier::BOLD
+                    } else {
+                        Modifier::empty()
+                    },
+                    ..Default::default()
+                },
+            ));
+            spans.push(Span::from(" "));
+        }
+        spans.push(Span::styled(
+            match selected {
+                Some(_) => "(<delete> = remove, f = next)",
+                None => "(f = select)",
+            },
+            Style {
+                fg: Color::DarkGray.into(),
+                ..Default::default()
+            },
+        ));
+        Paragraph::new(Text::from(Spans::from(spans))).render(area, buf);
+    }
+}
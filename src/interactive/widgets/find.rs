@@ -0,0 +1,162 @@
+use crate::interactive::CursorDirection;
+use crosstermion::{input::Key, input::Key::*};
+use dua::{traverse::TreeIndex, ByteFormat};
+use std::{borrow::Borrow, path::PathBuf};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// An entry anywhere in the tree whose name matched the query of a [`FindPane`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundEntry {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    pub size: u128,
+}
+
+/// Lists the entries of the whole tree matching a query to show one of them in the entries pane
+#[derive(Default)]
+pub struct FindPane {
+    query: String,
+    results: Vec<FoundEntry>,
+    /// The amount of matches, which may be more than the results we keep
+    num_matches: usize,
+    selected: usize,
+    list: List,
+}
+
+pub struct FindPaneProps {
+    pub border_style: Style,
+    pub has_focus: bool,
+    pub format: ByteFormat,
+}
+
+impl FindPane {
+    pub fn new(query: String, results: Vec<FoundEntry>, num_matches: usize) -> Self {
+        FindPane {
+            query,
+            results,
+            num_matches,
+            ..Default::default()
+        }
+    }
+
+    /// Handle `key` and return the result to show in the entries pane, if one was chosen.
+    pub fn process_events(&mut self, key: Key) -> Option<&FoundEntry> {
+        match key {
+            Char('o') | Char('\n') => return self.results.get(self.selected),
+            Char('H') => self.change_selection(CursorDirection::ToTop),
+            Char('G') => self.change_selection(CursorDirection::ToBottom),
+            Ctrl('u') | PageUp => self.change_selection(CursorDirection::PageUp),
+            Char('k') | Up => self.change_selection(CursorDirection::Up),
+            Char('j') | Down => self.change_selection(CursorDirection::Down),
+            Ctrl('d') | PageDown => self.change_selection(CursorDirection::PageDown),
+            _ => {}
+        };
+        None
+    }
+
+    fn change_selection(&mut self, direction: CursorDirection) {
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.results.len().saturating_sub(1));
+    }
+
+    pub fn render(&mut self, props: impl Borrow<FindPaneProps>, area: Rect, buf: &mut Buffer) {
+        let FindPaneProps {
+            border_style,
+            has_focus,
+            format,
+        } = props.borrow();
+
+        let found = match self.num_matches == self.results.len() {
+            true => format!("{} entries match '{}'", self.num_matches, self.query),
+            false => format!(
+                "{} entries match '{}', the largest {} are shown",
+                self.num_matches,
+                self.query,
+                self.results.len()
+            ),
+        };
+        let title = match has_focus {
+            true => format!("{} (o = show, q = close) ", found),
+            false => format!("{} ", found),
+        };
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let list_area = block.inner(area);
+        block.render(area, buf);
+
+        let selected = self.selected;
+        let entries = self.results.iter().enumerate().map(|(idx, entry)| {
+            let style = Style {
+                add_modifier: match (idx == selected, *has_focus) {
+                    (true, true) => Modifier::REVERSED | Modifier::BOLD,
+                    (true, false) => Modifier::REVERSED,
+                    (false, _) => Modifier::empty(),
+                },
+                ..Default::default()
+            };
+            vec![
+                Span::styled(
+                    format!(
+                        "{:>byte_column_width$} ",
+                        format.display(entry.size).to_string(),
+                        byte_column_width = format.width()
+                    ),
+                    Style {
+                        fg: Color::Green.into(),
+                        ..style
+                    },
+                ),
+                Span::styled(format!(" {} ", entry.path.display()), style),
+            ]
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, entries, list_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(name: &str, index: usize) -> FoundEntry {
+        FoundEntry {
+            index: TreeIndex::new(index),
+            path: name.into(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn the_selected_result_is_chosen() {
+        let mut pane = FindPane::new(
+            "core".into(),
+            vec![found("a/core.1", 1), found("core.2", 2)],
+            2,
+        );
+        assert_eq!(pane.process_events(Char('j')), None);
+        assert_eq!(
+            pane.process_events(Char('j')),
+            None,
+            "it stays at the last result"
+        );
+        assert_eq!(pane.process_events(Char('o')), Some(&found("core.2", 2)));
+        assert_eq!(pane.process_events(Char('H')), None);
+        assert_eq!(pane.process_events(Char('\n')), Some(&found("a/core.1", 1)));
+
+        let mut empty = FindPane::new("nothing".into(), Vec::new(), 0);
+        assert_eq!(empty.process_events(Char('o')), None);
+    }
+}
@@ -9,24 +9,72 @@ use tui::{
     widgets::{Paragraph, Widget},
 };
 
+/// How many characters the path of the selected entry moves with each scroll
+pub const PATH_SCROLL_STEP: usize = 16;
+
 pub struct Footer;
 
 pub struct FooterProps {
     pub total_bytes: Option<u128>,
+    /// The size of the current directory and of everything if all marked entries were deleted
+    pub size_without_marked: Option<(u128, u128)>,
     pub entries_traversed: u64,
+    pub unreadable_directories: usize,
+    pub symlink_loops: usize,
+    pub duplicate_directories: usize,
     pub format: ByteFormat,
     pub message: Option<String>,
+    /// The absolute path and size of the selected entry, shown on a line of its own if there is room for it
+    pub selected: Option<(String, u128)>,
+    /// How many characters the path of the selected entry is scrolled towards its beginning, if it doesn't fit
+    pub path_scroll: usize,
 }
 
 impl Footer {
     pub fn render(&self, props: impl Borrow<FooterProps>, area: Rect, buf: &mut Buffer) {
         let FooterProps {
             total_bytes,
+            size_without_marked,
             entries_traversed,
+            unreadable_directories,
+            symlink_loops,
+            duplicate_directories,
             format,
             message,
+            selected,
+            path_scroll,
         } = props.borrow();
 
+        let area = match selected {
+            Some((path, size)) if area.height >= 2 => {
+                let size = format!("  {} bytes ", size);
+                let width = (area.width as usize).saturating_sub(size.chars().count() + 1);
+                let path = scrolled(path, width, *path_scroll);
+                let hint = match path.starts_with('…') || path.ends_with('…') {
+                    true => "  [ ] = scroll",
+                    false => "",
+                };
+                Paragraph::new(Text::from(Spans::from(vec![
+                    Span::from(format!(" {}", path)),
+                    Span::styled(
+                        size,
+                        Style {
+                            fg: Color::Green.into(),
+                            ..Style::default()
+                        },
+                    ),
+                    Span::from(hint),
+                ])))
+                .render(Rect { height: 1, ..area }, buf);
+                Rect {
+                    y: area.y + 1,
+                    height: area.height - 1,
+                    ..area
+                }
+            }
+            _ => area,
+        };
+
         let spans = vec![
             Span::from(format!(
                 " Total disk usage: {}  Entries: {}   ",
@@ -37,6 +85,25 @@ impl Footer {
                 entries_traversed,
             ))
             .into(),
+            size_without_marked.map(|(current, total)| {
+                Span::from(format!(
+                    "Without marked: {} here, {} total   ",
+                    format.display(current),
+                    format.display(total)
+                ))
+            }),
+            match unreadable_directories {
+                0 => None,
+                n => Span::from(format!("Unreadable: {} (E = re-scan with sudo)   ", n)).into(),
+            },
+            match symlink_loops {
+                0 => None,
+                n => Span::from(format!("Symlink loops: {} (L = show)   ", n)).into(),
+            },
+            match duplicate_directories {
+                0 => None,
+                n => Span::from(format!("Duplicate directories: {} (L = show)   ", n)).into(),
+            },
             message.as_ref().map(|m| {
                 Span::styled(
                     m,
@@ -56,3 +123,47 @@ impl Footer {
         .render(area, buf);
     }
 }
+
+/// The part of `text` that fits into `width` characters, ending `scroll` characters before its end,
+/// with `…` in place of what is cut off.
+fn scrolled(text: &str, width: usize, scroll: usize) -> String {
+    let chars: Vec<_> = text.chars().collect();
+    if chars.len() <= width {
+        return text.to_owned();
+    }
+    if width < 2 {
+        return "…".repeat(width);
+    }
+    let end = chars.len().saturating_sub(scroll).max(width - 1);
+    if end == chars.len() {
+        return std::iter::once('…')
+            .chain(chars[end + 1 - width..].iter().copied())
+            .collect();
+    }
+    if end == width - 1 {
+        return chars[..end].iter().copied().chain(Some('…')).collect();
+    }
+    let start = end + 2 - width;
+    std::iter::once('…')
+        .chain(chars[start..end].iter().copied())
+        .chain(Some('…'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scrolled;
+
+    #[test]
+    fn long_paths_scroll_from_their_end() {
+        assert_eq!(scrolled("/a/b", 4, 0), "/a/b");
+        assert_eq!(scrolled("/home/user/file", 8, 0), "…er/file");
+        assert_eq!(scrolled("/home/user/file", 8, 4), "…/user/…");
+        assert_eq!(
+            scrolled("/home/user/file", 8, 100),
+            "/home/u…",
+            "it stops at the beginning"
+        );
+        assert_eq!(scrolled("/home/user/file", 1, 0), "…");
+    }
+}
@@ -1,3 +1,5 @@
+use dua::{ByteFormat, Filesystem};
+use std::borrow::Borrow;
 use tui::{
     buffer::Buffer,
     layout::Rect,
@@ -8,8 +10,28 @@ use tui::{
 
 pub struct Header;
 
+pub struct HeaderProps<'a> {
+    pub bg_color: Color,
+    pub is_paused: bool,
+    pub format: ByteFormat,
+    /// The size of the directory currently shown in the entries pane
+    pub root_bytes: u128,
+    /// The size of everything that was scanned, if the scan is complete
+    pub total_bytes: Option<u128>,
+    /// The filesystem the directory currently shown is on, if known
+    pub filesystem: Option<&'a Filesystem>,
+}
+
 impl Header {
-    pub fn render(&self, bg_color: Color, area: Rect, buf: &mut Buffer) {
+    pub fn render<'a>(&self, props: impl Borrow<HeaderProps<'a>>, area: Rect, buf: &mut Buffer) {
+        let HeaderProps {
+            bg_color,
+            is_paused,
+            format,
+            root_bytes,
+            total_bytes,
+            filesystem,
+        } = *props.borrow();
         let standard = Style {
             fg: Color::Black.into(),
             bg: bg_color.into(),
@@ -29,7 +51,7 @@ impl Header {
         let italic = |text: &'static str| modified(text, Modifier::UNDERLINED);
         let text = |text: &'static str| Span::styled(text, standard);
 
-        let spans = vec![
+        let mut spans = vec![
             bold(" D"),
             text("isk "),
             bold("U"),
@@ -41,7 +63,49 @@ impl Header {
             italic("(press "),
             modified("?", Modifier::BOLD | Modifier::UNDERLINED),
             italic(" for help)"),
+            text("    "),
+            Span::styled(
+                format!(
+                    "Here: {}",
+                    format.display(root_bytes).to_string().trim_start()
+                ),
+                Style {
+                    add_modifier: Modifier::BOLD,
+                    ..standard
+                },
+            ),
         ];
+        if let Some(total_bytes) = total_bytes.filter(|total| *total != root_bytes) {
+            spans.push(Span::styled(
+                format!(
+                    " ({:.1}% of {})",
+                    match total_bytes {
+                        0 => 0.0,
+                        total => root_bytes as f64 / total as f64 * 100.0,
+                    },
+                    format.display(total_bytes).to_string().trim_start()
+                ),
+                standard,
+            ));
+        }
+        if let Some(Filesystem { device, fs_type }) = filesystem {
+            spans.push(Span::styled(
+                format!("  on {} ({})", device, fs_type),
+                standard,
+            ));
+        }
+        if is_paused {
+            spans.push(text("    "));
+            spans.push(Span::styled(
+                " SCAN PAUSED ",
+                Style {
+                    fg: Color::White.into(),
+                    bg: Color::Red.into(),
+                    add_modifier: Modifier::BOLD,
+                    ..Default::default()
+                },
+            ));
+        }
         Paragraph::new(Text::from(Spans::from(spans)))
             .style(Style {
                 bg: bg_color.into(),
@@ -50,3 +114,60 @@ impl Header {
             .render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(root_bytes: u128, total_bytes: Option<u128>) -> String {
+        rendered_on(root_bytes, total_bytes, None)
+    }
+
+    fn rendered_on(
+        root_bytes: u128,
+        total_bytes: Option<u128>,
+        filesystem: Option<&Filesystem>,
+    ) -> String {
+        let area = Rect::new(0, 0, 120, 1);
+        let mut buf = Buffer::empty(area);
+        Header.render(
+            HeaderProps {
+                bg_color: Color::White,
+                is_paused: false,
+                format: ByteFormat::Metric,
+                root_bytes,
+                total_bytes,
+                filesystem,
+            },
+            area,
+            &mut buf,
+        );
+        buf.content.iter().map(|c| c.symbol.as_str()).collect()
+    }
+
+    #[test]
+    fn it_shows_the_size_of_the_current_root_relative_to_the_total() {
+        assert!(rendered(500, Some(2000)).contains("Here: 500  B (25.0% of 2.00 KB)"));
+        assert!(
+            rendered(2000, Some(2000))
+                .trim_end()
+                .ends_with("Here: 2.00 KB"),
+            "there is no point in a percentage at the top level"
+        );
+        assert!(
+            rendered(500, None).trim_end().ends_with("Here: 500  B"),
+            "the total is unknown while scanning"
+        );
+    }
+
+    #[test]
+    fn it_shows_the_filesystem_of_the_current_root() {
+        let filesystem = Filesystem {
+            device: "/dev/sdb1".into(),
+            fs_type: "vfat".into(),
+        };
+        assert!(rendered_on(500, Some(2000), Some(&filesystem))
+            .trim_end()
+            .ends_with("(25.0% of 2.00 KB)  on /dev/sdb1 (vfat)"));
+    }
+}
@@ -104,36 +104,173 @@ impl HelpPane {
                 );
                 hotkey("<tab>", "Cycle between all open panes", None);
                 hotkey("?", "Show or hide the help pane", None);
+                hotkey(
+                    "Shift + l",
+                    "Show or hide the symlink loops, duplicate directories and symlink farms found",
+                    Some("Loops are only detected with --follow-links, farms are summarized by their targets"),
+                );
+                hotkey(
+                    "Shift + b",
+                    "Show or hide how much space each category of files takes up",
+                    Some("Configured as 'category = <name>: <glob>, …' lines"),
+                );
+                hotkey(
+                    "Shift + u",
+                    "Suggest entries that are likely safe to clean up, and why",
+                    Some("Mark them with 'x' in the pane to review and delete them"),
+                );
+                hotkey(
+                    "Shift + w",
+                    "Show the trash bin to restore entries deleted by mistake",
+                    Some("<space> chooses entries, r restores them. Not visible before the next scan"),
+                );
+                hotkey(
+                    "i",
+                    "Show or hide details of the selected entry",
+                    Some("Including its oldest and newest file once the scan is done"),
+                );
+                hotkey(
+                    "[ / ]",
+                    "Scroll the path of the selected entry in the footer",
+                    Some("Towards its beginning or back to its end, if it is too long to be shown"),
+                );
                 spacer();
             }
             title("Keys for Navigation");
             {
                 hotkey("j/<down>", "move down an entry", None);
                 hotkey("k/<up>", "move up an entry", None);
-                hotkey("o/l/<enter>", "descent into the selected directory", None);
+                hotkey(
+                    "o/l/<enter>",
+                    "descent into the selected directory",
+                    Some("Mount points skipped with --stay-on-filesystem are scanned first"),
+                );
                 hotkey("<right>", "^", None);
                 hotkey(
                     "u/h/<left>",
                     "ascent one level into the parent directory",
-                    None,
+                    Some("The directory you came from is selected and highlighted"),
                 );
                 hotkey("<backspace>", "^", None);
                 hotkey("Ctrl + d", "move down 10 entries at once", None);
                 hotkey("<Page Down>", "^", None);
                 hotkey("Ctrl + u", "move up 10 entries at once", None);
                 hotkey("<Page Up>", "^", None);
+                hotkey(
+                    "Shift + t",
+                    "toggle tunneling through single-child directories",
+                    Some("when entering or leaving directories"),
+                );
+                hotkey("Shift + j", "enter the next sibling directory", None);
+                hotkey("Shift + k", "enter the previous sibling directory", None);
                 hotkey("H", "Move to the top of the entries list", None);
                 hotkey("G", "Move to the bottom of the entries list", None);
                 spacer();
             }
             title("Keys for display");
             {
-                hotkey("s", "toggle sort by size ascending/descending", None);
+                hotkey(
+                    "s",
+                    "cycle sorting by size descending/ascending, name, modification time and entry count",
+                    Some("The time or count of each entry is shown while sorting by it"),
+                );
+                hotkey(
+                    "w",
+                    "toggle sort by recent growth",
+                    Some("the size of files modified in the week before the scan"),
+                );
                 hotkey(
                     "g",
                     "cycle through percentage display and bar options",
+                    Some("including a bar drawn with #, like ncdu"),
+                );
+                hotkey(
+                    "v",
+                    "toggle the column showing the share of the parent directory",
                     None,
                 );
+                hotkey(
+                    "Shift + v",
+                    "toggle between shares of the parent directory and of all scanned bytes",
+                    Some("to see which deep directory takes up most of the disk"),
+                );
+                hotkey(
+                    "Shift + a",
+                    "switch between apparent sizes and disk usage",
+                    Some("once the scan is done, without scanning again"),
+                );
+                hotkey(
+                    "< | >",
+                    "make bars shorter or longer, to give names more or less room",
+                    Some("remembered for the next session"),
+                );
+                hotkey(
+                    "b",
+                    "cycle through byte formats",
+                    Some("metric, binary, plain bytes and gigabytes only"),
+                );
+                hotkey(
+                    "e",
+                    "toggle showing exact sizes in the current directory",
+                    Some("with thousands separators, to compare entries that round alike"),
+                );
+                hotkey(
+                    "t",
+                    "toggle showing totals as if marked entries were deleted",
+                    Some("for the current directory and in total, in the footer"),
+                );
+                hotkey(
+                    "n",
+                    "toggle the column showing the amount of hard links",
+                    None,
+                );
+                hotkey(
+                    "c",
+                    "toggle the column showing the amount of entries in directories",
+                    Some("at any depth, to find what uses up inodes"),
+                );
+                hotkey(
+                    "m",
+                    "toggle the column showing when entries were last modified",
+                    Some("the newest time of anything within directories, in UTC"),
+                );
+                hotkey(
+                    "Shift + s",
+                    "toggle a pinned row summarizing the current directory",
+                    Some("with its size, amount of items and share of its parent"),
+                );
+                hotkey(
+                    "a",
+                    "toggle highlighting entries much larger than their siblings",
+                    Some("more than 3x the median size, marked with ▲"),
+                );
+                hotkey(
+                    "Shift + n",
+                    "toggle showing only files with multiple hard links",
+                    Some("Deleting these frees nothing while other links remain"),
+                );
+                hotkey(".", "toggle hiding entries starting with a dot", None);
+                hotkey(
+                    "Ctrl + f",
+                    "find entries by name in the whole tree",
+                    Some("Text or a glob like core.*, the largest first. o shows a result in its directory"),
+                );
+                hotkey(
+                    "/",
+                    "only show entries whose name matches while typing",
+                    Some("Like 'dlg' for 'debug.log'. <enter> keeps the filter, <esc> clears it"),
+                );
+                hotkey(
+                    "C",
+                    "toggle hiding cloud placeholders",
+                    Some("i.e. only show content that is stored locally, marked with ☁ otherwise"),
+                );
+                hotkey(
+                    "f",
+                    "select the next active filter, shown below the header",
+                    None,
+                );
+                hotkey("<delete>", "remove the selected filter", None);
                 spacer();
             }
             title("Keys for entry operations");
@@ -154,6 +291,61 @@ impl HelpPane {
                     None,
                 );
                 hotkey("<space bar>", "Toggle the currently selected entry", None);
+                hotkey(
+                    "Shift + d",
+                    "Delete the currently selected entry without marking it",
+                    Some("Only once its name was typed, which shows its path and size to be sure"),
+                );
+                hotkey(
+                    "Shift + m",
+                    "Mark all files larger than the given size in this directory",
+                    Some("Files within all of its subdirectories are included"),
+                );
+                hotkey(
+                    "*",
+                    "Mark all entries in this directory matching the given glob",
+                    Some("Like '*.log', 'target/' for directories only, or '**/*.tmp' for all depths"),
+                );
+                hotkey(
+                    "Shift + x",
+                    "Export the entries of this directory to a file",
+                    Some("As shown, in CSV, JSON or du-style text depending on the file extension"),
+                );
+                hotkey(
+                    ":",
+                    "Open the command line to run cd, mark, filter, sort, export or set",
+                    Some("<tab> completes, ↑ and ↓ recall previous commands, paths on disk can be pasted"),
+                );
+                hotkey(
+                    "+",
+                    "Mark the entry at the path typed into the prompt",
+                    Some("Paths outside of this scan are scanned and added first"),
+                );
+                hotkey(
+                    "Shift + f",
+                    "Flag the currently selected entry for follow-up, or remove its flag",
+                    Some("Flags are unrelated to deletion, see --export-flagged"),
+                );
+                hotkey(
+                    "Shift + z",
+                    "Estimate the space compression would save for the selected entry",
+                    Some("Compresses samples of its files with zstd, or gzip if unavailable"),
+                );
+                hotkey(
+                    "r",
+                    "Refresh the selected entry",
+                    Some("Scans it again to pick up changes made since"),
+                );
+                hotkey(
+                    "Shift + r",
+                    "Rescan all inputs",
+                    Some("Keeps the current directory, marks and flags if they still exist"),
+                );
+                hotkey(
+                    "Shift + e",
+                    "Re-scan unreadable directories using sudo",
+                    Some("Requires cached credentials, see --sudo"),
+                );
                 spacer();
             }
             title("Keys in the Mark pane");
@@ -163,10 +355,40 @@ impl HelpPane {
                     "remove the selected entry from the list",
                     None,
                 );
+                hotkey(
+                    "o/<enter>",
+                    "show the selected entry in its directory",
+                    Some("It stays marked, the Mark pane loses focus"),
+                );
                 hotkey(
                     "Ctrl + r",
-                    "Permanently delete all marked entries without prompt!",
-                    Some("This operation cannot be undone!"),
+                    "Permanently delete all marked entries, once confirmed by typing 'yes'",
+                    Some("This operation cannot be undone! Press <esc> to abort while deleting"),
+                );
+                hotkey(
+                    "c",
+                    "Set the size to free per deletion, then pause",
+                    Some("Entries are deleted in the order they were marked, the rest stay marked for the next batch"),
+                );
+                hotkey(
+                    "Ctrl + a",
+                    "Archive all marked entries into a file, then delete them",
+                    Some("Uses tar and .tar.zst compresses. Nothing is deleted unless the archive checks out"),
+                );
+                hotkey(
+                    "Ctrl + v",
+                    "Move all marked entries into a directory, keeping their location relative to each other",
+                    Some("Proposes the directory set with --move-to. Across filesystems, entries are copied first"),
+                );
+                hotkey(
+                    "Ctrl + e",
+                    "Write the BLAKE3 checksum and size of all marked files to a file",
+                    Some("To verify later that a backup contains everything that was deleted"),
+                );
+                hotkey(
+                    "n",
+                    "Check which marked entries could be deleted, without deleting anything",
+                    Some("Entries that would fail show why, like a directory that isn't writable"),
                 );
                 #[cfg(feature = "trash-move")]
                 hotkey(
@@ -178,6 +400,11 @@ impl HelpPane {
             }
             title("Keys for application control");
             {
+                hotkey(
+                    "p",
+                    "pause or resume the scan while it is running",
+                    Some("Paused scans don't read from disk"),
+                );
                 hotkey(
                     "Ctrl + c",
                     "close the application. No questions asked!",
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+/// Return the Nerd Font glyph to show in front of the entry with the given `name`.
+pub fn icon_for(name: &Path, is_dir: bool) -> char {
+    if is_dir {
+        return match name.to_str() {
+            Some(".git") => '\u{e5fb}',
+            _ => '\u{f07b}',
+        };
+    }
+    let extension = name
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("rs") => '\u{e7a8}',
+        Some("md" | "markdown") => '\u{e609}',
+        Some("json") => '\u{e60b}',
+        Some("toml" | "yaml" | "yml" | "ini" | "conf" | "cfg") => '\u{e615}',
+        Some("lock") => '\u{f023}',
+        Some("sh" | "bash" | "zsh" | "fish") => '\u{f489}',
+        Some("py") => '\u{e606}',
+        Some("js" | "mjs" | "cjs") => '\u{e74e}',
+        Some("ts" | "tsx") => '\u{e628}',
+        Some("go") => '\u{e626}',
+        Some("c" | "h") => '\u{e61e}',
+        Some("cpp" | "cc" | "cxx" | "hpp") => '\u{e61d}',
+        Some("java" | "jar") => '\u{e738}',
+        Some("html" | "htm") => '\u{e736}',
+        Some("css" | "scss") => '\u{e749}',
+        Some("png" | "jpg" | "jpeg" | "gif" | "bmp" | "svg" | "webp" | "ico") => '\u{f1c5}',
+        Some("mp3" | "flac" | "wav" | "ogg" | "m4a") => '\u{f1c7}',
+        Some("mp4" | "mkv" | "avi" | "mov" | "webm") => '\u{f1c8}',
+        Some("pdf") => '\u{f1c1}',
+        Some("zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar") => '\u{f410}',
+        Some("iso" | "img" | "dmg") => '\u{f0a0}',
+        _ => '\u{f15b}',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_picks_icons_by_directory_status_and_extension() {
+        assert_eq!(icon_for(Path::new("src"), true), '\u{f07b}');
+        assert_eq!(icon_for(Path::new("main.rs"), true), '\u{f07b}');
+        assert_eq!(icon_for(Path::new("main.rs"), false), '\u{e7a8}');
+        assert_eq!(icon_for(Path::new("PHOTO.JPG"), false), '\u{f1c5}');
+        assert_eq!(icon_for(Path::new("Makefile"), false), '\u{f15b}');
+    }
+}
@@ -1,7 +1,11 @@
 use crate::interactive::{
+    absolute_path_of, path_of,
     widgets::{
-        Entries, EntriesProps, Footer, FooterProps, Header, HelpPane, HelpPaneProps, MarkPane,
-        MarkPaneProps, COLOR_MARKED,
+        CategoriesPane, CategoriesPaneProps, DetailsPane, DetailsPaneProps, DiagnosticsPane,
+        DiagnosticsPaneProps, Entries, EntriesProps, FilterChips, FilterChipsProps, FindPane,
+        FindPaneProps, Footer, FooterProps, Header, HeaderProps, HelpPane, HelpPaneProps, MarkPane,
+        MarkPaneProps, SuggestionsPane, SuggestionsPaneProps, TrashPane, TrashPaneProps,
+        COLOR_MARKED,
     },
     AppState, DisplayOptions, FocussedPane,
 };
@@ -25,11 +29,29 @@ pub struct MainWindowProps<'a> {
 #[derive(Default)]
 pub struct MainWindow {
     pub help_pane: Option<HelpPane>,
+    pub diagnostics_pane: Option<DiagnosticsPane>,
+    pub categories_pane: Option<CategoriesPane>,
+    pub details_pane: Option<DetailsPane>,
+    pub trash_pane: Option<TrashPane>,
+    pub find_pane: Option<FindPane>,
+    pub suggestions_pane: Option<SuggestionsPane>,
     pub entries_pane: Entries,
     pub mark_pane: Option<MarkPane>,
 }
 
 impl MainWindow {
+    /// Close whichever pane is shown next to the entries, as only one of them is shown at a time.
+    /// The mark pane stays, as it's shown below them.
+    pub fn close_side_panes(&mut self) {
+        self.help_pane = None;
+        self.diagnostics_pane = None;
+        self.categories_pane = None;
+        self.details_pane = None;
+        self.trash_pane = None;
+        self.find_pane = None;
+        self.suggestions_pane = None;
+    }
+
     pub fn render<'a>(
         &mut self,
         props: impl Borrow<MainWindowProps<'a>>,
@@ -37,16 +59,19 @@ impl MainWindow {
         buf: &mut Buffer,
     ) {
         let MainWindowProps {
-            traversal:
-                Traversal {
-                    tree,
-                    entries_traversed,
-                    total_bytes,
-                    ..
-                },
+            traversal,
             display,
             state,
         } = props.borrow();
+        let Traversal {
+            tree,
+            entries_traversed,
+            total_bytes,
+            permission_denied,
+            symlink_loops,
+            duplicate_directories,
+            ..
+        } = traversal;
 
         let (entries_style, help_style, mark_style) = {
             let grey = Style {
@@ -62,26 +87,68 @@ impl MainWindow {
             };
             match state.focussed {
                 Main => (bold, grey, grey),
-                Help => (grey, bold, grey),
+                Help | Trash | Find | Suggestions => (grey, bold, grey),
                 Mark => (grey, grey, bold),
             }
         };
 
-        let (header_area, entries_area, footer_area) = {
+        let selected = state.selected.and_then(|index| {
+            tree.node_weight(index).map(|entry| {
+                (
+                    absolute_path_of(tree, index).display().to_string(),
+                    entry.size,
+                )
+            })
+        });
+        let (header_area, filters_area, entries_area, footer_area) = {
+            let filters_height = if state.filters.is_empty() { 0 } else { 1 };
+            let footer_height = if selected.is_some() { 2 } else { 1 };
             let regions = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Length(1), Max(256), Length(1)].as_ref())
+                .constraints(
+                    [
+                        Length(1),
+                        Length(filters_height),
+                        Max(256),
+                        Length(footer_height),
+                    ]
+                    .as_ref(),
+                )
                 .split(area);
-            (regions[0], regions[1], regions[2])
+            (regions[0], regions[1], regions[2], regions[3])
         };
         {
             let marked = self.mark_pane.as_ref().map(|p| p.marked());
-            let bg_color = match (marked.map_or(true, |m| m.is_empty()), state.focussed) {
+            let bg_color = match (marked.is_none_or(|m| m.is_empty()), state.focussed) {
                 (false, FocussedPane::Mark) => Color::LightRed,
                 (false, _) => COLOR_MARKED,
                 (_, _) => Color::White,
             };
-            Header.render(bg_color, header_area, buf);
+            Header.render(
+                HeaderProps {
+                    bg_color,
+                    is_paused: state.is_paused,
+                    format: display.byte_format,
+                    root_bytes: tree.node_weight(state.root).map_or(0, |e| e.size),
+                    total_bytes: *total_bytes,
+                    filesystem: state
+                        .root_filesystem
+                        .as_ref()
+                        .and_then(|(_, filesystem)| filesystem.as_ref()),
+                },
+                header_area,
+                buf,
+            );
+        }
+        if !state.filters.is_empty() {
+            FilterChips.render(
+                FilterChipsProps {
+                    filters: &state.filters,
+                    selected: state.selected_filter,
+                },
+                filters_area,
+                buf,
+            );
         }
         let (entries_area, help_pane, mark_pane) = {
             let regions = Layout::default()
@@ -89,21 +156,24 @@ impl MainWindow {
                 .constraints([Percentage(50), Percentage(50)].as_ref())
                 .split(entries_area);
             let (left_pane, right_pane) = (regions[0], regions[1]);
-            match (&mut self.help_pane, &mut self.mark_pane) {
-                (Some(ref mut pane), None) => (left_pane, Some((right_pane, pane)), None),
-                (None, Some(ref mut pane)) => (left_pane, None, Some((right_pane, pane))),
-                (Some(ref mut help), Some(ref mut mark)) => {
+            let has_info_pane = self.help_pane.is_some()
+                || self.diagnostics_pane.is_some()
+                || self.categories_pane.is_some()
+                || self.details_pane.is_some()
+                || self.trash_pane.is_some()
+                || self.find_pane.is_some()
+                || self.suggestions_pane.is_some();
+            match (has_info_pane, &mut self.mark_pane) {
+                (true, None) => (left_pane, Some(right_pane), None),
+                (false, Some(ref mut pane)) => (left_pane, None, Some((right_pane, pane))),
+                (true, Some(ref mut mark)) => {
                     let regions = Layout::default()
                         .direction(Direction::Vertical)
                         .constraints([Percentage(50), Percentage(50)].as_ref())
                         .split(right_pane);
-                    (
-                        left_pane,
-                        Some((regions[0], help)),
-                        Some((regions[1], mark)),
-                    )
+                    (left_pane, Some(regions[0]), Some((regions[1], mark)))
                 }
-                (None, None) => (entries_area, None, None),
+                (false, None) => (entries_area, None, None),
             }
         };
 
@@ -111,37 +181,119 @@ impl MainWindow {
             let props = MarkPaneProps {
                 border_style: mark_style,
                 format: display.byte_format,
+                deletion_cap: state.deletion_cap,
             };
             pane.render(props, mark_area, buf);
         }
 
-        if let Some((help_area, pane)) = help_pane {
-            let props = HelpPaneProps {
-                border_style: help_style,
-                has_focus: matches!(state.focussed, Help),
-            };
-            pane.render(props, help_area, buf);
+        if let Some(help_area) = help_pane {
+            if let Some(pane) = self.help_pane.as_mut() {
+                let props = HelpPaneProps {
+                    border_style: help_style,
+                    has_focus: matches!(state.focussed, Help),
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.diagnostics_pane.as_mut() {
+                let props = DiagnosticsPaneProps {
+                    border_style: help_style,
+                    symlink_loops,
+                    duplicate_directories,
+                    format: display.byte_format,
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.categories_pane.as_mut() {
+                let props = CategoriesPaneProps {
+                    border_style: help_style,
+                    format: display.byte_format,
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.details_pane.as_mut() {
+                let selected = state
+                    .selected
+                    .and_then(|idx| state.entries.iter().find(|e| e.index == idx));
+                let (index, is_dir) = selected.map_or((state.root, true), |e| (e.index, e.is_dir));
+                let path = path_of(tree, index);
+                let entry = tree.node_weight(index);
+                let props = DetailsPaneProps {
+                    border_style: help_style,
+                    format: display.byte_format,
+                    size_model: display.size_model,
+                    path: &path,
+                    size: entry.map_or(0, |e| e.size),
+                    is_dir,
+                    mtime_range: entry.and_then(|e| e.mtime_range),
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.trash_pane.as_mut() {
+                let props = TrashPaneProps {
+                    border_style: help_style,
+                    has_focus: matches!(state.focussed, Trash),
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.find_pane.as_mut() {
+                let props = FindPaneProps {
+                    border_style: help_style,
+                    has_focus: matches!(state.focussed, Find),
+                    format: display.byte_format,
+                };
+                pane.render(props, help_area, buf);
+            } else if let Some(pane) = self.suggestions_pane.as_mut() {
+                let props = SuggestionsPaneProps {
+                    border_style: help_style,
+                    has_focus: matches!(state.focussed, Suggestions),
+                    format: display.byte_format,
+                    marked: self.mark_pane.as_ref().map(|p| p.marked()),
+                };
+                pane.render(props, help_area, buf);
+            }
         }
 
         let marked = self.mark_pane.as_ref().map(|p| p.marked());
         let props = EntriesProps {
-            tree: &tree,
+            tree,
             root: state.root,
             display: *display,
             entries: &state.entries,
+            sorting: state.sorting,
             marked,
+            flagged: &state.flagged,
+            highlighted: state.came_from,
+            baseline: state.baseline.as_ref(),
+            exact_sizes: state.exact_sizes_of == Some(state.root),
+            in_progress: traversal.in_progress.filter(|_| state.is_scanning),
             selected: state.selected,
             border_style: entries_style,
             is_focussed: matches!(state.focussed, Main),
         };
         self.entries_pane.render(props, entries_area, buf);
 
+        let size_without_marked = match (display.show_size_without_marked, &self.mark_pane) {
+            (true, Some(pane)) => {
+                let size_of = |index| tree.node_weight(index).map_or(0, |e| e.size);
+                let (root, top) = (state.root, traversal.root_index);
+                let current = size_of(root).saturating_sub(pane.marked_bytes_within(tree, root));
+                let total = total_bytes
+                    .unwrap_or_else(|| size_of(top))
+                    .saturating_sub(pane.marked_bytes_within(tree, top));
+                Some((current, total))
+            }
+            _ => None,
+        };
         Footer.render(
             FooterProps {
                 total_bytes: *total_bytes,
+                size_without_marked,
                 format: display.byte_format,
                 entries_traversed: *entries_traversed,
+                unreadable_directories: permission_denied.len(),
+                symlink_loops: symlink_loops.len(),
+                duplicate_directories: duplicate_directories.len(),
                 message: state.message.clone(),
+                selected,
+                path_scroll: state
+                    .path_scroll
+                    .filter(|(index, _)| Some(*index) == state.selected)
+                    .map_or(0, |(_, scroll)| scroll),
             },
             footer_area,
             buf,
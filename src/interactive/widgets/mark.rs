@@ -4,13 +4,14 @@ use crate::interactive::{
 use crosstermion::{input::Key, input::Key::*};
 use dua::{
     traverse::{Tree, TreeIndex},
-    ByteFormat,
+    ByteFormat, DeviceSpace, SharedExtents,
 };
 use itertools::Itertools;
 use std::{
     borrow::Borrow,
     collections::{btree_map::Entry, BTreeMap},
     path::PathBuf,
+    sync::mpsc::{self, Receiver},
 };
 use tui::{
     buffer::Buffer,
@@ -30,6 +31,18 @@ pub enum MarkMode {
     Delete,
     #[cfg(feature = "trash-move")]
     Trash,
+    /// Write all marked entries into an archive and delete them once it was verified
+    Archive,
+    /// Ask for the directory to move all marked entries into
+    Move,
+    /// Ask for the file to write the checksums of all marked files to
+    Checksums,
+    /// Check which marked entries could be deleted, without deleting anything
+    DryRun,
+    /// Show the given marked entry within its parent directory in the entries pane
+    Reveal(TreeIndex),
+    /// Ask for the amount of bytes to free at most before a deletion pauses
+    SetDeletionCap,
 }
 
 pub type EntryMarkMap = BTreeMap<TreeIndex, EntryMark>;
@@ -38,6 +51,8 @@ pub struct EntryMark {
     pub path: PathBuf,
     pub index: usize,
     pub num_errors_during_deletion: usize,
+    /// Why deleting this entry would fail, as found by the last dry run
+    pub dry_run_failure: Option<String>,
     pub is_dir: bool,
 }
 
@@ -48,11 +63,28 @@ pub struct MarkPane {
     list: List,
     has_focus: bool,
     last_sorting_index: usize,
+    /// The space of the device the first marked entry is on, to show the impact of a deletion
+    device_space: Option<DeviceSpace>,
+    /// Data of the marked files that other files still refer to, determined when the pane is focussed for deletion
+    shared_extents: SharedExtentsProbe,
+}
+
+/// The state of determining the [`SharedExtents`] of the marked entries, which happens on another thread as it
+/// takes a while with many marked files.
+#[derive(Default)]
+enum SharedExtentsProbe {
+    /// The marks changed since they were last determined
+    #[default]
+    Outdated,
+    Running(Receiver<Option<SharedExtents>>),
+    Done(Option<SharedExtents>),
 }
 
 pub struct MarkPaneProps {
     pub border_style: Style,
     pub format: ByteFormat,
+    /// The amount of bytes after which a deletion pauses, if set
+    pub deletion_cap: Option<u128>,
 }
 
 impl MarkPane {
@@ -64,10 +96,20 @@ impl MarkPane {
         self.has_focus = has_focus;
         if has_focus {
             self.selected = Some(self.marked.len().saturating_sub(1));
+            self.probe_shared_extents();
         } else {
             self.selected = None
         }
     }
+    /// Start determining the shared extents of the marked entries in the background, unless they are known already.
+    fn probe_shared_extents(&mut self) {
+        if let SharedExtentsProbe::Outdated = self.shared_extents {
+            let paths: Vec<_> = self.marked.values().map(|m| m.path.clone()).collect();
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || tx.send(SharedExtents::of(&paths)).ok());
+            self.shared_extents = SharedExtentsProbe::Running(rx);
+        }
+    }
     pub fn toggle_index(
         mut self,
         index: TreeIndex,
@@ -75,16 +117,22 @@ impl MarkPane {
         is_dir: bool,
         toggle: bool,
     ) -> Option<Self> {
+        self.shared_extents = SharedExtentsProbe::Outdated;
         match self.marked.entry(index) {
             Entry::Vacant(entry) => {
                 if let Some(e) = tree.node_weight(index) {
                     let sorting_index = self.last_sorting_index + 1;
                     self.last_sorting_index = sorting_index;
+                    let path = path_of(tree, index);
+                    if sorting_index == 1 {
+                        self.device_space = DeviceSpace::of(&path);
+                    }
                     entry.insert(EntryMark {
                         size: e.size,
-                        path: path_of(tree, index),
+                        path,
                         index: sorting_index,
                         num_errors_during_deletion: 0,
+                        dry_run_failure: None,
                         is_dir,
                     });
                 }
@@ -101,11 +149,55 @@ impl MarkPane {
             Some(self)
         }
     }
+    /// Forget the marks of all entries at `indices`, which are no longer part of the tree.
+    pub fn unmark_removed(mut self, indices: &[TreeIndex]) -> Option<Self> {
+        self.shared_extents = SharedExtentsProbe::Outdated;
+        for index in indices {
+            self.marked.remove(index);
+        }
+        if self.marked.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
     pub fn marked(&self) -> &EntryMarkMap {
         &self.marked
     }
+    /// The amount of bytes deleting all marked entries would free within `root`, not counting marked entries
+    /// within other marked directories twice. If `root` itself would be deleted, that is all of it.
+    pub fn marked_bytes_within(&self, tree: &Tree, root: TreeIndex) -> u128 {
+        let mut ancestor = Some(root);
+        while let Some(index) = ancestor {
+            if self.marked.contains_key(&index) {
+                return tree.node_weight(root).map_or(0, |e| e.size);
+            }
+            ancestor = tree.neighbors_directed(index, petgraph::Incoming).next();
+        }
+        self.marked
+            .iter()
+            .filter(|(index, _)| {
+                let mut is_within_root = false;
+                let mut index = **index;
+                while let Some(parent) = tree.neighbors_directed(index, petgraph::Incoming).next() {
+                    if self.marked.contains_key(&parent) {
+                        return false;
+                    }
+                    is_within_root |= parent == root;
+                    index = parent;
+                }
+                is_within_root
+            })
+            .map(|(_, mark)| mark.size)
+            .sum()
+    }
+    pub fn set_dry_run_failure(&mut self, index: TreeIndex, failure: Option<String>) {
+        if let Some(mark) = self.marked.get_mut(&index) {
+            mark.dry_run_failure = failure;
+        }
+    }
     pub fn into_paths(self) -> impl Iterator<Item = PathBuf> {
-        self.marked.into_iter().map(|(_k, v)| v.path)
+        self.marked.into_values().map(|v| v.path)
     }
     pub fn process_events(mut self, key: Key) -> Option<(Self, Option<MarkMode>)> {
         let action = None;
@@ -113,8 +205,24 @@ impl MarkPane {
             Ctrl('r') => return Some(self.prepare_deletion(MarkMode::Delete)),
             #[cfg(feature = "trash-move")]
             Ctrl('t') => return Some(self.prepare_deletion(MarkMode::Trash)),
+            Ctrl('a') => return Some(self.prepare_deletion(MarkMode::Archive)),
+            Ctrl('v') => return Some(self.prepare_deletion(MarkMode::Move)),
+            Ctrl('e') => return Some((self, Some(MarkMode::Checksums))),
+            Char('n') => return Some((self, Some(MarkMode::DryRun))),
+            Char('c') => return Some((self, Some(MarkMode::SetDeletionCap))),
             Char('x') | Char('d') | Char(' ') => {
-                return self.remove_selected().map(|s| (s, action))
+                return self.remove_selected().map(|mut s| {
+                    s.probe_shared_extents();
+                    (s, action)
+                })
+            }
+            Char('o') | Char('\n') => {
+                if let Some(idx) = self
+                    .selected
+                    .and_then(|s| self.tree_index_by_list_position(s))
+                {
+                    return Some((self, Some(MarkMode::Reveal(idx))));
+                }
             }
             Char('H') => self.change_selection(CursorDirection::ToTop),
             Char('G') => self.change_selection(CursorDirection::ToBottom),
@@ -127,26 +235,41 @@ impl MarkPane {
         Some((self, action))
     }
 
+    /// Call `delete_fn` with each marked entry in the order they were marked, and unmark those it deleted.
+    /// If `cap` is set, stop once the deleted entries amount to at least that many bytes, and stop before the next
+    /// entry once `is_aborted` returns true.
+    ///
+    /// Returns the pane if entries remain marked, along with the amount of bytes that were deleted.
     pub fn iterate_deletable_items(
         mut self,
+        cap: Option<u128>,
+        is_aborted: impl Fn() -> bool,
         mut delete_fn: impl FnMut(Self, TreeIndex) -> Result<Self, (Self, usize)>,
-    ) -> Option<Self> {
+    ) -> (Option<Self>, u128) {
+        let mut bytes_deleted = 0;
         loop {
+            if cap.is_some_and(|cap| bytes_deleted >= cap) || is_aborted() {
+                return (Some(self), bytes_deleted);
+            }
             match self.next_entry_for_deletion() {
-                Some(entry_to_delete) => match delete_fn(self, entry_to_delete) {
-                    Ok(pane) => {
-                        self = pane;
-                        match self.delete_entry() {
-                            Some(p) => self = p,
-                            None => return None,
+                Some(entry_to_delete) => {
+                    let size = self.marked.get(&entry_to_delete).map_or(0, |m| m.size);
+                    match delete_fn(self, entry_to_delete) {
+                        Ok(pane) => {
+                            self = pane;
+                            bytes_deleted += size;
+                            match self.delete_entry() {
+                                Some(p) => self = p,
+                                None => return (None, bytes_deleted),
+                            }
+                        }
+                        Err((pane, num_errors)) => {
+                            self = pane;
+                            self.set_error_on_marked_item(num_errors)
                         }
                     }
-                    Err((pane, num_errors)) => {
-                        self = pane;
-                        self.set_error_on_marked_item(num_errors)
-                    }
-                },
-                None => return Some(self),
+                }
+                None => return (Some(self), bytes_deleted),
             }
         }
     }
@@ -184,6 +307,7 @@ impl MarkPane {
     fn prepare_deletion(mut self, mark: MarkMode) -> (Self, Option<MarkMode>) {
         for entry in self.marked.values_mut() {
             entry.num_errors_during_deletion = 0;
+            entry.dry_run_failure = None;
         }
         self.selected = Some(0);
         (self, Some(mark))
@@ -194,6 +318,7 @@ impl MarkPane {
             let se_len = self.marked.len();
             if let Some(idx) = idx {
                 self.marked.remove(&idx);
+                self.shared_extents = SharedExtentsProbe::Outdated;
                 let new_len = se_len.saturating_sub(1);
                 if new_len == 0 {
                     return None;
@@ -234,13 +359,45 @@ impl MarkPane {
         let MarkPaneProps {
             border_style,
             format,
+            deletion_cap,
         } = props.borrow();
 
+        if let SharedExtentsProbe::Running(rx) = &self.shared_extents {
+            if let Ok(shared_extents) = rx.try_recv() {
+                self.shared_extents = SharedExtentsProbe::Done(shared_extents);
+            }
+        }
         let marked: &_ = &self.marked;
+        let marked_bytes = marked.values().map(|v| v.size).sum::<u128>();
         let title = format!(
-            "Marked {} items ({}) ",
+            "Marked {} items ({}{}{}{}) ",
             marked.len(),
-            format.display(marked.iter().map(|(_k, v)| v.size).sum::<u128>())
+            format.display(marked_bytes),
+            match self.device_space {
+                Some(DeviceSpace { used, available }) if used > 0 => {
+                    let reclaimed = marked_bytes.min(used as u128);
+                    format!(
+                        ", {:.1}% of used space, {} free after deletion",
+                        reclaimed as f64 * 100.0 / used as f64,
+                        format.display(available as u128 + reclaimed)
+                    )
+                }
+                _ => String::new(),
+            },
+            match self.shared_extents {
+                SharedExtentsProbe::Done(Some(SharedExtents { files, bytes })) if files > 0 =>
+                    format!(
+                        ", {} in {} file{} shared with unmarked files won't be freed",
+                        format.display(bytes as u128),
+                        files,
+                        if files == 1 { "" } else { "s" }
+                    ),
+                _ => String::new(),
+            },
+            match deletion_cap {
+                Some(cap) => format!(", deleting {} at a time", format.display(*cap)),
+                None => String::new(),
+            }
         );
         let selected = self.selected;
         let has_focus = self.has_focus;
@@ -265,6 +422,8 @@ impl MarkPane {
                         v.path.display(),
                         if v.num_errors_during_deletion != 0 {
                             format!("{} IO deletion errors", v.num_errors_during_deletion)
+                        } else if let Some(failure) = &v.dry_run_failure {
+                            format!("would fail: {}", failure)
                         } else {
                             "".to_string()
                         }
@@ -381,7 +540,7 @@ impl MarkPane {
                         ..default_style
                     },
                 ),
-                Span::styled(" to delete without prompt", default_style),
+                Span::styled(" to delete permanently", default_style),
             ])))
             .style(default_style)
             .render(help_line_area, buf);
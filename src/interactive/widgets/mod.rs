@@ -1,21 +1,43 @@
+mod categories;
+mod details;
+mod diagnostics;
 mod entries;
+mod filters;
+mod find;
 mod footer;
 mod header;
 mod help;
+mod icons;
 mod main;
 mod mark;
+mod palette;
+mod suggestions;
+mod trash;
 
+pub use categories::*;
+pub use details::*;
+pub use diagnostics::*;
 pub use entries::*;
+pub use filters::*;
+pub use find::*;
 pub use footer::*;
 pub use header::*;
 pub use help::*;
+pub use icons::*;
 pub use main::*;
 pub use mark::*;
+pub use palette::*;
+pub use suggestions::*;
+pub use trash::*;
 
 use tui::style::Color;
 
 pub const COLOR_MARKED: Color = Color::Yellow;
 pub const COLOR_MARKED_DARK: Color = Color::Rgb(176, 126, 0);
+/// The background of every other row in the entries pane, on terminals with true color support
+pub const COLOR_ROW_SHADING: Color = Color::Rgb(38, 38, 38);
+/// The background of every other row in the entries pane, on terminals with 256 colors
+pub const COLOR_ROW_SHADING_256: Color = Color::Indexed(235);
 
 fn entry_color(fg: Option<Color>, is_file: bool, is_marked: bool) -> Option<Color> {
     match (is_file, is_marked) {
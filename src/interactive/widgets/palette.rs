@@ -0,0 +1,286 @@
+use tui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+
+/// How many colors the terminal we are running in can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Indexed256,
+    Ansi16,
+    /// Only the eight basic colors, without their bright variants, like the Linux console
+    Ansi8,
+    /// No colors at all, so only modifiers like reversed or bold text can tell things apart
+    Monochrome,
+}
+
+/// The 16 ANSI colors along with their usual appearance
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The levels of each component in the color cube of 256-color terminals
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The color depth of the terminal we are running in, judging by its environment variables.
+pub fn color_depth_from_env() -> ColorDepth {
+    color_depth(
+        std::env::var("TERM").ok().as_deref(),
+        std::env::var("COLORTERM").ok().as_deref(),
+        std::env::var_os("NO_COLOR").is_some(),
+    )
+}
+
+pub(crate) fn color_depth(
+    term: Option<&str>,
+    colorterm: Option<&str>,
+    no_color: bool,
+) -> ColorDepth {
+    let term = match term {
+        _ if no_color => return ColorDepth::Monochrome,
+        None | Some("dumb") => return ColorDepth::Monochrome,
+        Some(term) => term,
+    };
+    match colorterm {
+        Some("truecolor" | "24bit") => ColorDepth::TrueColor,
+        _ if term.contains("256color") => ColorDepth::Indexed256,
+        _ if term.contains("16color") => ColorDepth::Ansi16,
+        // hardware terminals and their emulations, as commonly used over serial links
+        _ if term.starts_with("vt") => ColorDepth::Monochrome,
+        _ if matches!(term, "linux" | "ansi" | "cons25" | "sun") => ColorDepth::Ansi8,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+impl ColorDepth {
+    /// Replace all colors in `buf` by the closest ones this depth can show.
+    ///
+    /// Without colors, text that was red or yellow, like marked entries and warnings, is made bold instead,
+    /// and text on a colored background, like the warning bar of the mark pane, is reversed.
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == ColorDepth::TrueColor {
+            return;
+        }
+        for cell in buf.content.iter_mut() {
+            if self == ColorDepth::Monochrome {
+                if is_warning(cell.fg) {
+                    cell.modifier.insert(Modifier::BOLD);
+                }
+                if !matches!(cell.bg, Color::Reset | Color::Black) {
+                    cell.modifier.insert(Modifier::REVERSED);
+                }
+            }
+            cell.fg = self.foreground(cell.fg);
+            cell.bg = self.background(cell.bg);
+        }
+    }
+
+    /// The color closest to `color` when used for text.
+    pub fn foreground(self, color: Color) -> Color {
+        match (self, self.reduce(color)) {
+            // dark gray text is still supposed to be readable, which black text on most terminals isn't
+            (ColorDepth::Ansi8, Color::Black) if color != Color::Black => Color::Reset,
+            (_, reduced) => reduced,
+        }
+    }
+
+    /// The color closest to `color` when used as background.
+    pub fn background(self, color: Color) -> Color {
+        self.reduce(color)
+    }
+
+    fn reduce(self, color: Color) -> Color {
+        match (self, color) {
+            (_, Color::Reset) | (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Monochrome, _) => Color::Reset,
+            (ColorDepth::Indexed256, Color::Rgb(r, g, b)) => {
+                Color::Indexed(nearest_indexed(r, g, b))
+            }
+            (ColorDepth::Indexed256, _) => color,
+            (ColorDepth::Ansi16, _) => ansi16(color),
+            (ColorDepth::Ansi8, _) => match ansi16(color) {
+                Color::DarkGray => Color::Black,
+                Color::LightRed => Color::Red,
+                Color::LightGreen => Color::Green,
+                Color::LightYellow => Color::Yellow,
+                Color::LightBlue => Color::Blue,
+                Color::LightMagenta => Color::Magenta,
+                Color::LightCyan => Color::Cyan,
+                Color::White => Color::Gray,
+                basic => basic,
+            },
+        }
+    }
+}
+
+fn is_warning(color: Color) -> bool {
+    matches!(
+        ansi16(color),
+        Color::Red | Color::LightRed | Color::Yellow | Color::LightYellow
+    )
+}
+
+/// The ANSI color closest to `color`.
+fn ansi16(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index) => match ANSI_COLORS.get(index as usize) {
+            Some((named, _)) => return *named,
+            None => indexed_to_rgb(index),
+        },
+        named => return named,
+    };
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map(|(named, _)| *named)
+        .expect("non-empty palette")
+}
+
+/// The index of the color closest to the given one among the color cube and the gray ramp of 256-color terminals.
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|i| (CUBE_LEVELS[*i] as i32 - v as i32).abs())
+            .expect("non-empty levels") as u8
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    match distance((r, g, b), indexed_to_rgb(cube)) <= distance((r, g, b), indexed_to_rgb(gray)) {
+        true => cube,
+        false => gray,
+    }
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (index - 232);
+            (v, v, v)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interactive::widgets::{
+        COLOR_MARKED_DARK, COLOR_ROW_SHADING, COLOR_ROW_SHADING_256,
+    };
+    use tui::{layout::Rect, style::Style};
+
+    #[test]
+    fn color_depth_is_detected_from_the_environment() {
+        use ColorDepth::*;
+        assert_eq!(
+            color_depth(Some("xterm-256color"), Some("truecolor"), false),
+            TrueColor
+        );
+        assert_eq!(color_depth(Some("tmux-256color"), None, false), Indexed256);
+        assert_eq!(color_depth(Some("xterm"), None, false), Ansi16);
+        assert_eq!(color_depth(Some("rxvt-16color"), None, false), Ansi16);
+        assert_eq!(color_depth(Some("linux"), None, false), Ansi8);
+        assert_eq!(color_depth(Some("vt220"), None, false), Monochrome);
+        assert_eq!(
+            color_depth(Some("dumb"), Some("truecolor"), false),
+            Monochrome
+        );
+        assert_eq!(color_depth(None, None, false), Monochrome);
+        assert_eq!(color_depth(Some("xterm-256color"), None, true), Monochrome);
+    }
+
+    #[test]
+    fn colors_are_mapped_to_the_closest_available_one() {
+        assert_eq!(
+            ColorDepth::Indexed256.background(COLOR_ROW_SHADING),
+            COLOR_ROW_SHADING_256,
+            "the gray ramp is used for grays"
+        );
+        assert_eq!(
+            ColorDepth::Indexed256.foreground(Color::Rgb(255, 0, 0)),
+            Color::Indexed(196)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.foreground(COLOR_MARKED_DARK),
+            Color::Yellow
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.foreground(Color::Indexed(9)),
+            Color::LightRed
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.foreground(Color::Indexed(235)),
+            Color::Black
+        );
+        assert_eq!(ColorDepth::Ansi8.foreground(Color::LightRed), Color::Red);
+        assert_eq!(
+            ColorDepth::Ansi8.foreground(Color::DarkGray),
+            Color::Reset,
+            "dim text stays readable"
+        );
+        assert_eq!(ColorDepth::Ansi8.background(Color::DarkGray), Color::Black);
+        assert_eq!(
+            ColorDepth::Monochrome.foreground(Color::Green),
+            Color::Reset
+        );
+        assert_eq!(
+            ColorDepth::TrueColor.foreground(COLOR_MARKED_DARK),
+            COLOR_MARKED_DARK
+        );
+    }
+
+    #[test]
+    fn warnings_stay_distinguishable_without_colors() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buf.set_string(
+            0,
+            0,
+            "w",
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        );
+        buf.set_string(1, 0, "m", Style::default().fg(COLOR_MARKED_DARK));
+        buf.set_string(2, 0, "n", Style::default().fg(Color::Green));
+        ColorDepth::Monochrome.apply(&mut buf);
+
+        let modifiers: Vec<_> = buf.content.iter().map(|cell| cell.modifier).collect();
+        assert_eq!(
+            modifiers,
+            vec![Modifier::REVERSED, Modifier::BOLD, Modifier::empty()]
+        );
+        assert!(buf
+            .content
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+    }
+}
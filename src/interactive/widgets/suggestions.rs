@@ -0,0 +1,150 @@
+use crate::interactive::{
+    widgets::{EntryMarkMap, COLOR_MARKED},
+    CursorDirection, Suggestion,
+};
+use crosstermion::{input::Key, input::Key::*};
+use dua::{traverse::TreeIndex, ByteFormat};
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// What to do with the suggestion selected in the [`SuggestionsPane`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionAction {
+    /// Show the entry in its directory
+    Show(TreeIndex),
+    /// Mark the entry for deletion, or unmark it if it is marked already
+    ToggleMark { index: TreeIndex, is_dir: bool },
+}
+
+/// Lists entries that can likely be cleaned up, along with why, to review and mark them
+#[derive(Default)]
+pub struct SuggestionsPane {
+    suggestions: Vec<Suggestion>,
+    selected: usize,
+    list: List,
+}
+
+pub struct SuggestionsPaneProps<'a> {
+    pub border_style: Style,
+    pub has_focus: bool,
+    pub format: ByteFormat,
+    pub marked: Option<&'a EntryMarkMap>,
+}
+
+impl SuggestionsPane {
+    pub fn new(suggestions: Vec<Suggestion>) -> Self {
+        SuggestionsPane {
+            suggestions,
+            ..Default::default()
+        }
+    }
+
+    /// Handle `key` and return what to do with the selected suggestion, if anything.
+    pub fn process_events(&mut self, key: Key) -> Option<SuggestionAction> {
+        let selected = self.suggestions.get(self.selected)?;
+        let (index, is_dir) = (selected.index, selected.is_dir);
+        match key {
+            Char('o') | Char('\n') => return Some(SuggestionAction::Show(index)),
+            Char('x') | Char(' ') => {
+                self.change_selection(CursorDirection::Down);
+                return Some(SuggestionAction::ToggleMark { index, is_dir });
+            }
+            Char('H') => self.change_selection(CursorDirection::ToTop),
+            Char('G') => self.change_selection(CursorDirection::ToBottom),
+            Ctrl('u') | PageUp => self.change_selection(CursorDirection::PageUp),
+            Char('k') | Up => self.change_selection(CursorDirection::Up),
+            Char('j') | Down => self.change_selection(CursorDirection::Down),
+            Ctrl('d') | PageDown => self.change_selection(CursorDirection::PageDown),
+            _ => {}
+        };
+        None
+    }
+
+    fn change_selection(&mut self, direction: CursorDirection) {
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.suggestions.len().saturating_sub(1));
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<SuggestionsPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let SuggestionsPaneProps {
+            border_style,
+            has_focus,
+            format,
+            marked,
+        } = props.borrow();
+
+        let total: u128 = self.suggestions.iter().map(|s| s.size).sum();
+        let found = format!(
+            "{} cleanup suggestions with {}",
+            self.suggestions.len(),
+            format.display(total).to_string().trim_start()
+        );
+        let title = match has_focus {
+            true => format!("{} (x = mark, o = show, q = close) ", found),
+            false => format!("{} ", found),
+        };
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let list_area = block.inner(area);
+        block.render(area, buf);
+
+        let selected = self.selected;
+        let entries = self
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(idx, suggestion)| {
+                let is_marked = marked.is_some_and(|m| m.contains_key(&suggestion.index));
+                let style = Style {
+                    add_modifier: match (idx == selected, *has_focus) {
+                        (true, true) => Modifier::REVERSED | Modifier::BOLD,
+                        (true, false) => Modifier::REVERSED,
+                        (false, _) => Modifier::empty(),
+                    },
+                    fg: is_marked.then_some(COLOR_MARKED),
+                    ..Default::default()
+                };
+                vec![
+                    Span::styled(
+                        format!(
+                            "{:>byte_column_width$} ",
+                            format.display(suggestion.size).to_string(),
+                            byte_column_width = format.width()
+                        ),
+                        Style {
+                            fg: Color::Green.into(),
+                            ..style
+                        },
+                    ),
+                    Span::styled(format!(" {} ", suggestion.path.display()), style),
+                    Span::styled(
+                        format!(" {}", suggestion.rationale),
+                        Style {
+                            fg: Color::DarkGray.into(),
+                            ..style
+                        },
+                    ),
+                ]
+            });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, entries, list_area, buf);
+    }
+}
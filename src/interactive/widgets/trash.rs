@@ -0,0 +1,171 @@
+use super::details::format_utc;
+use crate::interactive::{CursorDirection, TrashedEntry};
+use crosstermion::{input::Key, input::Key::*};
+use std::{borrow::Borrow, collections::BTreeSet};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// Lists the entries in the trash bin to restore some of them
+#[derive(Default)]
+pub struct TrashPane {
+    entries: Vec<TrashedEntry>,
+    selected: usize,
+    /// Positions of the entries chosen for restoration
+    chosen: BTreeSet<usize>,
+    list: List,
+}
+
+pub struct TrashPaneProps {
+    pub border_style: Style,
+    pub has_focus: bool,
+}
+
+impl TrashPane {
+    pub fn new(entries: Vec<TrashedEntry>) -> Self {
+        TrashPane {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    /// Handle `key` and return the entries to restore, either the chosen ones or the selected one if none is chosen.
+    pub fn process_events(&mut self, key: Key) -> Option<Vec<TrashedEntry>> {
+        match key {
+            Char(' ') | Char('d') => {
+                if !self.chosen.remove(&self.selected) && self.selected < self.entries.len() {
+                    self.chosen.insert(self.selected);
+                }
+                self.change_selection(CursorDirection::Down);
+            }
+            Char('r') => {
+                let chosen = match self.chosen.is_empty() {
+                    true => BTreeSet::from([self.selected]),
+                    false => std::mem::take(&mut self.chosen),
+                };
+                let (restore, keep) = std::mem::take(&mut self.entries)
+                    .into_iter()
+                    .enumerate()
+                    .partition::<Vec<_>, _>(|(idx, _)| chosen.contains(idx));
+                self.entries = keep.into_iter().map(|(_, e)| e).collect();
+                self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+                return Some(restore.into_iter().map(|(_, e)| e).collect());
+            }
+            Char('H') => self.change_selection(CursorDirection::ToTop),
+            Char('G') => self.change_selection(CursorDirection::ToBottom),
+            Ctrl('u') | PageUp => self.change_selection(CursorDirection::PageUp),
+            Char('k') | Up => self.change_selection(CursorDirection::Up),
+            Char('j') | Down => self.change_selection(CursorDirection::Down),
+            Ctrl('d') | PageDown => self.change_selection(CursorDirection::PageDown),
+            _ => {}
+        };
+        None
+    }
+
+    /// Put `entries` back into the list, for instance if restoring them failed.
+    pub fn reinsert(&mut self, entries: Vec<TrashedEntry>) {
+        self.entries.extend(entries);
+        self.entries
+            .sort_by_key(|e| std::cmp::Reverse(e.time_deleted));
+    }
+
+    fn change_selection(&mut self, direction: CursorDirection) {
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn render(&mut self, props: impl Borrow<TrashPaneProps>, area: Rect, buf: &mut Buffer) {
+        let TrashPaneProps {
+            border_style,
+            has_focus,
+        } = props.borrow();
+
+        let title = match has_focus {
+            true => format!(
+                "Trash bin: {} entries (space = choose, r = restore, q = close) ",
+                self.entries.len()
+            ),
+            false => format!("Trash bin: {} entries ", self.entries.len()),
+        };
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let list_area = block.inner(area);
+        block.render(area, buf);
+
+        let (selected, chosen) = (self.selected, &self.chosen);
+        let entries = self.entries.iter().enumerate().map(|(idx, entry)| {
+            let is_chosen = chosen.contains(&idx);
+            let style = Style {
+                fg: is_chosen.then_some(Color::Yellow),
+                add_modifier: match (idx == selected, *has_focus) {
+                    (true, true) => Modifier::REVERSED | Modifier::BOLD,
+                    (true, false) => Modifier::REVERSED,
+                    (false, _) => Modifier::empty(),
+                },
+                ..Default::default()
+            };
+            let deleted = entry.time_deleted.clamp(0, u32::MAX as i64) as u32;
+            vec![
+                Span::styled(
+                    format!(" {} ", format_utc(deleted)),
+                    Style {
+                        fg: Color::Green.into(),
+                        ..style
+                    },
+                ),
+                Span::styled(format!("{} ", entry.original_path.display()), style),
+            ]
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, entries, list_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, time_deleted: i64) -> TrashedEntry {
+        TrashedEntry {
+            id: name.into(),
+            original_path: name.into(),
+            time_deleted,
+        }
+    }
+
+    #[test]
+    fn chosen_entries_are_restored_or_the_selected_one() {
+        let mut pane = TrashPane::new(vec![entry("c", 3), entry("b", 2), entry("a", 1)]);
+        assert_eq!(pane.process_events(Char(' ')), None, "choose c");
+        assert_eq!(pane.process_events(Char('j')), None);
+        assert_eq!(pane.process_events(Char(' ')), None, "choose a");
+        assert_eq!(
+            pane.process_events(Char('r')),
+            Some(vec![entry("c", 3), entry("a", 1)])
+        );
+        assert_eq!(
+            pane.process_events(Char('r')),
+            Some(vec![entry("b", 2)]),
+            "without a choice, it's the selected entry"
+        );
+        assert_eq!(
+            pane.process_events(Char('r')),
+            Some(vec![]),
+            "nothing is left"
+        );
+
+        pane.reinsert(vec![entry("a", 1), entry("b", 2)]);
+        assert_eq!(pane.process_events(Char('r')), Some(vec![entry("b", 2)]));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Write the `count` largest files found anywhere below `paths` to `out`, the largest first, along with their size.
+///
+/// Only the largest files seen so far are kept while traversing, so memory use doesn't grow with the amount of files.
+/// Files with multiple hard links are only considered at the first path they are seen at.
+pub fn largest_files(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    count: usize,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut largest = BinaryHeap::<Reverse<(u128, PathBuf)>>::new();
+    let mut inodes = InodeFilter::default();
+    for path in paths {
+        let path = path.as_ref();
+        let sizes = FileSizes::new(&walk_options, path)?;
+        for entry in walk_options.iter_from_path(path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            let size = match &entry.client_state {
+                Some(Ok(m)) if sizes.counts(m, &mut inodes) => match sizes.size_of(&entry, m) {
+                    Ok(size) => size as u128,
+                    Err(_) => {
+                        res.num_errors += 1;
+                        continue;
+                    }
+                },
+                Some(Err(_)) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            if count == 0
+                || largest.len() == count
+                    && largest
+                        .peek()
+                        .is_some_and(|Reverse((smallest, _))| size <= *smallest)
+            {
+                continue;
+            }
+            largest.push(Reverse((size, entry.path())));
+            if largest.len() > count {
+                largest.pop();
+            }
+        }
+    }
+
+    let mut largest: Vec<_> = largest.into_iter().map(|Reverse(file)| file).collect();
+    largest.sort_by(|(ls, lp), (rs, rp)| rs.cmp(ls).then(lp.cmp(rp)));
+    let format = walk_options.byte_format;
+    for (size, path) in largest {
+        writeln!(
+            out,
+            "{:>w$} {}",
+            format.display(size).to_string(),
+            path.display(),
+            w = format.width()
+        )?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testutils::TempTree, ByteFormat};
+    use std::fs;
+
+    #[test]
+    fn only_the_largest_files_are_listed_with_the_largest_first() -> Result<()> {
+        let root = TempTree::new("largest-files");
+        root.file("deep/er/huge", vec![0; 300])?;
+        root.file("deep/small", vec![0; 10])?;
+        root.file("large", vec![0; 200])?;
+        root.file("same", vec![0; 200])?;
+        root.file("tiny", vec![0; 1])?;
+        let largest = |count| -> Result<String> {
+            let mut out = Vec::new();
+            largest_files(&mut out, WalkOptions::for_tests(), count, Some(&root))?;
+            Ok(String::from_utf8(out)?)
+        };
+
+        let w = ByteFormat::Bytes.width();
+        assert_eq!(
+            largest(3)?,
+            format!(
+                "{:>w$} {}\n{:>w$} {}\n{:>w$} {}\n",
+                "300 b",
+                root.join("deep/er/huge").display(),
+                "200 b",
+                root.join("large").display(),
+                "200 b",
+                root.join("same").display(),
+                w = w
+            )
+        );
+        assert_eq!(largest(0)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn sizes_follow_the_size_model_like_aggregating_does() -> Result<()> {
+        let root = TempTree::new("largest-files-size-model");
+        // sparse, so it has no blocks allocated but still takes filesystem blocks
+        fs::File::create(root.join("file"))?.set_len(100_000)?;
+        let walk_options = WalkOptions {
+            apparent_size: false,
+            round_to_fs_blocks: true,
+            ..WalkOptions::for_tests()
+        };
+        let mut out = Vec::new();
+        let res = largest_files(&mut out, walk_options.clone(), 1, Some(&root));
+        let mut aggregated = Vec::new();
+        crate::aggregate(
+            &mut aggregated,
+            None::<io::Stderr>,
+            walk_options,
+            false,
+            false,
+            false,
+            Some(&"csv".parse()?),
+            None,
+            None,
+            Some(&root),
+        )?;
+        assert_eq!(res?.num_errors, 0);
+
+        let size = String::from_utf8(out)?
+            .split_whitespace()
+            .next()
+            .expect("a size")
+            .to_owned();
+        let aggregated_size = String::from_utf8(aggregated)?
+            .lines()
+            .nth(1)
+            .and_then(|line| line.split(',').nth(1))
+            .expect("a row")
+            .to_owned();
+        assert_eq!(size, aggregated_size);
+        assert_ne!(size, "0", "the apparent size is rounded up");
+        Ok(())
+    }
+}
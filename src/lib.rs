@@ -4,12 +4,66 @@
 extern crate jwalk;
 
 mod aggregate;
+mod apppackages;
 mod common;
+mod crashartifacts;
 mod crossdev;
+mod devicespace;
+mod diff;
+mod duplicatedirs;
+mod emergency;
+mod filestats;
+mod filetype;
+mod fixture;
+mod fsquirks;
+mod ignorefiles;
 mod inodefilter;
+mod inputcheck;
+mod largestfiles;
+mod logs;
+mod placeholder;
+#[cfg(feature = "s3")]
+mod s3;
+mod selftest;
+mod sharedextents;
+mod snapshot;
+mod store;
+mod template;
+#[cfg(test)]
+mod testutils;
+mod unreadable;
 
+pub mod privileged;
 pub mod traverse;
 
 pub use aggregate::aggregate;
+pub use apppackages::{summarize_app_packages, AppPackageLocations};
 pub use common::*;
+pub use crashartifacts::{crash_artifact_of, summarize_crash_artifacts, CrashArtifact};
+pub use devicespace::DeviceSpace;
+pub use diff::{diff_snapshots, display_growth, size_changes, Baseline, SizeChange};
+pub use duplicatedirs::DuplicateDirectory;
+pub(crate) use duplicatedirs::SeenDirectories;
+pub use emergency::emergency;
+pub use filestats::file_statistics;
+pub use filetype::{file_type_of, FileType};
+#[doc(hidden)]
+pub use fixture::{
+    create_fixture, generate_fixture, FixtureSpec, FixtureSummary, SizeDistribution,
+};
+pub use fsquirks::{Filesystem, FilesystemQuirks};
+pub use ignorefiles::glob_matches;
+pub use inodefilter::hard_links_of;
 pub(crate) use inodefilter::InodeFilter;
+pub use inputcheck::{check_input, usable_inputs};
+pub use largestfiles::largest_files;
+pub use logs::summarize_logs;
+pub use placeholder::is_cloud_placeholder;
+#[cfg(feature = "s3")]
+pub use s3::{list_bucket, objects_to_tree, parse_listing, ListedObject, S3Url};
+pub use selftest::selftest;
+pub use sharedextents::SharedExtents;
+pub use snapshot::{load_snapshot, save_snapshot};
+pub use store::summarize_store;
+pub use template::{json_escape, OutputTemplate, TemplateValues};
+pub use unreadable::{summarize_unreadable, UnreadableSummary};
@@ -0,0 +1,499 @@
+use crate::{ByteFormat, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Read},
+    path::{Component, Path},
+    process::{Command, Stdio},
+    time::{Duration, SystemTime},
+};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+/// Archived journal files and rotated logs older than this are suggested to be removed
+const OLD_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// How old files are grouped, by the longest age they are younger than
+const AGES: &[(&str, Duration)] = &[
+    ("less than a day", DAY),
+    ("less than a week", Duration::from_secs(7 * 24 * 60 * 60)),
+    ("less than a month", OLD_AGE),
+];
+const COMPRESSION_EXTENSIONS: &[&str] = &["gz", "xz", "bz2", "zst", "lz4"];
+
+/// The service that wrote the log file at `relative` below the log directory, which is the directory it is in or
+/// its name without rotation suffixes, along with whether it was rotated, like `syslog` for `syslog.2.gz`.
+fn service_of(relative: &Path) -> Option<(String, bool)> {
+    let mut components = relative.components();
+    let first = match components.next()? {
+        Component::Normal(first) => first.to_str()?,
+        _ => return None,
+    };
+    let name = relative.file_name()?.to_str()?;
+    let (_, rotated) = without_rotation_suffix(name);
+    match components.next() {
+        Some(_) => Some((first.to_owned(), rotated)),
+        None => Some((without_rotation_suffix(name).0.to_owned(), rotated)),
+    }
+}
+
+/// `name` without the suffixes added by `logrotate`, like `.1`, `.2.gz` or `-20240131`, and whether it had any.
+fn without_rotation_suffix(name: &str) -> (&str, bool) {
+    let mut stem = name;
+    if let Some((rest, extension)) = stem.rsplit_once('.') {
+        if COMPRESSION_EXTENSIONS.contains(&extension) {
+            stem = rest;
+        }
+    }
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if let Some((rest, number)) = stem.rsplit_once('.') {
+        if is_number(number) && !rest.is_empty() {
+            return (rest, true);
+        }
+    }
+    if let Some((rest, date)) = stem.rsplit_once('-') {
+        if date.len() == 8 && is_number(date) && !rest.is_empty() {
+            return (rest, true);
+        }
+    }
+    (stem, stem.len() != name.len())
+}
+
+/// The group of journal files and rotated logs a file of the given `age` belongs to.
+fn age_group(age: Duration) -> &'static str {
+    AGES.iter()
+        .find(|(_, limit)| age < *limit)
+        .map_or("older", |(name, _)| name)
+}
+
+/// Add the size of each entry written by `journalctl -o export` to the unit it was logged by, or the program if it
+/// wasn't logged by a unit.
+fn sizes_by_unit(mut export: impl BufRead) -> io::Result<BTreeMap<String, u128>> {
+    let mut sizes = BTreeMap::new();
+    let (mut entry_size, mut unit, mut identifier) = (0u128, None, None);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = export.read_until(b'\n', &mut line)?;
+        if read == 0 || line == b"\n" {
+            if entry_size > 0 {
+                let name = unit
+                    .take()
+                    .or_else(|| identifier.take())
+                    .unwrap_or_else(|| "unknown".to_owned());
+                *sizes.entry(name).or_insert(0) += entry_size;
+            }
+            (entry_size, unit, identifier) = (0, None, None);
+            if read == 0 {
+                return Ok(sizes);
+            }
+            continue;
+        }
+        entry_size += read as u128;
+        let field = line.strip_suffix(b"\n").unwrap_or(&line);
+        match field.iter().position(|b| *b == b'=') {
+            Some(pos) => {
+                let value = || String::from_utf8_lossy(&field[pos + 1..]).into_owned();
+                match &field[..pos] {
+                    b"_SYSTEMD_UNIT" => unit = Some(value()),
+                    b"SYSLOG_IDENTIFIER" => identifier = Some(value()),
+                    _ => {}
+                }
+            }
+            None => {
+                // binary fields are followed by their length and data, and a newline
+                let mut len = [0; 8];
+                export.read_exact(&mut len)?;
+                let len = u64::from_le_bytes(len);
+                let skipped = io::copy(&mut (&mut export).take(len + 1), &mut io::sink())?;
+                entry_size += 8 + skipped as u128;
+            }
+        }
+    }
+}
+
+/// What the entries of each unit take in the journal, as measured by reading all of it with `journalctl`.
+fn journal_sizes_by_unit(journal: &Path) -> Result<BTreeMap<String, u128>> {
+    let mut child = Command::new("journalctl")
+        .arg("--directory")
+        .arg(journal)
+        .args(["--output=export", "--no-pager"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Could not run 'journalctl'")?;
+    let sizes = sizes_by_unit(io::BufReader::new(
+        child.stdout.take().expect("stdout to be piped"),
+    ));
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "'journalctl' failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(sizes?)
+}
+
+/// The log files written by one service
+#[derive(Default, Debug)]
+struct Usage {
+    bytes: u128,
+    /// The size of its rotated logs
+    old_bytes: u128,
+}
+
+#[derive(Default, Debug)]
+struct LogFiles {
+    /// The size of the current journal files, and of those archived grouped by age
+    journal_active: u128,
+    journal_by_age: BTreeMap<&'static str, u128>,
+    /// The size of the archived journal files older than [`OLD_AGE`]
+    journal_old: u128,
+    services: BTreeMap<String, Usage>,
+    /// The size of the rotated logs older than [`OLD_AGE`]
+    rotated_old: u128,
+}
+
+impl LogFiles {
+    fn collect(walk_options: &WalkOptions, log_dir: &Path, res: &mut WalkResult) -> Self {
+        let now = SystemTime::now();
+        let mut files = LogFiles::default();
+        let sizes = match FileSizes::new(walk_options, log_dir) {
+            Ok(sizes) => sizes,
+            Err(_) => {
+                res.num_errors += 1;
+                return files;
+            }
+        };
+        let mut inodes = InodeFilter::default();
+        for entry in walk_options.iter_from_path(log_dir) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            let m = match &entry.client_state {
+                Some(Ok(m)) if m.is_file() && sizes.counts(m, &mut inodes) => m,
+                Some(Err(_)) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            let bytes = match sizes.size_of(&entry, m) {
+                Ok(bytes) => bytes as u128,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let age = m
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            let relative = match path.strip_prefix(log_dir) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let name = relative.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if relative.starts_with("journal")
+                && (name.ends_with(".journal") || name.ends_with(".journal~"))
+            {
+                // archived files are named like `system@<ids>.journal`, or end in `~` if they weren't closed
+                if name.contains('@') || name.ends_with('~') {
+                    *files.journal_by_age.entry(age_group(age)).or_insert(0) += bytes;
+                    if age >= OLD_AGE {
+                        files.journal_old += bytes;
+                    }
+                } else {
+                    files.journal_active += bytes;
+                }
+                continue;
+            }
+            if let Some((service, rotated)) = service_of(relative) {
+                let usage = files.services.entry(service).or_default();
+                usage.bytes += bytes;
+                if rotated {
+                    usage.old_bytes += bytes;
+                    if age >= OLD_AGE {
+                        files.rotated_old += bytes;
+                    }
+                }
+            }
+        }
+        files
+    }
+
+    fn journal_bytes(&self) -> u128 {
+        self.journal_active + self.journal_by_age.values().sum::<u128>()
+    }
+}
+
+/// Write the space taken by the systemd journal in `log_dir` by unit and by age to `out`, along with the space taken
+/// by the other logs in it by service, and suggest how to remove archived journal files and rotated logs older than
+/// a month.
+///
+/// The journal's usage by unit is estimated by the size of their entries as read by `journalctl`, and left out if
+/// that's not possible.
+pub fn summarize_logs(
+    out: impl io::Write,
+    walk_options: WalkOptions,
+    log_dir: &Path,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let files = LogFiles::collect(&walk_options, log_dir, &mut res);
+    let journal = log_dir.join("journal");
+    let units = match files.journal_bytes() {
+        0 => None,
+        _ => Some(journal_sizes_by_unit(&journal)),
+    };
+    write_log_usage(out, walk_options.byte_format, log_dir, &files, units)?;
+    Ok(res)
+}
+
+fn write_log_usage(
+    mut out: impl io::Write,
+    format: ByteFormat,
+    log_dir: &Path,
+    files: &LogFiles,
+    units: Option<Result<BTreeMap<String, u128>>>,
+) -> io::Result<()> {
+    let size = |bytes: u128| format.display(bytes).to_string();
+    let trimmed = |bytes: u128| format.display(bytes).to_string().trim().to_owned();
+    let journal_bytes = files.journal_bytes();
+    if journal_bytes > 0 {
+        let journal = log_dir.join("journal");
+        match units {
+            Some(Ok(units)) if !units.is_empty() => {
+                writeln!(
+                    out,
+                    "Journal in {} by unit, estimated by the size of their entries:",
+                    journal.display()
+                )?;
+                let total: u128 = units.values().sum();
+                let mut units: Vec<_> = units.into_iter().collect();
+                units.sort_by(|(ln, l), (rn, r)| l.cmp(r).then(ln.cmp(rn)));
+                for (unit, entry_bytes) in units {
+                    writeln!(
+                        out,
+                        "{:>w$} {}",
+                        size(journal_bytes * entry_bytes / total),
+                        unit,
+                        w = format.width()
+                    )?;
+                }
+                writeln!(out)?;
+            }
+            Some(Ok(_)) => {}
+            Some(Err(err)) => writeln!(out, "Journal usage by unit is unknown: {:#}\n", err)?,
+            None => {}
+        }
+        writeln!(out, "Journal in {} by age:", journal.display())?;
+        writeln!(
+            out,
+            "{:>w$} active",
+            size(files.journal_active),
+            w = format.width()
+        )?;
+        for (group, _) in AGES.iter().chain(Some(&("older", OLD_AGE))) {
+            if let Some(bytes) = files.journal_by_age.get(group) {
+                writeln!(
+                    out,
+                    "{:>w$} archived, {}",
+                    size(*bytes),
+                    group,
+                    w = format.width()
+                )?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    if !files.services.is_empty() {
+        writeln!(out, "Logs in {} by service:", log_dir.display())?;
+        let mut services: Vec<_> = files.services.iter().collect();
+        services.sort_by(|(ln, l), (rn, r)| l.bytes.cmp(&r.bytes).then(ln.cmp(rn)));
+        for (service, usage) in services {
+            let rotated = match usage.old_bytes {
+                0 => "-".to_owned(),
+                bytes => format!("{} rotated", trimmed(bytes)),
+            };
+            writeln!(
+                out,
+                "{:>w$} {:>r$} {}",
+                size(usage.bytes),
+                rotated,
+                service,
+                w = format.width(),
+                r = format.width() + " rotated".len()
+            )?;
+        }
+        writeln!(out)?;
+    }
+
+    let log_bytes: u128 = files.services.values().map(|u| u.bytes).sum();
+    writeln!(
+        out,
+        "{} in the journal and {} in other logs",
+        trimmed(journal_bytes),
+        trimmed(log_bytes)
+    )?;
+    if files.journal_old > 0 {
+        const MIB: u128 = 1024 * 1024;
+        let keep = journal_bytes - files.journal_old;
+        writeln!(
+            out,
+            "'sudo journalctl --vacuum-size={}M' frees {} by removing archived journal files older than a month, \
+             like 'sudo journalctl --vacuum-time=1month'",
+            keep.div_ceil(MIB).max(1),
+            trimmed(files.journal_old)
+        )?;
+    }
+    if files.rotated_old > 0 {
+        writeln!(
+            out,
+            "Rotated logs older than a month take {}, 'logrotate' removes them if configured with a lower 'rotate' count",
+            trimmed(files.rotated_old)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn logs_are_grouped_by_service_without_rotation_suffixes() {
+        for (path, expected) in [
+            ("syslog", Some(("syslog", false))),
+            ("syslog.1", Some(("syslog", true))),
+            ("syslog.2.gz", Some(("syslog", true))),
+            ("dpkg.log", Some(("dpkg.log", false))),
+            ("dpkg.log.12.xz", Some(("dpkg.log", true))),
+            ("messages-20240131", Some(("messages", true))),
+            ("boot.log-20240131.gz", Some(("boot.log", true))),
+            ("Xorg.0.log", Some(("Xorg.0.log", false))),
+            ("wtmp.gz", Some(("wtmp", true))),
+            ("nginx/access.log", Some(("nginx", false))),
+            ("nginx/error.log.3.gz", Some(("nginx", true))),
+        ] {
+            assert_eq!(
+                service_of(Path::new(path)),
+                expected.map(|(s, r)| (s.to_owned(), r)),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn journal_entries_are_summed_up_by_unit() -> io::Result<()> {
+        let mut export = Vec::new();
+        export.extend_from_slice(
+            b"__CURSOR=s=1\n_SYSTEMD_UNIT=nginx.service\nSYSLOG_IDENTIFIER=nginx\nMESSAGE=hi\n\n",
+        );
+        export.extend_from_slice(b"SYSLOG_IDENTIFIER=kernel\nMESSAGE\n");
+        export.extend_from_slice(&5u64.to_le_bytes());
+        export.extend_from_slice(b"a\nb\0c\n\n");
+        export.extend_from_slice(b"MESSAGE=x\n");
+        let sizes = sizes_by_unit(&export[..])?;
+        assert_eq!(
+            sizes.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("kernel".to_owned(), 25 + 8 + 8 + 6),
+                ("nginx.service".into(), 76),
+                ("unknown".into(), 10),
+            ],
+            "programs stand in for units, and binary fields are skipped"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn journal_files_are_grouped_by_age_and_logs_by_service() -> Result<()> {
+        let root = TempTree::new("logs");
+        let journal = root.join("journal/0123456789abcdef");
+        fs::create_dir_all(&journal)?;
+        fs::create_dir_all(root.join("nginx"))?;
+        let write = |path: PathBuf, len: usize, days_old: u64| -> io::Result<()> {
+            fs::write(&path, vec![0; len])?;
+            fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now() - DAY * days_old as u32)
+        };
+        write(journal.join("system.journal"), 8 << 20, 0)?;
+        write(journal.join("system@00-01-02.journal"), 4 << 20, 3)?;
+        write(journal.join("user-1000@00-01-02.journal"), 2 << 20, 40)?;
+        write(journal.join("system@00-01-03.journal~"), 1 << 20, 100)?;
+        write(root.join("syslog"), 100, 0)?;
+        write(root.join("syslog.2.gz"), 10, 45)?;
+        write(root.join("nginx/error.log.1"), 1000, 2)?;
+        let format = ByteFormat::Bytes;
+        let walk_options = WalkOptions {
+            byte_format: format,
+            ..WalkOptions::for_tests()
+        };
+        let mut res = WalkResult::default();
+        let files = LogFiles::collect(&walk_options, &root, &mut res);
+        let mut out = Vec::new();
+        write_log_usage(
+            &mut out,
+            format,
+            &root,
+            &files,
+            Some(Ok(vec![
+                ("a.service".to_owned(), 1),
+                ("b.service".into(), 3),
+            ]
+            .into_iter()
+            .collect())),
+        )?;
+        assert_eq!(res.num_errors, 0);
+
+        let w = format.width();
+        let r = w + " rotated".len();
+        let mib = 1 << 20;
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "Journal in {journal} by unit, estimated by the size of their entries:\n\
+                 {:>w$} a.service\n{:>w$} b.service\n\n\
+                 Journal in {journal} by age:\n\
+                 {:>w$} active\n{:>w$} archived, less than a week\n{:>w$} archived, older\n\n\
+                 Logs in {root} by service:\n\
+                 {:>w$} {:>r$} syslog\n{:>w$} {:>r$} nginx\n\n\
+                 {} b in the journal and 1110 b in other logs\n\
+                 'sudo journalctl --vacuum-size=12M' frees {} b by removing archived journal files older than a month, \
+                 like 'sudo journalctl --vacuum-time=1month'\n\
+                 Rotated logs older than a month take 10 b, 'logrotate' removes them if configured with a lower 'rotate' count\n",
+                format!("{} b", 15 * mib / 4),
+                format!("{} b", 15 * mib * 3 / 4),
+                format!("{} b", 8 * mib),
+                format!("{} b", 4 * mib),
+                format!("{} b", 3 * mib),
+                "110 b",
+                "10 b rotated",
+                "1000 b",
+                "1000 b rotated",
+                15 * mib,
+                3 * mib,
+                journal = root.join("journal").display(),
+                root = root.display(),
+                w = w,
+                r = r,
+            )
+        );
+        Ok(())
+    }
+}
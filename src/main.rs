@@ -1,7 +1,8 @@
 #![forbid(unsafe_code)]
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Clap;
-use dua::{ByteFormat, TraversalSorting};
+use dua::{ByteFormat, TraversalSorting, WalkResult};
+use itertools::Itertools;
 use std::{fs, io, io::Write, path::PathBuf, process};
 
 mod crossdev;
@@ -9,8 +10,8 @@ mod crossdev;
 mod interactive;
 mod options;
 
-fn stderr_if_tty() -> Option<io::Stderr> {
-    if atty::is(atty::Stream::Stderr) {
+fn stderr_if_tty(quiet: bool) -> Option<io::Stderr> {
+    if !quiet && atty::is(atty::Stream::Stderr) {
         Some(io::stderr())
     } else {
         None
@@ -56,22 +57,73 @@ fn main() -> Result<()> {
     use options::Command::*;
 
     let opt: options::Args = options::Args::parse_from(wild::args_os());
+    if opt.timeout.is_some() && !matches!(opt.command, None | Some(Aggregate { .. })) {
+        bail!("--timeout only applies to aggregating, like 'dua --timeout 1m <input>' or 'dua --timeout 1m aggregate <input>'");
+    }
     let threads = derive_default_threads(opt.threads);
     let walk_options = dua::WalkOptions {
         threads,
         byte_format: opt.format.map(Into::into).unwrap_or(ByteFormat::Metric),
-        apparent_size: opt.apparent_size,
+        apparent_size: opt.apparent_size || opt.size_model == options::SizeModel::Apparent,
         count_hard_links: opt.count_hard_links,
         sorting: TraversalSorting::None,
         cross_filesystems: !opt.stay_on_filesystem,
+        follow_links: opt.follow_links,
+        round_to_fs_blocks: !opt.apparent_size
+            && opt.size_model == options::SizeModel::FilesystemBlocks,
+        respect_ignore_files: opt.respect_ignore_files,
+    };
+    let rescan_with_privileges = opt.sudo;
+    let quiet = opt.quiet;
+    if rescan_with_privileges {
+        dua::privileged::authenticate()?;
+    }
+    // buckets are browsed like a snapshot, with a tree built from the listing of their objects
+    #[cfg(all(
+        feature = "s3",
+        any(feature = "tui-unix", feature = "tui-crossplatform")
+    ))]
+    let (command, listing) = match opt.command {
+        Some(S3 { url }) => (
+            Some(Interactive {
+                input: Vec::new(),
+                auto_descend: false,
+                icons: false,
+                export_flagged: None,
+                move_to: None,
+                no_row_shading: false,
+                no_trust_mtime: false,
+                load: None,
+                compare: None,
+            }),
+            Some(dua::list_bucket(&url)?),
+        ),
+        command => (command, None),
     };
-    let res = match opt.command {
+    #[cfg(not(all(
+        feature = "s3",
+        any(feature = "tui-unix", feature = "tui-crossplatform")
+    )))]
+    let command = opt.command;
+    let res = match command {
         #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
-        Some(Interactive { input }) => {
-            use crate::interactive::{Interaction, TerminalApp};
+        Some(Interactive {
+            input,
+            auto_descend,
+            icons,
+            export_flagged,
+            move_to,
+            no_row_shading,
+            no_trust_mtime,
+            load,
+            compare,
+        }) => {
+            use crate::interactive::{title, Config, Interaction, InteractiveOptions, TerminalApp};
             use anyhow::{anyhow, Context};
             use crosstermion::terminal::{tui::new_terminal, AlternateRawScreen};
 
+            #[cfg(not(feature = "s3"))]
+            let listing = None;
             let no_tty_msg = "Interactive mode requires a connected terminal";
             if atty::isnt(atty::Stream::Stderr) {
                 return Err(anyhow!(no_tty_msg));
@@ -81,14 +133,38 @@ fn main() -> Result<()> {
                 AlternateRawScreen::try_from(io::stderr()).with_context(|| no_tty_msg)?,
             )
             .with_context(|| "Could not instantiate terminal")?;
+            let (input, problems) = match load.is_some() || listing.is_some() {
+                true => (Vec::new(), Vec::new()),
+                false => usable_paths_from(input, !opt.stay_on_filesystem)?,
+            };
+            if input.is_empty() && !problems.is_empty() {
+                bail!("{}", problems.iter().map(ToString::to_string).join("\n"));
+            }
+            title::save();
             let res = TerminalApp::initialize(
                 &mut terminal,
                 walk_options,
-                paths_from(input, !opt.stay_on_filesystem)?,
+                input,
                 Interaction::Full,
+                InteractiveOptions {
+                    auto_descend,
+                    icons,
+                    row_shading: !no_row_shading,
+                    config_path: Config::default_path(),
+                    snapshot: load,
+                    listing,
+                    compare,
+                    move_to,
+                    skipped_inputs: problems.iter().map(ToString::to_string).collect(),
+                    trust_mtime: !no_trust_mtime,
+                },
             )?
             .map(|(keys_rx, mut app)| {
+                if rescan_with_privileges {
+                    app.rescan_permission_denied(&mut terminal);
+                }
                 let res = app.process_events(&mut terminal, keys_rx.into_iter());
+                let flagged = app.state.flagged_paths(&app.traversal);
 
                 let res = res.map(|r| {
                     (
@@ -102,24 +178,42 @@ fn main() -> Result<()> {
                 // Leak app memory to avoid having to wait for the hashmap to deallocate,
                 // which causes a noticeable delay shortly before the the program exits anyway.
                 std::mem::forget(app);
-                res
+                (res, flagged)
             });
 
             drop(terminal);
+            title::restore();
             io::stderr().flush().ok();
 
+            let res = match res {
+                Some((res, flagged)) => {
+                    if let Some(export_path) = export_flagged {
+                        let mut out =
+                            io::BufWriter::new(std::fs::File::create(&export_path).with_context(
+                                || format!("Could not create '{}'", export_path.display()),
+                            )?);
+                        for path in flagged {
+                            writeln!(out, "{}", path.display())?;
+                        }
+                        out.flush()?;
+                    }
+                    Some(res)
+                }
+                None => None,
+            };
+
             // Exit 'quickly' to avoid having to not have to deal with slightly different types in the other match branches
             std::process::exit(
                 res.transpose()?
                     .map(|(walk_result, paths)| {
-                        if let Some(paths) = paths {
+                        if let Some(paths) = paths.filter(|_| !quiet) {
                             for path in paths {
                                 println!("{}", path.display())
                             }
                         }
                         walk_result.to_exit_code()
                     })
-                    .unwrap_or(0),
+                    .unwrap_or(dua::ExitCode::Interrupted as i32),
             );
         }
         Some(Aggregate {
@@ -127,58 +221,225 @@ fn main() -> Result<()> {
             no_total,
             no_sort,
             statistics,
+            threshold,
+            template,
+            depth,
         }) => {
-            let stdout = io::stdout();
-            let stdout_locked = stdout.lock();
-            let (res, stats) = dua::aggregate(
-                stdout_locked,
-                stderr_if_tty(),
+            let (mut res, stats) = dua::aggregate(
+                stdout_unless(quiet),
+                stderr_if_tty(quiet),
                 walk_options,
                 !no_total,
                 !no_sort,
+                rescan_with_privileges,
+                template.as_ref(),
+                depth,
+                opt.timeout,
                 paths_from(input, !opt.stay_on_filesystem)?,
             )?;
-            if statistics {
+            if statistics && !quiet {
                 writeln!(io::stderr(), "{:?}", stats).ok();
             }
+            if res.timed_out && !quiet {
+                report_timeout(opt.timeout, stats.inputs_not_scanned);
+            }
+            res.threshold_exceeded = threshold.is_some_and(|t| stats.total_bytes > t);
             res
         }
         None => {
-            let stdout = io::stdout();
-            let stdout_locked = stdout.lock();
-            dua::aggregate(
-                stdout_locked,
-                stderr_if_tty(),
+            let (res, stats) = dua::aggregate(
+                stdout_unless(quiet),
+                stderr_if_tty(quiet),
                 walk_options,
                 true,
                 true,
+                rescan_with_privileges,
+                None,
+                None,
+                opt.timeout,
                 paths_from(opt.input, !opt.stay_on_filesystem)?,
-            )?
-            .0
+            )?;
+            if res.timed_out && !quiet {
+                report_timeout(opt.timeout, stats.inputs_not_scanned);
+            }
+            res
+        }
+        Some(Unreadable { input }) => dua::summarize_unreadable(
+            stdout_unless(quiet),
+            walk_options,
+            paths_from(input, !opt.stay_on_filesystem)?,
+        )?,
+        Some(Selftest { input }) => dua::selftest(
+            stdout_unless(quiet),
+            walk_options,
+            paths_from(input, !opt.stay_on_filesystem)?,
+        )?,
+        Some(Emergency { seconds, input }) => dua::emergency(
+            stdout_unless(quiet),
+            walk_options,
+            paths_from(input, !opt.stay_on_filesystem)?,
+            std::time::Duration::from_secs(seconds),
+        )?,
+        Some(Stats {
+            by_extension,
+            json,
+            input,
+        }) => dua::file_statistics(
+            stdout_unless(quiet),
+            walk_options,
+            by_extension,
+            json,
+            paths_from(input, !opt.stay_on_filesystem)?,
+        )?,
+        Some(Top { count, input }) => dua::largest_files(
+            stdout_unless(quiet),
+            walk_options,
+            count,
+            paths_from(input, !opt.stay_on_filesystem)?,
+        )?,
+        Some(CrashArtifacts { input }) => dua::summarize_crash_artifacts(
+            stdout_unless(quiet),
+            walk_options,
+            paths_from(input, !opt.stay_on_filesystem)?,
+        )?,
+        Some(Logs { log_dir }) => {
+            dua::summarize_logs(stdout_unless(quiet), walk_options, &log_dir)?
+        }
+        Some(Apps) => {
+            dua::summarize_app_packages(stdout_unless(quiet), walk_options, &Default::default())?
+        }
+        Some(Store { store }) => {
+            dua::summarize_store(stdout_unless(quiet), walk_options, store.as_deref())?
+        }
+        Some(Snapshot {
+            cmd: options::SnapshotCommand::Save { file, input },
+        }) => dua::save_snapshot(
+            walk_options,
+            paths_from(input, !opt.stay_on_filesystem)?,
+            &file,
+        )?,
+        Some(Diff { before, after }) => dua::diff_snapshots(
+            stdout_unless(quiet),
+            walk_options.byte_format,
+            walk_options.apparent_size,
+            &before,
+            &after,
+        )?,
+        Some(GenFixture {
+            seed,
+            depth,
+            fan_out,
+            files,
+            min_size,
+            max_size,
+            distribution,
+            symlinks,
+            hard_links,
+            sparse,
+            root,
+        }) => dua::generate_fixture(
+            stdout_unless(quiet),
+            walk_options.byte_format,
+            &root,
+            &dua::FixtureSpec {
+                seed,
+                depth,
+                fan_out,
+                files_per_dir: files,
+                min_size,
+                max_size,
+                distribution: distribution.into(),
+                symlinks,
+                hard_links,
+                sparse,
+            },
+        )?,
+        #[cfg(all(
+            feature = "s3",
+            any(feature = "tui-unix", feature = "tui-crossplatform")
+        ))]
+        Some(S3 { .. }) => unreachable!("buckets are shown interactively"),
+        Some(PrivilegedMeasure { input }) => {
+            let stdout = io::stdout();
+            dua::privileged::measure(stdout.lock(), walk_options, input)?;
+            WalkResult::default()
         }
     };
 
+    for symlink_loop in res.symlink_loops.iter().filter(|_| !quiet) {
+        eprintln!(
+            "Symlink loop: {} -> {}",
+            symlink_loop.path.display(),
+            symlink_loop.ancestor.display()
+        );
+    }
+    for duplicate in res.duplicate_directories.iter().filter(|_| !quiet) {
+        eprintln!(
+            "Duplicate directory: {} = {}",
+            duplicate.path.display(),
+            duplicate.original.display()
+        );
+    }
     process::exit(res.to_exit_code());
 }
 
-fn paths_from(paths: Vec<PathBuf>, cross_filesystems: bool) -> Result<Vec<PathBuf>, io::Error> {
+/// Tell that `--timeout` stopped the traversal, and what that means for the sizes that were printed.
+fn report_timeout(timeout: Option<std::time::Duration>, inputs_not_scanned: usize) {
+    eprintln!(
+        "Stopped after {:?}, sizes marked incomplete are lower bounds{}",
+        timeout.unwrap_or_default(),
+        match inputs_not_scanned {
+            0 => "".into(),
+            1 => " and 1 input wasn't scanned".into(),
+            n => format!(" and {} inputs weren't scanned", n),
+        }
+    );
+}
+
+fn stdout_unless(quiet: bool) -> Box<dyn io::Write> {
+    if quiet {
+        Box::new(io::sink())
+    } else {
+        Box::new(io::stdout().lock())
+    }
+}
+
+/// The paths to scan, which are all entries in the current directory if `paths` is empty.
+/// Explicitly given paths must all be usable, otherwise the problems with each of them are returned as error.
+fn paths_from(paths: Vec<PathBuf>, cross_filesystems: bool) -> Result<Vec<PathBuf>> {
+    let (paths, problems) = usable_paths_from(paths, cross_filesystems)?;
+    if !problems.is_empty() {
+        bail!("{}", problems.iter().map(ToString::to_string).join("\n"));
+    }
+    Ok(paths)
+}
+
+/// Like [`paths_from()`], but leave out explicitly given paths that can't be scanned and return their problems.
+fn usable_paths_from(
+    paths: Vec<PathBuf>,
+    cross_filesystems: bool,
+) -> Result<(Vec<PathBuf>, Vec<anyhow::Error>)> {
     let device_id = std::env::current_dir()
         .ok()
         .and_then(|cwd| crossdev::init(&cwd).ok());
 
     if paths.is_empty() {
-        cwd_dirlist().map(|paths| match device_id {
-            Some(device_id) if !cross_filesystems => paths
-                .into_iter()
-                .filter(|p| match p.metadata() {
-                    Ok(meta) => crossdev::is_same_device(device_id, &meta),
-                    Err(_) => true,
-                })
-                .collect(),
-            _ => paths,
-        })
+        let paths = cwd_dirlist()?;
+        Ok((
+            match device_id {
+                Some(device_id) if !cross_filesystems => paths
+                    .into_iter()
+                    .filter(|p| match p.metadata() {
+                        Ok(meta) => crossdev::is_same_device(device_id, &meta),
+                        Err(_) => true,
+                    })
+                    .collect(),
+                _ => paths,
+            },
+            Vec::new(),
+        ))
     } else {
-        Ok(paths)
+        Ok(dua::usable_inputs(paths))
     }
 }
 
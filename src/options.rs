@@ -1,7 +1,7 @@
 use clap::Clap;
 use dua::ByteFormat as LibraryByteFormat;
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::{convert::TryFrom, str::FromStr, time::Duration};
 
 #[derive(PartialEq, Debug)]
 pub enum ByteFormat {
@@ -32,7 +32,7 @@ impl FromStr for ByteFormat {
 }
 
 impl ByteFormat {
-    const VARIANTS: &'static [&'static str] =
+    pub const VARIANTS: &'static [&'static str] =
         &["metric", "binary", "bytes", "MB", "MiB", "GB", "GiB"];
 }
 
@@ -50,11 +50,75 @@ impl From<ByteFormat> for LibraryByteFormat {
     }
 }
 
+/// How the size of files is computed, see [`dua::WalkOptions::size_model_description()`].
+#[derive(PartialEq, Debug)]
+pub enum SizeModel {
+    Apparent,
+    Blocks,
+    FilesystemBlocks,
+}
+
+impl FromStr for SizeModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "apparent" => SizeModel::Apparent,
+            "blocks" => SizeModel::Blocks,
+            "fs-blocks" => SizeModel::FilesystemBlocks,
+            _ => return Err(format!("Invalid size model: {:?}", s)),
+        })
+    }
+}
+
+impl SizeModel {
+    const VARIANTS: &'static [&'static str] = &["apparent", "blocks", "fs-blocks"];
+}
+
+/// How the sizes of files created by 'gen-fixture' are spread, see [`dua::SizeDistribution`].
+#[derive(PartialEq, Debug)]
+pub enum SizeDistribution {
+    Uniform,
+    Log,
+}
+
+impl FromStr for SizeDistribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "uniform" => SizeDistribution::Uniform,
+            "log" => SizeDistribution::Log,
+            _ => return Err(format!("Invalid size distribution: {:?}", s)),
+        })
+    }
+}
+
+impl SizeDistribution {
+    const VARIANTS: &'static [&'static str] = &["uniform", "log"];
+}
+
+impl From<SizeDistribution> for dua::SizeDistribution {
+    fn from(input: SizeDistribution) -> Self {
+        match input {
+            SizeDistribution::Uniform => dua::SizeDistribution::Uniform,
+            SizeDistribution::Log => dua::SizeDistribution::LogUniform,
+        }
+    }
+}
+
 #[derive(Debug, Clap)]
 #[clap(name = "dua", about = "A tool to learn about disk usage, fast!", version = clap::crate_version!())]
 #[clap(setting = clap::AppSettings::ColoredHelp)]
 #[clap(setting = clap::AppSettings::GlobalVersion)]
 #[clap(override_usage = "dua [FLAGS] [OPTIONS] [SUBCOMMAND] [input]...")]
+#[clap(after_help = "EXIT CODES:
+    0      Success
+    1      Some entries could not be read, or a fatal error occurred
+    2      Invalid command-line arguments
+    3      The total size exceeded --threshold
+    124    Stopped by --timeout before the traversal finished
+    130    Interrupted before the traversal finished")]
 pub struct Args {
     #[clap(subcommand)]
     pub command: Option<Command>,
@@ -79,6 +143,13 @@ pub struct Args {
     #[clap(short = 'A', long)]
     pub apparent_size: bool,
 
+    /// How to compute the size of files, which explains most differences to other tools.
+    /// blocks - the amount of allocated 512-byte blocks, like 'du' (default)
+    /// fs-blocks - the apparent size rounded up to the block size of the filesystem
+    /// apparent - the apparent size, same as --apparent-size
+    #[clap(long, default_value = "blocks", possible_values(&SizeModel::VARIANTS))]
+    pub size_model: SizeModel,
+
     /// Count hard-linked files each time they are seen
     #[clap(short = 'l', long)]
     pub count_hard_links: bool,
@@ -87,6 +158,31 @@ pub struct Args {
     #[clap(short = 'x', long)]
     pub stay_on_filesystem: bool,
 
+    /// If set, symbolic links will be followed and their targets counted as if they were located where the link is.
+    /// Links pointing to one of their own ancestors are detected and reported instead of being followed.
+    #[clap(short = 'L', long, visible_alias = "dereference")]
+    pub follow_links: bool,
+
+    /// If set, entries excluded by `.gitignore` and `.ignore` files are skipped, along with `.git` directories.
+    /// This shows how much of a repository is tracked, as opposed to build artifacts and other generated files.
+    #[clap(long)]
+    pub respect_ignore_files: bool,
+
+    /// If set, directories we are not permitted to read will be scanned again with elevated privileges using `sudo`.
+    /// Credentials are requested before the scan starts.
+    #[clap(long)]
+    pub sudo: bool,
+
+    /// If set, stop aggregating after the given duration, like '60s', '5m' or '1h', and print what was found until then
+    /// sorted by size. Sizes of inputs that weren't fully traversed are marked as incomplete.
+    /// Only applies to aggregating, without a subcommand or with 'aggregate'.
+    #[clap(long, value_name = "duration", parse(try_from_str = parse_duration))]
+    pub timeout: Option<Duration>,
+
+    /// If set, only errors will be printed, leaving the result to be communicated through the exit code.
+    #[clap(short = 'q', long)]
+    pub quiet: bool,
+
     /// One or more input files or directories. If unset, we will use all entries in the current working directory.
     #[clap(parse(from_os_str))]
     pub input: Vec<PathBuf>,
@@ -98,10 +194,54 @@ pub enum Command {
     #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
     #[clap(name = "interactive", visible_alias = "i")]
     Interactive {
+        /// If set, entering a directory descends through all directories that contain nothing but a single directory.
+        /// Can be toggled with 'T' at runtime.
+        #[clap(long)]
+        auto_descend: bool,
+        /// If set, show an icon in front of each entry. Requires a terminal font with Nerd Font glyphs.
+        #[clap(long)]
+        icons: bool,
+        /// If set, write the paths of all entries flagged with 'F' to the given file upon exit, one per line.
+        #[clap(long, value_name = "file", parse(from_os_str))]
+        export_flagged: Option<PathBuf>,
+        /// If set, moving marked entries with 'Ctrl + v' proposes this directory to move them into.
+        #[clap(long, value_name = "directory", parse(from_os_str))]
+        move_to: Option<PathBuf>,
+        /// If set, don't shade every other row of the entries list.
+        /// Shading is only used on terminals with 256 or more colors, and never if NO_COLOR is set.
+        #[clap(long)]
+        no_row_shading: bool,
+        /// If set, rescanning all inputs with 'R' reads every directory again. Otherwise only directories whose
+        /// modification time changed since the scan are read, which misses files that were modified in place.
+        #[clap(long)]
+        no_trust_mtime: bool,
+        /// If set, show the scan saved with 'dua snapshot save' to the given file instead of scanning the inputs.
+        /// Its entries can be browsed, but not deleted, moved or refreshed.
+        #[clap(
+            long,
+            value_name = "file",
+            parse(from_os_str),
+            conflicts_with = "input"
+        )]
+        load: Option<PathBuf>,
+        /// If set, show how much each entry grew or shrank since the scan saved with 'dua snapshot save' to the given file.
+        #[clap(long, value_name = "snapshot", parse(from_os_str))]
+        compare: Option<PathBuf>,
         /// One or more input files or directories. If unset, we will use all entries in the current working directory.
         #[clap(parse(from_os_str))]
         input: Vec<PathBuf>,
     },
+    /// List the objects of a bucket and browse them read-only in the terminal user interface, with a directory for each
+    /// part of their keys before a '/'. Uses the 'aws' command line tool along with its credentials.
+    #[cfg(all(
+        feature = "s3",
+        any(feature = "tui-unix", feature = "tui-crossplatform")
+    ))]
+    #[clap(name = "s3")]
+    S3 {
+        /// The bucket and the prefix of all objects to list, like 's3://bucket/logs/'
+        url: dua::S3Url,
+    },
     /// Aggregrate the consumed space of one or more directories or files
     #[clap(name = "aggregate", visible_alias = "a")]
     Aggregate {
@@ -115,8 +255,229 @@ pub enum Command {
         /// If set, no total column will be computed for multiple inputs
         #[clap(long)]
         no_total: bool,
+        /// If set, exit with code 3 if the total size of all inputs exceeds the given size, like '10GB' or '512MiB'.
+        #[clap(long, parse(try_from_str = parse_size))]
+        threshold: Option<u128>,
+        /// Print each path according to the given template instead, like '{path}\t{size:bytes}\t{count}'.
+        /// Placeholders are {path}, {size}, {size:<format>} with any byte format or 'bytes' for plain numbers,
+        /// {count} for the amount of entries and {errors} for the amount of IO errors.
+        /// '\t' and '\n' insert tabs and newlines, '{{' and '}}' literal braces.
+        /// 'csv' and 'tsv' print rows of path,size_bytes,entries,errors below a header instead.
+        #[clap(long = "format", value_name = "template")]
+        template: Option<dua::OutputTemplate>,
+        /// If set, also print the total of each directory down to the given amount of levels below the inputs,
+        /// like 'du -d'. With --no-sort, directories are printed before the directory they are in.
+        #[clap(short = 'd', long, value_name = "levels")]
+        depth: Option<usize>,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Summarize all directories which can't be read by owner and parent directory, to learn where hidden bytes may be
+    #[clap(name = "unreadable", visible_alias = "u")]
+    Unreadable {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Compare the sizes computed by 'aggregate' with the ones obtained by a plain walk using 'stat' and with 'du',
+    /// and exit with a non-zero exit code if they disagree.
+    #[clap(name = "selftest")]
+    Selftest {
         /// One or more input files or directories. If unset, we will use all entries in the current working directory.
         #[clap(parse(from_os_str))]
         input: Vec<PathBuf>,
     },
+    /// Quickly list entries likely to free a lot of space, like the largest files, caches, core dumps and old logs,
+    /// without waiting for a full traversal. Meant for the moment the disk is full and everything is slow.
+    /// The usual locations of caches, crash dumps and logs are looked at first, then each input for an equal share
+    /// of the remaining time.
+    #[clap(name = "emergency")]
+    Emergency {
+        /// The amount of seconds to look for entries, after which whatever was found is printed
+        #[clap(long, default_value = "5")]
+        seconds: u64,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Print the amount of files below the inputs and their total size, optionally for each file extension.
+    #[clap(name = "stats")]
+    Stats {
+        /// If set, print the amount of files and their size for each file extension, the largest last.
+        #[clap(long)]
+        by_extension: bool,
+        /// If set, print the statistics as JSON instead.
+        #[clap(long)]
+        json: bool,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List the largest files found anywhere below the inputs along with their size, the largest first.
+    #[clap(name = "top")]
+    Top {
+        /// The amount of files to list
+        #[clap(short = 'n', long, default_value = "20")]
+        count: usize,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List core dumps, heap profiles and crash minidumps, detected by name and confirmed by their content,
+    /// grouped by kind along with their total size.
+    #[clap(name = "crash-artifacts")]
+    CrashArtifacts {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Group the paths of a Nix or Guix store by the name of the derivation that built them, along with how much of
+    /// them the garbage collector would remove as no root refers to them. Uses 'nix-store' or 'guix' to learn that.
+    #[clap(name = "store")]
+    Store {
+        /// The store to summarize. If unset, /nix/store or /gnu/store, whichever exists.
+        #[clap(parse(from_os_str))]
+        store: Option<PathBuf>,
+    },
+    /// Summarize the space taken by the systemd journal by unit and by age, and by the other logs by service,
+    /// along with the 'journalctl --vacuum-size' command that removes archived journal files older than a month.
+    #[clap(name = "logs")]
+    Logs {
+        /// The directory with the logs and the 'journal' directory
+        #[clap(parse(from_os_str), default_value = "/var/log")]
+        log_dir: PathBuf,
+    },
+    /// Summarize the space taken by each Flatpak and Snap application and runtime, along with how much of it their
+    /// old revisions take, which pruning them would free.
+    #[clap(name = "apps")]
+    Apps,
+    /// Save snapshots of a scan, to browse huge directory trees later without scanning them again
+    #[clap(name = "snapshot")]
+    Snapshot {
+        #[clap(subcommand)]
+        cmd: SnapshotCommand,
+    },
+    /// List all directories that grew or shrank between two snapshots saved with 'dua snapshot save',
+    /// with the largest change last.
+    #[clap(name = "diff")]
+    Diff {
+        /// The snapshot of the earlier scan
+        #[clap(parse(from_os_str))]
+        before: PathBuf,
+        /// The snapshot of the later scan
+        #[clap(parse(from_os_str))]
+        after: PathBuf,
+    },
+    /// Create a reproducible synthetic directory tree for testing the performance and correctness of traversals
+    /// and deletions. Meant for development, hence not listed in the help.
+    #[clap(name = "gen-fixture", setting = clap::AppSettings::Hidden)]
+    GenFixture {
+        /// Trees created with the same seed and otherwise equal options are identical
+        #[clap(long, default_value = "0")]
+        seed: u64,
+        /// The amount of directory levels below the root
+        #[clap(long, default_value = "3")]
+        depth: u32,
+        /// The amount of directories in each directory above the deepest level
+        #[clap(long, default_value = "4")]
+        fan_out: u32,
+        /// The amount of files in each directory, including the root
+        #[clap(long, default_value = "8")]
+        files: u32,
+        /// The smallest size of a file, like '0' or '4KB'
+        #[clap(long, default_value = "0", parse(try_from_str = parse_file_size))]
+        min_size: u64,
+        /// The largest size of a file, like '1MB'
+        #[clap(long, default_value = "1MB", parse(try_from_str = parse_file_size))]
+        max_size: u64,
+        /// How file sizes are spread between the smallest and largest one.
+        /// uniform - every size is equally likely
+        /// log - small files are much more common than large ones (default)
+        #[clap(long, default_value = "log", possible_values(&SizeDistribution::VARIANTS))]
+        distribution: SizeDistribution,
+        /// The amount of symbolic links to random files and directories, which may be ancestors of the link
+        #[clap(long, default_value = "0")]
+        symlinks: u32,
+        /// The amount of additional hard links to random files
+        #[clap(long, default_value = "0")]
+        hard_links: u32,
+        /// If set, files are created without writing their content, so they take next to no space on disk
+        #[clap(long)]
+        sparse: bool,
+        /// The directory to create the tree in, which must not exist or be empty
+        #[clap(parse(from_os_str))]
+        root: PathBuf,
+    },
+    /// Print the size in bytes and the amount of IO errors of each input, one line per input.
+    /// Used internally to re-scan unreadable directories with elevated privileges.
+    #[clap(name = "privileged-measure", setting = clap::AppSettings::Hidden)]
+    PrivilegedMeasure {
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clap)]
+pub enum SnapshotCommand {
+    /// Scan the inputs and save the result to the given file, to be shown later with 'dua i --load <file>'
+    #[clap(name = "save")]
+    Save {
+        /// The file to write the snapshot to
+        #[clap(parse(from_os_str))]
+        file: PathBuf,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+}
+
+fn parse_file_size(size: &str) -> Result<u64, String> {
+    u64::try_from(parse_size(size)?).map_err(|_| format!("{:?} is too large for a file", size))
+}
+
+fn parse_size(size: &str) -> Result<u128, String> {
+    byte_unit::Byte::from_str(size)
+        .map(|b| b.get_bytes())
+        .map_err(|err| format!("Invalid size {:?}: {}", size, err))
+}
+
+/// Parse durations like '60s', '1.5m', '2h' or '500ms', with plain numbers being seconds.
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(duration.len());
+    let (amount, unit) = duration.split_at(unit_start);
+    let seconds_per_unit = match unit.trim() {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 60.0 * 60.0,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit {:?}, use ms, s, m or h",
+                unit
+            ))
+        }
+    };
+    amount
+        .parse::<f64>()
+        .ok()
+        .and_then(|amount| Duration::try_from_secs_f64(amount * seconds_per_unit).ok())
+        .ok_or_else(|| format!("Invalid duration {:?}", duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_of_the_privileged_helper_may_look_like_flags() {
+        let args = Args::try_parse_from(vec!["dua", "privileged-measure", "--", "-dir"])
+            .expect("valid arguments");
+        assert!(matches!(
+            args.command,
+            Some(Command::PrivilegedMeasure { input }) if input == vec![PathBuf::from("-dir")]
+        ));
+    }
 }
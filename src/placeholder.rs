@@ -0,0 +1,34 @@
+/// Return true if the file described by `metadata` is a placeholder of a cloud storage provider whose content
+/// isn't necessarily available locally, like OneDrive files that are only available online, or files evicted
+/// by iCloud Drive or Dropbox on macOS.
+///
+/// The size on disk of such files is what's actually stored locally, which may be nothing, while their
+/// apparent size is the size of the file in the cloud.
+#[cfg(windows)]
+pub fn is_cloud_placeholder(metadata: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+
+    metadata.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(metadata: &std::fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    const SF_DATALESS: u32 = 0x40000000;
+
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_cloud_placeholder(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
@@ -0,0 +1,169 @@
+use crate::{FileSizes, InodeFilter, WalkOptions};
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The name of the hidden sub-command run through `sudo` to measure directories we can't read ourselves.
+pub const HELPER_SUBCOMMAND: &str = "privileged-measure";
+
+/// Ask `sudo` to validate (and cache) the user's credentials, prompting for a password if needed.
+///
+/// This must happen while we still own the terminal, as later invocations of `sudo` never prompt.
+pub fn authenticate() -> Result<()> {
+    let status = Command::new("sudo")
+        .arg("-v")
+        .status()
+        .with_context(|| "Could not run 'sudo' to obtain elevated privileges")?;
+    if !status.success() {
+        bail!("'sudo' did not grant elevated privileges");
+    }
+    Ok(())
+}
+
+/// Re-scan all `paths` with elevated privileges and return their size in bytes along with the
+/// amount of IO errors encountered, in the order of `paths`.
+///
+/// This runs our own executable through `sudo -n`, which fails if [`authenticate()`] wasn't called before.
+pub fn sizes_of(walk_options: &WalkOptions, paths: &[PathBuf]) -> Result<Vec<(u128, u64)>> {
+    let output = Command::new("sudo")
+        .arg("-n")
+        .arg(std::env::current_exe()?)
+        .args(helper_args(walk_options, paths))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| "Could not run 'sudo' to re-scan unreadable directories")?;
+    if !output.status.success() {
+        bail!("'sudo' failed to re-scan - are credentials cached? Try running with --sudo");
+    }
+    let sizes = parse_sizes(&String::from_utf8_lossy(&output.stdout))?;
+    if sizes.len() != paths.len() {
+        bail!(
+            "Expected sizes of {} paths from privileged helper, got {}",
+            paths.len(),
+            sizes.len()
+        );
+    }
+    Ok(sizes)
+}
+
+/// Write the size in bytes and the amount of IO errors of each of the given `paths` to `out`, one line per path.
+///
+/// This is what runs with elevated privileges on behalf of [`sizes_of()`].
+pub fn measure(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<()> {
+    let mut inodes = InodeFilter::default();
+    for path in paths {
+        let mut num_bytes = 0u128;
+        let mut num_errors = 0u64;
+        let sizes = FileSizes::new(&walk_options, path.as_ref())?;
+        for entry in walk_options.iter_from_path(path.as_ref()) {
+            match entry {
+                Ok(entry) => {
+                    if entry.read_children_error.is_some() {
+                        num_errors += 1;
+                    }
+                    num_bytes += match entry.client_state {
+                        Some(Ok(ref m)) if sizes.counts(m, &mut inodes) => {
+                            sizes.size_of(&entry, m).unwrap_or_else(|_| {
+                                num_errors += 1;
+                                0
+                            })
+                        }
+                        Some(Ok(_)) | None => 0,
+                        Some(Err(_)) => {
+                            num_errors += 1;
+                            0
+                        }
+                    } as u128;
+                }
+                Err(_) => num_errors += 1,
+            }
+        }
+        writeln!(out, "{}\t{}", num_bytes, num_errors)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn is_permission_denied(err: &jwalk::Error) -> bool {
+    err.io_error()
+        .is_some_and(|err| err.kind() == io::ErrorKind::PermissionDenied)
+}
+
+/// The arguments making our executable measure `paths` with `walk_options`, which are never taken for flags.
+fn helper_args(walk_options: &WalkOptions, paths: &[PathBuf]) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["--threads".into(), walk_options.threads.to_string().into()];
+    if walk_options.apparent_size {
+        args.push("--apparent-size".into());
+    }
+    if walk_options.round_to_fs_blocks {
+        args.extend(["--size-model".into(), "fs-blocks".into()]);
+    }
+    if walk_options.count_hard_links {
+        args.push("--count-hard-links".into());
+    }
+    if !walk_options.cross_filesystems {
+        args.push("--stay-on-filesystem".into());
+    }
+    if walk_options.follow_links {
+        args.push("--follow-links".into());
+    }
+    if walk_options.respect_ignore_files {
+        args.push("--respect-ignore-files".into());
+    }
+    args.extend([HELPER_SUBCOMMAND.into(), "--".into()]);
+    args.extend(paths.iter().map(|p| p.into()));
+    args
+}
+
+fn parse_sizes(output: &str) -> Result<Vec<(u128, u64)>> {
+    output
+        .lines()
+        .map(|line| {
+            let mut tokens = line.split('\t');
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some(bytes), Some(errors), None) => Ok((bytes.parse()?, errors.parse()?)),
+                _ => Err(anyhow!(
+                    "Unexpected line from privileged helper: {:?}",
+                    line
+                )),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_helper_output() {
+        assert_eq!(
+            parse_sizes("1024\t0\n0\t3\n").unwrap(),
+            vec![(1024, 0), (0, 3)]
+        );
+        assert!(parse_sizes("").unwrap().is_empty());
+        assert!(parse_sizes("1024\n").is_err(), "errors are mandatory");
+        assert!(parse_sizes("a\t0\n").is_err(), "sizes must be numbers");
+    }
+
+    #[test]
+    fn paths_are_passed_after_all_flags() {
+        let args = helper_args(
+            &WalkOptions::for_tests(),
+            &["-dir".into(), "--apparent-size".into()],
+        );
+        assert_eq!(
+            &args[args.len() - 4..],
+            [HELPER_SUBCOMMAND, "--", "-dir", "--apparent-size"],
+            "paths looking like flags are still paths"
+        );
+    }
+}
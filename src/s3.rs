@@ -0,0 +1,277 @@
+use crate::traverse::{EntryData, MtimeRange, Traversal, TreeIndex, RECENT_PERIOD_DAYS};
+use anyhow::{anyhow, bail, Context, Result};
+use petgraph::{visit::DfsPostOrder, Direction};
+use std::{collections::HashMap, convert::TryFrom, fmt, process::Command, str::FromStr};
+
+/// The separator of pseudo-directories in object keys
+const DELIMITER: char = '/';
+
+/// A bucket along with the prefix of the objects to look at, as in `s3://bucket/prefix`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Url {
+    pub bucket: String,
+    /// The start of the keys of all objects to list, which may be empty to list the whole bucket
+    pub prefix: String,
+}
+
+impl FromStr for S3Url {
+    type Err = anyhow::Error;
+
+    fn from_str(url: &str) -> Result<Self> {
+        let path = url
+            .strip_prefix("s3://")
+            .ok_or_else(|| anyhow!("Expected a URL like 's3://bucket/prefix', got '{}'", url))?;
+        let (bucket, prefix) = path.split_once(DELIMITER).unwrap_or((path, ""));
+        if bucket.is_empty() {
+            bail!("The URL '{}' lacks the name of the bucket", url);
+        }
+        Ok(S3Url {
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+        })
+    }
+}
+
+impl fmt::Display for S3Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "s3://{}/{}", self.bucket, self.prefix)
+    }
+}
+
+/// An object as listed by the object storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedObject {
+    pub key: String,
+    pub size: u128,
+    /// The time of the last modification in seconds since the unix epoch, if known
+    pub modified: Option<u32>,
+}
+
+/// List all objects below `url` with the `aws` command line tool and build a tree of them, like [`objects_to_tree()`].
+///
+/// Credentials, the region and custom endpoints are taken from the configuration of the tool, so other
+/// S3-compatible object storages work as well.
+pub fn list_bucket(url: &S3Url) -> Result<Traversal> {
+    let output = Command::new("aws")
+        .args(["s3api", "list-objects-v2", "--bucket", &url.bucket])
+        .args(["--prefix", &url.prefix])
+        .args(["--output", "text"])
+        .args(["--query", "Contents[].[Key,Size,LastModified]"])
+        .output()
+        .context("Could not run 'aws', which is needed to list objects")?;
+    if !output.status.success() {
+        bail!(
+            "Could not list the objects of '{}': {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let objects = parse_listing(&String::from_utf8_lossy(&output.stdout))?;
+    Ok(objects_to_tree(url, objects))
+}
+
+/// Parse the tab-separated key, size and modification time of each object, one per line.
+///
+/// An empty listing is printed as `None`.
+pub fn parse_listing(listing: &str) -> Result<Vec<ListedObject>> {
+    listing
+        .lines()
+        .filter(|line| !line.is_empty() && *line != "None")
+        .map(|line| {
+            // keys may contain tabs, unlike sizes and times
+            let mut fields = line.rsplitn(3, '\t');
+            let (modified, size, key) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(modified), Some(size), Some(key)) => (modified, size, key),
+                _ => bail!("Expected a key, size and time in listing line '{}'", line),
+            };
+            Ok(ListedObject {
+                key: key.into(),
+                size: size
+                    .parse()
+                    .with_context(|| format!("Invalid size in listing line '{}'", line))?,
+                modified: parse_timestamp(modified),
+            })
+        })
+        .collect()
+}
+
+/// Build a tree of `objects`, with a directory for each distinct part of their keys before a `/`.
+///
+/// The only input is the prefix of `url` up to its last `/`, so a prefix like `logs/2024` shows all pseudo-directories
+/// starting with `2024` in `logs/`. Keys ending in `/` are the markers some tools create for empty directories.
+pub fn objects_to_tree(url: &S3Url, objects: Vec<ListedObject>) -> Traversal {
+    let base_len = url.prefix.rfind(DELIMITER).map_or(0, |pos| pos + 1);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs().min(u32::MAX as u64) as u32);
+    let mut t = Traversal {
+        recent_since: now.saturating_sub(RECENT_PERIOD_DAYS * 24 * 60 * 60),
+        ..Default::default()
+    };
+    t.root_index = t.tree.add_node(EntryData::default());
+    let input = t.tree.add_node(EntryData {
+        name: format!("s3://{}/{}", url.bucket, &url.prefix[..base_len]).into(),
+        ..Default::default()
+    });
+    t.tree.add_edge(t.root_index, input, ());
+
+    let mut directories = HashMap::<(TreeIndex, String), TreeIndex>::new();
+    for object in objects {
+        let relative = match object.key.get(base_len..) {
+            Some(relative) if object.key.starts_with(&url.prefix) => relative,
+            _ => continue,
+        };
+        let is_marker = relative.ends_with(DELIMITER);
+        let mut parts: Vec<_> = relative
+            .split(DELIMITER)
+            .filter(|p| !p.is_empty())
+            .collect();
+        let name = match is_marker {
+            true => None,
+            false => parts.pop(),
+        };
+        let mut parent = input;
+        for part in parts {
+            parent = *directories.entry((parent, part.into())).or_insert_with(|| {
+                let index = t.tree.add_node(EntryData {
+                    name: part.into(),
+                    ..Default::default()
+                });
+                t.tree.add_edge(parent, index, ());
+                index
+            });
+        }
+        if let Some(name) = name {
+            let index = t.tree.add_node(EntryData {
+                name: name.into(),
+                size: object.size,
+                alternate_size: object.size,
+                mtime_range: object.modified.map(|secs| MtimeRange {
+                    oldest: secs,
+                    newest: secs,
+                }),
+                ..Default::default()
+            });
+            t.tree.add_edge(parent, index, ());
+        }
+    }
+
+    let mut dfs = DfsPostOrder::new(&t.tree, t.root_index);
+    while let Some(index) = dfs.next(&t.tree) {
+        let children: Vec<_> = t
+            .tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .collect();
+        if !children.is_empty() {
+            t.tree[index].size = children.iter().map(|idx| t.tree[*idx].size).sum();
+        }
+    }
+    t.aggregate_directories();
+    t.aggregate_entry_counts();
+    t.aggregate_recent_sizes();
+    t.entries_traversed = t.tree[t.root_index].entry_count;
+    t.total_bytes = Some(t.tree[t.root_index].size);
+    t
+}
+
+/// The seconds since the unix epoch of a time like `2024-03-01T12:30:00.000Z` or `2024-03-01T12:30:00+00:00`,
+/// which object storages always give in UTC.
+fn parse_timestamp(time: &str) -> Option<u32> {
+    let field = |range: std::ops::Range<usize>| time.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // days since the epoch in the proleptic gregorian calendar, with years starting in March
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    u32::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn urls_name_a_bucket_and_an_optional_prefix() -> Result<()> {
+        assert_eq!(
+            "s3://data/logs/2024".parse::<S3Url>()?,
+            S3Url {
+                bucket: "data".into(),
+                prefix: "logs/2024".into()
+            }
+        );
+        assert_eq!("s3://data".parse::<S3Url>()?.prefix, "");
+        assert!("data/logs".parse::<S3Url>().is_err());
+        assert!("s3:///logs".parse::<S3Url>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn listings_are_parsed_with_keys_containing_tabs() -> Result<()> {
+        let objects = parse_listing(
+            "a/b.log\t10\t2024-03-01T12:30:00.000Z\nodd\tname\t5\t1970-01-02T00:00:01+00:00\n",
+        )?;
+        assert_eq!(
+            objects,
+            vec![
+                ListedObject {
+                    key: "a/b.log".into(),
+                    size: 10,
+                    modified: Some(1_709_296_200)
+                },
+                ListedObject {
+                    key: "odd\tname".into(),
+                    size: 5,
+                    modified: Some(86_401)
+                }
+            ]
+        );
+        assert_eq!(parse_listing("None\n")?, Vec::new());
+        assert!(parse_listing("key-without-size\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn keys_are_split_into_pseudo_directories() {
+        let object = |key: &str, size| ListedObject {
+            key: key.into(),
+            size,
+            modified: Some(1_000),
+        };
+        let url = S3Url {
+            bucket: "data".into(),
+            prefix: "logs/2".into(),
+        };
+        let t = objects_to_tree(
+            &url,
+            vec![
+                object("logs/2023/jan/1.log", 100),
+                object("logs/2023/jan/2.log", 50),
+                object("logs/2024/feb/", 0),
+                object("logs/2024.tar", 7),
+                object("logs/1999/old.log", 1_000),
+            ],
+        );
+        let size_of = |name: &str| {
+            t.tree
+                .node_weights()
+                .find(|e| e.name == Path::new(name))
+                .map(|e| (e.size, e.entry_count))
+        };
+        assert_eq!(size_of("s3://data/logs/"), Some((157, 7)));
+        assert_eq!(size_of("2023"), Some((150, 3)));
+        assert_eq!(size_of("jan"), Some((150, 2)));
+        assert_eq!(size_of("feb"), Some((0, 0)), "markers are directories");
+        assert_eq!(size_of("2024.tar"), Some((7, 0)));
+        assert_eq!(size_of("1999"), None, "the prefix must match");
+        assert_eq!(t.total_bytes, Some(157));
+        assert_eq!(t.entries_traversed, 8);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{aggregate, crossdev, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Sizes of a path as obtained without our own traversal machinery
+#[derive(Default, Debug, PartialEq, Eq)]
+struct Reference {
+    /// The sum of `st_size` of all files
+    apparent: u128,
+    /// The sum of `st_blocks * 512` of all files, if the platform provides it
+    blocks: Option<u128>,
+    errors: u64,
+}
+
+/// Measure each of `paths` like `dua aggregate` does, and compare the result with a plain recursive walk
+/// using the platform's `stat` information as well as with `du`, if available. A reconciliation table is
+/// written to `out`.
+///
+/// Any mismatch between our sizes and the ones derived from `stat` is counted as error, `du` is
+/// informational only as it also counts the space used by directories themselves.
+/// Symbolic links are never followed, independently of `walk_options`.
+pub fn selftest(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    for path in paths {
+        let path = path.as_ref();
+        let measure = |apparent_size| -> Result<_> {
+            let (walk_result, stats) = aggregate(
+                io::sink(),
+                None::<io::Stderr>,
+                WalkOptions {
+                    apparent_size,
+                    follow_links: false,
+                    round_to_fs_blocks: false,
+                    respect_ignore_files: false,
+                    ..walk_options.clone()
+                },
+                false,
+                false,
+                false,
+                None,
+                None,
+                None,
+                Some(path),
+            )?;
+            Ok((stats.total_bytes, walk_result.num_errors))
+        };
+        let (apparent, apparent_errors) = measure(true)?;
+        let (on_disk, on_disk_errors) = measure(false)?;
+        let reference = reference_sizes(&walk_options, path)?;
+
+        writeln!(out, "{}", path.display())?;
+        writeln!(
+            out,
+            "  {:<24} {:>16} {:>16} {:>16}",
+            "", "dua", "reference", "difference"
+        )?;
+        let mut row = |name: &str, ours: u128, theirs: Option<u128>| -> io::Result<bool> {
+            let (theirs, difference, matches) = match theirs {
+                Some(theirs) => (
+                    theirs.to_string(),
+                    (ours as i128 - theirs as i128).to_string(),
+                    ours == theirs,
+                ),
+                None => ("-".into(), "-".into(), true),
+            };
+            writeln!(
+                out,
+                "  {:<24} {:>16} {:>16} {:>16}",
+                name, ours, theirs, difference
+            )?;
+            Ok(matches)
+        };
+        let mut mismatches = 0;
+        if !row(
+            "apparent size (st_size)",
+            apparent,
+            Some(reference.apparent),
+        )? {
+            mismatches += 1;
+        }
+        if !row("disk usage (st_blocks)", on_disk, reference.blocks)? {
+            mismatches += 1;
+        }
+        row("disk usage (du -sk)", on_disk, du_bytes(path))?;
+        row(
+            "errors",
+            (apparent_errors.max(on_disk_errors)) as u128,
+            Some(reference.errors as u128),
+        )?;
+        writeln!(
+            out,
+            "  {}",
+            match mismatches {
+                0 => "OK".to_string(),
+                n => format!("MISMATCH in {} size{}", n, if n > 1 { "s" } else { "" }),
+            }
+        )?;
+        res.num_errors += apparent_errors.max(on_disk_errors) + mismatches;
+    }
+    Ok(res)
+}
+
+fn reference_sizes(walk_options: &WalkOptions, root: &Path) -> Result<Reference> {
+    let root_meta = root.symlink_metadata()?;
+    let mut reference = Reference {
+        blocks: blocks_of(&root_meta).map(|_| 0),
+        ..Default::default()
+    };
+    let device_id = crossdev::init(root)?;
+    let mut inodes = InodeFilter::default();
+    let mut directories = Vec::new();
+    if root_meta.is_dir() {
+        directories.push(PathBuf::from(root));
+    } else {
+        add_file(
+            &mut reference,
+            &mut inodes,
+            walk_options,
+            device_id,
+            root_meta,
+        );
+    }
+    while let Some(directory) = directories.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => {
+                reference.errors += 1;
+                continue;
+            }
+        };
+        for entry in entries {
+            let meta = match entry.and_then(|e| Ok((e.path(), e.path().symlink_metadata()?))) {
+                Ok((path, meta)) if meta.is_dir() => {
+                    directories.push(path);
+                    continue;
+                }
+                Ok((_, meta)) => meta,
+                Err(_) => {
+                    reference.errors += 1;
+                    continue;
+                }
+            };
+            add_file(&mut reference, &mut inodes, walk_options, device_id, meta);
+        }
+    }
+    Ok(reference)
+}
+
+fn add_file(
+    reference: &mut Reference,
+    inodes: &mut InodeFilter,
+    walk_options: &WalkOptions,
+    device_id: u64,
+    meta: fs::Metadata,
+) {
+    if (walk_options.count_hard_links || inodes.add(&meta))
+        && (walk_options.cross_filesystems || crossdev::is_same_device(device_id, &meta))
+    {
+        reference.apparent += meta.len() as u128;
+        if let (Some(total), Some(blocks)) = (reference.blocks.as_mut(), blocks_of(&meta)) {
+            *total += blocks;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn blocks_of(meta: &fs::Metadata) -> Option<u128> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.blocks() as u128 * 512)
+}
+
+#[cfg(not(unix))]
+fn blocks_of(_meta: &fs::Metadata) -> Option<u128> {
+    None
+}
+
+/// The disk usage of `path` according to `du -sk`, in bytes.
+fn du_bytes(path: &Path) -> Option<u128> {
+    let output = Command::new("du")
+        .arg("-sk")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    parse_du(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_du(output: &str) -> Option<u128> {
+    output
+        .split_whitespace()
+        .next()?
+        .parse::<u128>()
+        .ok()
+        .map(|kb| kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_du_output() {
+        assert_eq!(parse_du("12\t/some/path\n"), Some(12 * 1024));
+        assert_eq!(parse_du(""), None);
+        assert_eq!(parse_du("du: cannot access"), None);
+    }
+
+    #[test]
+    fn it_agrees_with_stat_on_fixtures() -> Result<()> {
+        let mut out = Vec::new();
+        let res = selftest(
+            &mut out,
+            WalkOptions {
+                byte_format: crate::ByteFormat::Metric,
+                apparent_size: false,
+                cross_filesystems: false,
+                ..WalkOptions::for_tests()
+            },
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample-01")),
+        )?;
+        assert_eq!(res.num_errors, 0, "{}", String::from_utf8_lossy(&out));
+        assert!(String::from_utf8_lossy(&out).contains("OK"));
+        Ok(())
+    }
+}
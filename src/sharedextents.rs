@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+/// The amount of files to inspect at most, to keep the check quick enough to be done interactively
+const MAX_FILES: usize = 10_000;
+
+/// Data of files on copy-on-write filesystems that is also referenced by other files, like reflinked copies
+/// or snapshots, and which therefore isn't freed when the files are deleted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SharedExtents {
+    /// The amount of files sharing data with files that aren't among the inspected ones
+    pub files: usize,
+    /// The amount of bytes shared with other files
+    pub bytes: u64,
+}
+
+/// A range of blocks on disk used by a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Extent {
+    physical_start: u64,
+    blocks: u64,
+    is_shared: bool,
+}
+
+impl SharedExtents {
+    /// Find the data of all files at or below `paths` that is shared with files not among them, or `None` if
+    /// that can't be determined.
+    ///
+    /// This runs `filefrag -v`, which prints the extents of a file along with a `shared` flag obtained from the
+    /// `FIEMAP` ioctl.
+    #[cfg(target_os = "linux")]
+    pub fn of(paths: &[PathBuf]) -> Option<Self> {
+        let mut files = Vec::new();
+        for path in paths {
+            collect_files(path, &mut files);
+        }
+        let mut extents_by_file = Vec::with_capacity(files.len());
+        for chunk in files.chunks(256) {
+            let output = std::process::Command::new("filefrag")
+                .arg("-v")
+                .args(chunk)
+                .stdin(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()?;
+            // files that can't be inspected are left out, which fails the command
+            extents_by_file.extend(parse_filefrag(&String::from_utf8_lossy(&output.stdout)));
+        }
+        Some(Self::shared_with_others(&extents_by_file))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn of(_paths: &[PathBuf]) -> Option<Self> {
+        None
+    }
+
+    /// Sum up all shared extents of `extents_by_file`, along with the block size of each file, which don't
+    /// overlap with one of another file among them.
+    ///
+    /// All extents are sorted by where they start, so an extent overlaps one of another file before it if the one
+    /// of them reaching the furthest does, and one of another file after it if the one of them starting the soonest does.
+    fn shared_with_others(extents_by_file: &[(u64, Vec<Extent>)]) -> Self {
+        let mut extents: Vec<_> = extents_by_file
+            .iter()
+            .enumerate()
+            .flat_map(|(file, (_, extents))| extents.iter().map(move |extent| (file, *extent)))
+            .collect();
+        extents.sort_unstable_by_key(|(_, extent)| extent.physical_start);
+
+        let mut overlaps = vec![false; extents.len()];
+        let mut furthest = Leading::default();
+        for ((file, extent), overlaps) in extents.iter().zip(overlaps.iter_mut()) {
+            *overlaps = furthest
+                .of_other_than(*file)
+                .is_some_and(|end| end > extent.physical_start);
+            furthest.add(*file, extent.end(), |end, other| end > other);
+        }
+        let mut soonest = Leading::default();
+        for ((file, extent), overlaps) in extents.iter().zip(overlaps.iter_mut()).rev() {
+            *overlaps |= soonest
+                .of_other_than(*file)
+                .is_some_and(|start| start < extent.end());
+            soonest.add(*file, extent.physical_start, |start, other| start <= other);
+        }
+
+        let mut shared_blocks = vec![0; extents_by_file.len()];
+        for ((file, extent), overlaps) in extents.iter().zip(overlaps) {
+            if extent.is_shared && !overlaps {
+                shared_blocks[*file] += extent.blocks;
+            }
+        }
+        let mut shared = SharedExtents::default();
+        for (blocks, (block_size, _)) in shared_blocks.into_iter().zip(extents_by_file) {
+            if blocks > 0 {
+                shared.files += 1;
+                shared.bytes += blocks * block_size;
+            }
+        }
+        shared
+    }
+}
+
+/// The leading value of all files seen so far, and the leading value among all other files than the one it
+/// belongs to, which is all it takes to know the leading value of all files other than any given one.
+#[derive(Default)]
+struct Leading {
+    first: Option<(usize, u64)>,
+    second: Option<(usize, u64)>,
+}
+
+impl Leading {
+    fn add(&mut self, file: usize, value: u64, leads: impl Fn(u64, u64) -> bool) {
+        let beats = |current: Option<(usize, u64)>| current.is_none_or(|(_, v)| leads(value, v));
+        if self.first.is_some_and(|(first, _)| first == file) {
+            if beats(self.first) {
+                self.first = Some((file, value));
+            }
+        } else if beats(self.first) {
+            self.second = self.first;
+            self.first = Some((file, value));
+        } else if beats(self.second) {
+            self.second = Some((file, value));
+        }
+    }
+
+    fn of_other_than(&self, file: usize) -> Option<u64> {
+        match self.first {
+            Some((first, _)) if first == file => self.second.map(|(_, v)| v),
+            first => first.map(|(_, v)| v),
+        }
+    }
+}
+
+impl Extent {
+    fn end(&self) -> u64 {
+        self.physical_start + self.blocks
+    }
+}
+
+/// Add `path` to `files` if it is a file, or all files below it if it is a directory, without following symlinks.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if files.len() >= MAX_FILES {
+        return;
+    }
+    let meta = match path.symlink_metadata() {
+        Ok(meta) => meta,
+        Err(_) => return,
+    };
+    if meta.is_file() {
+        files.push(path.to_owned());
+    } else if meta.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_files(&entry.path(), files);
+            }
+        }
+    }
+}
+
+/// Parse the output of `filefrag -v`, which for each file is a line with its size and block size, followed
+/// by a table of its extents with the physical offset and length in blocks along with flags like `shared`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_filefrag(output: &str) -> Vec<(u64, Vec<Extent>)> {
+    let mut files = Vec::new();
+    for line in output.lines() {
+        if line.starts_with("File size of ") {
+            // like `(256 blocks of 4096 bytes)`, or `(1 block of 4096 bytes)`
+            let block_size = line
+                .rsplit_once(" of ")
+                .and_then(|(_, rest)| rest.split_whitespace().next()?.parse().ok());
+            if let Some(block_size) = block_size {
+                files.push((block_size, Vec::new()));
+            }
+            continue;
+        }
+        let fields: Vec<_> = line.split(':').map(str::trim).collect();
+        let is_extent = fields.len() >= 5 && fields[0].parse::<u64>().is_ok();
+        let extent = is_extent
+            .then(|| {
+                Some(Extent {
+                    physical_start: fields[2].split("..").next()?.trim().parse().ok()?,
+                    blocks: fields[3].parse().ok()?,
+                    is_shared: fields[fields.len() - 1].split(',').any(|f| f == "shared"),
+                })
+            })
+            .flatten();
+        if let (Some(extent), Some((_, extents))) = (extent, files.last_mut()) {
+            extents.push(extent);
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_filefrag_output() {
+        let files = parse_filefrag(
+            "Filesystem type is: 9123683e\n\
+             File size of a is 1048576 (256 blocks of 4096 bytes)\n \
+             ext:     logical_offset:        physical_offset: length:   expected: flags:\n   \
+             0:        0..     127:       3392..      3519:    128:             shared\n   \
+             1:      128..     255:       5000..      5127:    128:       3520: last,eof\n\
+             a: 2 extents found\n\
+             File size of b is 524288 (128 blocks of 4096 bytes)\n \
+             ext:     logical_offset:        physical_offset: length:   expected: flags:\n   \
+             0:        0..     127:       3392..      3519:    128:             last,shared,eof\n\
+             b: 1 extent found\n",
+        );
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, 4096);
+        assert_eq!(
+            parse_filefrag("File size of c is 1633 (1 block of 4096 bytes)\n"),
+            vec![(4096, Vec::new())]
+        );
+        assert_eq!(
+            files[0].1,
+            vec![
+                Extent {
+                    physical_start: 3392,
+                    blocks: 128,
+                    is_shared: true
+                },
+                Extent {
+                    physical_start: 5000,
+                    blocks: 128,
+                    is_shared: false
+                }
+            ]
+        );
+
+        assert_eq!(
+            SharedExtents::shared_with_others(&files),
+            SharedExtents::default(),
+            "both files share their data with each other only"
+        );
+        assert_eq!(
+            SharedExtents::shared_with_others(&files[1..]),
+            SharedExtents {
+                files: 1,
+                bytes: 128 * 4096
+            }
+        );
+
+        let extent = |physical_start, blocks, is_shared| Extent {
+            physical_start,
+            blocks,
+            is_shared,
+        };
+        let files = vec![
+            (1, vec![extent(0, 10, true), extent(40, 5, true)]),
+            (1, vec![extent(2, 1, true), extent(30, 10, true)]),
+            (1, vec![extent(5, 1, false), extent(42, 1, true)]),
+        ];
+        assert_eq!(
+            SharedExtents::shared_with_others(&files),
+            SharedExtents { files: 1, bytes: 10 },
+            "extents count unless they overlap with one of another file, no matter how far away it starts"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    traverse::{EntryData, MtimeRange, Traversal, TreeIndex},
+    WalkOptions, WalkResult,
+};
+use anyhow::{bail, Context, Result};
+use petgraph::Direction;
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// The first bytes of every snapshot, ending in the version of the format
+const MAGIC: &[u8] = b"DUASNAP\x02";
+/// Names longer than this indicate a corrupted snapshot, as no filesystem allows them
+const MAX_NAME_LEN: u32 = 64 * 1024;
+
+const METADATA_IO_ERROR: u8 = 1;
+const HAS_MTIME_RANGE: u8 = 1 << 1;
+const PERMISSION_DENIED: u8 = 1 << 2;
+const IS_SYMLINK: u8 = 1 << 3;
+
+/// Traverse `paths` and save the result to the snapshot at `destination`, to be loaded with `dua i --load`.
+pub fn save_snapshot(
+    walk_options: WalkOptions,
+    paths: Vec<PathBuf>,
+    destination: &Path,
+) -> Result<WalkResult> {
+    let apparent_size = walk_options.apparent_size;
+    let traversal = Traversal::from_walk(walk_options, paths, |_| Ok(false))?
+        .expect("traversals without updates run to completion");
+    let file = fs::File::create(destination)
+        .with_context(|| format!("Could not create snapshot '{}'", destination.display()))?;
+    let mut out = BufWriter::new(file);
+    traversal.write_snapshot(&mut out, apparent_size)?;
+    out.flush()?;
+    Ok(WalkResult {
+        num_errors: traversal.io_errors,
+        symlink_loops: traversal.symlink_loops,
+        ..Default::default()
+    })
+}
+
+/// Load the snapshot at `path` written by [`save_snapshot()`], with sizes according to `apparent_size`.
+pub fn load_snapshot(path: &Path, apparent_size: bool) -> Result<Traversal> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Could not open snapshot '{}'", path.display()))?;
+    Traversal::from_snapshot(BufReader::new(file), apparent_size)
+        .with_context(|| format!("Could not load snapshot '{}'", path.display()))
+}
+
+impl Traversal {
+    /// Write the tree and the totals of this traversal to `out`, which must have been done with `apparent_size`.
+    ///
+    /// Entries are written depth-first, each followed by its children. The names of the inputs are made absolute
+    /// so the snapshot can be loaded from any directory. Symlink loops aren't part of it.
+    pub fn write_snapshot(&self, mut out: impl Write, apparent_size: bool) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[apparent_size as u8])?;
+        out.write_all(&self.entries_traversed.to_le_bytes())?;
+        out.write_all(&self.io_errors.to_le_bytes())?;
+        out.write_all(&self.recent_since.to_le_bytes())?;
+
+        let mut stack = vec![self.root_index];
+        while let Some(index) = stack.pop() {
+            let entry = &self.tree[index];
+            let children: Vec<_> = self
+                .tree
+                .neighbors_directed(index, Direction::Outgoing)
+                .collect();
+            let is_input = self
+                .tree
+                .neighbors_directed(index, Direction::Incoming)
+                .next()
+                == Some(self.root_index);
+            let name = match is_input {
+                true => entry
+                    .name
+                    .canonicalize()
+                    .unwrap_or_else(|_| entry.name.clone()),
+                false => entry.name.clone(),
+            };
+            let name = name_to_bytes(&name);
+            let mut flags = 0;
+            if entry.metadata_io_error {
+                flags |= METADATA_IO_ERROR;
+            }
+            if entry.mtime_range.is_some() {
+                flags |= HAS_MTIME_RANGE;
+            }
+            if self.permission_denied.contains(&index) {
+                flags |= PERMISSION_DENIED;
+            }
+            if entry.is_symlink {
+                flags |= IS_SYMLINK;
+            }
+
+            out.write_all(&(children.len() as u32).to_le_bytes())?;
+            out.write_all(&(name.len() as u32).to_le_bytes())?;
+            out.write_all(&name)?;
+            out.write_all(&entry.size.to_le_bytes())?;
+            out.write_all(&entry.alternate_size.to_le_bytes())?;
+            out.write_all(&[flags])?;
+            if let Some(MtimeRange { oldest, newest }) = entry.mtime_range {
+                out.write_all(&oldest.to_le_bytes())?;
+                out.write_all(&newest.to_le_bytes())?;
+            }
+            // reversed so children are written in the order of the tree
+            stack.extend(children.into_iter().rev());
+        }
+        Ok(())
+    }
+
+    /// Read a traversal written by [`Traversal::write_snapshot()`], with sizes according to `apparent_size`.
+    pub fn from_snapshot(mut input: impl Read, apparent_size: bool) -> Result<Traversal> {
+        let mut magic = [0; MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            bail!("Not a snapshot, or one written by an incompatible version");
+        }
+        let snapshot_is_apparent_size = read_u8(&mut input)? != 0;
+        let mut t = Traversal {
+            entries_traversed: read_u64(&mut input)?,
+            io_errors: read_u64(&mut input)?,
+            recent_since: read_u32(&mut input)?,
+            ..Default::default()
+        };
+
+        let (root_index, num_children) = read_entry(&mut input, &mut t, None)?;
+        t.root_index = root_index;
+        let mut remaining_children: Vec<(TreeIndex, u32)> = vec![(root_index, num_children)];
+        while let Some((parent, remaining)) = remaining_children.last_mut() {
+            if *remaining == 0 {
+                remaining_children.pop();
+                continue;
+            }
+            *remaining -= 1;
+            let parent = *parent;
+            let (index, num_children) = read_entry(&mut input, &mut t, Some(parent))?;
+            remaining_children.push((index, num_children));
+        }
+        if input.read(&mut [0])? != 0 {
+            bail!("Unexpected data after the last entry");
+        }
+
+        t.total_bytes = Some(t.tree[root_index].size);
+        t.aggregate_entry_counts();
+        if snapshot_is_apparent_size != apparent_size {
+            t.swap_size_model();
+        } else {
+            t.aggregate_recent_sizes();
+        }
+        Ok(t)
+    }
+}
+
+/// Read the next entry from `input` and add it below `parent`, returning its index and the amount of its children.
+fn read_entry(
+    input: &mut impl Read,
+    t: &mut Traversal,
+    parent: Option<TreeIndex>,
+) -> Result<(TreeIndex, u32)> {
+    let num_children = read_u32(input)?;
+    let name_len = read_u32(input)?;
+    if name_len > MAX_NAME_LEN {
+        bail!("Entry name of {} bytes is too long", name_len);
+    }
+    let mut name = vec![0; name_len as usize];
+    input.read_exact(&mut name)?;
+    let size = read_u128(input)?;
+    let alternate_size = read_u128(input)?;
+    let flags = read_u8(input)?;
+    let mtime_range = match flags & HAS_MTIME_RANGE {
+        0 => None,
+        _ => Some(MtimeRange {
+            oldest: read_u32(input)?,
+            newest: read_u32(input)?,
+        }),
+    };
+    let index = t.tree.add_node(EntryData {
+        name: name_from_bytes(name),
+        size,
+        alternate_size,
+        metadata_io_error: flags & METADATA_IO_ERROR != 0,
+        mtime_range,
+        recent_size: 0,
+        entry_count: 0,
+        is_symlink: flags & IS_SYMLINK != 0,
+    });
+    if let Some(parent) = parent {
+        t.tree.add_edge(parent, index, ());
+    }
+    if flags & PERMISSION_DENIED != 0 {
+        t.permission_denied.push(index);
+    }
+    Ok((index, num_children))
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    input.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_u128(input: &mut impl Read) -> io::Result<u128> {
+    let mut buf = [0; 16];
+    input.read_exact(&mut buf)?;
+    Ok(u128::from_le_bytes(buf))
+}
+
+#[cfg(unix)]
+fn name_to_bytes(name: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    name.as_os_str().as_bytes().to_owned()
+}
+
+#[cfg(unix)]
+fn name_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    std::ffi::OsString::from_vec(bytes).into()
+}
+
+#[cfg(not(unix))]
+fn name_to_bytes(name: &Path) -> Vec<u8> {
+    name.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(unix))]
+fn name_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_round_trip() -> Result<()> {
+        let walk_options = WalkOptions {
+            apparent_size: false,
+            ..WalkOptions::for_tests()
+        };
+        let input = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample-01");
+        let traversal = Traversal::from_walk(walk_options, vec![input.clone()], |_| Ok(false))?
+            .expect("complete");
+        let mut snapshot = Vec::new();
+        traversal.write_snapshot(&mut snapshot, false)?;
+
+        let loaded = Traversal::from_snapshot(snapshot.as_slice(), false)?;
+        assert_eq!(loaded.tree.node_count(), traversal.tree.node_count());
+        assert_eq!(loaded.total_bytes, traversal.total_bytes);
+        assert_eq!(loaded.entries_traversed, traversal.entries_traversed);
+        let names = |t: &Traversal| {
+            let mut names: Vec<_> = t
+                .tree
+                .node_weights()
+                .map(|e| {
+                    (
+                        e.name.clone(),
+                        e.size,
+                        e.mtime_range.map(|r| (r.oldest, r.newest)),
+                    )
+                })
+                .collect();
+            names.sort();
+            names
+        };
+        let mut expected = names(&traversal);
+        for (name, _, _) in expected.iter_mut().filter(|(name, _, _)| *name == input) {
+            *name = input.canonicalize()?;
+        }
+        expected.sort();
+        assert_eq!(names(&loaded), expected);
+
+        let apparent = Traversal::from_snapshot(snapshot.as_slice(), true)?;
+        assert_eq!(
+            apparent.total_bytes,
+            Some(traversal.tree[traversal.root_index].alternate_size),
+            "sizes follow the requested size model"
+        );
+
+        assert!(Traversal::from_snapshot(&snapshot[..snapshot.len() - 1], false).is_err());
+        assert!(
+            Traversal::from_snapshot(&b"DUASNAP\x01"[..], false).is_err(),
+            "older versions"
+        );
+        Ok(())
+    }
+}
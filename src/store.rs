@@ -0,0 +1,331 @@
+use crate::{ByteFormat, FileSizes, InodeFilter, WalkOptions, WalkResult};
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashSet},
+    ffi::OsString,
+    fs, io,
+    path::Path,
+    process::Command,
+};
+
+/// The stores looked for if none is given
+const KNOWN_STORES: &[&str] = &["/nix/store", "/gnu/store"];
+/// The length of the hash every name in a store starts with
+const HASH_LEN: usize = 32;
+
+/// The name of the store path named `file_name` without its hash, like `hello-2.12.1` for
+/// `yq8qwihvg6ai1h6z2yw2bs5as6hz5y2l-hello-2.12.1`, or `None` if it isn't named like a store path.
+fn store_path_name(file_name: &str) -> Option<&str> {
+    let is_nix_base32 =
+        |b: u8| b.is_ascii_digit() || (b.is_ascii_lowercase() && !b"eotu".contains(&b));
+    let hash = file_name.as_bytes().get(..HASH_LEN)?;
+    let name = file_name.get(HASH_LEN..)?.strip_prefix('-')?;
+    (hash.iter().all(|b| is_nix_base32(*b)) && !name.is_empty()).then_some(name)
+}
+
+/// The name of the derivation that produced a store path named `name`, which is everything up to the first `-`
+/// not followed by a letter, like `gcc` for `gcc-12.3.0-lib`. This is how Nix separates names from versions.
+fn derivation_name(name: &str) -> &str {
+    let name = name.strip_suffix(".drv").unwrap_or(name);
+    name.char_indices()
+        .find(|&(pos, c)| {
+            c == '-'
+                && !name[pos + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_alphabetic)
+        })
+        .map_or(name, |(pos, _)| &name[..pos])
+}
+
+/// The names of all store paths in `store` that no garbage collector root refers to, as listed by the tool
+/// managing it.
+fn dead_paths(store: &Path) -> Result<HashSet<OsString>> {
+    let (program, args): (_, &[_]) = match store.ends_with("gnu/store") {
+        true => ("guix", &["gc", "--list-dead"]),
+        false => ("nix-store", &["--gc", "--print-dead"]),
+    };
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Could not run '{}'", program))?;
+    if !output.status.success() {
+        bail!(
+            "'{}' failed with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| Path::new(line.trim()).file_name())
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// What we know about all store paths built by derivations of the same name
+#[derive(Default, Debug, PartialEq, Eq)]
+struct Derivation {
+    paths: u64,
+    bytes: u128,
+    /// The amount of paths no root refers to, and their size
+    dead_paths: u64,
+    dead_bytes: u128,
+}
+
+/// Write the size of all paths in the Nix or Guix `store` to `out`, grouped by the name of their derivation with
+/// the largest group last, along with how much of it no root refers to and would be removed by the garbage
+/// collector. If `store` isn't set, `/nix/store` or `/gnu/store` is used, whichever exists.
+///
+/// The unreferenced paths are listed by `nix-store` or `guix`, and are left out if that's not possible.
+/// Files shared by hard links, as done when optimising the store, count once for the first path they are seen in.
+pub fn summarize_store(
+    out: impl io::Write,
+    walk_options: WalkOptions,
+    store: Option<&Path>,
+) -> Result<WalkResult> {
+    let store = match store {
+        Some(store) => store.to_owned(),
+        None => match KNOWN_STORES.iter().map(Path::new).find(|s| s.is_dir()) {
+            Some(store) => store.to_owned(),
+            None => {
+                bail!("Neither /nix/store nor /gnu/store exist, please pass the store to summarize")
+            }
+        },
+    };
+    let dead = dead_paths(&store);
+    write_store_summary(out, walk_options, &store, dead)
+}
+
+fn write_store_summary(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    store: &Path,
+    dead: Result<HashSet<OsString>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut derivations = BTreeMap::<String, Derivation>::new();
+    let mut inodes = InodeFilter::default();
+    let mut store_paths: Vec<_> = fs::read_dir(store)
+        .with_context(|| format!("Could not read the store at '{}'", store.display()))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = derivation_name(store_path_name(entry.file_name().to_str()?)?).to_owned();
+            Some((name, entry.path()))
+        })
+        .collect();
+    store_paths.sort();
+    for (name, path) in store_paths {
+        let mut bytes = 0;
+        let sizes = FileSizes::new(&walk_options, &path)?;
+        for entry in walk_options.iter_from_path(&path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            if entry.read_children_error.is_some() {
+                res.num_errors += 1;
+            }
+            match &entry.client_state {
+                Some(Ok(m)) if sizes.counts(m, &mut inodes) => match sizes.size_of(&entry, m) {
+                    Ok(size) => bytes += size as u128,
+                    Err(_) => res.num_errors += 1,
+                },
+                Some(Err(_)) => res.num_errors += 1,
+                _ => {}
+            }
+        }
+        let derivation = derivations.entry(name).or_default();
+        derivation.paths += 1;
+        derivation.bytes += bytes;
+        let is_dead = match (&dead, path.file_name()) {
+            (Ok(dead), Some(file_name)) => dead.contains(file_name),
+            _ => false,
+        };
+        if is_dead {
+            derivation.dead_paths += 1;
+            derivation.dead_bytes += bytes;
+        }
+    }
+
+    let mut derivations: Vec<_> = derivations.into_iter().collect();
+    derivations.sort_by(|(ln, l), (rn, r)| l.bytes.cmp(&r.bytes).then(ln.cmp(rn)));
+    let format = walk_options.byte_format;
+    let mut total = Derivation::default();
+    for (name, derivation) in &derivations {
+        write!(
+            out,
+            "{:>w$} ",
+            format.display(derivation.bytes).to_string(),
+            w = format.width()
+        )?;
+        if dead.is_ok() {
+            write!(
+                out,
+                "{:>w$} ",
+                collectable(format, derivation),
+                w = format.width() + " collectable".len()
+            )?;
+        }
+        writeln!(
+            out,
+            "{} ({} path{})",
+            name,
+            derivation.paths,
+            plural(derivation.paths)
+        )?;
+        total.paths += derivation.paths;
+        total.bytes += derivation.bytes;
+        total.dead_paths += derivation.dead_paths;
+        total.dead_bytes += derivation.dead_bytes;
+    }
+    write!(
+        out,
+        "{} in {} store path{} of {} derivation{}",
+        format.display(total.bytes).to_string().trim(),
+        total.paths,
+        plural(total.paths),
+        derivations.len(),
+        plural(derivations.len() as u64)
+    )?;
+    match dead {
+        Ok(_) => writeln!(
+            out,
+            ", of which {} in {} path{} can be garbage collected",
+            format.display(total.dead_bytes).to_string().trim(),
+            total.dead_paths,
+            plural(total.dead_paths)
+        )?,
+        Err(err) => writeln!(
+            out,
+            ", but what can be garbage collected is unknown: {:#}",
+            err
+        )?,
+    }
+    Ok(res)
+}
+
+fn collectable(format: ByteFormat, derivation: &Derivation) -> String {
+    match derivation.dead_paths {
+        0 => "-".into(),
+        _ => format!(
+            "{} collectable",
+            format.display(derivation.dead_bytes).to_string().trim()
+        ),
+    }
+}
+
+fn plural(n: u64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::TempTree;
+
+    #[test]
+    fn store_paths_are_named_after_their_derivation() {
+        for (file_name, expected) in [
+            (
+                "yq8qwihvg6ai1h6z2yw2bs5as6hz5y2l-hello-2.12.1",
+                Some("hello"),
+            ),
+            (
+                "0c7c9lyxjyhp9wyx6s4rf1j4hrgmdkfn-gcc-12.3.0-lib",
+                Some("gcc"),
+            ),
+            (
+                "5bb2qxwdxvfrzj3rk5kkflqcr4psx8gq-firefox-bin-120.0",
+                Some("firefox-bin"),
+            ),
+            (
+                "qv2d8cgm6hcd3rbd8kll7lbdjy4psqya-python3.11-numpy-1.26.0",
+                Some("python3.11-numpy"),
+            ),
+            (
+                "7ig1q3ahw5lj2w6pxb8cn7dxm4g9c6ms-bash-5.2-p15.drv",
+                Some("bash"),
+            ),
+            ("ap3b8l6ppmbd5g5wz2l4vfxq6nmvcgfr-source", Some("source")),
+            ("ap3b8l6ppmbd5g5wz2l4vfxq6nmvcgfr-", None),
+            ("eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee-hello", None),
+            ("short-hello-1.0", None),
+            (".links", None),
+        ] {
+            assert_eq!(
+                store_path_name(file_name).map(derivation_name),
+                expected,
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn store_paths_are_summarized_by_derivation_with_what_can_be_collected() -> Result<()> {
+        let store = TempTree::new("store");
+        fs::create_dir_all(store.join("11111111111111111111111111111111-gcc-12.3.0/bin"))?;
+        fs::write(
+            store.join("11111111111111111111111111111111-gcc-12.3.0/bin/gcc"),
+            vec![0; 300],
+        )?;
+        fs::create_dir_all(store.join("22222222222222222222222222222222-gcc-12.3.0-lib"))?;
+        fs::write(
+            store.join("22222222222222222222222222222222-gcc-12.3.0-lib/libgcc.so"),
+            vec![0; 200],
+        )?;
+        fs::write(
+            store.join("33333333333333333333333333333333-hello-2.12.1.drv"),
+            vec![0; 10],
+        )?;
+        fs::create_dir_all(store.join(".links"))?;
+        fs::write(store.join(".links/ignored"), vec![0; 1000])?;
+        let walk_options = WalkOptions::for_tests();
+        let summary = |dead: Result<HashSet<OsString>>| -> Result<String> {
+            let mut out = Vec::new();
+            write_store_summary(&mut out, walk_options.clone(), &store, dead)?;
+            Ok(String::from_utf8(out)?)
+        };
+        let dead = summary(Ok(Some(OsString::from(
+            "22222222222222222222222222222222-gcc-12.3.0-lib",
+        ))
+        .into_iter()
+        .collect()));
+        let unknown = summary(Err(anyhow::anyhow!("no nix-store")));
+
+        let w = ByteFormat::Bytes.width();
+        assert_eq!(
+            dead?,
+            format!(
+                "{:>w$} {:>c$} hello (1 path)\n{:>w$} {:>c$} gcc (2 paths)\n\
+                 510 b in 3 store paths of 2 derivations, of which 200 b in 1 path can be garbage collected\n",
+                "10 b",
+                "-",
+                "500 b",
+                "200 b collectable",
+                w = w,
+                c = w + " collectable".len()
+            )
+        );
+        assert_eq!(
+            unknown?,
+            format!(
+                "{:>w$} hello (1 path)\n{:>w$} gcc (2 paths)\n\
+                 510 b in 3 store paths of 2 derivations, but what can be garbage collected is unknown: no nix-store\n",
+                "10 b",
+                "500 b",
+                w = w
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use crate::ByteFormat;
+use anyhow::{bail, Result};
+use std::{io, path::Path, str::FromStr};
+
+/// A user-provided template like `{path}\t{size:bytes}` to print one line per aggregated path.
+///
+/// Supported placeholders are `{path}`, `{size}` (in the configured byte format), `{size:<format>}`
+/// with `<format>` being `bytes` for the plain amount of bytes or any other byte format,
+/// `{count}` for the amount of entries traversed and `{errors}` for the amount of IO errors.
+/// The escape sequences `\t`, `\n` and `\\` are supported, as well as `{{` and `}}` for literal braces.
+///
+/// The templates `csv` and `tsv` write `path,size_bytes,entries,errors` rows below a header line instead, with paths
+/// quoted or escaped as needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    tokens: Vec<Token>,
+    header: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    Path,
+    /// The path as field of a comma-separated row, quoted if needed
+    CsvPath,
+    /// The path as field of a tab-separated row, with tabs, newlines and backslashes escaped
+    TsvPath,
+    Size(Option<ByteFormat>),
+    SizeInBytes,
+    Count,
+    Errors,
+}
+
+/// The values to fill into an [`OutputTemplate`] for a single path
+pub struct TemplateValues<'a> {
+    pub path: &'a Path,
+    pub num_bytes: u128,
+    pub num_entries: u64,
+    pub num_errors: u64,
+}
+
+impl OutputTemplate {
+    /// Rows of `path,size_bytes,entries,errors` separated by `separator`, with the path written as `path` token.
+    fn separated_values(path: Token, separator: &str, header: &'static str) -> Self {
+        let separator = Token::Literal(separator.into());
+        OutputTemplate {
+            tokens: vec![
+                path,
+                separator.clone(),
+                Token::SizeInBytes,
+                separator.clone(),
+                Token::Count,
+                separator,
+                Token::Errors,
+            ],
+            header: Some(header),
+        }
+    }
+
+    /// The line to write once before all others, naming the columns
+    pub fn header(&self) -> Option<&str> {
+        self.header
+    }
+
+    /// Write a line for `values` to `out`, using `byte_format` for sizes without explicit format.
+    pub fn write_line(
+        &self,
+        out: &mut impl io::Write,
+        byte_format: ByteFormat,
+        values: &TemplateValues<'_>,
+    ) -> io::Result<()> {
+        for token in &self.tokens {
+            match token {
+                Token::Literal(text) => write!(out, "{}", text)?,
+                Token::Path => write!(out, "{}", values.path.display())?,
+                Token::CsvPath => {
+                    let path = values.path.display().to_string();
+                    match path.contains([',', '"', '\n', '\r']) {
+                        true => write!(out, "\"{}\"", path.replace('"', "\"\""))?,
+                        false => write!(out, "{}", path)?,
+                    }
+                }
+                Token::TsvPath => write!(
+                    out,
+                    "{}",
+                    values
+                        .path
+                        .display()
+                        .to_string()
+                        .replace('\\', "\\\\")
+                        .replace('\t', "\\t")
+                        .replace('\n', "\\n")
+                        .replace('\r', "\\r")
+                )?,
+                Token::Size(format) => write!(
+                    out,
+                    "{}",
+                    format
+                        .unwrap_or(byte_format)
+                        .display(values.num_bytes)
+                        .to_string()
+                        .trim_start()
+                )?,
+                Token::SizeInBytes => write!(out, "{}", values.num_bytes)?,
+                Token::Count => write!(out, "{}", values.num_entries)?,
+                Token::Errors => write!(out, "{}", values.num_errors)?,
+            }
+        }
+        writeln!(out)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(template: &str) -> Result<Self> {
+        match template {
+            "csv" => {
+                return Ok(Self::separated_values(
+                    Token::CsvPath,
+                    ",",
+                    "path,size_bytes,entries,errors",
+                ))
+            }
+            "tsv" => {
+                return Ok(Self::separated_values(
+                    Token::TsvPath,
+                    "\t",
+                    "path\tsize_bytes\tentries\terrors",
+                ))
+            }
+            _ => {}
+        }
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '}' => match chars.next() {
+                    Some('}') => literal.push('}'),
+                    _ => bail!("Unmatched '}}' in template - use '}}}}' for a literal brace"),
+                },
+                '{' => {
+                    let rest = chars.as_str();
+                    if let Some(rest) = rest.strip_prefix('{') {
+                        literal.push('{');
+                        chars = rest.chars();
+                        continue;
+                    }
+                    let end = match rest.find('}') {
+                        Some(end) => end,
+                        None => bail!("Unterminated placeholder in template: '{{{}'", rest),
+                    };
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(parse_placeholder(&rest[..end])?);
+                    chars = rest[end + 1..].chars();
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(OutputTemplate {
+            tokens,
+            header: None,
+        })
+    }
+}
+
+/// Escape `s` to be written within the quotes of a JSON string.
+pub fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Token> {
+    Ok(match placeholder.split_once(':') {
+        None => match placeholder {
+            "path" => Token::Path,
+            "size" => Token::Size(None),
+            "count" => Token::Count,
+            "errors" => Token::Errors,
+            _ => bail!(
+                "Unknown placeholder '{{{}}}' - use one of path, size, count or errors",
+                placeholder
+            ),
+        },
+        Some(("size", format)) => match format {
+            "bytes" | "Bytes" => Token::SizeInBytes,
+            "metric" | "Metric" => Token::Size(Some(ByteFormat::Metric)),
+            "binary" | "Binary" => Token::Size(Some(ByteFormat::Binary)),
+            "GB" | "Gb" | "gb" => Token::Size(Some(ByteFormat::GB)),
+            "GiB" | "gib" => Token::Size(Some(ByteFormat::GiB)),
+            "MB" | "Mb" | "mb" => Token::Size(Some(ByteFormat::MB)),
+            "MiB" | "mib" => Token::Size(Some(ByteFormat::MiB)),
+            _ => bail!("Unknown byte format in placeholder '{{{}}}'", placeholder),
+        },
+        Some(_) => bail!(
+            "Only the 'size' placeholder supports a format, got '{{{}}}'",
+            placeholder
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str) -> String {
+        let mut out = Vec::new();
+        template
+            .parse::<OutputTemplate>()
+            .unwrap()
+            .write_line(
+                &mut out,
+                ByteFormat::Metric,
+                &TemplateValues {
+                    path: Path::new("dir"),
+                    num_bytes: 1500,
+                    num_entries: 3,
+                    num_errors: 1,
+                },
+            )
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn it_fills_in_placeholders_and_escapes() {
+        assert_eq!(render(r"{path}\t{size:bytes}\t{count}"), "dir\t1500\t3\n");
+        assert_eq!(render("{size} {size:binary}"), "1.50 KB 1.46 KiB\n");
+        assert_eq!(render("{{errors}}={errors}"), "{errors}=1\n");
+        assert_eq!(render(r"a\\b\x"), "a\\b\\x\n");
+    }
+
+    #[test]
+    fn csv_and_tsv_write_rows_below_a_header() {
+        let row = |template: &str, path: &str| {
+            let template = template.parse::<OutputTemplate>().unwrap();
+            let mut out = Vec::new();
+            template
+                .write_line(
+                    &mut out,
+                    ByteFormat::Metric,
+                    &TemplateValues {
+                        path: Path::new(path),
+                        num_bytes: 1500,
+                        num_entries: 3,
+                        num_errors: 1,
+                    },
+                )
+                .unwrap();
+            (
+                template.header().map(ToOwned::to_owned),
+                String::from_utf8(out).unwrap(),
+            )
+        };
+        assert_eq!(
+            row("csv", "dir"),
+            (
+                Some("path,size_bytes,entries,errors".into()),
+                "dir,1500,3,1\n".into()
+            )
+        );
+        assert_eq!(row("csv", "a,\"b\"").1, "\"a,\"\"b\"\"\",1500,3,1\n");
+        assert_eq!(
+            row("tsv", "a\tb\\c"),
+            (
+                Some("path\tsize_bytes\tentries\terrors".into()),
+                "a\\tb\\\\c\t1500\t3\t1\n".into()
+            )
+        );
+        assert_eq!(row("{path}", "dir").0, None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_templates() {
+        for invalid in ["{nope}", "{size:ZB}", "{path:bytes}", "{path", "}"] {
+            assert!(
+                invalid.parse::<OutputTemplate>().is_err(),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+}
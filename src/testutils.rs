@@ -0,0 +1,85 @@
+use crate::{ByteFormat, TraversalSorting, WalkOptions};
+use std::{
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+impl WalkOptions {
+    /// Walk on a single thread and count apparent sizes in bytes, without skipping or following anything.
+    pub(crate) fn for_tests() -> Self {
+        WalkOptions {
+            threads: 1,
+            byte_format: ByteFormat::Bytes,
+            apparent_size: true,
+            count_hard_links: false,
+            sorting: TraversalSorting::None,
+            cross_filesystems: true,
+            follow_links: false,
+            round_to_fs_blocks: false,
+            respect_ignore_files: false,
+        }
+    }
+}
+
+/// An empty directory no other test uses, which is deleted along with everything in it when dropped.
+pub(crate) struct TempTree {
+    pub root: PathBuf,
+}
+
+impl TempTree {
+    /// Create the directory with a name starting with `name`.
+    pub(crate) fn new(name: &str) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "dua-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&root).expect("the temporary directory to be writable");
+        TempTree { root }
+    }
+
+    /// Create the directory at `relative` along with its parents, and return its path.
+    pub(crate) fn dir(&self, relative: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = self.join(relative);
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    /// Write `contents` to the file at `relative`, creating its parent directories, and return its path.
+    pub(crate) fn file(
+        &self,
+        relative: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> io::Result<PathBuf> {
+        let path = self.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.root).ok();
+    }
+}
+
+impl AsRef<Path> for TempTree {
+    fn as_ref(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Deref for TempTree {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.root
+    }
+}
@@ -1,12 +1,19 @@
-use crate::{crossdev, get_size_or_panic, InodeFilter, WalkOptions};
-use anyhow::Result;
-use filesize::PathExt;
-use petgraph::{graph::NodeIndex, stable_graph::StableGraph, Directed, Direction};
+use crate::{
+    get_size_or_panic, privileged::is_permission_denied, DuplicateDirectory, FileSizes,
+    InodeFilter, Pause, SeenDirectories, SymlinkLoop, WalkOptions,
+};
+use anyhow::{Context, Result};
+use petgraph::{
+    graph::NodeIndex,
+    stable_graph::StableGraph,
+    visit::{Dfs, DfsPostOrder},
+    Directed, Direction,
+};
 use std::{
+    collections::HashSet,
     fs::Metadata,
-    io,
-    path::{Path, PathBuf},
-    time::{Duration, Instant},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
 };
 
 pub type TreeIndex = NodeIndex;
@@ -17,11 +24,65 @@ pub struct EntryData {
     pub name: PathBuf,
     /// The entry's size in bytes. If it's a directory, the size is the aggregated file size of all children
     pub size: u128,
+    /// The apparent size if `size` is the disk usage, or the disk usage if `size` is the apparent size,
+    /// to switch between both without another traversal. See [`Traversal::swap_size_model()`].
+    pub alternate_size: u128,
     /// If set, the item meta-data could not be obtained
     pub metadata_io_error: bool,
+    /// The modification time of the file, or the range of modification times of all files below a directory.
+    pub mtime_range: Option<MtimeRange>,
+    /// The size of the file if it was modified recently, see [`Traversal::recent_since`], or the size of all
+    /// recently modified files below a directory. Used to estimate how fast directories grow.
+    pub recent_size: u128,
+    /// The amount of files and directories below a directory at any depth, or 0 for files
+    pub entry_count: u64,
+    /// If set, the entry is a symbolic link, even if it was followed
+    pub is_symlink: bool,
+}
+
+/// The oldest and newest modification time of one or more files, in seconds since the unix epoch
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct MtimeRange {
+    pub oldest: u32,
+    pub newest: u32,
+}
+
+impl MtimeRange {
+    /// The range containing only the modification time in `meta`, if the platform provides it.
+    pub fn of(meta: &Metadata) -> Option<Self> {
+        let secs = meta
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs().min(u32::MAX as u64) as u32);
+        Some(MtimeRange {
+            oldest: secs,
+            newest: secs,
+        })
+    }
+
+    /// The range spanning both `self` and `other`.
+    pub fn merge(self, other: Self) -> Self {
+        MtimeRange {
+            oldest: self.oldest.min(other.oldest),
+            newest: self.newest.max(other.newest),
+        }
+    }
+
+    /// The range spanning the ranges of all direct children of the directory at `index`.
+    pub fn of_children(tree: &Tree, index: TreeIndex) -> Option<Self> {
+        tree.neighbors_directed(index, Direction::Outgoing)
+            .filter_map(|idx| tree.node_weight(idx)?.mtime_range)
+            .reduce(MtimeRange::merge)
+    }
 }
 
 const REFRESH_RATE: Duration = Duration::from_millis(100);
+/// Filesystems store modification times with a granularity of up to two seconds, so directories modified less than
+/// this before a traversal started may have been modified after it started.
+const MTIME_GRANULARITY: Duration = Duration::from_secs(2);
+/// Files modified within this many days before a scan count as recently modified
+pub const RECENT_PERIOD_DAYS: u32 = 7;
 
 /// The result of the previous filesystem traversal
 #[derive(Default, Debug)]
@@ -36,6 +97,28 @@ pub struct Traversal {
     pub io_errors: u64,
     /// Total amount of bytes seen during the traversal
     pub total_bytes: Option<u128>,
+    /// Directories whose contents we were not permitted to read, which are thus missing from the totals
+    pub permission_denied: Vec<TreeIndex>,
+    /// The entries at which reading a directory failed, once per failure. Along with the entries whose metadata
+    /// couldn't be obtained, these are what [`io_errors`](Self::io_errors) counts.
+    pub read_errors: Vec<TreeIndex>,
+    /// Symbolic links we didn't follow as they point to one of their own ancestors
+    pub symlink_loops: Vec<SymlinkLoop>,
+    /// Directories we didn't read as they were already seen at another path, like through a bind mount
+    pub duplicate_directories: Vec<DuplicateDirectory>,
+    /// Mount points we didn't descend into as the traversal stays on the filesystems of its inputs, which appear empty
+    pub skipped_mounts: Vec<TreeIndex>,
+    /// Set it to stop reading directories until it is unset again, while the traversal is running
+    pub pause: Pause,
+    /// The directory whose entries are currently being read, while the traversal is running.
+    /// It and its ancestors have the provisional size of all entries seen so far.
+    pub in_progress: Option<TreeIndex>,
+    /// Files modified at or after this time, in seconds since the unix epoch, count as recently modified.
+    /// It's [`RECENT_PERIOD_DAYS`] before the traversal started.
+    pub recent_since: u32,
+    /// When the traversal started, or when [`refresh_changed()`](Self::refresh_changed()) last started if it
+    /// succeeded. Unset for trees that weren't built by traversing the filesystem.
+    pub started_at: Option<SystemTime>,
 }
 
 impl Traversal {
@@ -61,9 +144,15 @@ impl Traversal {
         let mut t = {
             let mut tree = Tree::new();
             let root_index = tree.add_node(EntryData::default());
+            let started_at = SystemTime::now();
+            let now = started_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs().min(u32::MAX as u64) as u32);
             Traversal {
                 tree,
                 root_index,
+                recent_since: now.saturating_sub(RECENT_PERIOD_DAYS * 24 * 60 * 60),
+                started_at: Some(started_at),
                 ..Default::default()
             }
         };
@@ -73,6 +162,7 @@ impl Traversal {
         let mut current_size_at_depth: u128 = 0;
         let mut previous_depth = 0;
         let mut inodes = InodeFilter::default();
+        let seen = SeenDirectories::default();
 
         let mut last_checked = Instant::now();
 
@@ -84,20 +174,11 @@ impl Traversal {
             walk_options.threads = num_cpus::get();
         }
 
-        #[cfg(not(windows))]
-        fn size_on_disk(_parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
-            name.size_on_disk_fast(meta)
-        }
-        #[cfg(windows)]
-        fn size_on_disk(parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
-            parent.join(name).size_on_disk_fast(meta)
-        }
-
         for path in input.into_iter() {
             let mut last_seen_eid = 0;
-            let device_id = crossdev::init(path.as_ref())?;
+            let sizes = FileSizes::new(&walk_options, path.as_ref())?;
             for (eid, entry) in walk_options
-                .iter_from_path(path.as_ref())
+                .pausable_iter_from_path(path.as_ref(), Some(t.pause.clone()), Some(seen.clone()))
                 .into_iter()
                 .enumerate()
             {
@@ -105,38 +186,48 @@ impl Traversal {
                 let mut data = EntryData::default();
                 match entry {
                     Ok(entry) => {
-                        data.name = if entry.depth < 1 {
-                            path.clone()
-                        } else {
-                            entry.file_name.into()
-                        };
-                        let file_size = match &entry.client_state {
-                            Some(Ok(ref m))
-                                if !m.is_dir()
-                                    && (walk_options.count_hard_links || inodes.add(m))
-                                    && (walk_options.cross_filesystems
-                                        || crossdev::is_same_device(device_id, m)) =>
-                            {
-                                if walk_options.apparent_size {
-                                    m.len()
-                                } else {
-                                    size_on_disk(&entry.parent_path, &data.name, m).unwrap_or_else(
-                                        |_| {
-                                            t.io_errors += 1;
-                                            data.metadata_io_error = true;
-                                            0
-                                        },
-                                    )
+                        if let Some(symlink_loop) = SymlinkLoop::from_entry(&entry) {
+                            t.symlink_loops.push(symlink_loop);
+                        }
+                        if let Some(duplicate) = seen.duplicate_of(&entry) {
+                            t.duplicate_directories.push(duplicate);
+                        }
+                        let is_symlink = entry.path_is_symlink();
+                        let is_skipped_mount = !walk_options.cross_filesystems
+                            && entry.depth > 0
+                            && entry.file_type.is_dir()
+                            && entry.read_children_path.is_none()
+                            && entry.metadata().is_ok_and(|m| !sizes.is_on_root_device(&m));
+                        let (file_size, alternate_size) = match &entry.client_state {
+                            Some(Ok(ref m)) if sizes.counts(m, &mut inodes) => {
+                                let alternate_size = match walk_options.apparent_size {
+                                    true => sizes.size_on_disk(&entry, m).unwrap_or(0),
+                                    false => m.len(),
+                                };
+                                match sizes.size_of(&entry, m) {
+                                    Ok(size) => (size, alternate_size),
+                                    Err(_) => {
+                                        t.io_errors += 1;
+                                        data.metadata_io_error = true;
+                                        (0, alternate_size)
+                                    }
                                 }
                             }
-                            Some(Ok(_)) => 0,
+                            Some(Ok(_)) => (0, 0),
                             Some(Err(_)) => {
                                 t.io_errors += 1;
                                 data.metadata_io_error = true;
-                                0
+                                (0, 0)
                             }
-                            None => 0, // a directory
-                        } as u128;
+                            None => (0, 0), // a directory
+                        };
+                        let (file_size, alternate_size) =
+                            (file_size as u128, alternate_size as u128);
+                        data.name = if entry.depth < 1 {
+                            path.clone()
+                        } else {
+                            entry.file_name.into()
+                        };
 
                         match (entry.depth, previous_depth) {
                             (n, p) if n > p => {
@@ -168,21 +259,45 @@ impl Traversal {
                         };
 
                         data.size = file_size;
+                        data.alternate_size = alternate_size;
+                        data.is_symlink = is_symlink;
+                        if let Some(Ok(m)) = &entry.client_state {
+                            if !m.is_dir() {
+                                data.mtime_range = MtimeRange::of(m);
+                            }
+                        }
                         let entry_index = t.tree.add_node(data);
+                        if let Some(err) = &entry.read_children_error {
+                            t.io_errors += 1;
+                            t.read_errors.push(entry_index);
+                            if is_permission_denied(err) {
+                                t.permission_denied.push(entry_index);
+                            }
+                        }
+
+                        if is_skipped_mount {
+                            t.skipped_mounts.push(entry_index);
+                        }
 
                         t.tree.add_edge(parent_node_idx, entry_index, ());
                         previous_node_idx = entry_index;
                         previous_depth = entry.depth;
                     }
-                    Err(_) => {
-                        if previous_depth == 0 {
-                            data.name = path.clone();
-                            let entry_index = t.tree.add_node(data);
-                            t.tree.add_edge(parent_node_idx, entry_index, ());
+                    Err(err) => match SymlinkLoop::from_error(&err) {
+                        Some(symlink_loop) => t.symlink_loops.push(symlink_loop),
+                        None => {
+                            let failed_at = if previous_depth == 0 {
+                                data.name = path.clone();
+                                let entry_index = t.tree.add_node(data);
+                                t.tree.add_edge(parent_node_idx, entry_index, ());
+                                entry_index
+                            } else {
+                                parent_node_idx
+                            };
+                            t.read_errors.push(failed_at);
+                            t.io_errors += 1
                         }
-
-                        t.io_errors += 1
-                    }
+                    },
                 }
 
                 if eid != 0
@@ -198,6 +313,17 @@ impl Traversal {
                     last_seen_eid = eid;
                     last_checked = now;
 
+                    // let directories in progress show the size of what we have seen so far
+                    let mut index = parent_node_idx;
+                    let mut provisional_size = current_size_at_depth;
+                    set_size_or_panic(&mut t.tree, index, provisional_size);
+                    for size in sizes_per_depth_level.iter().rev() {
+                        index = parent_or_panic(&mut t.tree, index);
+                        provisional_size += size;
+                        set_size_or_panic(&mut t.tree, index, provisional_size);
+                    }
+                    t.in_progress = Some(parent_node_idx);
+
                     if update(&mut t)? {
                         return Ok(None);
                     }
@@ -215,10 +341,357 @@ impl Traversal {
         let root_size = t.recompute_root_size();
         set_size_or_panic(&mut t.tree, t.root_index, root_size);
         t.total_bytes = Some(root_size);
+        t.in_progress = None;
+        t.aggregate_directories();
+        t.aggregate_recent_sizes();
+        t.aggregate_entry_counts();
 
         Ok(Some(t))
     }
 
+    /// Traverse `path` and add it to the tree as another top-level entry, as if it was passed as input from the start.
+    /// Returns the index of the new entry.
+    ///
+    /// Hard links to files seen by the previous traversal are counted again.
+    pub fn add_input(&mut self, walk_options: WalkOptions, path: PathBuf) -> Result<TreeIndex> {
+        let other = Traversal::from_walk(walk_options, vec![path], |_| Ok(false))?
+            .expect("traversals without updates run to completion");
+        let other_top = other
+            .tree
+            .neighbors_directed(other.root_index, Direction::Outgoing)
+            .next()
+            .expect("the input we just traversed");
+
+        let top = self.tree.add_node(other.tree[other_top].clone());
+        self.tree.add_edge(self.root_index, top, ());
+        self.graft_children_of(&other, other_top, top);
+        if other.permission_denied.contains(&other_top) {
+            self.permission_denied.push(top);
+        }
+        self.symlink_loops.extend(other.symlink_loops);
+        self.duplicate_directories
+            .extend(other.duplicate_directories);
+        self.entries_traversed += other.entries_traversed;
+        self.io_errors += other.io_errors;
+
+        let root_size = self.recompute_root_size();
+        let alternate_size = self.alternate_size_of_children(self.root_index);
+        let recent_size = self.recent_size_of_children(self.root_index);
+        let entry_count = self.entry_count_of_children(self.root_index);
+        let mtime_range = MtimeRange::of_children(&self.tree, self.root_index);
+        let root = self
+            .tree
+            .node_weight_mut(self.root_index)
+            .expect("root is present");
+        root.size = root_size;
+        root.alternate_size = alternate_size;
+        root.recent_size = recent_size;
+        root.entry_count = entry_count;
+        root.mtime_range = mtime_range;
+        self.total_bytes = Some(root_size);
+        Ok(top)
+    }
+
+    /// Traverse the entry at `index`, found at `path` on disk, once more and replace everything below it with
+    /// what was found, updating the sizes of all of its ancestors.
+    /// Returns the indices of all entries that were removed in the process, which are no longer valid.
+    ///
+    /// Hard links to files seen outside of the entry are counted again.
+    pub fn refresh(
+        &mut self,
+        walk_options: WalkOptions,
+        index: TreeIndex,
+        path: PathBuf,
+    ) -> Result<Vec<TreeIndex>> {
+        let other = Traversal::from_walk(walk_options, vec![path.clone()], |_| Ok(false))?
+            .expect("traversals without updates run to completion");
+        let other_top = other
+            .tree
+            .neighbors_directed(other.root_index, Direction::Outgoing)
+            .next()
+            .expect("the input we just traversed");
+
+        let previous_children: Vec<_> = self
+            .tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .collect();
+        let mut removed = Vec::new();
+        for child in previous_children {
+            let mut dfs = Dfs::new(&self.tree, child);
+            while let Some(idx) = dfs.next(&self.tree) {
+                removed.push(idx);
+            }
+        }
+        // what was found below the entry before is replaced by what the new traversal found
+        let replaced: HashSet<_> = removed.iter().copied().chain(Some(index)).collect();
+        let previous_errors = replaced
+            .iter()
+            .filter(|idx| self.tree[**idx].metadata_io_error)
+            .count()
+            + self
+                .read_errors
+                .iter()
+                .filter(|idx| replaced.contains(idx))
+                .count();
+        self.read_errors.retain(|idx| !replaced.contains(idx));
+        self.symlink_loops
+            .retain(|symlink_loop| !symlink_loop.path.starts_with(&path));
+        self.duplicate_directories
+            .retain(|duplicate| !duplicate.path.starts_with(&path));
+
+        // add new entries before removing old ones so none of the removed indices are reused
+        self.graft_children_of(&other, other_top, index);
+        for idx in &removed {
+            self.tree.remove_node(*idx);
+        }
+        self.permission_denied.retain(|idx| !replaced.contains(idx));
+        if other.permission_denied.contains(&other_top) {
+            self.permission_denied.push(index);
+        }
+        self.skipped_mounts.retain(|idx| !replaced.contains(idx));
+        self.symlink_loops.extend(other.symlink_loops);
+        self.duplicate_directories
+            .extend(other.duplicate_directories);
+        self.entries_traversed = (self.entries_traversed + other.entries_traversed)
+            .saturating_sub(removed.len() as u64 + 1);
+        self.io_errors = self.io_errors.saturating_sub(previous_errors as u64) + other.io_errors;
+
+        let name = std::mem::take(&mut self.tree[index].name);
+        self.tree[index] = EntryData {
+            name,
+            ..other.tree[other_top].clone()
+        };
+        let mut ancestor = self
+            .tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next();
+        while let Some(idx) = ancestor {
+            let size = self
+                .tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .map(|child| get_size_or_panic(&self.tree, child))
+                .sum();
+            let alternate_size = self.alternate_size_of_children(idx);
+            let recent_size = self.recent_size_of_children(idx);
+            let entry_count = self.entry_count_of_children(idx);
+            let mtime_range = MtimeRange::of_children(&self.tree, idx);
+            let entry = &mut self.tree[idx];
+            entry.size = size;
+            entry.alternate_size = alternate_size;
+            entry.recent_size = recent_size;
+            entry.entry_count = entry_count;
+            entry.mtime_range = mtime_range;
+            ancestor = self
+                .tree
+                .neighbors_directed(idx, Direction::Incoming)
+                .next();
+        }
+        self.total_bytes = Some(get_size_or_panic(&self.tree, self.root_index));
+        Ok(removed)
+    }
+
+    /// Like [`refresh()`](Self::refresh()) for all inputs, but only directories that were modified since the
+    /// previous traversal started are traversed again, while the entries below all others are kept as they are.
+    /// Returns how many entries were traversed again along with everything below them.
+    ///
+    /// Files modified in place don't change the modification time of their directory, so their sizes aren't updated.
+    /// If the tree wasn't built by traversing the filesystem, all inputs are traversed again.
+    pub fn refresh_changed(&mut self, walk_options: WalkOptions) -> Result<usize> {
+        let started_at = SystemTime::now();
+        let unchanged_before = self
+            .started_at
+            .and_then(|previous| previous.checked_sub(MTIME_GRANULARITY));
+        let mut pending: Vec<_> = self
+            .tree
+            .neighbors_directed(self.root_index, Direction::Outgoing)
+            .map(|idx| (idx, self.tree[idx].name.clone(), true))
+            .collect();
+        let mut refreshed = 0;
+        while let Some((index, path, is_input)) = pending.pop() {
+            let mut children = self
+                .tree
+                .neighbors_directed(index, Direction::Outgoing)
+                .peekable();
+            // files always have a modification time, unless their metadata couldn't be obtained
+            let may_be_directory =
+                children.peek().is_some() || self.tree[index].mtime_range.is_none();
+            if !(is_input || may_be_directory) || self.skipped_mounts.contains(&index) {
+                continue;
+            }
+            let metadata = match walk_options.follow_links {
+                true => path.metadata(),
+                false => path.symlink_metadata(),
+            };
+            let is_unchanged = match (metadata, unchanged_before) {
+                (Ok(m), Some(before)) => {
+                    m.is_dir() && m.modified().is_ok_and(|modified| modified < before)
+                }
+                _ => false,
+            };
+            if is_unchanged {
+                pending.extend(children.map(|idx| (idx, path.join(&self.tree[idx].name), false)));
+                continue;
+            }
+            self.refresh(walk_options.clone(), index, path.clone())
+                .with_context(|| format!("Could not scan '{}'", path.display()))?;
+            refreshed += 1;
+        }
+        self.started_at = Some(started_at);
+        Ok(refreshed)
+    }
+
+    /// Copy all entries below `other_parent` in `other` below `parent`, along with the directories we weren't
+    /// permitted to read, the failures to read directories and the mount points we skipped.
+    fn graft_children_of(&mut self, other: &Traversal, other_parent: TreeIndex, parent: TreeIndex) {
+        let mut new_index_of = std::collections::HashMap::new();
+        new_index_of.insert(other_parent, parent);
+        let mut dfs = Dfs::new(&other.tree, other_parent);
+        while let Some(idx) = dfs.next(&other.tree) {
+            if idx == other_parent {
+                continue;
+            }
+            let other_parent = other
+                .tree
+                .neighbors_directed(idx, Direction::Incoming)
+                .next()
+                .expect("every entry below the parent has a parent");
+            let new_idx = self.tree.add_node(other.tree[idx].clone());
+            self.tree.add_edge(new_index_of[&other_parent], new_idx, ());
+            new_index_of.insert(idx, new_idx);
+        }
+        self.permission_denied.extend(
+            other
+                .permission_denied
+                .iter()
+                .filter(|idx| **idx != other_parent)
+                .filter_map(|idx| new_index_of.get(idx)),
+        );
+        self.skipped_mounts.extend(
+            other
+                .skipped_mounts
+                .iter()
+                .filter_map(|idx| new_index_of.get(idx)),
+        );
+        self.read_errors.extend(
+            other
+                .read_errors
+                .iter()
+                .filter_map(|idx| new_index_of.get(idx)),
+        );
+    }
+
+    /// Set the size of the directory at `index` to its size as measured with elevated privileges,
+    /// and propagate the change to all of its ancestors.
+    pub fn set_privileged_size(&mut self, index: TreeIndex, size: u128) {
+        let previous_size = match self.tree.node_weight_mut(index) {
+            Some(entry) => std::mem::replace(&mut entry.size, size),
+            None => return,
+        };
+        let mut index = index;
+        while let Some(parent_idx) = self
+            .tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next()
+        {
+            let entry = self
+                .tree
+                .node_weight_mut(parent_idx)
+                .expect("valid parent index");
+            entry.size = entry.size - previous_size + size;
+            index = parent_idx;
+        }
+        self.total_bytes = self.tree.node_weight(self.root_index).map(|e| e.size);
+    }
+
+    /// Let each directory span the modification times of all files below it, after all files are known.
+    /// Set the modification time range and alternate size of all directories from the ones of their children.
+    pub(crate) fn aggregate_directories(&mut self) {
+        let mut dfs = DfsPostOrder::new(&self.tree, self.root_index);
+        while let Some(idx) = dfs.next(&self.tree) {
+            if self
+                .tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .next()
+                .is_some()
+            {
+                let range = MtimeRange::of_children(&self.tree, idx);
+                let alternate_size = self.alternate_size_of_children(idx);
+                let entry = self
+                    .tree
+                    .node_weight_mut(idx)
+                    .expect("index from traversal");
+                entry.mtime_range = range;
+                entry.alternate_size = alternate_size;
+            }
+        }
+    }
+
+    /// Set the recent size of all entries according to the modification times of files and [`Traversal::recent_since`].
+    pub(crate) fn aggregate_recent_sizes(&mut self) {
+        let mut dfs = DfsPostOrder::new(&self.tree, self.root_index);
+        while let Some(idx) = dfs.next(&self.tree) {
+            let is_dir = self
+                .tree
+                .neighbors_directed(idx, Direction::Outgoing)
+                .next()
+                .is_some();
+            let recent_size = match is_dir {
+                true => self.recent_size_of_children(idx),
+                false => {
+                    let entry = &self.tree[idx];
+                    match entry.mtime_range {
+                        Some(range) if range.newest >= self.recent_since => entry.size,
+                        _ => 0,
+                    }
+                }
+            };
+            self.tree[idx].recent_size = recent_size;
+        }
+    }
+
+    /// Set the amount of entries below each directory from the ones of their children.
+    pub(crate) fn aggregate_entry_counts(&mut self) {
+        let mut dfs = DfsPostOrder::new(&self.tree, self.root_index);
+        while let Some(idx) = dfs.next(&self.tree) {
+            self.tree[idx].entry_count = self.entry_count_of_children(idx);
+        }
+    }
+
+    /// The amount of entries below the directory at `index`, which are its direct children and everything below them.
+    pub fn entry_count_of_children(&self, index: TreeIndex) -> u64 {
+        self.tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .filter_map(|idx| self.tree.node_weight(idx).map(|e| 1 + e.entry_count))
+            .sum()
+    }
+
+    /// The sum of the recent sizes of all direct children of the directory at `index`.
+    pub fn recent_size_of_children(&self, index: TreeIndex) -> u128 {
+        self.tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .filter_map(|idx| self.tree.node_weight(idx).map(|e| e.recent_size))
+            .sum()
+    }
+
+    /// The sum of the alternate sizes of all direct children of the directory at `index`.
+    pub fn alternate_size_of_children(&self, index: TreeIndex) -> u128 {
+        self.tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .filter_map(|idx| self.tree.node_weight(idx).map(|e| e.alternate_size))
+            .sum()
+    }
+
+    /// Exchange `size` and `alternate_size` of all entries, to switch between apparent sizes and disk usage.
+    ///
+    /// This is only valid once the traversal is complete, as alternate sizes of directories are aggregated at the end.
+    pub fn swap_size_model(&mut self) {
+        for entry in self.tree.node_weights_mut() {
+            std::mem::swap(&mut entry.size, &mut entry.alternate_size);
+        }
+        self.total_bytes = self.tree.node_weight(self.root_index).map(|e| e.size);
+        self.aggregate_recent_sizes();
+    }
+
     fn recompute_root_size(&self) -> u128 {
         self.tree
             .neighbors_directed(self.root_index, Direction::Outgoing)
@@ -0,0 +1,148 @@
+use crate::{privileged::is_permission_denied, WalkOptions, WalkResult};
+use anyhow::Result;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// What we know about the unreadable directories sharing an owner and parent directory
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct UnreadableSummary {
+    /// The amount of unreadable directories
+    pub directories: u64,
+    /// The amount of sub-directories within the unreadable directories as indicated by their link count, if available
+    pub subdirectories: Option<u64>,
+}
+
+/// Traverse `paths` and write a summary of all directories we are not permitted to read to `out`,
+/// grouped by owner and parent directory.
+///
+/// Only information available without permission to read the directories themselves is used.
+pub fn summarize_unreadable(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut by_owner_and_parent = BTreeMap::<(PathBuf, Option<u32>), UnreadableSummary>::new();
+    for path in paths {
+        for entry in walk_options.iter_from_path(path.as_ref()) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    continue;
+                }
+            };
+            match &entry.read_children_error {
+                Some(err) if is_permission_denied(err) => {
+                    res.num_errors += 1;
+                    let meta = entry.path().symlink_metadata().ok();
+                    let summary = by_owner_and_parent
+                        .entry((
+                            entry.parent_path.to_path_buf(),
+                            meta.as_ref().and_then(owner_of),
+                        ))
+                        .or_default();
+                    summary.directories += 1;
+                    if let Some(subdirectories) = meta.as_ref().and_then(subdirectories_of) {
+                        *summary.subdirectories.get_or_insert(0) += subdirectories;
+                    }
+                }
+                Some(_) => res.num_errors += 1,
+                None => {}
+            }
+        }
+    }
+
+    if by_owner_and_parent.is_empty() {
+        writeln!(out, "All directories could be read")?;
+        return Ok(res);
+    }
+    let user_names = user_names();
+    writeln!(
+        out,
+        "{:<16} {:>11} {:>14}  parent",
+        "owner", "directories", "subdirectories"
+    )?;
+    for ((parent, owner), summary) in by_owner_and_parent {
+        let owner = match owner {
+            Some(uid) => user_names
+                .get(&uid)
+                .cloned()
+                .unwrap_or_else(|| uid.to_string()),
+            None => "?".into(),
+        };
+        writeln!(
+            out,
+            "{:<16} {:>11} {:>14}  {}",
+            owner,
+            summary.directories,
+            summary
+                .subdirectories
+                .map_or_else(|| "?".into(), |n| n.to_string()),
+            parent.display()
+        )?;
+    }
+    Ok(res)
+}
+
+#[cfg(unix)]
+fn owner_of(meta: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.uid())
+}
+
+#[cfg(not(unix))]
+fn owner_of(_meta: &Metadata) -> Option<u32> {
+    None
+}
+
+/// On most unix filesystems, the link count of a directory is its amount of sub-directories plus two,
+/// one for its own `.` entry and one for its entry in the parent directory.
+#[cfg(unix)]
+fn subdirectories_of(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink().checked_sub(2)
+}
+
+#[cfg(not(unix))]
+fn subdirectories_of(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+fn user_names() -> HashMap<u32, String> {
+    std::fs::read_to_string("/etc/passwd")
+        .map(|passwd| parse_passwd(&passwd))
+        .unwrap_or_default()
+}
+
+fn parse_passwd(passwd: &str) -> HashMap<u32, String> {
+    passwd
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_user_names_from_passwd() {
+        let names = parse_passwd(
+            "# comment\nroot:x:0:0:root:/root:/bin/bash\nbroken\nme:x:1000:1000::/home/me:/bin/sh\n",
+        );
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get(&0).map(String::as_str), Some("root"));
+        assert_eq!(names.get(&1000).map(String::as_str), Some("me"));
+    }
+}